@@ -10,9 +10,9 @@ use crate::dto::{
     CreateRoomRequest, CreateUrlContentRequest, CreateUrlContentResponse, DeleteContentRequest,
     DeleteContentResponse, DeleteRoomResponse, FileMergeRequest, FileMergeResponse,
     FullRoomGcStatusView, IssueTokenRequest, IssueTokenResponse, LogoutRequest, MergedFileInfo,
-    MessagePage, PublicConfigResponse, PublicRoomConfig, PublicRoomExpiryConfig, ReservedFileInfo,
-    RevokeTokenResponse, RoomContentView, RoomTokenClaims, RoomTokenView, RoomView,
-    RunRoomGcResponse, TokenType, UpdateContentRequest, UpdateContentResponse,
+    MessagePage, MissingChunkRange, PublicConfigResponse, PublicRoomConfig, PublicRoomExpiryConfig,
+    ReservedFileInfo, RevokeTokenResponse, RoomContentView, RoomTokenClaims, RoomTokenView,
+    RoomView, RunRoomGcResponse, TokenType, UpdateContentRequest, UpdateContentResponse,
     UpdateRoomPermissionRequest, UpdateRoomSettingsRequest, UploadContentResponse,
     UploadPreparationRequest, UploadPreparationResponse, UploadStatusQuery, UploadStatusResponse,
     ValidateTokenRequest, ValidateTokenResponse, VerifyRoomPasswordRequest,
@@ -22,9 +22,9 @@ use crate::dto::{
 use crate::models::content::{ContentType, RoomContent};
 #[cfg(feature = "typescript-export")]
 use crate::models::{
-    ChunkStatus, CreateRefreshTokenRequest, RefreshTokenRequest, RefreshTokenResponse,
-    RoomChunkUpload, RoomRefreshToken, RoomStatus, RoomUploadReservation, TokenBlacklistEntry,
-    UploadFileDescriptor, UploadStatus,
+    ChunkStatus, CreateRefreshTokenRequest, HashAlgorithm, RefreshTokenRequest,
+    RefreshTokenResponse, RoomChunkUpload, RoomRefreshToken, RoomStatus, RoomUploadReservation,
+    TokenBlacklistEntry, UploadFileDescriptor, UploadStatus,
 };
 
 #[cfg(feature = "typescript-export")]
@@ -43,6 +43,7 @@ pub fn export_ts_types_to(output_dir: &Path) -> Result<(), ts_rs::ExportError> {
     RoomUploadReservation::export_all(&output_dir_cfg)?;
     UploadFileDescriptor::export_all(&output_dir_cfg)?;
     UploadStatus::export_all(&output_dir_cfg)?;
+    HashAlgorithm::export_all(&output_dir_cfg)?;
 
     TokenType::export_all(&output_dir_cfg)?;
     RoomTokenClaims::export_all(&output_dir_cfg)?;
@@ -85,6 +86,7 @@ pub fn export_ts_types_to(output_dir: &Path) -> Result<(), ts_rs::ExportError> {
     ChunkUploadResponse::export_all(&output_dir_cfg)?;
     UploadStatusQuery::export_all(&output_dir_cfg)?;
     ChunkStatusInfo::export_all(&output_dir_cfg)?;
+    MissingChunkRange::export_all(&output_dir_cfg)?;
     UploadStatusResponse::export_all(&output_dir_cfg)?;
     FileMergeRequest::export_all(&output_dir_cfg)?;
     FileMergeResponse::export_all(&output_dir_cfg)?;
@@ -113,6 +115,7 @@ pub fn exported_ts_type_names() -> &'static [&'static str] {
         "RoomUploadReservation",
         "UploadFileDescriptor",
         "UploadStatus",
+        "HashAlgorithm",
         "TokenType",
         "RoomTokenClaims",
         "CreateRoomRequest",
@@ -151,6 +154,7 @@ pub fn exported_ts_type_names() -> &'static [&'static str] {
         "ChunkUploadResponse",
         "UploadStatusQuery",
         "ChunkStatusInfo",
+        "MissingChunkRange",
         "UploadStatusResponse",
         "FileMergeRequest",
         "FileMergeResponse",
@@ -182,6 +186,7 @@ pub fn api_schema_json_pretty() -> Result<String, serde_json::Error> {
         room_upload_reservation: RoomUploadReservation,
         upload_file_descriptor: UploadFileDescriptor,
         upload_status: UploadStatus,
+        hash_algorithm: HashAlgorithm,
         token_type: TokenType,
         room_token_claims: RoomTokenClaims,
         create_room_request: CreateRoomRequest,
@@ -220,6 +225,7 @@ pub fn api_schema_json_pretty() -> Result<String, serde_json::Error> {
         chunk_upload_response: ChunkUploadResponse,
         upload_status_query: UploadStatusQuery,
         chunk_status_info: ChunkStatusInfo,
+        missing_chunk_range: MissingChunkRange,
         upload_status_response: UploadStatusResponse,
         file_merge_request: FileMergeRequest,
         file_merge_response: FileMergeResponse,
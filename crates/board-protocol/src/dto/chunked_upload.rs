@@ -2,7 +2,7 @@ use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-use crate::models::{ChunkStatus, UploadFileDescriptor, UploadStatus};
+use crate::models::{ChunkStatus, HashAlgorithm, UploadFileDescriptor, UploadStatus};
 
 /// 分块上传预留请求
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -10,6 +10,10 @@ use crate::models::{ChunkStatus, UploadFileDescriptor, UploadStatus};
 #[cfg_attr(feature = "typescript-export", ts(export))]
 pub struct ChunkedUploadPreparationRequest {
     pub files: Vec<UploadFileDescriptor>,
+    /// 分块与整体文件的校验算法，默认 SHA-256
+    #[serde(default)]
+    #[cfg_attr(feature = "typescript-export", ts(optional))]
+    pub hash_algorithm: Option<HashAlgorithm>,
 }
 
 /// 分块上传预留响应
@@ -23,6 +27,8 @@ pub struct ChunkedUploadPreparationResponse {
     pub upload_token: String,
     /// 预留过期时间
     pub expires_at: NaiveDateTime,
+    /// 本次上传使用的校验算法
+    pub hash_algorithm: HashAlgorithm,
     /// 文件清单
     pub files: Vec<ReservedFileInfo>,
 }
@@ -61,7 +67,7 @@ pub struct ChunkUploadRequest {
     /// 分块大小
     #[cfg_attr(feature = "typescript-export", ts(type = "number"))]
     pub chunk_size: i64,
-    /// 分块哈希（可选，用于完整性验证）
+    /// 分块哈希（可选，用于完整性验证），按预留时选择的算法计算
     #[cfg_attr(feature = "typescript-export", ts(optional))]
     pub chunk_hash: Option<String>,
 }
@@ -116,6 +122,25 @@ pub struct ChunkStatusInfo {
     pub uploaded_at: Option<NaiveDateTime>,
 }
 
+/// 尚未上传的一段连续分块
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "typescript-export", derive(ts_rs::TS, schemars::JsonSchema))]
+#[cfg_attr(feature = "typescript-export", ts(export))]
+pub struct MissingChunkRange {
+    /// 起始分块索引
+    #[cfg_attr(feature = "typescript-export", ts(type = "number"))]
+    pub start_chunk: i64,
+    /// 结束分块索引（含）
+    #[cfg_attr(feature = "typescript-export", ts(type = "number"))]
+    pub end_chunk: i64,
+    /// 起始字节偏移
+    #[cfg_attr(feature = "typescript-export", ts(type = "number"))]
+    pub offset: i64,
+    /// 字节长度
+    #[cfg_attr(feature = "typescript-export", ts(type = "number"))]
+    pub length: i64,
+}
+
 /// 上传状态查询响应
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[cfg_attr(feature = "typescript-export", derive(ts_rs::TS, schemars::JsonSchema))]
@@ -127,6 +152,8 @@ pub struct UploadStatusResponse {
     pub upload_token: String,
     /// 上传状态
     pub upload_status: UploadStatus,
+    /// 校验算法
+    pub hash_algorithm: HashAlgorithm,
     /// 总分块数
     #[cfg_attr(feature = "typescript-export", ts(type = "number"))]
     pub total_chunks: i64,
@@ -139,6 +166,8 @@ pub struct UploadStatusResponse {
     pub expires_at: NaiveDateTime,
     /// 已上传分块详细信息
     pub chunk_details: Vec<ChunkStatusInfo>,
+    /// 尚未上传的分块区间，客户端只需补传这些部分
+    pub missing_ranges: Vec<MissingChunkRange>,
     /// 预留大小
     #[cfg_attr(feature = "typescript-export", ts(type = "number"))]
     pub reserved_size: i64,
@@ -158,6 +187,7 @@ pub struct UploadStatusResponse {
 #[cfg_attr(feature = "typescript-export", ts(export))]
 pub struct FileMergeRequest {
     pub reservation_id: String,
+    /// 整个文件的哈希，按预留时选择的算法计算
     pub final_hash: String,
 }
 
@@ -169,11 +199,15 @@ pub struct MergedFileInfo {
     pub file_name: String,
     #[cfg_attr(feature = "typescript-export", ts(type = "number"))]
     pub file_size: i64,
+    /// 存储内容的 SHA-256，与上传时选择的校验算法无关
     pub file_hash: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[cfg_attr(feature = "typescript-export", ts(type = "number"))]
     #[cfg_attr(feature = "typescript-export", ts(optional))]
     pub content_id: Option<i64>,
+    /// 房间要求移除图片元数据，但图片超过移除的大小上限，保留了原有元数据
+    #[serde(default)]
+    pub metadata_retained: bool,
 }
 
 /// 文件合并完成响应
@@ -4,7 +4,9 @@ use std::path::Path;
 use utoipa::ToSchema;
 
 use crate::models::UploadFileDescriptor;
-use crate::models::content::{ContentType, RoomContent};
+use crate::models::content::{
+    ContentType, IntegrityStatus, RoomContent, ScanStatus, ThumbnailStatus,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[cfg_attr(feature = "typescript-export", derive(ts_rs::TS, schemars::JsonSchema))]
@@ -19,8 +21,17 @@ pub struct RoomContentView {
     #[cfg_attr(feature = "typescript-export", ts(type = "number | null"))]
     pub size: Option<i64>,
     pub mime_type: Option<String>,
+    /// 服务端根据文件内容检测到的类型，无法识别时为空
+    pub detected_mime_type: Option<String>,
+    pub hash: Option<String>,
     #[cfg_attr(feature = "typescript-export", ts(type = "number"))]
     pub sequence_number: i32,
+    /// 缩略图就绪后的下载地址，仍需附带 `token` 查询参数
+    pub thumbnail_url: Option<String>,
+    /// 病毒扫描状态，`clean` 以外的文件拒绝下载
+    pub scan_status: ScanStatus,
+    /// `missing` 表示存储中的文件已丢失，无法下载
+    pub integrity_status: IntegrityStatus,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
@@ -36,15 +47,24 @@ impl From<RoomContent> for RoomContentView {
             })
         });
 
+        let id = value.id.unwrap_or_default();
+        let thumbnail_url = (value.thumbnail_status == ThumbnailStatus::Ready)
+            .then(|| format!("/api/v1/contents/{id}/thumbnail"));
+
         Self {
-            id: value.id.unwrap_or_default(),
+            id,
             content_type: value.content_type,
             text: value.text,
             file_name,
             url: value.url,
             size: value.size,
             mime_type: value.mime_type,
+            detected_mime_type: value.detected_mime_type,
+            hash: value.hash,
             sequence_number: value.sequence_number,
+            thumbnail_url,
+            scan_status: value.scan_status,
+            integrity_status: value.integrity_status,
             created_at: value.created_at,
             updated_at: value.updated_at,
         }
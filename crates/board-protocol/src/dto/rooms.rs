@@ -31,6 +31,8 @@ pub struct RoomView {
     pub updated_at: NaiveDateTime,
     pub permission: u8,
     pub password_protected: bool,
+    /// 房间级图片元数据移除开关，为空时沿用部署配置
+    pub strip_image_metadata: Option<bool>,
 }
 
 impl From<&Room> for RoomView {
@@ -49,6 +51,7 @@ impl From<&Room> for RoomView {
             updated_at: room.updated_at,
             permission: room.permission.bits(),
             password_protected: room.password.is_some(),
+            strip_image_metadata: room.strip_image_metadata,
         }
     }
 }
@@ -148,6 +151,9 @@ pub struct UpdateRoomSettingsRequest {
     #[cfg_attr(feature = "typescript-export", ts(type = "number | null"))]
     #[cfg_attr(feature = "typescript-export", ts(optional))]
     pub max_size: Option<i64>,
+    /// 上传图片时是否移除 EXIF/XMP 元数据（可选）
+    #[cfg_attr(feature = "typescript-export", ts(optional))]
+    pub strip_image_metadata: Option<bool>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, Row, any::AnyRow, postgres::PgRow, sqlite::SqliteRow};
 
 use crate::models::room::row_utils::read_datetime_from_any;
+use crate::models::room::upload_reservation::HashAlgorithm;
 use utoipa::ToSchema;
 
 /// 分块状态枚举
@@ -131,8 +132,9 @@ pub struct RoomChunkUpload {
     pub chunk_index: i64, // 分块索引（从 0 开始）
     #[cfg_attr(feature = "typescript-export", ts(type = "number"))]
     pub chunk_size: i64, // 分块大小
-    pub chunk_hash: Option<String>, // 分块哈希值
-    pub upload_status: ChunkStatus, // 分块状态
+    pub chunk_hash: Option<String>,    // 分块哈希值
+    pub upload_status: ChunkStatus,    // 分块状态
+    pub hash_algorithm: HashAlgorithm, // chunk_hash 使用的算法
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
@@ -153,6 +155,7 @@ impl RoomChunkUpload {
             chunk_size,
             chunk_hash,
             upload_status: ChunkStatus::Pending,
+            hash_algorithm: HashAlgorithm::default(),
             created_at: now,
             updated_at: now,
         }
@@ -217,6 +220,7 @@ fn build_chunk_from_sqlite(row: &SqliteRow) -> Result<RoomChunkUpload, sqlx::Err
         chunk_size: row.try_get("chunk_size")?,
         chunk_hash: row.try_get("chunk_hash")?,
         upload_status: row.try_get("upload_status")?,
+        hash_algorithm: row.try_get("hash_algorithm")?,
         created_at: row.try_get("created_at")?,
         updated_at: row.try_get("updated_at")?,
     })
@@ -230,6 +234,7 @@ fn build_chunk_from_pg(row: &PgRow) -> Result<RoomChunkUpload, sqlx::Error> {
         chunk_size: row.try_get("chunk_size")?,
         chunk_hash: row.try_get("chunk_hash")?,
         upload_status: row.try_get("upload_status")?,
+        hash_algorithm: row.try_get("hash_algorithm")?,
         created_at: row.try_get("created_at")?,
         updated_at: row.try_get("updated_at")?,
     })
@@ -243,6 +248,7 @@ fn build_chunk_from_any(row: &AnyRow) -> Result<RoomChunkUpload, sqlx::Error> {
         chunk_size: row.try_get("chunk_size")?,
         chunk_hash: row.try_get("chunk_hash")?,
         upload_status: row.try_get("upload_status")?,
+        hash_algorithm: row.try_get("hash_algorithm")?,
         created_at: read_datetime_from_any(row, "created_at")?,
         updated_at: read_datetime_from_any(row, "updated_at")?,
     })
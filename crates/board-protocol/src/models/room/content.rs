@@ -19,6 +19,66 @@ pub enum ContentType {
     Url = 3,
 }
 
+/// 缩略图生成状态
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type,
+)]
+#[cfg_attr(feature = "typescript-export", derive(ts_rs::TS, schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "INTEGER")]
+#[repr(i64)]
+#[cfg_attr(feature = "typescript-export", ts(export))]
+pub enum ThumbnailStatus {
+    /// 非图片内容，不生成缩略图
+    #[default]
+    None = 0,
+    Pending = 1,
+    Ready = 2,
+    /// 解码或编码失败，不再重试
+    Failed = 3,
+}
+
+/// 病毒扫描状态，扫描通过前文件不可下载
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type,
+)]
+#[cfg_attr(feature = "typescript-export", derive(ts_rs::TS, schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "INTEGER")]
+#[repr(i64)]
+#[cfg_attr(feature = "typescript-export", ts(export))]
+pub enum ScanStatus {
+    /// 扫描通过，或无需扫描（文本、链接、未启用扫描）
+    #[default]
+    Clean = 0,
+    Pending = 1,
+    Infected = 2,
+    /// 扫描器无法给出结论（如超出 clamd 大小限制），按未通过处理
+    Failed = 3,
+}
+
+/// 存储文件的一致性状态，由后台对账与完整性巡检任务维护
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type,
+)]
+#[cfg_attr(feature = "typescript-export", derive(ts_rs::TS, schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "INTEGER")]
+#[repr(i64)]
+#[cfg_attr(feature = "typescript-export", ts(export))]
+pub enum IntegrityStatus {
+    #[default]
+    Ok = 0,
+    /// 记录存在但存储中已找不到文件
+    Missing = 1,
+    /// 文件内容与记录的摘要不一致
+    Corrupted = 2,
+}
+
+/// 可生成缩略图的图片 MIME 类型
+pub const THUMBNAIL_SOURCE_MIME_TYPES: &[&str] =
+    &["image/jpeg", "image/png", "image/gif", "image/webp"];
+
 /// 数据库 RoomContent 模型
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[cfg_attr(feature = "typescript-export", derive(ts_rs::TS, schemars::JsonSchema))]
@@ -35,9 +95,18 @@ pub struct RoomContent {
     pub file_name: Option<String>, // The original file name (for display and download)
     #[cfg_attr(feature = "typescript-export", ts(type = "number | null"))]
     pub size: Option<i64>, // The size of the content, maybe the usize is better but the SQLite does not support u64
-    pub mime_type: Option<String>,
+    pub mime_type: Option<String>, // The MIME type declared by the client
+    #[serde(default)]
+    pub detected_mime_type: Option<String>, // The MIME type detected from the file's leading bytes
+    pub hash: Option<String>,      // Lowercase hex SHA-256 of the stored file, files only
     #[cfg_attr(feature = "typescript-export", ts(type = "number"))]
     pub sequence_number: i32,
+    #[serde(default)]
+    pub thumbnail_status: ThumbnailStatus,
+    #[serde(default)]
+    pub scan_status: ScanStatus,
+    #[serde(default)]
+    pub integrity_status: IntegrityStatus,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
@@ -53,7 +122,12 @@ fn build_room_content_sqlite(row: &SqliteRow) -> Result<RoomContent, sqlx::Error
         file_name: row.try_get("file_name")?,
         size: row.try_get("size")?,
         mime_type: row.try_get("mime_type")?,
+        detected_mime_type: row.try_get("detected_mime_type")?,
+        hash: row.try_get("hash")?,
         sequence_number: row.try_get("sequence_number")?,
+        thumbnail_status: row.try_get("thumbnail_status")?,
+        scan_status: row.try_get("scan_status")?,
+        integrity_status: row.try_get("integrity_status")?,
         created_at: row.try_get("created_at")?,
         updated_at: row.try_get("updated_at")?,
     })
@@ -70,7 +144,12 @@ fn build_room_content_pg(row: &PgRow) -> Result<RoomContent, sqlx::Error> {
         file_name: row.try_get("file_name")?,
         size: row.try_get("size")?,
         mime_type: row.try_get("mime_type")?,
+        detected_mime_type: row.try_get("detected_mime_type")?,
+        hash: row.try_get("hash")?,
         sequence_number: row.try_get("sequence_number")?,
+        thumbnail_status: row.try_get("thumbnail_status")?,
+        scan_status: row.try_get("scan_status")?,
+        integrity_status: row.try_get("integrity_status")?,
         created_at: row.try_get("created_at")?,
         updated_at: row.try_get("updated_at")?,
     })
@@ -87,7 +166,12 @@ fn build_room_content_any(row: &AnyRow) -> Result<RoomContent, sqlx::Error> {
         file_name: row.try_get("file_name")?,
         size: row.try_get("size")?,
         mime_type: row.try_get("mime_type")?,
+        detected_mime_type: row.try_get("detected_mime_type")?,
+        hash: row.try_get("hash")?,
         sequence_number: row.try_get("sequence_number")?,
+        thumbnail_status: row.try_get("thumbnail_status")?,
+        scan_status: row.try_get("scan_status")?,
+        integrity_status: row.try_get("integrity_status")?,
         created_at: read_datetime_from_any(row, "created_at")?,
         updated_at: read_datetime_from_any(row, "updated_at")?,
     })
@@ -134,9 +218,39 @@ impl RoomContent {
             file_name: None,
             size: None,
             mime_type: None,
+            detected_mime_type: None,
+            hash: None,
+            thumbnail_status: ThumbnailStatus::None,
+            scan_status: ScanStatus::Clean,
+            integrity_status: IntegrityStatus::Ok,
         }
     }
 
+    /// 服务端检测到的类型优先，无法识别时退回客户端声明的类型
+    pub fn effective_mime_type(&self) -> Option<&str> {
+        self.detected_mime_type
+            .as_deref()
+            .or(self.mime_type.as_deref())
+    }
+
+    /// 已落盘的栅格图片才生成缩略图
+    pub fn supports_thumbnail(&self) -> bool {
+        if self.path.is_none() {
+            return false;
+        }
+        self.content_type == ContentType::Image
+            || self.effective_mime_type().is_some_and(|mime| {
+                THUMBNAIL_SOURCE_MIME_TYPES
+                    .iter()
+                    .any(|candidate| mime.eq_ignore_ascii_case(candidate))
+            })
+    }
+
+    /// 扫描通过后才允许下载文件或生成缩略图
+    pub fn is_scan_clean(&self) -> bool {
+        self.scan_status == ScanStatus::Clean
+    }
+
     /// Get timestamp for version control
     pub fn timestamp(&self) -> i64 {
         self.updated_at.and_utc().timestamp()
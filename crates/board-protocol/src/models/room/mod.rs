@@ -21,7 +21,9 @@ pub use refresh_token::{
     TokenBlacklistEntry,
 };
 pub use token::RoomToken;
-pub use upload_reservation::{RoomUploadReservation, UploadFileDescriptor, UploadStatus};
+pub use upload_reservation::{
+    HashAlgorithm, RoomUploadReservation, UploadFileDescriptor, UploadStatus,
+};
 
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, Default, sqlx::Type,
@@ -71,6 +73,9 @@ pub struct Room {
     #[cfg_attr(feature = "typescript-export", ts(type = "number"))]
     #[cfg_attr(feature = "typescript-export", schemars(with = "u8"))]
     pub permission: RoomPermission,
+    /// 上传时是否移除图片元数据，为空时沿用部署配置
+    #[serde(default)]
+    pub strip_image_metadata: Option<bool>,
 }
 
 fn build_room_from_sqlite(row: &SqliteRow) -> Result<Room, sqlx::Error> {
@@ -88,6 +93,7 @@ fn build_room_from_sqlite(row: &SqliteRow) -> Result<Room, sqlx::Error> {
         created_at: row.try_get("created_at")?,
         updated_at: row.try_get("updated_at")?,
         permission: row.try_get("permission")?,
+        strip_image_metadata: read_optional_flag(row.try_get("strip_image_metadata")?),
     })
 }
 
@@ -106,6 +112,7 @@ fn build_room_from_pg(row: &PgRow) -> Result<Room, sqlx::Error> {
         created_at: row.try_get("created_at")?,
         updated_at: row.try_get("updated_at")?,
         permission: row.try_get("permission")?,
+        strip_image_metadata: read_optional_flag(row.try_get("strip_image_metadata")?),
     })
 }
 
@@ -126,9 +133,15 @@ fn build_room_from_any(row: &AnyRow) -> Result<Room, sqlx::Error> {
         created_at: read_datetime_from_any(row, "created_at")?,
         updated_at: read_datetime_from_any(row, "updated_at")?,
         permission,
+        strip_image_metadata: read_optional_flag(row.try_get("strip_image_metadata")?),
     })
 }
 
+/// 可空开关以整数存储：NULL 表示未设置，0 / 1 表示关闭 / 开启
+fn read_optional_flag(raw: Option<i64>) -> Option<bool> {
+    raw.map(|value| value != 0)
+}
+
 impl<'r> FromRow<'r, SqliteRow> for Room {
     fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
         build_room_from_sqlite(row)
@@ -164,6 +177,7 @@ impl Room {
             created_at: now,
             updated_at: now,
             permission: RoomPermission::new().with_all(),
+            strip_image_metadata: None,
         }
     }
 
@@ -121,6 +121,118 @@ impl<'q> sqlx::Encode<'q, sqlx::Any> for UploadStatus {
     }
 }
 
+/// 分块与整体文件的校验算法，由客户端在预留时选择
+///
+/// 摘要均以小写十六进制表示，CRC32C 为 8 位。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "typescript-export", derive(ts_rs::TS, schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "typescript-export", ts(export))]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+    Crc32c,
+}
+
+impl std::fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_storage_value())
+    }
+}
+
+impl HashAlgorithm {
+    fn as_storage_value(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Crc32c => "crc32c",
+        }
+    }
+}
+
+impl std::str::FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            "crc32c" => Ok(HashAlgorithm::Crc32c),
+            _ => Err(format!("Invalid hash algorithm: {}", s)),
+        }
+    }
+}
+
+impl sqlx::Type<sqlx::Sqlite> for HashAlgorithm {
+    fn type_info() -> sqlx::sqlite::SqliteTypeInfo {
+        <String as sqlx::Type<sqlx::Sqlite>>::type_info()
+    }
+}
+
+impl<'r> sqlx::Decode<'r, sqlx::Sqlite> for HashAlgorithm {
+    fn decode(value: sqlx::sqlite::SqliteValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        let s = <&str as sqlx::Decode<sqlx::Sqlite>>::decode(value)?;
+        s.parse().map_err(|e: String| e.into())
+    }
+}
+
+impl<'q> sqlx::Encode<'q, sqlx::Sqlite> for HashAlgorithm {
+    fn encode_by_ref(
+        &self,
+        args: &mut Vec<sqlx::sqlite::SqliteArgumentValue<'q>>,
+    ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+        args.push(sqlx::sqlite::SqliteArgumentValue::Text(
+            std::borrow::Cow::Borrowed(self.as_storage_value()),
+        ));
+        Ok(sqlx::encode::IsNull::No)
+    }
+}
+
+impl sqlx::Type<sqlx::Postgres> for HashAlgorithm {
+    fn type_info() -> sqlx::postgres::PgTypeInfo {
+        <String as sqlx::Type<sqlx::Postgres>>::type_info()
+    }
+}
+
+impl<'r> sqlx::Decode<'r, sqlx::Postgres> for HashAlgorithm {
+    fn decode(value: sqlx::postgres::PgValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        let s = <&str as sqlx::Decode<sqlx::Postgres>>::decode(value)?;
+        s.parse().map_err(|e: String| e.into())
+    }
+}
+
+impl<'q> sqlx::Encode<'q, sqlx::Postgres> for HashAlgorithm {
+    fn encode_by_ref(
+        &self,
+        buf: &mut <sqlx::Postgres as sqlx::Database>::ArgumentBuffer<'q>,
+    ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+        <&str as sqlx::Encode<sqlx::Postgres>>::encode(self.as_storage_value(), buf)
+    }
+}
+
+impl sqlx::Type<sqlx::Any> for HashAlgorithm {
+    fn type_info() -> <sqlx::Any as sqlx::Database>::TypeInfo {
+        <String as sqlx::Type<sqlx::Any>>::type_info()
+    }
+}
+
+impl<'r> sqlx::Decode<'r, sqlx::Any> for HashAlgorithm {
+    fn decode(value: sqlx::any::AnyValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        let s = <&str as sqlx::Decode<sqlx::Any>>::decode(value)?;
+        s.parse().map_err(|e: String| e.into())
+    }
+}
+
+impl<'q> sqlx::Encode<'q, sqlx::Any> for HashAlgorithm {
+    fn encode_by_ref(
+        &self,
+        buf: &mut <sqlx::Any as sqlx::Database>::ArgumentBuffer<'q>,
+    ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+        <&str as sqlx::Encode<sqlx::Any>>::encode(self.as_storage_value(), buf)
+    }
+}
+
 /// 客户端上报的文件信息
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[cfg_attr(feature = "typescript-export", derive(ts_rs::TS, schemars::JsonSchema))]
@@ -170,6 +282,7 @@ pub struct RoomUploadReservation {
     #[cfg_attr(feature = "typescript-export", ts(type = "number | null"))]
     pub chunk_size: Option<i64>, // 分块大小
     pub upload_status: Option<UploadStatus>, // 上传状态
+    pub hash_algorithm: HashAlgorithm, // 校验算法
 }
 
 fn build_room_upload_reservation_sqlite(
@@ -194,6 +307,7 @@ fn build_room_upload_reservation_sqlite(
         file_hash: row.try_get("file_hash")?,
         chunk_size: row.try_get("chunk_size")?,
         upload_status: row.try_get("upload_status")?,
+        hash_algorithm: row.try_get("hash_algorithm")?,
     })
 }
 
@@ -216,6 +330,7 @@ fn build_room_upload_reservation_pg(row: &PgRow) -> Result<RoomUploadReservation
         file_hash: row.try_get("file_hash")?,
         chunk_size: row.try_get("chunk_size")?,
         upload_status: row.try_get("upload_status")?,
+        hash_algorithm: row.try_get("hash_algorithm")?,
     })
 }
 
@@ -246,6 +361,7 @@ fn build_room_upload_reservation_any(row: &AnyRow) -> Result<RoomUploadReservati
         file_hash: row.try_get("file_hash")?,
         chunk_size: row.try_get("chunk_size")?,
         upload_status: row.try_get("upload_status")?,
+        hash_algorithm: row.try_get("hash_algorithm")?,
     })
 }
 
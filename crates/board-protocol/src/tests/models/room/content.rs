@@ -1,4 +1,5 @@
-use crate::models::content::{ContentType, RoomContent};
+use crate::dto::RoomContentView;
+use crate::models::content::{ContentType, RoomContent, ThumbnailStatus};
 
 #[test]
 fn set_text_records_utf8_byte_size() {
@@ -37,3 +38,49 @@ fn set_url_records_utf8_byte_size() {
     let expected = "https://例子。测试/路径".len() as i64;
     assert_eq!(content.size, Some(expected));
 }
+
+#[test]
+fn thumbnail_url_only_exposed_when_ready() {
+    let now = chrono::Utc::now().naive_utc();
+    let mut content = RoomContent::builder()
+        .id(42)
+        .room_id(1)
+        .content_type(ContentType::File)
+        .sequence_number(0)
+        .now(now)
+        .build();
+    content.set_path(
+        "1/photo.png".to_string(),
+        ContentType::File,
+        10,
+        "image/PNG".to_string(),
+    );
+    assert!(content.supports_thumbnail());
+
+    content.thumbnail_status = ThumbnailStatus::Pending;
+    assert_eq!(RoomContentView::from(content.clone()).thumbnail_url, None);
+
+    content.thumbnail_status = ThumbnailStatus::Ready;
+    assert_eq!(
+        RoomContentView::from(content).thumbnail_url.as_deref(),
+        Some("/api/v1/contents/42/thumbnail")
+    );
+}
+
+#[test]
+fn svg_is_not_a_thumbnail_source() {
+    let now = chrono::Utc::now().naive_utc();
+    let mut content = RoomContent::builder()
+        .room_id(1)
+        .content_type(ContentType::File)
+        .sequence_number(0)
+        .now(now)
+        .build();
+    content.set_path(
+        "1/logo.svg".to_string(),
+        ContentType::File,
+        10,
+        "image/svg+xml".to_string(),
+    );
+    assert!(!content.supports_thumbnail());
+}
@@ -0,0 +1,10 @@
+-- Persist the SHA-256 digest of stored files.
+--
+-- Existing rows keep a NULL hash; downloads simply omit the digest header for them.
+
+ALTER TABLE room_contents
+    ADD COLUMN hash TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_room_contents_hash
+    ON room_contents(hash)
+    WHERE hash IS NOT NULL;
@@ -0,0 +1,9 @@
+-- Keep the MIME type detected from a file's leading bytes next to the type
+-- declared by the client (`mime_type`).
+--
+-- Downloads prefer the detected type and never serve active types (HTML, SVG,
+-- JavaScript, XML) as themselves. Existing rows stay NULL and fall back to the
+-- declared type under the same policy.
+
+ALTER TABLE room_contents
+    ADD COLUMN detected_mime_type TEXT;
@@ -0,0 +1,7 @@
+-- Per-room override for stripping EXIF/XMP metadata from uploaded images.
+--
+-- strip_image_metadata: NULL = follow the deployment default
+-- (`upload.strip_image_metadata`), 0 = keep metadata, 1 = strip.
+
+ALTER TABLE rooms
+    ADD COLUMN strip_image_metadata INTEGER;
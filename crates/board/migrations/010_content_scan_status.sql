@@ -0,0 +1,12 @@
+-- Quarantine uploaded files until a content scanner has checked them.
+--
+-- scan_status: 0 = clean, 1 = pending, 2 = infected, 3 = failed.
+-- Existing rows predate scanning and stay clean; only files uploaded while
+-- scanning is enabled start out pending.
+
+ALTER TABLE room_contents
+    ADD COLUMN scan_status INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_room_contents_scan_pending
+    ON room_contents(id)
+    WHERE scan_status = 1;
@@ -0,0 +1,12 @@
+-- Track when the stored file of a content was last re-hashed.
+--
+-- integrity_status gains 2 = corrupted.
+-- The integrity scrub task records a baseline hash for rows that have none,
+-- then periodically re-hashes files and compares the digest with `hash`.
+
+ALTER TABLE room_contents
+    ADD COLUMN verified_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_room_contents_verified_at
+    ON room_contents(verified_at)
+    WHERE path IS NOT NULL;
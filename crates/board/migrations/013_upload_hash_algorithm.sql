@@ -0,0 +1,12 @@
+-- Record the checksum algorithm negotiated for a chunked upload.
+--
+-- hash_algorithm: 'sha256', 'blake3' or 'crc32c'. Chunk rows carry the
+-- algorithm their chunk_hash was computed with so the merge stage can reject
+-- chunks that do not match their reservation. Existing rows were verified
+-- with SHA-256.
+
+ALTER TABLE room_upload_reservations
+    ADD COLUMN hash_algorithm TEXT NOT NULL DEFAULT 'sha256';
+
+ALTER TABLE room_chunk_uploads
+    ADD COLUMN hash_algorithm TEXT NOT NULL DEFAULT 'sha256';
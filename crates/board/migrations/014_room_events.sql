@@ -0,0 +1,14 @@
+-- Recent WebSocket room events, replayed to clients that reconnect with
+-- `last_event_id`.
+--
+-- event_id increases by one per room_name (the room slug used by the
+-- broadcaster). Only the newest `websocket.event_log_capacity` events of each
+-- room are kept; message holds the serialized event without its id.
+
+CREATE TABLE IF NOT EXISTS room_events (
+    room_name TEXT NOT NULL,
+    event_id INTEGER NOT NULL,
+    message TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (room_name, event_id)
+);
@@ -0,0 +1,9 @@
+-- Serialize tus PATCH requests for one upload across instances.
+--
+-- write_lease identifies the request currently appending to the upload and
+-- write_lease_until is when the lease lapses unless the holder renews it. A
+-- request that crashed without releasing its lease blocks the upload only
+-- until write_lease_until passes.
+
+ALTER TABLE room_upload_reservations ADD COLUMN write_lease TEXT;
+ALTER TABLE room_upload_reservations ADD COLUMN write_lease_until DATETIME;
@@ -0,0 +1,13 @@
+-- Content-addressed blob store used when `storage.dedup` is enabled.
+--
+-- Deduplicated files live under `blobs/<xx>/<sha256>` and are shared by every
+-- `room_contents` row with the same hash. `ref_count` tracks those rows; the
+-- object is removed from storage once the last reference is released.
+
+CREATE TABLE IF NOT EXISTS storage_blobs (
+    hash TEXT PRIMARY KEY,
+    size BIGINT NOT NULL DEFAULT 0 CHECK (size >= 0),
+    ref_count BIGINT NOT NULL DEFAULT 0 CHECK (ref_count >= 0),
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
@@ -0,0 +1,22 @@
+-- Track background thumbnail generation for image contents.
+--
+-- thumbnail_status: 0 = not applicable, 1 = pending, 2 = ready, 3 = failed.
+-- Thumbnails are stored under `<room_id>/.thumbnails/<content_id>/` so that
+-- purging the room prefix removes them together with the originals.
+-- Existing raster images are queued so older uploads get previews as well.
+
+ALTER TABLE room_contents
+    ADD COLUMN IF NOT EXISTS thumbnail_status BIGINT NOT NULL DEFAULT 0;
+
+UPDATE room_contents
+SET thumbnail_status = 1
+WHERE thumbnail_status = 0
+  AND path IS NOT NULL
+  AND (
+    content_type = 1
+    OR lower(mime_type) IN ('image/jpeg', 'image/png', 'image/gif', 'image/webp')
+  );
+
+CREATE INDEX IF NOT EXISTS idx_room_contents_thumbnail_pending
+    ON room_contents(id)
+    WHERE thumbnail_status = 1;
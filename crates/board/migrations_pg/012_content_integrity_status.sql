@@ -0,0 +1,8 @@
+-- Track whether the stored file of a content still exists.
+--
+-- integrity_status: 0 = ok, 1 = missing.
+-- The storage reconciliation task marks rows whose file disappeared from
+-- storage and clears the mark again if the file is restored.
+
+ALTER TABLE room_contents
+    ADD COLUMN IF NOT EXISTS integrity_status BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,8 @@
+-- Serialize blob deletion against new references.
+--
+-- deleting = 1 while the last releaser removes the object from storage. A new
+-- reference taken in that window waits for the flag to clear and then uploads
+-- the object again instead of pointing at a file that is about to disappear.
+
+ALTER TABLE storage_blobs
+    ADD COLUMN IF NOT EXISTS deleting BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,19 @@
+-- Presence list shared by all instances when the PostgreSQL event bus is enabled.
+--
+-- Each instance registers its own WebSocket connections and periodically bumps
+-- heartbeat_at for all of them. Rows whose heartbeat is older than the expiry
+-- belong to an instance that stopped without cleaning up and are ignored, then
+-- removed by the next heartbeat of any instance.
+
+CREATE TABLE IF NOT EXISTS websocket_presence (
+    connection_id TEXT PRIMARY KEY,
+    instance_id TEXT NOT NULL,
+    room_name TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    display_name TEXT,
+    connected_at BIGINT NOT NULL,
+    heartbeat_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_websocket_presence_room ON websocket_presence (room_name);
+CREATE INDEX IF NOT EXISTS idx_websocket_presence_instance ON websocket_presence (instance_id);
@@ -0,0 +1,8 @@
+-- Serialize the first write of a blob object.
+--
+-- upload_started_at is set while one caller checks for and writes the object.
+-- Concurrent references to the same hash wait for it to clear instead of
+-- writing the same key at the same time. A claim older than the stale limit
+-- belongs to a writer that stopped midway and may be taken over.
+
+ALTER TABLE storage_blobs ADD COLUMN IF NOT EXISTS upload_started_at TEXT;
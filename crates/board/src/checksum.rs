@@ -0,0 +1,126 @@
+use sha2::{Digest, Sha256};
+
+use crate::models::room::upload_reservation::HashAlgorithm;
+
+/// 按 [`HashAlgorithm`] 增量计算的校验和
+#[derive(Clone)]
+pub enum Checksum {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+    Crc32c(u32),
+}
+
+impl Checksum {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+            HashAlgorithm::Blake3 => Self::Blake3(Box::default()),
+            HashAlgorithm::Crc32c => Self::Crc32c(0),
+        }
+    }
+
+    /// 一次性计算整段数据的摘要（小写十六进制）
+    pub fn digest(algorithm: HashAlgorithm, data: &[u8]) -> String {
+        let mut checksum = Self::new(algorithm);
+        checksum.update(data);
+        checksum.finalize()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            Self::Crc32c(crc) => *crc = crc32c::crc32c_append(*crc, data),
+        }
+    }
+
+    pub fn finalize(self) -> String {
+        match self {
+            Self::Sha256(hasher) => hex::encode(hasher.finalize()),
+            Self::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+            Self::Crc32c(crc) => format!("{crc:08x}"),
+        }
+    }
+}
+
+/// 合并文件的摘要
+#[derive(Debug, Clone)]
+pub struct FileDigest {
+    /// 存储与去重使用的 SHA-256
+    pub sha256: String,
+    /// 客户端选择的算法计算的校验和，算法为 SHA-256 时与 `sha256` 相同
+    pub checksum: String,
+}
+
+/// 同时计算存储用的 SHA-256 与客户端选择的校验和
+///
+/// 客户端选择 SHA-256 时只计算一次。
+#[derive(Clone)]
+pub struct FileHasher {
+    sha256: Sha256,
+    checksum: Option<Checksum>,
+}
+
+impl FileHasher {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        Self {
+            sha256: Sha256::new(),
+            checksum: (algorithm != HashAlgorithm::Sha256).then(|| Checksum::new(algorithm)),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.sha256.update(data);
+        if let Some(checksum) = self.checksum.as_mut() {
+            checksum.update(data);
+        }
+    }
+
+    pub fn finalize(self) -> FileDigest {
+        let sha256 = hex::encode(self.sha256.finalize());
+        let checksum = match self.checksum {
+            Some(checksum) => checksum.finalize(),
+            None => sha256.clone(),
+        };
+        FileDigest { sha256, checksum }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digests_match_reference_values() {
+        assert_eq!(
+            Checksum::digest(HashAlgorithm::Crc32c, b"123456789"),
+            "e3069283"
+        );
+        assert_eq!(
+            Checksum::digest(HashAlgorithm::Blake3, b""),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+        assert_eq!(
+            Checksum::digest(HashAlgorithm::Sha256, b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn file_hasher_keeps_sha256_alongside_checksum() {
+        let mut hasher = FileHasher::new(HashAlgorithm::Crc32c);
+        hasher.update(b"1234");
+        hasher.update(b"56789");
+        let digest = hasher.finalize();
+        assert_eq!(digest.checksum, "e3069283");
+        assert_eq!(
+            digest.sha256,
+            Checksum::digest(HashAlgorithm::Sha256, b"123456789")
+        );
+
+        let digest = FileHasher::new(HashAlgorithm::Sha256).finalize();
+        assert_eq!(digest.checksum, digest.sha256);
+    }
+}
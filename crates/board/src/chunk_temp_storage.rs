@@ -1,25 +1,416 @@
-use std::io;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::io::{self, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
-pub fn reservation_dir(reservation_id: i64) -> PathBuf {
-    std::env::temp_dir()
-        .join("elizabeth")
-        .join("chunks")
-        .join(reservation_id.to_string())
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_util::io::StreamReader;
+
+use crate::checksum::{FileDigest, FileHasher};
+use crate::config::{ChunkStagingBackend, ChunkStagingConfig};
+use crate::models::room::upload_reservation::HashAlgorithm;
+use crate::storage::{StorageBackend, StorageError, StorageWriter, key};
+
+/// 读取尚未计入哈希的数据时的缓冲大小
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+
+/// 按偏移量连续累积的整体哈希，键为预留 ID
+type HashProgress = Arc<Mutex<HashMap<i64, (u64, FileHasher)>>>;
+
+/// 分块上传的暂存区
+///
+/// `local` 模式下分块按偏移量直接写入预分配的合并文件，可以乱序、并行上传，
+/// 合并时无需再拷贝；分块从开头连续到达时顺带累积整体哈希，完成时只需读取
+/// 尚未计入的部分。`storage` 模式下分块写入存储后端的 [`key::CHUNK_PREFIX`] 下，
+/// 任一实例都能继续上传或完成合并，合并时拷贝到本地目录再移入存储。
+pub struct ChunkStaging {
+    config: ChunkStagingConfig,
+    storage: Arc<dyn StorageBackend>,
+    hash_progress: HashProgress,
+}
+
+impl ChunkStaging {
+    pub fn new(config: ChunkStagingConfig, storage: Arc<dyn StorageBackend>) -> Self {
+        Self {
+            config,
+            storage,
+            hash_progress: HashProgress::default(),
+        }
+    }
+
+    /// 分块是否直接写入合并文件
+    pub fn assembles_in_place(&self) -> bool {
+        self.config.backend == ChunkStagingBackend::Local
+    }
+
+    /// 本地暂存目录下某个预留的子目录
+    pub fn reservation_dir(&self, reservation_id: i64) -> PathBuf {
+        self.config.dir.join(reservation_id.to_string())
+    }
+
+    pub fn merged_file_path(&self, reservation_id: i64) -> PathBuf {
+        self.reservation_dir(reservation_id).join("merged_file")
+    }
+
+    /// 为直接写入的合并文件预分配空间，`storage` 模式下不做处理
+    pub async fn preallocate(&self, reservation_id: i64, size: u64) -> Result<()> {
+        if !self.assembles_in_place() {
+            return Ok(());
+        }
+        let file = self.open_merged_file(reservation_id).await?;
+        file.set_len(size).await.with_context(|| {
+            format!(
+                "failed to preallocate {}",
+                self.merged_file_path(reservation_id).display()
+            )
+        })
+    }
+
+    /// 写入（或覆盖）一个分块，`offset` 为分块在文件中的起始位置
+    ///
+    /// `algorithm` 为预留选择的校验算法，用于累积整体哈希。
+    pub async fn write_chunk(
+        &self,
+        reservation_id: i64,
+        chunk_index: i64,
+        offset: u64,
+        algorithm: HashAlgorithm,
+        data: &[u8],
+    ) -> Result<()> {
+        let mut writer = self
+            .chunk_writer(reservation_id, chunk_index, offset, algorithm)
+            .await?;
+        if let Err(error) = writer.write(Bytes::copy_from_slice(data)).await {
+            writer.abort().await;
+            return Err(error);
+        }
+        writer.finish().await
+    }
+
+    /// 以流式方式写入（或覆盖）一个分块，适用于大小事先未知的请求体
+    pub async fn chunk_writer(
+        &self,
+        reservation_id: i64,
+        chunk_index: i64,
+        offset: u64,
+        algorithm: HashAlgorithm,
+    ) -> Result<ChunkWriter> {
+        match self.config.backend {
+            ChunkStagingBackend::Local => {
+                let path = self.merged_file_path(reservation_id);
+                let mut file = self.open_merged_file(reservation_id).await?;
+                file.seek(SeekFrom::Start(offset))
+                    .await
+                    .with_context(|| format!("failed to seek {}", path.display()))?;
+                let hash = self
+                    .take_hasher(reservation_id, offset, algorithm)
+                    .map(|hasher| {
+                        Box::new(ChunkHash {
+                            progress: self.hash_progress.clone(),
+                            reservation_id,
+                            start: offset,
+                            end: offset,
+                            original: hasher.clone(),
+                            hasher,
+                        })
+                    });
+                Ok(ChunkWriter::Local { file, path, hash })
+            }
+            ChunkStagingBackend::Storage => {
+                let chunk_key = key::chunk_key(reservation_id, chunk_index);
+                let writer = self
+                    .storage
+                    .writer(&chunk_key)
+                    .await
+                    .with_context(|| format!("failed to write {chunk_key}"))?;
+                Ok(ChunkWriter::Storage {
+                    writer,
+                    key: chunk_key,
+                })
+            }
+        }
+    }
+
+    /// 打开一个已暂存的分块，仅用于 `storage` 模式的合并
+    pub async fn open_chunk(
+        &self,
+        reservation_id: i64,
+        chunk_index: i64,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        let chunk_key = key::chunk_key(reservation_id, chunk_index);
+        let stream = self
+            .storage
+            .reader(&chunk_key)
+            .await
+            .with_context(|| format!("failed to open {chunk_key}"))?;
+        Ok(Box::new(StreamReader::new(stream)))
+    }
+
+    /// 直接写入的合并文件的摘要
+    ///
+    /// 已连续累积的部分不再读取；累积状态丢失（如进程重启）时从头计算。
+    pub async fn assembled_hash(
+        &self,
+        reservation_id: i64,
+        algorithm: HashAlgorithm,
+    ) -> Result<FileDigest> {
+        let (hashed, mut hasher) = self
+            .hash_progress
+            .lock()
+            .expect("hash progress lock poisoned")
+            .remove(&reservation_id)
+            .unwrap_or_else(|| (0, FileHasher::new(algorithm)));
+
+        let path = self.merged_file_path(reservation_id);
+        let mut file = tokio::fs::File::open(&path)
+            .await
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        file.seek(SeekFrom::Start(hashed))
+            .await
+            .with_context(|| format!("failed to seek {}", path.display()))?;
+        let mut buffer = vec![0; HASH_BUFFER_SIZE];
+        loop {
+            let read = file
+                .read(&mut buffer)
+                .await
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+        Ok(hasher.finalize())
+    }
+
+    /// 删除某个预留的全部暂存数据（含本地合并结果），不存在时视为成功
+    pub async fn remove_reservation(&self, reservation_id: i64) -> Result<()> {
+        self.hash_progress
+            .lock()
+            .expect("hash progress lock poisoned")
+            .remove(&reservation_id);
+        remove_dir(&self.reservation_dir(reservation_id)).await?;
+        if self.config.backend == ChunkStagingBackend::Storage {
+            let prefix = key::chunk_staging_prefix(reservation_id);
+            match self.storage.delete_all(&prefix).await {
+                Ok(()) | Err(StorageError::NotFound(_)) => {}
+                Err(error) => {
+                    return Err(error).with_context(|| format!("failed to delete {prefix}"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn open_merged_file(&self, reservation_id: i64) -> Result<tokio::fs::File> {
+        let dir = self.reservation_dir(reservation_id);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+        let path = self.merged_file_path(reservation_id);
+        tokio::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .await
+            .with_context(|| format!("failed to open {}", path.display()))
+    }
+
+    /// 分块恰好接在已累积部分之后时取出哈希状态，由写入器在完成后放回
+    ///
+    /// 覆盖已累积的部分时丢弃哈希状态，之后从头计算。
+    fn take_hasher(
+        &self,
+        reservation_id: i64,
+        offset: u64,
+        algorithm: HashAlgorithm,
+    ) -> Option<FileHasher> {
+        let mut progress = self
+            .hash_progress
+            .lock()
+            .expect("hash progress lock poisoned");
+        match progress.get(&reservation_id).map(|(hashed, _)| *hashed) {
+            Some(hashed) if hashed == offset => {
+                progress.remove(&reservation_id).map(|(_, hasher)| hasher)
+            }
+            Some(hashed) if offset < hashed => {
+                progress.remove(&reservation_id);
+                (offset == 0).then(|| FileHasher::new(algorithm))
+            }
+            None if offset == 0 => Some(FileHasher::new(algorithm)),
+            _ => None,
+        }
+    }
 }
 
-pub fn chunk_path(reservation_id: i64, chunk_index: i64) -> PathBuf {
-    reservation_dir(reservation_id).join(format!("chunk_{chunk_index}"))
+/// 写入过程中累积的整体哈希
+pub struct ChunkHash {
+    progress: HashProgress,
+    reservation_id: i64,
+    start: u64,
+    end: u64,
+    original: FileHasher,
+    hasher: FileHasher,
 }
 
-pub fn merged_file_path(reservation_id: i64) -> PathBuf {
-    reservation_dir(reservation_id).join("merged_file")
+impl ChunkHash {
+    fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+        self.end += data.len() as u64;
+    }
+
+    /// 放回哈希状态；未提交时恢复到写入前
+    fn store(self: Box<Self>, committed: bool) {
+        let state = if committed {
+            (self.end, self.hasher)
+        } else {
+            (self.start, self.original)
+        };
+        self.progress
+            .lock()
+            .expect("hash progress lock poisoned")
+            .insert(self.reservation_id, state);
+    }
+}
+
+/// [`ChunkStaging::chunk_writer`] 返回的分块写入器
+///
+/// 必须以 [`finish`](Self::finish) 或 [`abort`](Self::abort) 结束。
+/// 直接写入合并文件时放弃的字节会被之后的写入覆盖，不需要删除。
+pub enum ChunkWriter {
+    Local {
+        file: tokio::fs::File,
+        path: PathBuf,
+        hash: Option<Box<ChunkHash>>,
+    },
+    Storage {
+        writer: Box<dyn StorageWriter>,
+        key: String,
+    },
 }
 
-pub async fn remove_reservation_dir(reservation_id: i64) -> io::Result<()> {
-    match tokio::fs::remove_dir_all(reservation_dir(reservation_id)).await {
+impl ChunkWriter {
+    pub async fn write(&mut self, data: Bytes) -> Result<()> {
+        match self {
+            Self::Local { file, path, hash } => {
+                file.write_all(&data)
+                    .await
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+                if let Some(hash) = hash {
+                    hash.update(&data);
+                }
+                Ok(())
+            }
+            Self::Storage { writer, key } => writer
+                .write(data)
+                .await
+                .with_context(|| format!("failed to write {key}")),
+        }
+    }
+
+    pub async fn finish(self) -> Result<()> {
+        match self {
+            Self::Local {
+                mut file,
+                path,
+                hash,
+            } => {
+                let result = file
+                    .flush()
+                    .await
+                    .with_context(|| format!("failed to write {}", path.display()));
+                if let Some(hash) = hash {
+                    hash.store(result.is_ok());
+                }
+                result
+            }
+            Self::Storage { mut writer, key } => writer
+                .close()
+                .await
+                .with_context(|| format!("failed to write {key}")),
+        }
+    }
+
+    pub async fn abort(self) {
+        match self {
+            Self::Local { hash, .. } => {
+                if let Some(hash) = hash {
+                    hash.store(false);
+                }
+            }
+            Self::Storage { mut writer, key } => {
+                if let Err(error) = writer.abort().await {
+                    log::warn!("Failed to discard staged chunk {key}: {error:#}");
+                }
+            }
+        }
+    }
+}
+
+async fn remove_dir(dir: &Path) -> Result<()> {
+    match tokio::fs::remove_dir_all(dir).await {
         Ok(()) => Ok(()),
         Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
-        Err(error) => Err(error),
+        Err(error) => Err(error).with_context(|| format!("failed to remove {}", dir.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum::Checksum;
+    use crate::storage::{OpendalBackend, StorageConfig, StorageType};
+    use tempfile::TempDir;
+
+    fn staging(temp_dir: &TempDir) -> ChunkStaging {
+        let storage = OpendalBackend::new(StorageConfig {
+            storage_type: StorageType::Fs,
+            root: temp_dir
+                .path()
+                .join("storage")
+                .to_string_lossy()
+                .into_owned(),
+            s3_config: None,
+        })
+        .unwrap();
+        ChunkStaging::new(
+            ChunkStagingConfig {
+                backend: ChunkStagingBackend::Local,
+                dir: temp_dir.path().join("chunks"),
+            },
+            Arc::new(storage),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_rewriting_hashed_chunk_recomputes_hash() {
+        let temp_dir = TempDir::new().unwrap();
+        let staging = staging(&temp_dir);
+        let algorithm = HashAlgorithm::Sha256;
+
+        staging
+            .write_chunk(1, 0, 0, algorithm, b"AAAAAAAA")
+            .await
+            .unwrap();
+        staging
+            .write_chunk(1, 1, 8, algorithm, b"89abcdef")
+            .await
+            .unwrap();
+        staging
+            .write_chunk(1, 0, 0, algorithm, b"01234567")
+            .await
+            .unwrap();
+        staging
+            .write_chunk(1, 2, 16, algorithm, b"XYZ!")
+            .await
+            .unwrap();
+
+        let digest = staging.assembled_hash(1, algorithm).await.unwrap();
+        assert_eq!(
+            digest.sha256,
+            Checksum::digest(algorithm, b"0123456789abcdefXYZ!")
+        );
     }
 }
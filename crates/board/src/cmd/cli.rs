@@ -24,6 +24,34 @@ pub struct CliArgs {
     #[clap(short = 'd', long, env = "DATABASE_URL")]
     pub db_url: Option<String>,
 
+    /// The storage backend to use, "fs" or "s3", default_value = "fs"
+    #[clap(long, env = "STORAGE_BACKEND")]
+    pub storage_backend: Option<String>,
+
+    /// The storage root: a local directory for "fs", a key prefix for "s3", default_value = "storage/rooms"
+    #[clap(long, env = "STORAGE_ROOT")]
+    pub storage_root: Option<String>,
+
+    /// The S3/MinIO endpoint URL, e.g. "http://127.0.0.1:9000"
+    #[clap(long, env = "S3_ENDPOINT")]
+    pub s3_endpoint: Option<String>,
+
+    /// The S3/MinIO bucket name
+    #[clap(long, env = "S3_BUCKET")]
+    pub s3_bucket: Option<String>,
+
+    /// The S3/MinIO access key ID
+    #[clap(long, env = "S3_ACCESS_KEY_ID")]
+    pub s3_access_key_id: Option<String>,
+
+    /// The S3/MinIO secret access key
+    #[clap(long, env = "S3_SECRET_ACCESS_KEY")]
+    pub s3_secret_access_key: Option<String>,
+
+    /// The S3 region, e.g. "us-east-1"
+    #[clap(long, env = "S3_REGION")]
+    pub s3_region: Option<String>,
+
     /// The log level to use, -v for info, -vv for debug, -vvv/v for trace, default_value = "off"
     #[clap(short = 'v', long, action = clap::ArgAction::Count)]
     pub verbose: Option<u8>,
@@ -44,6 +72,19 @@ impl std::fmt::Debug for CliArgs {
                 "db_url",
                 &self.db_url.as_deref().map(redact_db_url_for_debug),
             )
+            .field("storage_backend", &self.storage_backend)
+            .field("storage_root", &self.storage_root)
+            .field("s3_endpoint", &self.s3_endpoint)
+            .field("s3_bucket", &self.s3_bucket)
+            .field(
+                "s3_access_key_id",
+                &self.s3_access_key_id.as_ref().map(|_| "[REDACTED]"),
+            )
+            .field(
+                "s3_secret_access_key",
+                &self.s3_secret_access_key.as_ref().map(|_| "[REDACTED]"),
+            )
+            .field("s3_region", &self.s3_region)
             .field("verbose", &self.verbose)
             .finish()
     }
@@ -69,6 +110,32 @@ pub enum Cli {
     #[command(alias = "run", alias = "serve")]
     Start(CliArgs),
 
+    /// Export a room with its contents and files to a portable archive
+    Export {
+        #[command(flatten)]
+        args: CliArgs,
+
+        /// The room name (slug) to export
+        room: String,
+
+        /// The archive file to write, defaults = "<room>.room.zip"
+        #[arg(short = 'o', long)]
+        output: Option<std::path::PathBuf>,
+    },
+
+    /// Import a room from an archive created by `export`
+    Import {
+        #[command(flatten)]
+        args: CliArgs,
+
+        /// The archive file to import
+        archive: std::path::PathBuf,
+
+        /// Import under a new room name instead of the archived one
+        #[arg(long)]
+        slug: Option<String>,
+    },
+
     #[cfg(feature = "completions")]
     /// Generate shell completions
     #[command(alias = "complete", alias = "comp", alias = "completion")]
@@ -1,6 +1,8 @@
 mod cli;
 #[cfg(feature = "completions")]
 mod completions;
+mod transfer;
 pub use cli::{Cli, CliArgs};
 #[cfg(feature = "completions")]
 pub(crate) use completions::output_completions;
+pub(crate) use transfer::{export_room, import_room};
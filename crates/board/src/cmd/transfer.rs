@@ -0,0 +1,52 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use tokio::io::AsyncWriteExt;
+
+use crate::repository::{IRoomRepository, RoomRepository};
+use crate::services::RoomImportOptions;
+use crate::state::AppState;
+
+/// 将房间导出到本地归档文件，返回写入的路径
+pub(crate) async fn export_room(
+    app_state: &AppState,
+    room: &str,
+    output: Option<PathBuf>,
+) -> anyhow::Result<PathBuf> {
+    let room = RoomRepository::new(app_state.db_pool.clone())
+        .find_by_name(room)
+        .await?
+        .with_context(|| format!("room '{room}' not found"))?;
+    let output = output.unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "{}.room.zip",
+            sanitize_filename::sanitize(&room.slug)
+        ))
+    });
+
+    let file = tokio::fs::File::create(&output)
+        .await
+        .with_context(|| format!("failed to create {}", output.display()))?;
+    let mut writer = tokio::io::BufWriter::new(file);
+    app_state
+        .services
+        .room_transfer
+        .export(&room, &mut writer)
+        .await?;
+    writer.flush().await?;
+    Ok(output)
+}
+
+/// 从本地归档文件导入房间，返回新房间的 slug
+pub(crate) async fn import_room(
+    app_state: &AppState,
+    archive: &Path,
+    slug: Option<String>,
+) -> anyhow::Result<String> {
+    let room = app_state
+        .services
+        .room_transfer
+        .import_file(archive, RoomImportOptions { slug })
+        .await?;
+    Ok(room.slug)
+}
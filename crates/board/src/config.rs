@@ -9,13 +9,15 @@ use chrono::Duration;
 use serde::{Deserialize, Serialize};
 
 use crate::models::permission::RoomPermission;
+use crate::storage::{S3Config, StorageType};
 
 use crate::constants::{
     auth::{DEFAULT_JWT_SERCET, DEFAULT_LEEWAY_SECONDS, DEFAULT_TTL_SECONDS},
     room::{DEFAULT_MAX_ROOM_CONTENT_SIZE, DEFAULT_MAX_TIMES_ENTER_ROOM},
-    storage::DEFAULT_STORAGE_ROOT,
+    storage::{DEFAULT_STORAGE_ROOT, MAX_THUMBNAIL_WIDTH},
     upload::DEFAULT_UPLOAD_RESERVATION_TTL_SECONDS,
     validation::MIN_JWT_SECRET_LENGTH,
+    websocket::{DEFAULT_EVENT_LOG_CAPACITY, DEFAULT_SEND_QUEUE_DEPTH},
 };
 
 /// 应用程序配置
@@ -26,6 +28,7 @@ pub struct AppConfig {
     pub storage: StorageConfig,
     pub room: RoomConfig,
     pub auth: AuthConfig,
+    pub websocket: WebsocketConfig,
 }
 
 /// 数据库配置
@@ -149,21 +152,586 @@ impl Default for ServerConfig {
 }
 
 /// 存储配置
+///
+/// `root` 在 `fs` 后端下是本地目录，在 `s3` 后端下是桶内的 key 前缀。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageConfig {
+    pub backend: StorageType,
     pub root: PathBuf,
+    pub s3: Option<S3Config>,
     pub upload_reservation_ttl_seconds: i64,
+    /// 是否启用内容寻址的去重存储
+    pub dedup: bool,
+    /// 图片缩略图生成策略
+    pub thumbnails: ThumbnailConfig,
+    /// 上传时是否默认移除图片元数据，房间设置可覆盖
+    pub strip_image_metadata: bool,
+    /// 上传文件的病毒扫描策略
+    pub scan: ScanConfig,
+    /// 存储与数据库对账策略
+    pub reconcile: ReconcileConfig,
+    /// 文件完整性巡检策略
+    pub scrub: ScrubConfig,
+    /// 分块上传的暂存位置
+    pub chunk_staging: ChunkStagingConfig,
 }
 
 impl Default for StorageConfig {
     fn default() -> Self {
         Self {
+            backend: StorageType::Fs,
             root: PathBuf::from(DEFAULT_STORAGE_ROOT),
+            s3: None,
             upload_reservation_ttl_seconds: DEFAULT_UPLOAD_RESERVATION_TTL_SECONDS,
+            dedup: false,
+            thumbnails: ThumbnailConfig::default(),
+            strip_image_metadata: true,
+            scan: ScanConfig::default(),
+            reconcile: ReconcileConfig::default(),
+            scrub: ScrubConfig::default(),
+            chunk_staging: ChunkStagingConfig::default(),
+        }
+    }
+}
+
+impl StorageConfig {
+    /// 转换为存储后端配置
+    pub fn backend_config(&self) -> crate::storage::StorageConfig {
+        crate::storage::StorageConfig {
+            storage_type: self.backend,
+            root: self.root.to_string_lossy().into_owned(),
+            s3_config: self.s3.clone(),
+        }
+    }
+
+    /// 验证存储配置
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.root.as_os_str().is_empty() {
+            return Err(ConfigError::InvalidStoragePath(
+                "Storage root path cannot be empty".to_string(),
+            ));
+        }
+
+        if self.backend == StorageType::S3
+            && self
+                .s3
+                .as_ref()
+                .is_none_or(|s3| s3.bucket.trim().is_empty())
+        {
+            return Err(ConfigError::InvalidStorageConfig(
+                "S3 storage requires a bucket".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// 从 configrs 的 `storage` 与 `upload` 段构建存储配置
+impl TryFrom<&configrs::AppConfig> for StorageConfig {
+    type Error = ConfigError;
+
+    fn try_from(value: &configrs::AppConfig) -> Result<Self, Self::Error> {
+        let backend = if value.storage.backend.trim().is_empty() {
+            StorageType::Fs
+        } else {
+            value
+                .storage
+                .backend
+                .parse::<StorageType>()
+                .map_err(|e| ConfigError::InvalidStorageConfig(e.to_string()))?
+        };
+        let root = if value.storage.root.trim().is_empty() {
+            PathBuf::from(DEFAULT_STORAGE_ROOT)
+        } else {
+            PathBuf::from(&value.storage.root)
+        };
+        let s3 = (backend == StorageType::S3).then(|| {
+            let s3 = &value.storage.s3;
+            S3Config {
+                endpoint: s3.endpoint.trim().to_string(),
+                bucket: s3.bucket.trim().to_string(),
+                access_key_id: s3.access_key_id.clone(),
+                secret_access_key: s3.secret_access_key.clone(),
+                region: s3
+                    .region
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|region| !region.is_empty())
+                    .map(str::to_owned),
+            }
+        });
+        let upload_reservation_ttl_seconds = if value.upload.reservation_ttl_seconds <= 0 {
+            DEFAULT_UPLOAD_RESERVATION_TTL_SECONDS
+        } else {
+            value.upload.reservation_ttl_seconds
+        };
+
+        Ok(Self {
+            backend,
+            root,
+            s3,
+            upload_reservation_ttl_seconds,
+            dedup: value.storage.dedup,
+            thumbnails: ThumbnailConfig::try_from(&value.storage.thumbnails)?,
+            strip_image_metadata: value.upload.strip_image_metadata,
+            scan: ScanConfig::try_from(&value.storage.scan)?,
+            reconcile: ReconcileConfig::from(&value.storage.reconcile),
+            scrub: ScrubConfig::from(&value.storage.scrub),
+            chunk_staging: ChunkStagingConfig::try_from(&value.upload)?,
+        })
+    }
+}
+
+/// 缩略图输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailFormat {
+    Jpeg,
+    /// 无损 WebP
+    Webp,
+}
+
+impl ThumbnailFormat {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Webp => "image/webp",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+        }
+    }
+}
+
+impl std::str::FromStr for ThumbnailFormat {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "jpeg" | "jpg" => Ok(Self::Jpeg),
+            "webp" => Ok(Self::Webp),
+            other => Err(ConfigError::InvalidStorageConfig(format!(
+                "Unsupported thumbnail format: {other}"
+            ))),
+        }
+    }
+}
+
+/// 缩略图生成配置
+///
+/// `widths` 已去重并升序排列，下载时按请求宽度选取最接近的一档。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThumbnailConfig {
+    pub enabled: bool,
+    pub widths: Vec<u32>,
+    pub format: ThumbnailFormat,
+    /// JPEG 压缩质量，WebP 缩略图为无损编码，不受影响
+    pub quality: u8,
+    /// 原图大小上限（字节）
+    pub max_source_size: u64,
+    pub interval_seconds: u64,
+    pub batch_limit: u32,
+}
+
+impl Default for ThumbnailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            widths: vec![320, 960],
+            format: ThumbnailFormat::Jpeg,
+            quality: 80,
+            max_source_size: 32 * 1024 * 1024,
+            interval_seconds: 5,
+            batch_limit: 16,
+        }
+    }
+}
+
+impl TryFrom<&configrs::ThumbnailConfig> for ThumbnailConfig {
+    type Error = ConfigError;
+
+    fn try_from(value: &configrs::ThumbnailConfig) -> Result<Self, Self::Error> {
+        let mut widths = value.widths.clone();
+        widths.sort_unstable();
+        widths.dedup();
+        if widths
+            .iter()
+            .any(|width| *width == 0 || *width > MAX_THUMBNAIL_WIDTH)
+        {
+            return Err(ConfigError::InvalidStorageConfig(format!(
+                "Thumbnail widths must be between 1 and {MAX_THUMBNAIL_WIDTH} pixels"
+            )));
+        }
+        if value.enabled && widths.is_empty() {
+            return Err(ConfigError::InvalidStorageConfig(
+                "Thumbnail generation requires at least one width".to_string(),
+            ));
+        }
+        if !(1..=100).contains(&value.quality) {
+            return Err(ConfigError::InvalidStorageConfig(
+                "Thumbnail quality must be between 1 and 100".to_string(),
+            ));
+        }
+        let format = value.format.parse()?;
+        if format == ThumbnailFormat::Webp && value.quality != Self::default().quality {
+            log::warn!(
+                "Thumbnail quality {} is ignored: WebP thumbnails are encoded losslessly",
+                value.quality
+            );
+        }
+
+        Ok(Self {
+            enabled: value.enabled,
+            widths,
+            format,
+            quality: value.quality,
+            max_source_size: value.max_source_size.as_u64(),
+            interval_seconds: value.interval_seconds.max(1),
+            batch_limit: value.batch_limit.max(1),
+        })
+    }
+}
+
+/// clamd 监听地址
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClamdAddress {
+    /// `host:port`
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+impl std::str::FromStr for ClamdAddress {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim();
+        if let Some(path) = value.strip_prefix("unix:") {
+            let path = path.trim_start_matches("//");
+            if !path.is_empty() {
+                return Ok(Self::Unix(PathBuf::from(path)));
+            }
+        } else {
+            let address = value.strip_prefix("tcp://").unwrap_or(value);
+            if address
+                .rsplit_once(':')
+                .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+            {
+                return Ok(Self::Tcp(address.to_string()));
+            }
+        }
+        Err(ConfigError::InvalidStorageConfig(format!(
+            "Invalid clamd address: {s}"
+        )))
+    }
+}
+
+/// 病毒扫描配置
+///
+/// 启用后带文件的新内容以 `pending` 状态入库，由后台任务交给 clamd 扫描。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanConfig {
+    pub enabled: bool,
+    pub clamd_address: ClamdAddress,
+    pub timeout_seconds: u64,
+    pub interval_seconds: u64,
+    pub batch_limit: u32,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            clamd_address: ClamdAddress::Tcp("127.0.0.1:3310".to_string()),
+            timeout_seconds: 60,
+            interval_seconds: 5,
+            batch_limit: 8,
+        }
+    }
+}
+
+impl TryFrom<&configrs::ScanConfig> for ScanConfig {
+    type Error = ConfigError;
+
+    fn try_from(value: &configrs::ScanConfig) -> Result<Self, Self::Error> {
+        Ok(Self {
+            enabled: value.enabled,
+            clamd_address: value.clamd_address.parse()?,
+            timeout_seconds: value.timeout_seconds.max(1),
+            interval_seconds: value.interval_seconds.max(1),
+            batch_limit: value.batch_limit.max(1),
+        })
+    }
+}
+
+/// 存储对账配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconcileConfig {
+    pub enabled: bool,
+    pub interval_seconds: u64,
+    /// 孤儿文件最后修改时间距今超过该值才会被处理
+    pub orphan_grace_seconds: u64,
+    pub delete_orphans: bool,
+}
+
+impl Default for ReconcileConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_seconds: 3600,
+            orphan_grace_seconds: 86400,
+            delete_orphans: false,
+        }
+    }
+}
+
+impl From<&configrs::ReconcileConfig> for ReconcileConfig {
+    fn from(value: &configrs::ReconcileConfig) -> Self {
+        Self {
+            enabled: value.enabled,
+            interval_seconds: value.interval_seconds.max(1),
+            orphan_grace_seconds: value.orphan_grace_seconds,
+            delete_orphans: value.delete_orphans,
+        }
+    }
+}
+
+/// 文件完整性巡检配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrubConfig {
+    pub enabled: bool,
+    pub interval_seconds: u64,
+    pub batch_limit: u32,
+    /// 距上次校验超过该值的文件才会重新计算摘要
+    pub reverify_after_seconds: u64,
+}
+
+impl Default for ScrubConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_seconds: 600,
+            batch_limit: 16,
+            reverify_after_seconds: 604800,
+        }
+    }
+}
+
+impl From<&configrs::ScrubConfig> for ScrubConfig {
+    fn from(value: &configrs::ScrubConfig) -> Self {
+        Self {
+            enabled: value.enabled,
+            interval_seconds: value.interval_seconds.max(1),
+            batch_limit: value.batch_limit.max(1),
+            reverify_after_seconds: value.reverify_after_seconds,
+        }
+    }
+}
+
+/// 分块暂存后端
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkStagingBackend {
+    /// 本地目录，仅处理该上传的实例可见
+    Local,
+    /// 存储后端，多实例共享
+    Storage,
+}
+
+impl std::str::FromStr for ChunkStagingBackend {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "local" => Ok(Self::Local),
+            "storage" => Ok(Self::Storage),
+            other => Err(ConfigError::InvalidStorageConfig(format!(
+                "Unsupported chunk staging backend: {other}"
+            ))),
         }
     }
 }
 
+/// 分块上传暂存配置
+///
+/// `dir` 在 `local` 模式下保存分块，两种模式下都用于生成临时的合并结果。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkStagingConfig {
+    pub backend: ChunkStagingBackend,
+    pub dir: PathBuf,
+}
+
+impl ChunkStagingConfig {
+    fn default_dir() -> PathBuf {
+        std::env::temp_dir().join("elizabeth").join("chunks")
+    }
+}
+
+impl Default for ChunkStagingConfig {
+    fn default() -> Self {
+        Self {
+            backend: ChunkStagingBackend::Local,
+            dir: Self::default_dir(),
+        }
+    }
+}
+
+impl TryFrom<&configrs::UploadConfig> for ChunkStagingConfig {
+    type Error = ConfigError;
+
+    fn try_from(value: &configrs::UploadConfig) -> Result<Self, Self::Error> {
+        let backend = if value.chunk_staging_backend.trim().is_empty() {
+            ChunkStagingBackend::Local
+        } else {
+            value.chunk_staging_backend.parse()?
+        };
+        let dir = if value.chunk_staging_dir.trim().is_empty() {
+            Self::default_dir()
+        } else {
+            PathBuf::from(value.chunk_staging_dir.trim())
+        };
+        Ok(Self { backend, dir })
+    }
+}
+
+/// WebSocket 房间事件总线后端
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventBusBackend {
+    /// 进程内广播，仅送达连接在本实例上的客户端
+    Memory,
+    /// PostgreSQL LISTEN/NOTIFY，送达所有实例
+    Postgres,
+}
+
+impl std::str::FromStr for EventBusBackend {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "memory" => Ok(Self::Memory),
+            "postgres" | "postgresql" => Ok(Self::Postgres),
+            other => Err(ConfigError::InvalidWebsocketConfig(format!(
+                "Unsupported event bus: {other}"
+            ))),
+        }
+    }
+}
+
+/// 连接的发送队列已满时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// 丢弃队列中最早的消息
+    DropOldest,
+    /// 用新消息替换队列中同一对象（内容、房间、用户）的旧事件，没有可替换的事件时丢弃最早的消息
+    Coalesce,
+    /// 以关闭码断开连接，客户端重连后通过事件日志补发
+    Disconnect,
+}
+
+impl std::str::FromStr for OverflowPolicy {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "drop_oldest" => Ok(Self::DropOldest),
+            "coalesce" => Ok(Self::Coalesce),
+            "disconnect" => Ok(Self::Disconnect),
+            other => Err(ConfigError::InvalidWebsocketConfig(format!(
+                "Unsupported overflow policy: {other}"
+            ))),
+        }
+    }
+}
+
+/// WebSocket 配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebsocketConfig {
+    pub event_bus: EventBusBackend,
+    /// `postgres` 事件总线的 NOTIFY 通道名
+    pub event_channel: String,
+    /// 每个房间保留的事件数，用于断线重连后补发；为 0 时不记录
+    pub event_log_capacity: u32,
+    /// 每个连接的发送队列长度
+    pub send_queue_depth: u32,
+    /// 发送队列已满时的处理方式
+    pub overflow_policy: OverflowPolicy,
+}
+
+impl WebsocketConfig {
+    fn default_event_channel() -> String {
+        "elizabeth_room_events".to_string()
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let channel = self.event_channel.as_str();
+        let valid = !channel.is_empty()
+            && channel.len() <= 63
+            && !channel.starts_with(|c: char| c.is_ascii_digit())
+            && channel
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(ConfigError::InvalidWebsocketConfig(format!(
+                "Event channel must be a PostgreSQL identifier of at most 63 characters: {channel:?}"
+            )));
+        }
+        if self.send_queue_depth == 0 {
+            return Err(ConfigError::InvalidWebsocketConfig(
+                "Send queue depth must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for WebsocketConfig {
+    fn default() -> Self {
+        Self {
+            event_bus: EventBusBackend::Memory,
+            event_channel: Self::default_event_channel(),
+            event_log_capacity: DEFAULT_EVENT_LOG_CAPACITY,
+            send_queue_depth: DEFAULT_SEND_QUEUE_DEPTH,
+            overflow_policy: OverflowPolicy::Disconnect,
+        }
+    }
+}
+
+impl TryFrom<&configrs::WebsocketConfig> for WebsocketConfig {
+    type Error = ConfigError;
+
+    fn try_from(value: &configrs::WebsocketConfig) -> Result<Self, Self::Error> {
+        let event_bus = if value.event_bus.trim().is_empty() {
+            EventBusBackend::Memory
+        } else {
+            value.event_bus.parse()?
+        };
+        let event_channel = if value.event_channel.trim().is_empty() {
+            Self::default_event_channel()
+        } else {
+            value.event_channel.trim().to_string()
+        };
+        let overflow_policy = if value.overflow_policy.trim().is_empty() {
+            OverflowPolicy::Disconnect
+        } else {
+            value.overflow_policy.parse()?
+        };
+        Ok(Self {
+            event_bus,
+            event_channel,
+            event_log_capacity: value.event_log_capacity,
+            send_queue_depth: value.send_queue_depth,
+            overflow_policy,
+        })
+    }
+}
+
 /// 房间配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RoomConfig {
@@ -484,10 +1052,14 @@ pub enum ConfigError {
     InvalidJwtSecret(String),
     #[error("Invalid storage path: {0}")]
     InvalidStoragePath(String),
+    #[error("Invalid storage configuration: {0}")]
+    InvalidStorageConfig(String),
     #[error("Invalid room configuration: {0}")]
     InvalidRoomConfig(String),
     #[error("Invalid database configuration: {0}")]
     InvalidDatabaseConfig(String),
+    #[error("Invalid websocket configuration: {0}")]
+    InvalidWebsocketConfig(String),
 }
 
 /// 配置验证器
@@ -497,12 +1069,8 @@ impl AppConfig {
         // 验证数据库配置
         self.database.validate()?;
 
-        // 验证存储路径
-        if self.storage.root.as_os_str().is_empty() {
-            return Err(ConfigError::InvalidStoragePath(
-                "Storage root path cannot be empty".to_string(),
-            ));
-        }
+        // 验证存储配置
+        self.storage.validate()?;
 
         // 验证房间配置
         if self.room.defaults.max_content_size <= 0 {
@@ -525,6 +1093,15 @@ impl AppConfig {
 
         self.room.expiry.validate()?;
 
+        self.websocket.validate()?;
+        if self.websocket.event_bus == EventBusBackend::Postgres
+            && self.database.database_kind() != DatabaseKind::PostgreSQL
+        {
+            return Err(ConfigError::InvalidWebsocketConfig(
+                "The postgres event bus requires a PostgreSQL database".to_string(),
+            ));
+        }
+
         Ok(())
     }
 
@@ -538,6 +1115,7 @@ impl AppConfig {
             storage: StorageConfig::default(),
             room: RoomConfig::default(),
             auth: auth_config,
+            websocket: WebsocketConfig::default(),
         })
     }
 
@@ -549,6 +1127,7 @@ impl AppConfig {
             storage: StorageConfig::default(),
             room: RoomConfig::default(),
             auth: AuthConfig::default(),
+            websocket: WebsocketConfig::default(),
         }
     }
 }
@@ -575,6 +1154,96 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_storage_config_from_configrs() {
+        let mut cfg = configrs::AppConfig::default();
+        let storage = StorageConfig::try_from(&cfg).unwrap();
+        assert_eq!(storage.backend, StorageType::Fs);
+        assert!(storage.s3.is_none());
+        assert!(!storage.dedup);
+        assert!(storage.thumbnails.enabled);
+        assert_eq!(storage.thumbnails.format, ThumbnailFormat::Jpeg);
+
+        cfg.storage.thumbnails.widths = vec![960, 0];
+        assert!(StorageConfig::try_from(&cfg).is_err());
+        cfg.storage.thumbnails.widths = vec![960, 320, 960];
+        cfg.storage.thumbnails.format = "WebP".into();
+        let storage = StorageConfig::try_from(&cfg).unwrap();
+        assert_eq!(storage.thumbnails.widths, vec![320, 960]);
+        assert_eq!(storage.thumbnails.format, ThumbnailFormat::Webp);
+
+        assert!(!storage.scan.enabled);
+        cfg.storage.scan.clamd_address = "unix:///run/clamav/clamd.ctl".into();
+        let storage = StorageConfig::try_from(&cfg).unwrap();
+        assert_eq!(
+            storage.scan.clamd_address,
+            ClamdAddress::Unix(PathBuf::from("/run/clamav/clamd.ctl"))
+        );
+        cfg.storage.scan.clamd_address = "clamav".into();
+        assert!(StorageConfig::try_from(&cfg).is_err());
+        cfg.storage.scan.clamd_address = "clamav:3310".into();
+
+        cfg.storage.backend = "s3".into();
+        cfg.storage.s3.endpoint = "http://minio:9000".into();
+        cfg.storage.s3.region = Some("  ".into());
+        let storage = StorageConfig::try_from(&cfg).unwrap();
+        assert_eq!(storage.backend, StorageType::S3);
+        let s3 = storage.s3.as_ref().unwrap();
+        assert_eq!(s3.endpoint, "http://minio:9000");
+        assert_eq!(s3.region, None);
+        // 未配置 bucket 时在验证阶段拒绝
+        assert!(matches!(
+            storage.validate(),
+            Err(ConfigError::InvalidStorageConfig(_))
+        ));
+
+        cfg.storage.backend = "ftp".into();
+        assert!(StorageConfig::try_from(&cfg).is_err());
+    }
+
+    #[test]
+    fn test_websocket_config_from_configrs() {
+        let mut cfg = configrs::WebsocketConfig::default();
+        let websocket = WebsocketConfig::try_from(&cfg).unwrap();
+        assert_eq!(websocket.event_bus, EventBusBackend::Memory);
+        assert_eq!(websocket.event_channel, "elizabeth_room_events");
+        assert_eq!(websocket.event_log_capacity, DEFAULT_EVENT_LOG_CAPACITY);
+        assert_eq!(websocket.send_queue_depth, DEFAULT_SEND_QUEUE_DEPTH);
+        assert_eq!(websocket.overflow_policy, OverflowPolicy::Disconnect);
+
+        cfg.overflow_policy = "drop-oldest".into();
+        cfg.send_queue_depth = 0;
+        let websocket = WebsocketConfig::try_from(&cfg).unwrap();
+        assert_eq!(websocket.overflow_policy, OverflowPolicy::DropOldest);
+        assert!(websocket.validate().is_err());
+        cfg.overflow_policy = "block".into();
+        assert!(WebsocketConfig::try_from(&cfg).is_err());
+        cfg.overflow_policy = "coalesce".into();
+        cfg.send_queue_depth = 16;
+
+        cfg.event_bus = "Postgres".into();
+        cfg.event_channel = "board-events".into();
+        let websocket = WebsocketConfig::try_from(&cfg).unwrap();
+        assert_eq!(websocket.event_bus, EventBusBackend::Postgres);
+        assert!(websocket.validate().is_err());
+
+        // postgres 事件总线依赖 PostgreSQL 数据库
+        let mut config = AppConfig::for_development();
+        config.websocket = WebsocketConfig {
+            event_bus: EventBusBackend::Postgres,
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidWebsocketConfig(_))
+        ));
+        config.database.url = "postgresql://localhost/elizabeth".into();
+        assert!(config.validate().is_ok());
+
+        cfg.event_bus = "redis".into();
+        assert!(WebsocketConfig::try_from(&cfg).is_err());
+    }
+
     #[test]
     fn test_config_builder() {
         let config = AppConfig::for_development();
@@ -60,6 +60,20 @@ pub mod auth {
 pub mod storage {
     /// 默认存储根目录
     pub const DEFAULT_STORAGE_ROOT: &str = "storage/rooms";
+
+    /// 缩略图宽度上限（像素）
+    pub const MAX_THUMBNAIL_WIDTH: u32 = 4096;
+}
+
+pub mod websocket {
+    /// 每个房间默认保留的事件数
+    pub const DEFAULT_EVENT_LOG_CAPACITY: u32 = 200;
+    /// 每个连接默认的发送队列长度
+    pub const DEFAULT_SEND_QUEUE_DEPTH: u32 = 256;
+    /// 同一连接转发“正在输入”事件的最小间隔（毫秒）
+    pub const TYPING_EVENT_INTERVAL_MS: u64 = 1000;
+    /// 在线列表中显示名称的最大字符数
+    pub const MAX_DISPLAY_NAME_CHARS: usize = 64;
 }
 
 pub mod upload {
@@ -69,6 +83,9 @@ pub mod upload {
     /// 最大文件块大小
     pub const MAX_CHUNK_SIZE: usize = 1024 * 1024; // 1MB
 
+    /// 移除图片元数据时完整读入内存的大小上限，超过时保留原文件
+    pub const MAX_METADATA_STRIP_SIZE: u64 = 64 * 1024 * 1024;
+
     /// 默认 multipart 请求体上限（字节）
     pub const MAX_MULTIPART_BODY_SIZE: usize = 100 * 1024 * 1024; // 100MB
 
@@ -81,6 +81,14 @@ pub enum AppError {
     /// 不支持的媒体类型错误
     #[error("Unsupported media type: {media_type}")]
     UnsupportedMediaType { media_type: String },
+
+    /// 前置条件不满足错误
+    #[error("Precondition failed: {message}")]
+    PreconditionFailed { message: String },
+
+    /// 校验和不匹配错误
+    #[error("Checksum mismatch: {message}")]
+    ChecksumMismatch { message: String },
 }
 
 impl AppError {
@@ -105,6 +113,11 @@ impl AppError {
             AppError::Timeout { .. } => StatusCode::REQUEST_TIMEOUT,
             AppError::PayloadTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
             AppError::UnsupportedMediaType { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AppError::PreconditionFailed { .. } => StatusCode::PRECONDITION_FAILED,
+            // tus 协议约定的 460 Checksum Mismatch
+            AppError::ChecksumMismatch { .. } => {
+                StatusCode::from_u16(460).unwrap_or(StatusCode::BAD_REQUEST)
+            }
         }
     }
 
@@ -129,6 +142,8 @@ impl AppError {
             AppError::Timeout { .. } => "TIMEOUT",
             AppError::PayloadTooLarge { .. } => "PAYLOAD_TOO_LARGE",
             AppError::UnsupportedMediaType { .. } => "UNSUPPORTED_MEDIA_TYPE",
+            AppError::PreconditionFailed { .. } => "PRECONDITION_FAILED",
+            AppError::ChecksumMismatch { .. } => "CHECKSUM_MISMATCH",
         }
     }
 
@@ -235,6 +250,20 @@ impl AppError {
             media_type: media_type.into(),
         }
     }
+
+    /// 创建前置条件不满足错误
+    pub fn precondition_failed(message: impl Into<String>) -> Self {
+        AppError::PreconditionFailed {
+            message: message.into(),
+        }
+    }
+
+    /// 创建校验和不匹配错误
+    pub fn checksum_mismatch(message: impl Into<String>) -> Self {
+        AppError::ChecksumMismatch {
+            message: message.into(),
+        }
+    }
 }
 
 /// 实现 IntoResponse trait，用于 Axum 响应
@@ -1,13 +1,27 @@
+use std::path::{Path as StdPath, PathBuf};
 use std::sync::Arc;
 
+use anyhow::Context;
 use axum::Json;
-use axum::extract::{Query, State};
-use axum::http::HeaderMap;
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::Response;
+use futures::StreamExt;
 use serde::Deserialize;
-use utoipa::ToSchema;
+use tokio::io::AsyncWriteExt;
+use utoipa::{IntoParams, ToSchema};
 
+use crate::dto::rooms::RoomView;
 use crate::dto::{FullRoomGcStatusView, RunRoomGcResponse};
 use crate::errors::{AppError, AppResult};
+use crate::handlers::content::archive::piped_body;
+use crate::models::Room;
+use crate::repository::{IRoomRepository, RoomRepository};
+use crate::services::{
+    MAX_MANIFEST_SIZE, RoomImportOptions, RoomTransferError, RoomTransferService,
+};
 use crate::state::AppState;
 
 type HandlerResult<T> = Result<Json<T>, AppError>;
@@ -16,12 +30,20 @@ const DEFAULT_ADMIN_LIMIT: u32 = 100;
 const MAX_ADMIN_LIMIT: u32 = 1000;
 const ADMIN_TOKEN_ENV: &str = "ELIZABETH_ADMIN_TOKEN";
 const ADMIN_TOKEN_HEADER: &str = "X-Elizabeth-Admin-Token";
+/// 导出管道缓冲区大小
+const EXPORT_PIPE_CAPACITY: usize = 256 * 1024;
 
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct AdminLimitQuery {
     pub limit: Option<u32>,
 }
 
+#[derive(Debug, Default, Deserialize, IntoParams, ToSchema)]
+pub struct RoomImportQuery {
+    /// 导入后使用的房间名称，省略时沿用归档中的名称
+    pub slug: Option<String>,
+}
+
 fn ensure_admin(headers: &HeaderMap) -> AppResult<()> {
     let expected = std::env::var(ADMIN_TOKEN_ENV).unwrap_or_default();
     if expected.trim().is_empty() {
@@ -129,3 +151,149 @@ pub async fn run_room_gc(
         cleaned: u32::try_from(report.expired_rooms + report.full_rooms).unwrap_or(u32::MAX),
     }))
 }
+
+/// 将房间导出为可迁移的归档（manifest.json + 文件）
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/rooms/{name}/export",
+    params(
+        ("name" = String, Path, description = "房间名称")
+    ),
+    responses(
+        (status = 200, description = "房间归档", content_type = "application/zip"),
+        (status = 403, description = "未授权"),
+        (status = 404, description = "房间不存在")
+    ),
+    tag = "admin"
+)]
+pub async fn export_room(
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    ensure_admin(&headers)?;
+
+    let room = RoomRepository::new(app_state.db_pool.clone())
+        .find_by_name(&name)
+        .await
+        .map_err(|e| AppError::internal(format!("Database error: {e}")))?
+        .ok_or_else(|| AppError::room_not_found(&name))?;
+
+    let body = export_body(app_state.services.room_transfer.clone(), room);
+    let mut response = Response::new(body);
+    *response.status_mut() = StatusCode::OK;
+    let disposition = HeaderValue::from_str(&format!(
+        "attachment; filename=\"{}.room.zip\"",
+        sanitize_filename::sanitize(&name)
+    ))
+    .map_err(|_| AppError::internal("Failed to build response headers"))?;
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/zip"));
+    headers.insert(CONTENT_DISPOSITION, disposition);
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    Ok(response)
+}
+
+/// 导出归档的响应体；导出失败时响应流以错误结束，不会被当作完整的归档
+pub(crate) fn export_body(transfer: Arc<RoomTransferService>, room: Room) -> Body {
+    piped_body(EXPORT_PIPE_CAPACITY, move |writer| async move {
+        transfer
+            .export(&room, writer)
+            .await
+            .with_context(|| format!("failed to export room {}", room.slug))
+    })
+}
+
+/// 从导出的归档导入房间；容量与权限按本部署的房间默认配置重新校验
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/rooms/import",
+    params(RoomImportQuery),
+    request_body(content = Vec<u8>, content_type = "application/zip", description = "房间归档"),
+    responses(
+        (status = 200, description = "导入成功", body = RoomView),
+        (status = 400, description = "归档无效或不符合房间策略"),
+        (status = 403, description = "未授权"),
+        (status = 409, description = "房间已存在"),
+        (status = 413, description = "内容超出房间容量")
+    ),
+    tag = "admin"
+)]
+pub async fn import_room(
+    headers: HeaderMap,
+    Query(query): Query<RoomImportQuery>,
+    State(app_state): State<Arc<AppState>>,
+    body: Body,
+) -> HandlerResult<RoomView> {
+    ensure_admin(&headers)?;
+
+    let limit =
+        app_state.room_creation_defaults().max_content_size.max(0) as u64 + MAX_MANIFEST_SIZE;
+    let archive_path = import_temp_path();
+    let result = async {
+        spool_body(body, &archive_path, limit).await?;
+        app_state
+            .services
+            .room_transfer
+            .import_file(&archive_path, RoomImportOptions { slug: query.slug })
+            .await
+            .map_err(map_transfer_error)
+    }
+    .await;
+    if let Err(e) = tokio::fs::remove_file(&archive_path).await
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        log::warn!("Failed to remove {}: {}", archive_path.display(), e);
+    }
+
+    Ok(Json(RoomView::from(&result?)))
+}
+
+fn import_temp_path() -> PathBuf {
+    std::env::temp_dir()
+        .join("elizabeth")
+        .join("imports")
+        .join(format!("{}.zip", uuid::Uuid::new_v4()))
+}
+
+/// 将请求体写入临时文件，ZIP 需要随机访问才能读取中央目录
+async fn spool_body(body: Body, path: &StdPath, limit: u64) -> AppResult<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| AppError::internal(format!("Failed to prepare import: {e}")))?;
+    }
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .map_err(|e| AppError::internal(format!("Failed to prepare import: {e}")))?;
+    let mut written = 0u64;
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| AppError::validation(format!("Read archive failed: {e}")))?;
+        written += chunk.len() as u64;
+        if written > limit {
+            return Err(AppError::payload_too_large(
+                "Archive exceeds the room size limit",
+            ));
+        }
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| AppError::internal(format!("Failed to write archive: {e}")))?;
+    }
+    file.flush()
+        .await
+        .map_err(|e| AppError::internal(format!("Failed to write archive: {e}")))
+}
+
+fn map_transfer_error(error: RoomTransferError) -> AppError {
+    match error {
+        RoomTransferError::InvalidArchive(_) | RoomTransferError::Policy(_) => {
+            AppError::validation(error.to_string())
+        }
+        RoomTransferError::TooLarge { .. } => AppError::payload_too_large(error.to_string()),
+        RoomTransferError::Conflict(_) => AppError::conflict(error.to_string()),
+        RoomTransferError::Internal(e) => {
+            AppError::internal(format!("Failed to import room: {e:#}"))
+        }
+    }
+}
@@ -3,16 +3,20 @@ use axum::{
     extract::{Path, Query, State},
 };
 use chrono::Utc;
+use std::collections::HashSet;
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::{
     dto::chunked_upload::{
         ChunkStatusInfo, ChunkedUploadPreparationRequest, ChunkedUploadPreparationResponse,
-        ReservedFileInfo, UploadStatusQuery, UploadStatusResponse,
+        MissingChunkRange, ReservedFileInfo, UploadStatusQuery, UploadStatusResponse,
     },
     errors::{AppError, AppResult},
-    models::room::upload_reservation::UploadStatus,
+    models::room::{
+        chunk_upload::RoomChunkUpload,
+        upload_reservation::{RoomUploadReservation, UploadStatus},
+    },
     repository::room_chunk_upload_repository::{
         IRoomChunkUploadRepository, RoomChunkUploadRepository,
     },
@@ -27,6 +31,7 @@ use super::{AuthToken, VerifiedRoomToken, verify_room_token};
 type HandlerResult<T> = AppResult<Json<T>>;
 
 pub mod complete;
+pub mod tus;
 pub mod upload;
 
 /// 预留分块上传空间
@@ -99,6 +104,7 @@ pub async fn prepare_chunked_upload(
     let file_manifest = serde_json::to_string(&payload.files)
         .map_err(|e| AppError::internal(format!("序列化文件清单失败：{}", e)))?;
 
+    let hash_algorithm = payload.hash_algorithm.unwrap_or_default();
     let reservation_repository = RoomUploadReservationRepository::new(app_state.db_pool.clone());
 
     // 计算每个文件的分块信息并构建响应
@@ -148,23 +154,33 @@ pub async fn prepare_chunked_upload(
             total_chunks = $1,
             uploaded_chunks = 0,
             chunk_size = $2,
-            upload_status = $3
-        WHERE id = $4
+            upload_status = $3,
+            hash_algorithm = $4
+        WHERE id = $5
         "#,
     )
     .bind(total_chunks)
     .bind(reservation_chunk_size)
     .bind(&status_str)
+    .bind(hash_algorithm.to_string())
     .bind(db_reservation_id)
     .execute(app_state.db_pool.as_ref())
     .await
     .map_err(|e| AppError::internal(format!("更新预留记录失败：{}", e)))?;
 
+    // 预分配合并文件，分块按偏移量直接写入
+    app_state
+        .chunk_staging()
+        .preallocate(db_reservation_id, total_reserved_size as u64)
+        .await
+        .map_err(|e| AppError::internal(format!("预分配上传文件失败：{:#}", e)))?;
+
     // 构建响应
     let response = ChunkedUploadPreparationResponse {
         reservation_id: db_reservation_id.to_string(),
         upload_token,
         expires_at,
+        hash_algorithm,
         files: reserved_files,
     };
 
@@ -270,6 +286,7 @@ pub async fn get_upload_status(
 
     // 计算已上传大小
     let uploaded_size: i64 = chunks.iter().map(|chunk| chunk.chunk_size).sum();
+    let missing_ranges = missing_chunk_ranges(&reservation, &chunks);
 
     // 构建分块详细信息
     let chunk_details: Vec<ChunkStatusInfo> = chunks
@@ -299,11 +316,13 @@ pub async fn get_upload_status(
         reservation_id: reservation.id.expect("预留 ID 不能为空").to_string(),
         upload_token: reservation.token_jti,
         upload_status,
+        hash_algorithm: reservation.hash_algorithm,
         total_chunks,
         uploaded_chunks,
         progress_percentage,
         expires_at: reservation.expires_at,
         chunk_details,
+        missing_ranges,
         reserved_size: reservation.reserved_size,
         uploaded_size,
         is_expired,
@@ -351,8 +370,12 @@ pub async fn cancel_chunked_upload(
     }
 
     // 清理临时分块文件
-    if let Err(e) = crate::chunk_temp_storage::remove_reservation_dir(reservation_id).await {
-        logrs::error!("清理临时分块文件失败：{}", e);
+    if let Err(e) = app_state
+        .chunk_staging()
+        .remove_reservation(reservation_id)
+        .await
+    {
+        logrs::error!("清理临时分块文件失败：{:#}", e);
     }
 
     // 释放预留空间
@@ -367,8 +390,54 @@ pub async fn cancel_chunked_upload(
     })))
 }
 
+/// 按分块索引合并相邻的未上传分块
+fn missing_chunk_ranges(
+    reservation: &RoomUploadReservation,
+    chunks: &[RoomChunkUpload],
+) -> Vec<MissingChunkRange> {
+    // tus 上传从当前偏移量连续追加，剩余部分即为一个区间
+    if tus::is_tus_upload(reservation) {
+        let uploaded_size: i64 = chunks.iter().map(|chunk| chunk.chunk_size).sum();
+        let remaining = reservation.reserved_size - uploaded_size;
+        if remaining <= 0 {
+            return Vec::new();
+        }
+        let next_chunk = chunks.len() as i64;
+        return vec![MissingChunkRange {
+            start_chunk: next_chunk,
+            end_chunk: next_chunk,
+            offset: uploaded_size,
+            length: remaining,
+        }];
+    }
+
+    let chunk_size = reservation.chunk_size.unwrap_or(0);
+    if chunk_size <= 0 {
+        return Vec::new();
+    }
+    let uploaded: HashSet<i64> = chunks.iter().map(|chunk| chunk.chunk_index).collect();
+    let mut ranges: Vec<MissingChunkRange> = Vec::new();
+    for index in (0..reservation.total_chunks.unwrap_or(0)).filter(|i| !uploaded.contains(i)) {
+        let offset = index * chunk_size;
+        let length = chunk_size.min(reservation.reserved_size - offset).max(0);
+        match ranges.last_mut() {
+            Some(range) if range.end_chunk + 1 == index => {
+                range.end_chunk = index;
+                range.length += length;
+            }
+            _ => ranges.push(MissingChunkRange {
+                start_chunk: index,
+                end_chunk: index,
+                offset,
+                length,
+            }),
+        }
+    }
+    ranges
+}
+
 pub(crate) fn ensure_reservation_access(
-    reservation: &crate::models::RoomUploadReservation,
+    reservation: &RoomUploadReservation,
     verified: &VerifiedRoomToken,
 ) -> Result<(), AppError> {
     if reservation.room_id != verified.claims.room_id {
@@ -1,3 +1,4 @@
+use std::io;
 use std::path::Path as StdPath;
 use std::sync::Arc;
 
@@ -6,22 +7,29 @@ use axum::{
     extract::{Path, State},
 };
 
-use super::super::{AuthToken, verify_room_token};
+use super::super::{AuthToken, content::unique_storage_key, verify_room_token};
 use super::ensure_reservation_access;
 use chrono::Utc;
+use futures::StreamExt;
 use sha2::{Digest, Sha256};
 use tokio::{
     fs,
     io::{AsyncReadExt, AsyncWriteExt},
 };
+use tokio_util::io::ReaderStream;
 
 use crate::{
+    checksum::{FileDigest, FileHasher},
+    constants::upload::MAX_METADATA_STRIP_SIZE,
     dto::chunked_upload::{FileMergeRequest, FileMergeResponse, MergedFileInfo},
     errors::{AppError, AppResult},
     models::room::{
+        Room,
         chunk_upload::RoomChunkUpload,
-        content::{ContentType, RoomContent},
-        upload_reservation::{RoomUploadReservation, UploadFileDescriptor, UploadStatus},
+        content::{IntegrityStatus, RoomContent, ScanStatus, ThumbnailStatus},
+        upload_reservation::{
+            HashAlgorithm, RoomUploadReservation, UploadFileDescriptor, UploadStatus,
+        },
     },
     repository::{
         IRoomContentRepository,
@@ -31,12 +39,17 @@ use crate::{
             IRoomUploadReservationRepository, RoomUploadReservationRepository,
         },
     },
+    services::{METADATA_STRIP_MIME_TYPES, strip_uploaded_image},
     state::AppState,
+    storage::{StorageType, sniff},
     validation::RoomNameValidator,
 };
 
 type HandlerResult<T> = AppResult<Json<T>>;
 
+/// 合并、校验与转存时的读缓冲大小
+const MERGE_BUFFER_SIZE: usize = 64 * 1024;
+
 /// 完成文件合并
 #[utoipa::path(
     post,
@@ -75,19 +88,49 @@ pub async fn complete_file_merge(
     ensure_reservation_access(&reservation, &verified)?;
 
     let chunk_repository = RoomChunkUploadRepository::new(app_state.db_pool.clone());
-    let reservation_db_id = reservation_id_or_error(&reservation)?;
-    let sorted_chunks = load_uploaded_chunks(
-        &chunk_repository,
-        reservation_db_id,
-        reservation.total_chunks,
+    let sorted_chunks = load_uploaded_chunks(&chunk_repository, &reservation).await?;
+    let merged_file = finalize_chunked_upload(
+        &app_state,
+        &room,
+        &verified.claims.jti,
+        &reservation,
+        &sorted_chunks,
+        Some(&payload.final_hash),
     )
     .await?;
-    let final_file_path = crate::chunk_temp_storage::merged_file_path(reservation_db_id);
 
-    merge_and_verify_chunks(
-        &sorted_chunks,
+    Ok(Json(FileMergeResponse {
+        reservation_id: payload.reservation_id.clone(),
+        merged_files: vec![merged_file],
+        message: "文件合并完成".to_string(),
+    }))
+}
+
+/// 合并已按序排列的分块，转存后登记为房间内容并消费预留
+///
+/// `expected_hash` 为客户端按预留选择的算法声明的整体哈希，不提供时不做比对；
+/// 登记的内容哈希始终为 SHA-256。
+pub(crate) async fn finalize_chunked_upload(
+    app_state: &AppState,
+    room: &Room,
+    owner_jti: &str,
+    reservation: &RoomUploadReservation,
+    sorted_chunks: &[RoomChunkUpload],
+    expected_hash: Option<&str>,
+) -> Result<MergedFileInfo, AppError> {
+    let room_id = reservation.room_id;
+    let reservation_db_id = reservation_id_or_error(reservation)?;
+    let reservation_repository = RoomUploadReservationRepository::new(app_state.db_pool.clone());
+    let final_file_path = app_state
+        .chunk_staging()
+        .merged_file_path(reservation_db_id);
+
+    let file_hash = merge_and_verify_chunks(
+        app_state,
+        sorted_chunks,
         &final_file_path,
-        &payload.final_hash,
+        reservation.hash_algorithm,
+        expected_hash,
         &reservation_repository,
         reservation_db_id,
     )
@@ -95,22 +138,40 @@ pub async fn complete_file_merge(
 
     let file_manifest = parse_file_manifest(&reservation.file_manifest)?;
     let file = first_manifest_file(&file_manifest)?;
-    let storage_dir = app_state.storage_root().join(room_id.to_string());
-    fs::create_dir_all(&storage_dir)
-        .await
-        .map_err(|e| AppError::internal(format!("创建存储目录失败：{}", e)))?;
-
-    let final_storage_path = unique_storage_path(&storage_dir, &file.name)?;
-    fs::rename(&final_file_path, &final_storage_path)
-        .await
-        .map_err(|e| AppError::internal(format!("移动文件失败：{}", e)))?;
+    let detected_mime = detect_merged_type(&final_file_path).await;
+    let strip_metadata = app_state.strip_image_metadata(room)
+        && detected_mime.is_some_and(|mime| METADATA_STRIP_MIME_TYPES.contains(&mime));
+    let metadata_retained = strip_metadata
+        && u64::try_from(file.size).map_or(true, |size| size > MAX_METADATA_STRIP_SIZE);
+    let (file_size, file_hash) = if metadata_retained {
+        log::warn!(
+            "Keeping metadata of {}: {} bytes exceeds the strip limit",
+            file.name,
+            file.size
+        );
+        (file.size, file_hash)
+    } else if strip_metadata {
+        strip_merged_metadata(&final_file_path, detected_mime, file.size, file_hash).await?
+    } else {
+        (file.size, file_hash)
+    };
+    let storage_key = store_merged_upload(
+        app_state,
+        room_id,
+        file,
+        file_size,
+        &final_file_path,
+        &file_hash,
+    )
+    .await?;
+    let final_storage_path = app_state.storage_path(&storage_key);
 
     reservation_repository
         .consume_reservation(
             reservation_db_id,
             room_id,
-            &verified.claims.jti,
-            file.size,
+            owner_jti,
+            file_size,
             &reservation.file_manifest,
         )
         .await
@@ -120,22 +181,26 @@ pub async fn complete_file_merge(
         .await
         .map_err(|e| AppError::internal(format!("更新上传状态失败：{}", e)))?;
 
-    cleanup_temp_dir(reservation_db_id).await;
+    cleanup_temp_dir(app_state, reservation_db_id).await;
 
     let content_repository = RoomContentRepository::new(app_state.db_pool.clone());
-    let created_content =
-        create_content_record(&content_repository, room_id, file, &final_storage_path).await?;
-
-    Ok(Json(FileMergeResponse {
-        reservation_id: payload.reservation_id.clone(),
-        merged_files: vec![MergedFileInfo {
-            file_name: file.name.clone(),
-            file_size: file.size,
-            file_hash: payload.final_hash.clone(),
-            content_id: created_content.id,
-        }],
-        message: "文件合并完成".to_string(),
-    }))
+    let content = build_room_content(
+        room_id,
+        file,
+        file_size,
+        &final_storage_path,
+        &file_hash,
+        detected_mime,
+    );
+    let created_content = create_content_record(&content_repository, app_state, content).await?;
+
+    Ok(MergedFileInfo {
+        file_name: file.name.clone(),
+        file_size,
+        file_hash,
+        content_id: created_content.id,
+        metadata_retained,
+    })
 }
 
 fn parse_reservation_id(raw: &str) -> Result<i64, AppError> {
@@ -184,22 +249,28 @@ fn validate_reservation_for_merge(
 
 async fn load_uploaded_chunks(
     repository: &RoomChunkUploadRepository,
-    reservation_id: i64,
-    total_chunks: Option<i64>,
+    reservation: &RoomUploadReservation,
 ) -> Result<Vec<RoomChunkUpload>, AppError> {
     let chunks = repository
-        .find_by_reservation_id(reservation_id)
+        .find_by_reservation_id(reservation_id_or_error(reservation)?)
         .await
         .map_err(|e| AppError::internal(format!("查询分块记录失败：{}", e)))?;
-    let total_chunks = total_chunks.unwrap_or(0);
-    validate_uploaded_chunks(&chunks, total_chunks)?;
+    validate_uploaded_chunks(
+        &chunks,
+        reservation.total_chunks.unwrap_or(0),
+        reservation.hash_algorithm,
+    )?;
 
     let mut sorted_chunks = chunks;
     sorted_chunks.sort_by_key(|chunk| chunk.chunk_index);
     Ok(sorted_chunks)
 }
 
-fn validate_uploaded_chunks(chunks: &[RoomChunkUpload], total_chunks: i64) -> Result<(), AppError> {
+fn validate_uploaded_chunks(
+    chunks: &[RoomChunkUpload],
+    total_chunks: i64,
+    hash_algorithm: HashAlgorithm,
+) -> Result<(), AppError> {
     if total_chunks == 0 {
         return Err(AppError::validation("总分块数为 0"));
     }
@@ -217,33 +288,51 @@ fn validate_uploaded_chunks(chunks: &[RoomChunkUpload], total_chunks: i64) -> Re
                 chunk.chunk_index
             )));
         }
+        if chunk.hash_algorithm != hash_algorithm {
+            return Err(AppError::validation(format!(
+                "分块{}的校验算法为 {}，与预留的 {} 不一致",
+                chunk.chunk_index, chunk.hash_algorithm, hash_algorithm
+            )));
+        }
     }
     Ok(())
 }
 
+/// 取得合并后的文件及其哈希并与客户端声明的哈希比对，返回内容的 SHA-256
+///
+/// 分块已直接写入合并文件时只需补算哈希，否则在拷贝分块的同时计算哈希，
+/// 都不会再完整读取一遍合并结果。
 async fn merge_and_verify_chunks(
+    app_state: &AppState,
     chunks: &[RoomChunkUpload],
     final_file_path: &StdPath,
-    final_hash: &str,
+    algorithm: HashAlgorithm,
+    expected_hash: Option<&str>,
     repository: &RoomUploadReservationRepository,
     reservation_id: i64,
-) -> Result<(), AppError> {
-    if let Err(e) = merge_chunks(chunks, final_file_path).await {
-        mark_upload_failed(repository, reservation_id).await;
-        return Err(AppError::internal(format!("文件合并失败：{}", e)));
-    }
-
-    match verify_file_hash(final_file_path, final_hash).await {
-        Ok(true) => Ok(()),
-        Ok(false) => {
-            cleanup_failed_merge(final_file_path, repository, reservation_id).await;
-            Err(AppError::validation("文件哈希验证失败"))
-        }
+) -> Result<String, AppError> {
+    let staging = app_state.chunk_staging();
+    let merged = if staging.assembles_in_place() {
+        staging.assembled_hash(reservation_id, algorithm).await
+    } else {
+        merge_chunks(app_state, chunks, final_file_path, algorithm).await
+    };
+    let digest = match merged {
+        Ok(digest) => digest,
         Err(e) => {
-            cleanup_failed_merge(final_file_path, repository, reservation_id).await;
-            Err(AppError::internal(format!("文件哈希验证失败：{}", e)))
+            mark_upload_failed(repository, reservation_id).await;
+            return Err(AppError::internal(format!("文件合并失败：{:#}", e)));
         }
+    };
+
+    if expected_hash.is_some_and(|expected| !expected.eq_ignore_ascii_case(&digest.checksum)) {
+        cleanup_failed_merge(final_file_path, repository, reservation_id).await;
+        return Err(AppError::validation(format!(
+            "文件哈希验证失败（{}）",
+            algorithm
+        )));
     }
+    Ok(digest.sha256)
 }
 
 async fn mark_upload_failed(repository: &RoomUploadReservationRepository, reservation_id: i64) {
@@ -274,44 +363,151 @@ fn first_manifest_file(
         .ok_or_else(|| AppError::internal("文件清单为空"))
 }
 
-fn unique_storage_path(storage_dir: &StdPath, file_name: &str) -> Result<String, AppError> {
-    let safe_file_name = sanitize_filename::sanitize(file_name);
-    let mut final_filename = safe_file_name.clone();
-    let mut counter = 1;
-    let mut final_storage_path = storage_dir.join(&final_filename);
-
-    while final_storage_path.exists() {
-        let path = StdPath::new(&safe_file_name);
-        let stem = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or(&safe_file_name);
-        let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("");
-
-        final_filename = if extension.is_empty() {
-            format!("{}({})", stem, counter)
-        } else {
-            format!("{}({}).{}", stem, counter, extension)
-        };
-        final_storage_path = storage_dir.join(&final_filename);
-        counter += 1;
+/// 保存合并后的文件并返回对象键；启用去重时写入共享 blob，已存在则只增加引用
+async fn store_merged_upload(
+    app_state: &AppState,
+    room_id: i64,
+    file: &UploadFileDescriptor,
+    file_size: i64,
+    merged_file_path: &StdPath,
+    file_hash: &str,
+) -> Result<String, AppError> {
+    let blob_store = app_state.blob_store();
+    if !blob_store.enabled() {
+        let storage_key =
+            unique_storage_key(app_state.storage().as_ref(), room_id, &file.name).await?;
+        store_merged_file(app_state, merged_file_path, &storage_key).await?;
+        return Ok(storage_key);
+    }
+
+    let lease = blob_store
+        .acquire(file_hash, file_size)
+        .await
+        .map_err(|e| AppError::internal(format!("登记去重文件失败：{:#}", e)))?;
+    if lease.needs_upload {
+        blob_store
+            .upload(
+                &lease,
+                store_merged_file(app_state, merged_file_path, &lease.key),
+            )
+            .await?;
+    }
+    Ok(lease.key)
+}
 
-        if counter > 1000 {
-            return Err(AppError::internal("Too many files with the same name"));
+/// 将合并后的临时文件移入存储后端
+///
+/// `fs` 后端下直接重命名到存储目录，暂存目录与存储不在同一文件系统时才回退为拷贝。
+async fn store_merged_file(
+    app_state: &AppState,
+    merged_file_path: &StdPath,
+    storage_key: &str,
+) -> Result<(), AppError> {
+    if app_state.config.storage.backend == StorageType::Fs
+        && rename_merged_file(app_state, merged_file_path, storage_key).await?
+    {
+        return Ok(());
+    }
+    copy_merged_file(app_state, merged_file_path, storage_key).await
+}
+
+/// 将合并文件重命名为存储目录下的对象，跨文件系统时返回 `false`
+async fn rename_merged_file(
+    app_state: &AppState,
+    merged_file_path: &StdPath,
+    storage_key: &str,
+) -> Result<bool, AppError> {
+    let target = app_state.storage_root().join(storage_key);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| AppError::internal(format!("创建存储目录失败：{}", e)))?;
+    }
+    match fs::rename(merged_file_path, &target).await {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => Ok(false),
+        Err(e) => Err(AppError::internal(format!("移动文件失败：{}", e))),
+    }
+}
+
+/// 将合并后的临时文件以流式写入存储后端，内存占用与文件大小无关
+async fn copy_merged_file(
+    app_state: &AppState,
+    merged_file_path: &StdPath,
+    storage_key: &str,
+) -> Result<(), AppError> {
+    let file = fs::File::open(merged_file_path)
+        .await
+        .map_err(|e| AppError::internal(format!("读取合并文件失败：{}", e)))?;
+    let mut writer = app_state
+        .storage()
+        .writer(storage_key)
+        .await
+        .map_err(|e| AppError::internal(format!("移动文件失败：{}", e)))?;
+
+    let mut chunks = ReaderStream::with_capacity(file, MERGE_BUFFER_SIZE);
+    while let Some(chunk) = chunks.next().await {
+        let result = match chunk {
+            Ok(chunk) => writer.write(chunk).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            let _ = writer.abort().await;
+            return Err(AppError::internal(format!("移动文件失败：{}", e)));
         }
     }
 
-    Ok(final_storage_path.to_string_lossy().into_owned())
+    writer
+        .close()
+        .await
+        .map_err(|e| AppError::internal(format!("移动文件失败：{}", e)))
+}
+
+/// 根据合并后文件的开头字节检测真实类型，读取失败时视为未知类型
+async fn detect_merged_type(merged_file_path: &StdPath) -> Option<&'static str> {
+    sniff::detect_file(merged_file_path)
+        .await
+        .unwrap_or_else(|e| {
+            logrs::warn!("检测文件类型失败：{}", e);
+            None
+        })
+}
+
+/// 移除合并文件中的图片元数据并写回，返回实际大小与哈希
+///
+/// 文件会完整读入内存，调用方需先确认大小不超过 [`MAX_METADATA_STRIP_SIZE`]。
+/// 客户端哈希已在合并时校验，这里的哈希对应最终存储的内容。
+async fn strip_merged_metadata(
+    merged_file_path: &StdPath,
+    detected_mime: Option<&'static str>,
+    file_size: i64,
+    file_hash: String,
+) -> Result<(i64, String), AppError> {
+    let data = fs::read(merged_file_path)
+        .await
+        .map_err(|e| AppError::internal(format!("读取合并文件失败：{}", e)))?;
+    let stripped = strip_uploaded_image(data, detected_mime);
+    let stripped_size = stripped.len() as i64;
+    if stripped_size == file_size {
+        return Ok((file_size, file_hash));
+    }
+
+    let stripped_hash = hex::encode(Sha256::digest(&stripped));
+    fs::write(merged_file_path, stripped)
+        .await
+        .map_err(|e| AppError::internal(format!("写入合并文件失败：{}", e)))?;
+    Ok((stripped_size, stripped_hash))
 }
 
 async fn create_content_record(
     repository: &RoomContentRepository,
-    room_id: i64,
-    file: &UploadFileDescriptor,
-    final_storage_path: &str,
+    app_state: &AppState,
+    mut content: RoomContent,
 ) -> Result<RoomContent, AppError> {
+    content.thumbnail_status = app_state.services.thumbnails.initial_status(&content);
+    content.scan_status = app_state.services.content_scan.initial_status(&content);
     repository
-        .create(&build_room_content(room_id, file, final_storage_path))
+        .create(&content)
         .await
         .map_err(|e| AppError::internal(format!("创建内容记录失败：{}", e)))
 }
@@ -319,72 +515,88 @@ async fn create_content_record(
 fn build_room_content(
     room_id: i64,
     file: &UploadFileDescriptor,
+    file_size: i64,
     final_storage_path: &str,
+    file_hash: &str,
+    detected_mime: Option<&str>,
 ) -> RoomContent {
     let now = chrono::Utc::now().naive_utc();
     RoomContent {
         id: None,
         room_id,
-        content_type: ContentType::File,
+        content_type: sniff::content_type_for(detected_mime),
         text: None,
         url: Some(file.name.clone()),
         path: Some(final_storage_path.to_string()),
         file_name: Some(file.name.clone()),
-        size: Some(file.size),
+        size: Some(file_size),
         mime_type: Some(
             file.mime
                 .clone()
-                .unwrap_or_else(|| "application/octet-stream".to_string()),
+                .unwrap_or_else(|| sniff::FALLBACK_MIME_TYPE.to_string()),
         ),
+        detected_mime_type: detected_mime.map(str::to_string),
+        hash: Some(file_hash.to_string()),
         sequence_number: 0,
+        thumbnail_status: ThumbnailStatus::None,
+        scan_status: ScanStatus::Clean,
+        integrity_status: IntegrityStatus::Ok,
         created_at: now,
         updated_at: now,
     }
 }
 
-async fn cleanup_temp_dir(reservation_id: i64) {
-    if let Err(e) = crate::chunk_temp_storage::remove_reservation_dir(reservation_id).await {
-        logrs::error!("清理临时文件失败：{}", e);
+async fn cleanup_temp_dir(app_state: &AppState, reservation_id: i64) {
+    if let Err(e) = app_state
+        .chunk_staging()
+        .remove_reservation(reservation_id)
+        .await
+    {
+        logrs::error!("清理临时文件失败：{:#}", e);
     }
 }
 
+/// 将存储后端中的分块依次拷贝到合并文件，返回合并结果的摘要
 async fn merge_chunks(
+    app_state: &AppState,
     chunks: &[RoomChunkUpload],
     output_path: &StdPath,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    algorithm: HashAlgorithm,
+) -> anyhow::Result<FileDigest> {
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
     let mut output_file = fs::File::create(output_path).await?;
+    let mut hasher = FileHasher::new(algorithm);
+    let mut buffer = vec![0; MERGE_BUFFER_SIZE];
 
     for chunk in chunks {
-        let chunk_path =
-            crate::chunk_temp_storage::chunk_path(chunk.reservation_id, chunk.chunk_index);
-        let mut chunk_file = fs::File::open(&chunk_path).await?;
-
-        let mut buffer = vec![0u8; chunk.chunk_size as usize];
-        chunk_file.read_exact(&mut buffer).await?;
-
-        output_file.write_all(&buffer).await?;
-    }
-
-    output_file.flush().await?;
-    Ok(())
-}
-
-async fn verify_file_hash(
-    file_path: &StdPath,
-    expected_hash: &str,
-) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-    let mut file = fs::File::open(file_path).await?;
-    let mut hasher = Sha256::new();
-    let mut buffer = [0; 8192];
-
-    loop {
-        let bytes_read = file.read(&mut buffer).await?;
-        if bytes_read == 0 {
-            break;
+        let chunk_file = app_state
+            .chunk_staging()
+            .open_chunk(chunk.reservation_id, chunk.chunk_index)
+            .await?;
+        let expected = chunk.chunk_size as u64;
+        let mut chunk_file = chunk_file.take(expected);
+        let mut copied = 0;
+        loop {
+            let read = chunk_file.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            output_file.write_all(&buffer[..read]).await?;
+            copied += read as u64;
+        }
+        if copied != expected {
+            anyhow::bail!(
+                "分块{}大小不符，期望 {} 字节，实际 {} 字节",
+                chunk.chunk_index,
+                expected,
+                copied
+            );
         }
-        hasher.update(&buffer[..bytes_read]);
     }
 
-    let calculated_hash = hex::encode(hasher.finalize());
-    Ok(calculated_hash == expected_hash)
+    output_file.flush().await?;
+    Ok(hasher.finalize())
 }
@@ -0,0 +1,819 @@
+//! tus 1.0 可续传上传协议
+//!
+//! 创建上传即创建一条分块上传预留，每个 PATCH 请求体作为一个分块追加，
+//! 偏移量为已暂存分块大小之和。字节全部到达后沿用分块合并流程生成房间内容。
+//! 支持 creation、expiration、termination 与 checksum 扩展。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use chrono::{DateTime, Duration, Utc};
+use futures::StreamExt;
+use sha1::{Digest as _, Sha1};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use super::super::{
+    AuthToken, VerifiedRoomToken, content::conditional::http_date, verify_room_token,
+};
+use super::complete::finalize_chunked_upload;
+use super::ensure_reservation_access;
+use crate::{
+    checksum::Checksum,
+    db::DbPool,
+    errors::{AppError, AppResult},
+    models::room::{
+        chunk_upload::{ChunkStatus, RoomChunkUpload},
+        upload_reservation::{
+            HashAlgorithm, RoomUploadReservation, UploadFileDescriptor, UploadStatus,
+        },
+    },
+    repository::{
+        room_chunk_upload_repository::{IRoomChunkUploadRepository, RoomChunkUploadRepository},
+        room_upload_reservation_repository::{
+            IRoomUploadReservationRepository, RoomUploadReservationRepository,
+        },
+    },
+    state::AppState,
+    validation::RoomNameValidator,
+};
+
+pub const TUS_VERSION: &str = "1.0.0";
+const TUS_EXTENSIONS: &str = "creation,expiration,termination,checksum";
+const TUS_CHECKSUM_ALGORITHMS: &str = "sha1,sha256";
+const OFFSET_CONTENT_TYPE: &str = "application/offset+octet-stream";
+/// Upload-Metadata 未提供文件名时使用的名称
+const DEFAULT_FILE_NAME: &str = "upload";
+/// tus 上传的预留令牌前缀，用于与普通分块上传区分
+const TUS_TOKEN_PREFIX: &str = "tus-";
+/// PATCH 写入租约的有效期，写入过程中过半即续期
+const WRITE_LEASE_DURATION: Duration = Duration::minutes(5);
+
+pub const TUS_RESUMABLE: HeaderName = HeaderName::from_static("tus-resumable");
+const TUS_VERSION_HEADER: HeaderName = HeaderName::from_static("tus-version");
+const TUS_EXTENSION: HeaderName = HeaderName::from_static("tus-extension");
+const TUS_CHECKSUM_ALGORITHM: HeaderName = HeaderName::from_static("tus-checksum-algorithm");
+const UPLOAD_LENGTH: HeaderName = HeaderName::from_static("upload-length");
+const UPLOAD_OFFSET: HeaderName = HeaderName::from_static("upload-offset");
+const UPLOAD_METADATA: HeaderName = HeaderName::from_static("upload-metadata");
+const UPLOAD_DEFER_LENGTH: HeaderName = HeaderName::from_static("upload-defer-length");
+const UPLOAD_EXPIRES: HeaderName = HeaderName::from_static("upload-expires");
+const UPLOAD_CHECKSUM: HeaderName = HeaderName::from_static("upload-checksum");
+
+/// 拒绝协议版本不符的请求；OPTIONS 用于协商版本，不做要求
+pub async fn require_tus_resumable(request: Request, next: Next) -> Response {
+    let supported = request.method() == Method::OPTIONS
+        || request
+            .headers()
+            .get(TUS_RESUMABLE)
+            .is_some_and(|version| version == TUS_VERSION);
+    if supported {
+        return next.run(request).await;
+    }
+    (
+        [(TUS_VERSION_HEADER, TUS_VERSION)],
+        AppError::precondition_failed(format!("仅支持 tus {TUS_VERSION}")),
+    )
+        .into_response()
+}
+
+/// 查询服务端支持的 tus 版本与扩展
+#[utoipa::path(
+    options,
+    path = "/api/v1/rooms/{name}/uploads/tus",
+    params(
+        ("name" = String, Path, description = "房间名称")
+    ),
+    responses(
+        (status = 204, description = "返回 Tus-Version、Tus-Extension 与 Tus-Checksum-Algorithm")
+    ),
+    tag = "chunked-upload"
+)]
+pub async fn tus_options() -> Response {
+    (
+        StatusCode::NO_CONTENT,
+        [
+            (TUS_VERSION_HEADER, TUS_VERSION),
+            (TUS_EXTENSION, TUS_EXTENSIONS),
+            (TUS_CHECKSUM_ALGORITHM, TUS_CHECKSUM_ALGORITHMS),
+        ],
+    )
+        .into_response()
+}
+
+/// 创建 tus 上传（creation 扩展）
+#[utoipa::path(
+    post,
+    path = "/api/v1/rooms/{name}/uploads/tus",
+    params(
+        ("name" = String, Path, description = "房间名称"),
+        ("Upload-Length" = i64, Header, description = "文件总字节数"),
+        ("Upload-Metadata" = Option<String>, Header, description = "base64 编码的元数据，支持 filename 与 filetype")
+    ),
+    responses(
+        (status = 201, description = "上传已创建，Location 指向上传地址"),
+        (status = 400, description = "请求头错误"),
+        (status = 403, description = "权限不足"),
+        (status = 412, description = "tus 版本不受支持"),
+        (status = 413, description = "房间空间不足")
+    ),
+    tag = "chunked-upload"
+)]
+pub async fn create_tus_upload(
+    Path(room_name): Path<String>,
+    AuthToken(token): AuthToken,
+    State(app_state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    RoomNameValidator::validate_identifier(&room_name)?;
+
+    if headers.contains_key(UPLOAD_DEFER_LENGTH) {
+        return Err(AppError::validation("不支持延迟声明上传长度"));
+    }
+    let length = header_i64(&headers, &UPLOAD_LENGTH)?
+        .ok_or_else(|| AppError::validation("缺少 Upload-Length"))?;
+    if length <= 0 {
+        return Err(AppError::validation("Upload-Length 必须大于 0"));
+    }
+    let mut metadata = parse_upload_metadata(&headers)?;
+
+    let verified = verify_room_token(app_state.clone(), &room_name, &token).await?;
+    if !verified.claims.as_permission().can_edit() {
+        return Err(AppError::permission_denied("token 无编辑权限"));
+    }
+    if !verified.room.can_add_content(length) {
+        return Err(AppError::payload_too_large("房间空间不足"));
+    }
+
+    let name = metadata
+        .remove("filename")
+        .or_else(|| metadata.remove("name"))
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_FILE_NAME.to_string());
+    let mime = metadata
+        .remove("filetype")
+        .or_else(|| metadata.remove("type"))
+        .filter(|mime| !mime.is_empty());
+    let file_manifest = serde_json::to_string(&[UploadFileDescriptor {
+        name,
+        size: length,
+        mime,
+        chunk_size: None,
+        file_hash: None,
+    }])
+    .map_err(|e| AppError::internal(format!("序列化文件清单失败：{}", e)))?;
+
+    let upload_token = format!("{TUS_TOKEN_PREFIX}{}", Uuid::new_v4());
+    let reservation_repository = RoomUploadReservationRepository::new(app_state.db_pool.clone());
+    let (reservation, _) = reservation_repository
+        .reserve_upload(
+            &verified.room,
+            &upload_token,
+            &verified.claims.jti,
+            &file_manifest,
+            length,
+            app_state.upload_reservation_ttl(),
+        )
+        .await
+        .map_err(|e| AppError::internal(format!("创建预留记录失败：{}", e)))?;
+    let reservation_id = reservation_id_or_error(&reservation)?;
+
+    // 分块数由 PATCH 次数决定，先按一个分块登记，每次追加后再调整
+    set_chunk_plan(&app_state, reservation_id, 1, Some(length)).await?;
+    app_state
+        .chunk_staging()
+        .preallocate(reservation_id, length as u64)
+        .await
+        .map_err(|e| AppError::internal(format!("预分配上传文件失败：{:#}", e)))?;
+
+    let location = format!("/api/v1/rooms/{room_name}/uploads/tus/{reservation_id}");
+    Ok((
+        StatusCode::CREATED,
+        [
+            (header::LOCATION, location),
+            (UPLOAD_EXPIRES, http_date(reservation.expires_at.and_utc())),
+        ],
+    )
+        .into_response())
+}
+
+/// 查询 tus 上传的当前偏移量
+#[utoipa::path(
+    head,
+    path = "/api/v1/rooms/{name}/uploads/tus/{upload_id}",
+    params(
+        ("name" = String, Path, description = "房间名称"),
+        ("upload_id" = i64, Path, description = "上传 ID（预留记录 ID）")
+    ),
+    responses(
+        (status = 200, description = "返回 Upload-Offset 与 Upload-Length"),
+        (status = 403, description = "权限不足"),
+        (status = 404, description = "上传不存在或已过期")
+    ),
+    tag = "chunked-upload"
+)]
+pub async fn get_tus_offset(
+    Path((room_name, upload_id)): Path<(String, i64)>,
+    AuthToken(token): AuthToken,
+    State(app_state): State<Arc<AppState>>,
+) -> AppResult<Response> {
+    let (_, reservation) = load_tus_upload(&app_state, &room_name, &token, upload_id).await?;
+    let chunks = RoomChunkUploadRepository::new(app_state.db_pool.clone())
+        .find_by_reservation_id(upload_id)
+        .await
+        .map_err(|e| AppError::internal(format!("查询分块记录失败：{}", e)))?;
+    let offset = uploaded_size(&chunks);
+    // 完成后预留大小可能因移除元数据而变化，此时以已接收字节数为准
+    let length = if reservation.consumed_at.is_some() {
+        offset
+    } else {
+        reservation.reserved_size
+    };
+
+    Ok((
+        StatusCode::OK,
+        [
+            (UPLOAD_OFFSET, offset.to_string()),
+            (UPLOAD_LENGTH, length.to_string()),
+            (UPLOAD_EXPIRES, http_date(reservation.expires_at.and_utc())),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+    )
+        .into_response())
+}
+
+/// 从当前偏移量追加数据，字节全部到达后生成房间内容
+#[utoipa::path(
+    patch,
+    path = "/api/v1/rooms/{name}/uploads/tus/{upload_id}",
+    params(
+        ("name" = String, Path, description = "房间名称"),
+        ("upload_id" = i64, Path, description = "上传 ID（预留记录 ID）"),
+        ("Upload-Offset" = i64, Header, description = "本次数据的起始偏移量"),
+        ("Upload-Checksum" = Option<String>, Header, description = "本次数据的校验和，如 `sha256 <base64>`")
+    ),
+    request_body(content = Vec<u8>, content_type = "application/offset+octet-stream"),
+    responses(
+        (status = 204, description = "数据已接收，Upload-Offset 为新的偏移量"),
+        (status = 400, description = "请求头错误或校验算法不受支持"),
+        (status = 403, description = "权限不足"),
+        (status = 404, description = "上传不存在或已过期"),
+        (status = 409, description = "偏移量不匹配、上传已完成或正被其他请求写入"),
+        (status = 413, description = "数据超过声明的长度"),
+        (status = 415, description = "Content-Type 错误"),
+        (status = 460, description = "校验和不匹配")
+    ),
+    tag = "chunked-upload"
+)]
+pub async fn patch_tus_upload(
+    Path((room_name, upload_id)): Path<(String, i64)>,
+    AuthToken(token): AuthToken,
+    State(app_state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Body,
+) -> AppResult<Response> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    if content_type != OFFSET_CONTENT_TYPE {
+        return Err(AppError::unsupported_media_type(content_type));
+    }
+    let client_offset = header_i64(&headers, &UPLOAD_OFFSET)?
+        .ok_or_else(|| AppError::validation("缺少 Upload-Offset"))?;
+    let checksum = parse_upload_checksum(&headers)?;
+
+    let (verified, _) = load_tus_upload(&app_state, &room_name, &token, upload_id).await?;
+    // 租约记录在数据库中并持有到合并结束，任一实例上同一上传的并发 PATCH 直接返回 409
+    let mut lease = WriteLease::acquire(&app_state, upload_id).await?;
+    let result = append_tus_upload(
+        &app_state,
+        &verified,
+        upload_id,
+        client_offset,
+        checksum,
+        body,
+        &mut lease,
+    )
+    .await;
+    lease.release().await;
+    result
+}
+
+/// 在持有写入租约的前提下追加数据，字节全部到达后合并
+async fn append_tus_upload(
+    app_state: &Arc<AppState>,
+    verified: &VerifiedRoomToken,
+    upload_id: i64,
+    client_offset: i64,
+    checksum: Option<UploadChecksum>,
+    body: Body,
+    lease: &mut WriteLease,
+) -> AppResult<Response> {
+    // 取得租约之前上一个请求可能已完成上传，重新读取预留
+    let reservation = RoomUploadReservationRepository::new(app_state.db_pool.clone())
+        .find_by_reservation_id(upload_id)
+        .await
+        .map_err(|e| AppError::internal(format!("查询预留记录失败：{}", e)))?
+        .ok_or_else(|| AppError::not_found("上传不存在"))?;
+    if reservation.consumed_at.is_some() {
+        return Err(AppError::conflict("上传已完成"));
+    }
+    let chunk_repository = RoomChunkUploadRepository::new(app_state.db_pool.clone());
+    let chunks = chunk_repository
+        .find_by_reservation_id(upload_id)
+        .await
+        .map_err(|e| AppError::internal(format!("查询分块记录失败：{}", e)))?;
+    let offset = uploaded_size(&chunks);
+    if client_offset != offset {
+        return Err(AppError::conflict(format!(
+            "Upload-Offset 不匹配，当前偏移量为 {offset}"
+        )));
+    }
+
+    let chunk_index = chunks
+        .iter()
+        .map(|chunk| chunk.chunk_index + 1)
+        .max()
+        .unwrap_or(0);
+    let received = receive_chunk(
+        app_state,
+        &reservation,
+        upload_id,
+        chunk_index,
+        offset,
+        body,
+        checksum,
+        lease,
+    )
+    .await?;
+
+    let mut new_offset = offset;
+    if let Some(chunk) = received {
+        new_offset += chunk.size;
+        lease.keep_alive().await?;
+        persist_chunk(&chunk_repository, upload_id, chunk_index, chunk).await?;
+        let uploaded_chunks = chunk_repository
+            .count_by_reservation_id(upload_id)
+            .await
+            .map_err(|e| AppError::internal(format!("统计已上传分块数失败：{}", e)))?;
+        let pending_chunks = i64::from(new_offset < reservation.reserved_size);
+        set_chunk_plan(app_state, upload_id, uploaded_chunks + pending_chunks, None).await?;
+        RoomUploadReservationRepository::new(app_state.db_pool.clone())
+            .update_uploaded_chunks(upload_id, uploaded_chunks)
+            .await
+            .map_err(|e| AppError::internal(format!("更新上传进度失败：{}", e)))?;
+    }
+
+    let mut response = (
+        StatusCode::NO_CONTENT,
+        [(UPLOAD_OFFSET, new_offset.to_string())],
+    )
+        .into_response();
+    if new_offset == reservation.reserved_size {
+        let mut chunks = chunk_repository
+            .find_by_reservation_id(upload_id)
+            .await
+            .map_err(|e| AppError::internal(format!("查询分块记录失败：{}", e)))?;
+        chunks.sort_by_key(|chunk| chunk.chunk_index);
+        lease.keep_alive().await?;
+        let merged = finalize_chunked_upload(
+            app_state,
+            &verified.room,
+            &verified.claims.jti,
+            &reservation,
+            &chunks,
+            None,
+        )
+        .await?;
+        if let Some(content_id) = merged.content_id
+            && let Ok(location) = HeaderValue::from_str(&format!("/api/v1/contents/{content_id}"))
+        {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_LOCATION, location);
+        }
+    }
+    Ok(response)
+}
+
+/// 终止 tus 上传，清理已接收的数据并释放预留空间（termination 扩展）
+#[utoipa::path(
+    delete,
+    path = "/api/v1/rooms/{name}/uploads/tus/{upload_id}",
+    params(
+        ("name" = String, Path, description = "房间名称"),
+        ("upload_id" = i64, Path, description = "上传 ID（预留记录 ID）")
+    ),
+    responses(
+        (status = 204, description = "上传已终止"),
+        (status = 403, description = "权限不足"),
+        (status = 404, description = "上传不存在或已过期"),
+        (status = 409, description = "上传已完成，无法终止")
+    ),
+    tag = "chunked-upload"
+)]
+pub async fn terminate_tus_upload(
+    Path((room_name, upload_id)): Path<(String, i64)>,
+    AuthToken(token): AuthToken,
+    State(app_state): State<Arc<AppState>>,
+) -> AppResult<Response> {
+    let (_, reservation) = load_tus_upload(&app_state, &room_name, &token, upload_id).await?;
+    if reservation.consumed_at.is_some() {
+        return Err(AppError::conflict("上传已完成，无法终止"));
+    }
+
+    if let Err(e) = app_state
+        .chunk_staging()
+        .remove_reservation(upload_id)
+        .await
+    {
+        logrs::error!("清理临时分块文件失败：{:#}", e);
+    }
+    RoomUploadReservationRepository::new(app_state.db_pool.clone())
+        .release_if_pending(upload_id)
+        .await
+        .map_err(|e| AppError::internal(format!("释放预留空间失败：{}", e)))?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// 预留记录是否由 tus 端点创建
+pub(crate) fn is_tus_upload(reservation: &RoomUploadReservation) -> bool {
+    reservation.token_jti.starts_with(TUS_TOKEN_PREFIX)
+}
+
+/// 加载并校验 tus 上传对应的预留记录
+async fn load_tus_upload(
+    app_state: &Arc<AppState>,
+    room_name: &str,
+    token: &str,
+    upload_id: i64,
+) -> Result<(VerifiedRoomToken, RoomUploadReservation), AppError> {
+    RoomNameValidator::validate_identifier(room_name)?;
+    let verified = verify_room_token(app_state.clone(), room_name, token).await?;
+
+    let reservation = RoomUploadReservationRepository::new(app_state.db_pool.clone())
+        .find_by_reservation_id(upload_id)
+        .await
+        .map_err(|e| AppError::internal(format!("查询预留记录失败：{}", e)))?
+        .filter(is_tus_upload)
+        .ok_or_else(|| AppError::not_found("上传不存在"))?;
+    if reservation.expires_at <= Utc::now().naive_utc()
+        || reservation.upload_status == Some(UploadStatus::Failed)
+    {
+        return Err(AppError::not_found("上传已过期或已失败"));
+    }
+    ensure_reservation_access(&reservation, &verified)?;
+    Ok((verified, reservation))
+}
+
+/// 一次 PATCH 接收到的数据
+struct ReceivedChunk {
+    size: i64,
+    hash: String,
+    hash_algorithm: HashAlgorithm,
+}
+
+/// 将请求体写入暂存区，返回写入的分块；请求体为空时返回 `None`
+///
+/// 未携带校验和的请求中途断开时保留已接收的部分，客户端可从新的偏移量继续。
+#[allow(clippy::too_many_arguments)]
+async fn receive_chunk(
+    app_state: &AppState,
+    reservation: &RoomUploadReservation,
+    reservation_id: i64,
+    chunk_index: i64,
+    offset: i64,
+    body: Body,
+    checksum: Option<UploadChecksum>,
+    lease: &mut WriteLease,
+) -> Result<Option<ReceivedChunk>, AppError> {
+    let hash_algorithm = reservation.hash_algorithm;
+    let remaining = reservation.reserved_size - offset;
+    let mut writer = app_state
+        .chunk_staging()
+        .chunk_writer(reservation_id, chunk_index, offset as u64, hash_algorithm)
+        .await
+        .map_err(|e| AppError::internal(format!("写入分块数据失败：{:#}", e)))?;
+    let mut hasher = Checksum::new(hash_algorithm);
+    let mut checksum_hasher = checksum.as_ref().map(|checksum| checksum.hasher());
+    let mut size: i64 = 0;
+    let mut interrupted = None;
+
+    let mut stream = body.into_data_stream();
+    while let Some(frame) = stream.next().await {
+        let data = match frame {
+            Ok(data) => data,
+            Err(e) => {
+                interrupted = Some(e.to_string());
+                break;
+            }
+        };
+        size += data.len() as i64;
+        if size > remaining {
+            writer.abort().await;
+            return Err(AppError::payload_too_large("数据超过声明的上传长度"));
+        }
+        hasher.update(&data);
+        if let Some(checksum_hasher) = checksum_hasher.as_mut() {
+            checksum_hasher.update(&data);
+        }
+        if let Err(e) = lease.keep_alive().await {
+            writer.abort().await;
+            return Err(e);
+        }
+        if let Err(e) = writer.write(data).await {
+            writer.abort().await;
+            return Err(AppError::internal(format!("写入分块数据失败：{:#}", e)));
+        }
+    }
+
+    if let Some(reason) = &interrupted
+        && (checksum.is_some() || size == 0)
+    {
+        writer.abort().await;
+        return Err(AppError::validation(format!("读取请求体失败：{reason}")));
+    }
+    if size == 0 {
+        writer.abort().await;
+        return Ok(None);
+    }
+    if let (Some(checksum), Some(checksum_hasher)) = (&checksum, checksum_hasher)
+        && checksum_hasher.finalize() != checksum.digest
+    {
+        writer.abort().await;
+        return Err(AppError::checksum_mismatch("Upload-Checksum 校验失败"));
+    }
+    writer
+        .finish()
+        .await
+        .map_err(|e| AppError::internal(format!("写入分块数据失败：{:#}", e)))?;
+    if let Some(reason) = interrupted {
+        logrs::warn!("tus 上传 {reservation_id} 中断，保留已接收的 {size} 字节：{reason}");
+    }
+
+    Ok(Some(ReceivedChunk {
+        size,
+        hash: hasher.finalize(),
+        hash_algorithm,
+    }))
+}
+
+/// 数据库中的上传写入租约，保证多个实例之间同一上传同时只有一个 PATCH 在写入
+///
+/// 持有者在写入过程中按需续期；请求异常终止未能释放时，租约在到期后自动失效。
+struct WriteLease {
+    db_pool: Arc<DbPool>,
+    reservation_id: i64,
+    token: String,
+    renew_after: DateTime<Utc>,
+    released: bool,
+}
+
+impl WriteLease {
+    async fn acquire(app_state: &AppState, reservation_id: i64) -> Result<Self, AppError> {
+        let token = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let acquired = RoomUploadReservationRepository::new(app_state.db_pool.clone())
+            .acquire_write_lease(
+                reservation_id,
+                &token,
+                (now + WRITE_LEASE_DURATION).naive_utc(),
+            )
+            .await
+            .map_err(|e| AppError::internal(format!("占用上传写入租约失败：{}", e)))?;
+        if !acquired {
+            return Err(AppError::conflict("上传正在被其他请求写入"));
+        }
+        Ok(Self {
+            db_pool: app_state.db_pool.clone(),
+            reservation_id,
+            token,
+            renew_after: now + WRITE_LEASE_DURATION / 2,
+            released: false,
+        })
+    }
+
+    /// 租约过半时续期，租约已被其他请求取代时返回 409
+    async fn keep_alive(&mut self) -> Result<(), AppError> {
+        let now = Utc::now();
+        if now < self.renew_after {
+            return Ok(());
+        }
+        let renewed = RoomUploadReservationRepository::new(self.db_pool.clone())
+            .renew_write_lease(
+                self.reservation_id,
+                &self.token,
+                (now + WRITE_LEASE_DURATION).naive_utc(),
+            )
+            .await
+            .map_err(|e| AppError::internal(format!("续期上传写入租约失败：{}", e)))?;
+        if !renewed {
+            return Err(AppError::conflict("上传写入租约已失效"));
+        }
+        self.renew_after = now + WRITE_LEASE_DURATION / 2;
+        Ok(())
+    }
+
+    async fn release(mut self) {
+        self.released = true;
+        release_write_lease(self.db_pool.clone(), self.reservation_id, &self.token).await;
+    }
+}
+
+/// 请求被取消而未能显式释放时，在后台释放租约
+impl Drop for WriteLease {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+        let db_pool = self.db_pool.clone();
+        let reservation_id = self.reservation_id;
+        let token = std::mem::take(&mut self.token);
+        tokio::spawn(async move {
+            release_write_lease(db_pool, reservation_id, &token).await;
+        });
+    }
+}
+
+async fn release_write_lease(db_pool: Arc<DbPool>, reservation_id: i64, token: &str) {
+    if let Err(e) = RoomUploadReservationRepository::new(db_pool)
+        .release_write_lease(reservation_id, token)
+        .await
+    {
+        logrs::warn!("释放上传 {} 的写入租约失败：{}", reservation_id, e);
+    }
+}
+
+async fn persist_chunk(
+    repository: &RoomChunkUploadRepository,
+    reservation_id: i64,
+    chunk_index: i64,
+    chunk: ReceivedChunk,
+) -> Result<(), AppError> {
+    let now = Utc::now().naive_utc();
+    repository
+        .create(&RoomChunkUpload {
+            id: None,
+            reservation_id,
+            chunk_index,
+            chunk_size: chunk.size,
+            chunk_hash: Some(chunk.hash),
+            upload_status: ChunkStatus::Uploaded,
+            hash_algorithm: chunk.hash_algorithm,
+            created_at: now,
+            updated_at: now,
+        })
+        .await
+        .map_err(|e| AppError::internal(format!("创建分块记录失败：{}", e)))?;
+    Ok(())
+}
+
+/// 更新预留的分块计划；`chunk_size` 仅在创建时设置为文件总长度
+async fn set_chunk_plan(
+    app_state: &AppState,
+    reservation_id: i64,
+    total_chunks: i64,
+    chunk_size: Option<i64>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE room_upload_reservations
+        SET chunked_upload = true,
+            total_chunks = $1,
+            chunk_size = COALESCE($2, chunk_size),
+            uploaded_chunks = COALESCE(uploaded_chunks, 0)
+        WHERE id = $3
+        "#,
+    )
+    .bind(total_chunks)
+    .bind(chunk_size)
+    .bind(reservation_id)
+    .execute(app_state.db_pool.as_ref())
+    .await
+    .map_err(|e| AppError::internal(format!("更新预留记录失败：{}", e)))?;
+    Ok(())
+}
+
+fn uploaded_size(chunks: &[RoomChunkUpload]) -> i64 {
+    chunks.iter().map(|chunk| chunk.chunk_size).sum()
+}
+
+fn reservation_id_or_error(reservation: &RoomUploadReservation) -> Result<i64, AppError> {
+    reservation
+        .id
+        .ok_or_else(|| AppError::internal("预留 ID 不能为空"))
+}
+
+fn header_i64(headers: &HeaderMap, name: &HeaderName) -> Result<Option<i64>, AppError> {
+    headers
+        .get(name)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|value| value.trim().parse::<i64>().ok())
+                .filter(|value| *value >= 0)
+                .ok_or_else(|| AppError::validation(format!("{name} 必须是非负整数")))
+        })
+        .transpose()
+}
+
+/// 解析 `Upload-Metadata`：逗号分隔的 `key base64(value)`，值可省略
+fn parse_upload_metadata(headers: &HeaderMap) -> Result<HashMap<String, String>, AppError> {
+    let Some(raw) = headers.get(UPLOAD_METADATA) else {
+        return Ok(HashMap::new());
+    };
+    let raw = raw
+        .to_str()
+        .map_err(|_| AppError::validation("Upload-Metadata 格式错误"))?;
+
+    let mut metadata = HashMap::new();
+    for pair in raw
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+    {
+        let (key, value) = pair.split_once(' ').unwrap_or((pair, ""));
+        let value = STANDARD
+            .decode(value.trim())
+            .ok()
+            .and_then(|value| String::from_utf8(value).ok())
+            .ok_or_else(|| AppError::validation(format!("Upload-Metadata 中 {key} 的值无效")))?;
+        metadata.insert(key.to_string(), value);
+    }
+    Ok(metadata)
+}
+
+/// `Upload-Checksum` 声明的算法与摘要
+struct UploadChecksum {
+    algorithm: ChecksumAlgorithm,
+    digest: Vec<u8>,
+}
+
+#[derive(Clone, Copy)]
+enum ChecksumAlgorithm {
+    Sha1,
+    Sha256,
+}
+
+enum ChecksumHasher {
+    Sha1(Sha1),
+    Sha256(Sha256),
+}
+
+impl UploadChecksum {
+    fn hasher(&self) -> ChecksumHasher {
+        match self.algorithm {
+            ChecksumAlgorithm::Sha1 => ChecksumHasher::Sha1(Sha1::new()),
+            ChecksumAlgorithm::Sha256 => ChecksumHasher::Sha256(Sha256::new()),
+        }
+    }
+}
+
+impl ChecksumHasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha1(hasher) => hasher.update(data),
+            Self::Sha256(hasher) => hasher.update(data),
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Sha1(hasher) => hasher.finalize().to_vec(),
+            Self::Sha256(hasher) => hasher.finalize().to_vec(),
+        }
+    }
+}
+
+fn parse_upload_checksum(headers: &HeaderMap) -> Result<Option<UploadChecksum>, AppError> {
+    let Some(raw) = headers.get(UPLOAD_CHECKSUM) else {
+        return Ok(None);
+    };
+    let (algorithm, digest) = raw
+        .to_str()
+        .ok()
+        .and_then(|raw| raw.trim().split_once(' '))
+        .ok_or_else(|| AppError::validation("Upload-Checksum 格式错误"))?;
+    let algorithm = match algorithm {
+        "sha1" => ChecksumAlgorithm::Sha1,
+        "sha256" => ChecksumAlgorithm::Sha256,
+        other => {
+            return Err(AppError::validation(format!("不支持的校验算法：{other}")));
+        }
+    };
+    let digest = STANDARD
+        .decode(digest.trim())
+        .map_err(|_| AppError::validation("Upload-Checksum 不是有效的 base64"))?;
+    Ok(Some(UploadChecksum { algorithm, digest }))
+}
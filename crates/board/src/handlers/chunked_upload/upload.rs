@@ -5,15 +5,14 @@ use axum::{
     extract::{Multipart, Path, State, multipart::Field},
 };
 use chrono::Utc;
-use sha2::{Digest, Sha256};
-use tokio::{fs, io::AsyncWriteExt};
 
 use crate::{
+    checksum::Checksum,
     dto::chunked_upload::{ChunkUploadRequest, ChunkUploadResponse},
     errors::{AppError, AppResult},
     models::room::{
         chunk_upload::{ChunkStatus, RoomChunkUpload},
-        upload_reservation::RoomUploadReservation,
+        upload_reservation::{HashAlgorithm, RoomUploadReservation},
     },
     repository::{
         room_chunk_upload_repository::{IRoomChunkUploadRepository, RoomChunkUploadRepository},
@@ -72,18 +71,29 @@ pub async fn upload_chunk(
 
     validate_chunk_reservation(&reservation)?;
     ensure_reservation_access(&reservation, &verified)?;
+    let offset = chunk_offset(&reservation, &parsed)?;
 
     let chunk_repository = RoomChunkUploadRepository::new(app_state.db_pool.clone());
     ensure_chunk_slot_empty(&chunk_repository, reservation_id, parsed.chunk_index).await?;
 
-    let calculated_hash = validate_chunk_hash(&parsed)?;
-    write_chunk_file(reservation_id, parsed.chunk_index, &parsed.chunk_data).await?;
+    let algorithm = reservation.hash_algorithm;
+    let calculated_hash = validate_chunk_hash(&parsed, algorithm)?;
+    write_chunk_file(
+        &app_state,
+        reservation_id,
+        parsed.chunk_index,
+        offset,
+        algorithm,
+        &parsed.chunk_data,
+    )
+    .await?;
     persist_chunk_record(
         &chunk_repository,
         &reservation_repository,
         reservation_id,
         &parsed,
         &calculated_hash,
+        algorithm,
     )
     .await?;
 
@@ -196,6 +206,37 @@ fn validate_chunk_reservation(reservation: &RoomUploadReservation) -> Result<(),
     Ok(())
 }
 
+/// 按预留的分块大小计算分块在文件中的位置，并校验索引与大小
+///
+/// 分块写入固定位置，因此可以乱序、并行上传；除最后一块外大小都必须等于预留的分块大小。
+fn chunk_offset(
+    reservation: &RoomUploadReservation,
+    parsed: &ParsedChunkUpload,
+) -> Result<u64, AppError> {
+    let total_chunks = reservation.total_chunks.unwrap_or(0);
+    let planned_size = reservation.chunk_size.unwrap_or(0);
+    let chunk_index = i64::from(parsed.chunk_index);
+    if chunk_index < 0 || chunk_index >= total_chunks || planned_size <= 0 {
+        return Err(AppError::validation(format!(
+            "分块索引超出范围，总分块：{}",
+            total_chunks
+        )));
+    }
+
+    let offset = chunk_index * planned_size;
+    let expected_size = planned_size.min(reservation.reserved_size - offset);
+    if expected_size <= 0 {
+        return Err(AppError::validation("分块超出预留大小"));
+    }
+    if i64::from(parsed.chunk_size) != expected_size {
+        return Err(AppError::validation(format!(
+            "分块{}大小应为 {} 字节",
+            chunk_index, expected_size
+        )));
+    }
+    Ok(offset as u64)
+}
+
 async fn ensure_chunk_slot_empty(
     chunk_repository: &RoomChunkUploadRepository,
     reservation_id: i64,
@@ -213,40 +254,44 @@ async fn ensure_chunk_slot_empty(
     Ok(())
 }
 
-fn validate_chunk_hash(parsed: &ParsedChunkUpload) -> Result<String, AppError> {
-    let mut hasher = Sha256::new();
-    hasher.update(&parsed.chunk_data);
-    let calculated_hash = hex::encode(hasher.finalize());
+/// 按预留选择的算法计算分块哈希，并与客户端提供的哈希比对（不区分大小写）
+fn validate_chunk_hash(
+    parsed: &ParsedChunkUpload,
+    algorithm: HashAlgorithm,
+) -> Result<String, AppError> {
+    let calculated_hash = Checksum::digest(algorithm, &parsed.chunk_data);
 
     if let Some(ref provided_hash) = parsed.chunk_hash
-        && provided_hash != &calculated_hash
+        && !provided_hash.eq_ignore_ascii_case(&calculated_hash)
     {
-        return Err(AppError::validation("分块哈希验证失败"));
+        return Err(AppError::validation(format!(
+            "分块哈希验证失败（{}）",
+            algorithm
+        )));
     }
 
     Ok(calculated_hash)
 }
 
 async fn write_chunk_file(
+    app_state: &AppState,
     reservation_id: i64,
     chunk_index: i32,
+    offset: u64,
+    algorithm: HashAlgorithm,
     chunk_data: &[u8],
 ) -> Result<(), AppError> {
-    let temp_dir = crate::chunk_temp_storage::reservation_dir(reservation_id);
-    fs::create_dir_all(&temp_dir)
-        .await
-        .map_err(|e| AppError::internal(format!("创建临时目录失败：{}", e)))?;
-
-    let chunk_file_path = crate::chunk_temp_storage::chunk_path(reservation_id, chunk_index.into());
-    let mut file = fs::File::create(&chunk_file_path)
+    app_state
+        .chunk_staging()
+        .write_chunk(
+            reservation_id,
+            chunk_index.into(),
+            offset,
+            algorithm,
+            chunk_data,
+        )
         .await
-        .map_err(|e| AppError::internal(format!("创建分块文件失败：{}", e)))?;
-
-    file.write_all(chunk_data)
-        .await
-        .map_err(|e| AppError::internal(format!("写入分块数据失败：{}", e)))?;
-
-    Ok(())
+        .map_err(|e| AppError::internal(format!("写入分块数据失败：{:#}", e)))
 }
 
 async fn persist_chunk_record(
@@ -255,6 +300,7 @@ async fn persist_chunk_record(
     reservation_id: i64,
     parsed: &ParsedChunkUpload,
     calculated_hash: &str,
+    hash_algorithm: HashAlgorithm,
 ) -> Result<(), AppError> {
     let chunk_record = RoomChunkUpload {
         id: None,
@@ -263,6 +309,7 @@ async fn persist_chunk_record(
         chunk_size: parsed.chunk_size.into(),
         chunk_hash: Some(calculated_hash.to_string()),
         upload_status: ChunkStatus::Uploaded,
+        hash_algorithm,
         created_at: Utc::now().naive_utc(),
         updated_at: Utc::now().naive_utc(),
     };
@@ -1,17 +1,25 @@
+pub mod archive;
+pub(crate) mod conditional;
 pub mod delete;
+pub(crate) mod disposition;
 pub mod download;
 pub mod message;
+pub(crate) mod range;
 pub(crate) mod shared;
+pub mod thumbnail;
 pub mod update;
 pub mod upload;
 pub mod url;
 
+pub use archive::download_room_archive;
 pub use delete::delete_contents;
 pub use download::download_content_global;
 pub use message::{create_message, list_messages};
+pub use thumbnail::download_thumbnail;
 pub use update::update_content;
 pub use url::create_url_content;
 
 pub(crate) use shared::{
-    ContentPermission, HandlerResult, ensure_permission, ensure_room_storage, room_id_or_error,
+    ContentPermission, HandlerResult, ensure_permission, ensure_scan_clean, map_read_error,
+    room_id_or_error, unique_storage_key,
 };
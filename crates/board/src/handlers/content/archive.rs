@@ -0,0 +1,343 @@
+//! 房间内容 ZIP 打包下载
+//!
+//! 归档在后台任务中边读存储边写入 `tokio::io::duplex` 管道，响应体直接读取管道另一端，
+//! 内存占用与房间大小无关。响应头发出后出错只能以错误结束响应流，由 hyper 中断传输，
+//! 客户端不会把不完整的归档当作成功下载。
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_zip::base::write::ZipFileWriter;
+use async_zip::{Compression, ZipDateTime, ZipEntryBuilder};
+use axum::body::Body;
+use axum::extract::{Path as AxumPath, Query, State};
+use axum::http::header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::Response;
+use futures::{AsyncWriteExt, StreamExt, future, stream};
+use serde::Deserialize;
+use tokio::io::{AsyncWrite, DuplexStream};
+use tokio_util::io::ReaderStream;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::errors::AppError;
+use crate::handlers::{AuthToken, verify_room_token};
+use crate::models::content::{ContentType, RoomContent};
+use crate::repository::{IRoomContentRepository, RoomContentRepository};
+use crate::state::AppState;
+use crate::storage::StorageError;
+use crate::validation::RoomNameValidator;
+
+use super::conditional::PRIVATE_CACHE_CONTROL;
+use super::{ContentPermission, ensure_permission, room_id_or_error};
+
+/// 管道缓冲区大小，写端领先读端超过该值时暂停读取存储
+const ARCHIVE_PIPE_CAPACITY: usize = 256 * 1024;
+const MESSAGES_ENTRY: &str = "messages.md";
+const LINKS_ENTRY: &str = "links.txt";
+
+#[derive(Debug, Default, Deserialize, IntoParams, ToSchema)]
+pub struct ArchiveQuery {
+    /// 逗号分隔的内容 id，省略时打包整个房间
+    pub ids: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/rooms/{name}/contents/archive",
+    params(
+        ("name" = String, Path, description = "房间名称"),
+        ("token" = String, Query, description = "有效的房间 token"),
+        ArchiveQuery
+    ),
+    responses(
+        (status = 200, description = "ZIP 归档（文件、messages.md 与 links.txt）", content_type = "application/zip"),
+        (status = 400, description = "内容 id 无效"),
+        (status = 401, description = "token 无效"),
+        (status = 403, description = "无查看权限"),
+        (status = 404, description = "房间或内容不存在")
+    ),
+    tag = "content"
+)]
+pub async fn download_room_archive(
+    AxumPath(name): AxumPath<String>,
+    AuthToken(token): AuthToken,
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<ArchiveQuery>,
+) -> Result<Response, AppError> {
+    RoomNameValidator::validate_identifier(&name)?;
+    let selected = query.ids.as_deref().map(parse_ids).transpose()?;
+
+    let verified = verify_room_token(app_state.clone(), &name, &token).await?;
+    ensure_permission(
+        &verified.claims,
+        verified.room.permission.can_view(),
+        ContentPermission::View,
+    )?;
+
+    let room_id = room_id_or_error(&verified.claims)?;
+    let repository = RoomContentRepository::new(app_state.db_pool.clone());
+    let mut contents = repository
+        .list_by_room(room_id)
+        .await
+        .map_err(|e| AppError::internal(format!("Query failed: {e}")))?;
+    if let Some(selected) = &selected {
+        contents.retain(|content| content.id.is_some_and(|id| selected.contains(&id)));
+        if contents.is_empty() {
+            return Err(AppError::not_found("Contents not found"));
+        }
+    }
+
+    let state = app_state.clone();
+    let room_name = name.clone();
+    let body = piped_body(ARCHIVE_PIPE_CAPACITY, move |writer| async move {
+        write_archive(writer, &state, &room_name, &contents)
+            .await
+            .with_context(|| format!("failed to stream archive for room {room_name}"))
+    });
+
+    archive_response(body, &name)
+}
+
+/// 在后台任务中向管道写入响应体，响应流读取管道另一端
+///
+/// 写入失败时管道正常关闭，因此在读完管道后检查任务结果，失败则以 `io::Error` 结束响应流，
+/// 让 hyper 中断传输而不是发送正常结尾。
+pub(crate) fn piped_body<F, Fut>(capacity: usize, write: F) -> Body
+where
+    F: FnOnce(DuplexStream) -> Fut,
+    Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    let (reader, writer) = tokio::io::duplex(capacity);
+    let task = tokio::spawn(write(writer));
+    let outcome = stream::once(async move {
+        let error = match task.await {
+            Ok(Ok(())) => return None,
+            Ok(Err(e)) => e,
+            Err(e) => anyhow::Error::from(e),
+        };
+        log::warn!("Aborting streamed response: {:#}", error);
+        Some(Err(io::Error::other(format!("{error:#}"))))
+    })
+    .filter_map(future::ready);
+    Body::from_stream(ReaderStream::new(reader).chain(outcome))
+}
+
+fn archive_response(body: Body, room_name: &str) -> Result<Response, AppError> {
+    let mut response = Response::new(body);
+    *response.status_mut() = StatusCode::OK;
+    let disposition = HeaderValue::from_str(&format!(
+        "attachment; filename=\"{}.zip\"",
+        sanitize_filename::sanitize(room_name)
+    ))
+    .map_err(|_| AppError::internal("Failed to build response headers"))?;
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/zip"));
+    headers.insert(CONTENT_DISPOSITION, disposition);
+    headers.insert(
+        CACHE_CONTROL,
+        HeaderValue::from_static(PRIVATE_CACHE_CONTROL),
+    );
+    Ok(response)
+}
+
+fn parse_ids(raw: &str) -> Result<HashSet<i64>, AppError> {
+    let ids = raw
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse::<i64>()
+                .map_err(|_| AppError::validation(format!("Invalid content id: {id}")))
+        })
+        .collect::<Result<HashSet<_>, _>>()?;
+    if ids.is_empty() {
+        return Err(AppError::validation("No content id provided"));
+    }
+    Ok(ids)
+}
+
+/// 写入归档：文件按原名存储（不压缩），文本与链接分别汇总为 `messages.md` 与 `links.txt`
+///
+/// 存储中已缺失或未通过病毒扫描的文件会被跳过。
+pub(crate) async fn write_archive<W>(
+    writer: W,
+    app_state: &AppState,
+    room_name: &str,
+    contents: &[RoomContent],
+) -> anyhow::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut zip = ZipFileWriter::with_tokio(writer);
+    let mut names = EntryNames::default();
+    names.reserve(MESSAGES_ENTRY);
+    names.reserve(LINKS_ENTRY);
+
+    for content in contents {
+        if !matches!(content.content_type, ContentType::File | ContentType::Image)
+            || !content.is_scan_clean()
+        {
+            continue;
+        }
+        let Some(path) = content.path.as_deref() else {
+            continue;
+        };
+        let key = app_state.storage_key(path);
+        let mut stream = match app_state.storage().reader(&key).await {
+            Ok(stream) => stream,
+            Err(StorageError::NotFound(_)) => {
+                log::warn!("Skipping missing file {} in room archive", key);
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let entry_name = names.unique(&display_name(content, path));
+        let entry = ZipEntryBuilder::new(entry_name.into(), Compression::Stored)
+            .last_modification_date(zip_date(content))
+            .unix_permissions(0o644);
+        let mut entry_writer = zip.write_entry_stream(entry).await?;
+        while let Some(chunk) = stream.next().await {
+            entry_writer.write_all(&chunk?).await?;
+        }
+        entry_writer.close().await?;
+    }
+
+    if let Some(messages) = render_messages(room_name, contents) {
+        let entry = ZipEntryBuilder::new(MESSAGES_ENTRY.into(), Compression::Deflate);
+        zip.write_entry_whole(entry, messages.as_bytes()).await?;
+    }
+    if let Some(links) = render_links(contents) {
+        let entry = ZipEntryBuilder::new(LINKS_ENTRY.into(), Compression::Deflate);
+        zip.write_entry_whole(entry, links.as_bytes()).await?;
+    }
+
+    let mut inner = zip.close().await?;
+    inner.close().await?;
+    Ok(())
+}
+
+fn display_name(content: &RoomContent, path: &str) -> String {
+    content
+        .file_name
+        .clone()
+        .or_else(|| {
+            Path::new(path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| format!("content-{}", content.id.unwrap_or_default()))
+}
+
+fn zip_date(content: &RoomContent) -> ZipDateTime {
+    ZipDateTime::from_chrono(&content.updated_at.and_utc())
+}
+
+/// 文本消息按时间顺序汇总为 Markdown
+fn render_messages(room_name: &str, contents: &[RoomContent]) -> Option<String> {
+    let mut messages = contents
+        .iter()
+        .filter(|content| content.content_type == ContentType::Text)
+        .filter_map(|content| content.text.as_deref().map(|text| (content, text)))
+        .peekable();
+    messages.peek()?;
+
+    let mut output = format!("# {room_name}\n");
+    for (content, text) in messages {
+        output.push_str(&format!(
+            "\n## {}\n\n{}\n",
+            content.created_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            text.trim_end()
+        ));
+    }
+    Some(output)
+}
+
+/// 每行一个链接，有名称时以制表符分隔：`名称\tURL`
+fn render_links(contents: &[RoomContent]) -> Option<String> {
+    let lines: Vec<String> = contents
+        .iter()
+        .filter(|content| content.content_type == ContentType::Url)
+        .filter_map(|content| {
+            let url = content.url.as_deref()?;
+            Some(match content.file_name.as_deref() {
+                Some(name) if !name.is_empty() => format!("{name}\t{url}"),
+                _ => url.to_string(),
+            })
+        })
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+    Some(lines.join("\n") + "\n")
+}
+
+/// 归档内的条目名去重，同名文件依次追加 `(1)`、`(2)` … 后缀
+#[derive(Default)]
+struct EntryNames {
+    used: HashSet<String>,
+}
+
+impl EntryNames {
+    fn reserve(&mut self, name: &str) {
+        self.used.insert(name.to_string());
+    }
+
+    fn unique(&mut self, name: &str) -> String {
+        let safe = sanitize_filename::sanitize(name);
+        let safe = if safe.is_empty() {
+            "file".to_string()
+        } else {
+            safe
+        };
+        if self.used.insert(safe.clone()) {
+            return safe;
+        }
+
+        let path = Path::new(&safe);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or(&safe);
+        let extension = path.extension().and_then(|s| s.to_str());
+        let mut counter = 1;
+        loop {
+            let candidate = match extension {
+                Some(extension) => format!("{stem}({counter}).{extension}"),
+                None => format!("{stem}({counter})"),
+            };
+            if self.used.insert(candidate.clone()) {
+                return candidate;
+            }
+            counter += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_names_are_unique_and_sanitized() {
+        let mut names = EntryNames::default();
+        names.reserve(MESSAGES_ENTRY);
+
+        assert_eq!(names.unique("report.pdf"), "report.pdf");
+        assert_eq!(names.unique("report.pdf"), "report(1).pdf");
+        assert_eq!(names.unique("report.pdf"), "report(2).pdf");
+        assert_eq!(names.unique("messages.md"), "messages(1).md");
+        assert_eq!(names.unique("../etc/passwd"), "..etcpasswd");
+        assert_eq!(names.unique("README"), "README");
+        assert_eq!(names.unique("README"), "README(1)");
+    }
+
+    #[test]
+    fn parse_ids_rejects_garbage() {
+        assert_eq!(parse_ids("1, 2,,3").unwrap(), HashSet::from([1, 2, 3]));
+        assert!(parse_ids("1,abc").is_err());
+        assert!(parse_ids(" , ").is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! 条件请求（ETag / Last-Modified / 304）
+//!
+//! 内容接口都需要 token，响应只允许浏览器私有缓存，且每次使用前须重新验证。
+
+use axum::body::Body;
+use axum::http::header::{
+    CACHE_CONTROL, ETAG, HeaderName, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::Response;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::models::content::RoomContent;
+
+/// token 保护资源的缓存策略：仅浏览器私有缓存，使用前须携带验证器重新验证
+pub(crate) const PRIVATE_CACHE_CONTROL: &str = "private, no-cache";
+
+/// 单个内容的强 ETag，由内容 id、更新时间与大小组成
+pub(crate) fn content_etag(content: &RoomContent) -> String {
+    format!(
+        "\"{:x}-{:x}-{:x}\"",
+        content.id.unwrap_or_default(),
+        content.updated_at.and_utc().timestamp_millis(),
+        content.size.unwrap_or_default()
+    )
+}
+
+/// 响应体的弱 ETag（SHA-256 前 16 字节），用于列表等序列化结果
+pub(crate) fn body_etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    format!("W/\"{}\"", hex::encode(&digest[..16]))
+}
+
+/// 按 RFC 9110 判断条件 GET 是否可返回 304
+///
+/// 存在 `If-None-Match` 时忽略 `If-Modified-Since`。
+pub(crate) fn is_not_modified(
+    headers: &HeaderMap,
+    etag: &str,
+    last_modified: Option<DateTime<Utc>>,
+) -> bool {
+    if let Some(if_none_match) = header_str(headers, &IF_NONE_MATCH) {
+        return if_none_match
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || weak_eq(tag, etag));
+    }
+
+    match (header_str(headers, &IF_MODIFIED_SINCE), last_modified) {
+        (Some(since), Some(modified)) => DateTime::parse_from_rfc2822(since)
+            .is_ok_and(|since| modified.timestamp() <= since.timestamp()),
+        _ => false,
+    }
+}
+
+/// 构造带验证器的 304 响应
+pub(crate) fn not_modified(etag: &str, last_modified: Option<DateTime<Utc>>) -> Response {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = StatusCode::NOT_MODIFIED;
+    insert_validators(response.headers_mut(), etag, last_modified);
+    response
+}
+
+/// 写入 `ETag`、`Last-Modified` 与 `Cache-Control`
+pub(crate) fn insert_validators(
+    headers: &mut HeaderMap,
+    etag: &str,
+    last_modified: Option<DateTime<Utc>>,
+) {
+    if let Ok(value) = HeaderValue::from_str(etag) {
+        headers.insert(ETAG, value);
+    }
+    if let Some(modified) = last_modified
+        && let Ok(value) = HeaderValue::from_str(&http_date(modified))
+    {
+        headers.insert(LAST_MODIFIED, value);
+    }
+    headers.insert(
+        CACHE_CONTROL,
+        HeaderValue::from_static(PRIVATE_CACHE_CONTROL),
+    );
+}
+
+/// RFC 9110 IMF-fixdate，例如 `Wed, 21 Oct 2015 07:28:00 GMT`
+pub(crate) fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+pub(crate) fn header_str<'a>(headers: &'a HeaderMap, name: &HeaderName) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// 弱比较：忽略 `W/` 前缀
+fn weak_eq(left: &str, right: &str) -> bool {
+    left.trim_start_matches("W/") == right.trim_start_matches("W/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(HeaderName, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(name.clone(), HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn if_none_match_uses_weak_comparison() {
+        let etag = "\"abc\"";
+        assert!(is_not_modified(
+            &headers(&[(IF_NONE_MATCH, "W/\"abc\"")]),
+            etag,
+            None
+        ));
+        assert!(is_not_modified(
+            &headers(&[(IF_NONE_MATCH, "\"x\", \"abc\"")]),
+            etag,
+            None
+        ));
+        assert!(is_not_modified(
+            &headers(&[(IF_NONE_MATCH, "*")]),
+            etag,
+            None
+        ));
+        assert!(!is_not_modified(
+            &headers(&[(IF_NONE_MATCH, "\"def\"")]),
+            etag,
+            None
+        ));
+    }
+
+    #[test]
+    fn if_modified_since_is_ignored_when_if_none_match_present() {
+        let modified = DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT")
+            .unwrap()
+            .with_timezone(&Utc);
+        let fresh = headers(&[(IF_MODIFIED_SINCE, "Wed, 21 Oct 2015 07:28:00 GMT")]);
+        let stale = headers(&[(IF_MODIFIED_SINCE, "Wed, 21 Oct 2015 07:27:59 GMT")]);
+        let both = headers(&[
+            (IF_NONE_MATCH, "\"other\""),
+            (IF_MODIFIED_SINCE, "Wed, 21 Oct 2015 07:28:00 GMT"),
+        ]);
+
+        assert!(is_not_modified(&fresh, "\"abc\"", Some(modified)));
+        assert!(!is_not_modified(&stale, "\"abc\"", Some(modified)));
+        assert!(!is_not_modified(&both, "\"abc\"", Some(modified)));
+        assert!(!is_not_modified(&fresh, "\"abc\"", None));
+    }
+}
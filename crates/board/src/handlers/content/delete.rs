@@ -7,7 +7,7 @@ use axum::extract::{Path as AxumPath, State};
 use crate::dto::content::{DeleteContentRequest, DeleteContentResponse};
 use crate::errors::AppError;
 use crate::handlers::{AuthToken, verify_room_token};
-use crate::models::content::RoomContent;
+use crate::models::content::{RoomContent, ThumbnailStatus};
 use crate::repository::{
     IRoomContentRepository, IRoomRepository, RoomContentRepository, RoomRepository,
 };
@@ -63,13 +63,22 @@ pub async fn delete_contents(
         return Err(AppError::not_found("Contents not found"));
     }
 
-    let freed_size = remove_content_files(&contents).await;
     let ids: Vec<i64> = contents.iter().filter_map(|content| content.id).collect();
-
-    repository
-        .delete_by_ids(room_id, &ids)
+    // 先删除记录并释放引用，提交后再删除文件；只处理本次实际删除的行
+    let (deleted, released) = repository
+        .delete_releasing(room_id, &ids, app_state.storage_root())
         .await
         .map_err(|e| AppError::internal(format!("Delete failed: {e}")))?;
+    let deleted_ids: HashSet<i64> = deleted.iter().copied().collect();
+    let contents: Vec<RoomContent> = contents
+        .into_iter()
+        .filter(|content| content.id.is_some_and(|id| deleted_ids.contains(&id)))
+        .collect();
+
+    if let Err(e) = app_state.blob_store().delete_released(released).await {
+        log::warn!("Failed to remove stored files of deleted contents: {:#}", e);
+    }
+    let freed_size = remove_thumbnails(&app_state, &contents).await;
 
     if freed_size > 0 {
         verified.room.current_size = (verified.room.current_size - freed_size).max(0);
@@ -83,7 +92,7 @@ pub async fn delete_contents(
     broadcast_content_deleted(app_state.clone(), name, contents);
 
     Ok(Json(DeleteContentResponse {
-        deleted: ids,
+        deleted,
         freed_size,
         current_size: verified.room.current_size,
     }))
@@ -102,11 +111,22 @@ fn collect_target_contents(contents: Vec<RoomContent>, target_ids: &[i64]) -> Ve
         .collect()
 }
 
-async fn remove_content_files(contents: &[RoomContent]) -> i64 {
+async fn remove_thumbnails(app_state: &AppState, contents: &[RoomContent]) -> i64 {
     let mut freed_size = 0;
     for content in contents {
-        if let Some(path) = &content.path {
-            tokio::fs::remove_file(path).await.ok();
+        if content.thumbnail_status != ThumbnailStatus::None
+            && let Some(content_id) = content.id
+            && let Err(e) = app_state
+                .services
+                .thumbnails
+                .remove(content.room_id, content_id)
+                .await
+        {
+            log::warn!(
+                "Failed to remove thumbnails of content {}: {:#}",
+                content_id,
+                e
+            );
         }
         freed_size += content.size.unwrap_or(0);
     }
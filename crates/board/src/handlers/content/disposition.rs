@@ -0,0 +1,163 @@
+//! `Content-Disposition` 构造与内联预览白名单
+//!
+//! 仅图片、PDF、纯文本与音视频允许以 `inline` 下发，并附带沙箱 CSP；
+//! HTML、SVG 等活动类型始终作为附件下载。文件名同时写入 ASCII 回退值与
+//! RFC 5987 编码的 `filename*`，保证中文文件名在各浏览器中正确显示。
+
+use axum::http::HeaderValue;
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::storage::sniff;
+
+/// 内联预览响应使用的 CSP：禁止脚本、插件与外部资源，并将文档置于沙箱
+pub(crate) const INLINE_CONTENT_SECURITY_POLICY: &str =
+    "default-src 'none'; img-src 'self'; media-src 'self'; style-src 'unsafe-inline'; sandbox";
+
+/// 允许内联预览的图片类型（不含 SVG）
+const INLINE_IMAGE_TYPES: &[&str] = &[
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "image/avif",
+    "image/bmp",
+];
+
+/// 下载方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Disposition {
+    /// 触发浏览器下载
+    #[default]
+    Attachment,
+    /// 在浏览器内直接预览，仅对白名单类型生效
+    Inline,
+}
+
+impl Disposition {
+    /// 根据实际下发的类型决定最终方式，不在白名单内的类型回退为附件
+    pub(crate) fn resolve(self, served_mime: &str) -> Self {
+        match self {
+            Self::Inline if is_inline_safe(served_mime) => Self::Inline,
+            _ => Self::Attachment,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Attachment => "attachment",
+            Self::Inline => "inline",
+        }
+    }
+}
+
+/// 是否允许以 `inline` 方式下发
+pub(crate) fn is_inline_safe(mime: &str) -> bool {
+    if sniff::is_active(mime) {
+        return false;
+    }
+    let essence = sniff::essence(mime);
+    INLINE_IMAGE_TYPES.contains(&essence.as_str())
+        || essence == "application/pdf"
+        || essence == "text/plain"
+        || essence.starts_with("audio/")
+        || essence.starts_with("video/")
+}
+
+/// 构造 `Content-Disposition` 头
+pub(crate) fn content_disposition(disposition: Disposition, file_name: &str) -> HeaderValue {
+    let value = format!(
+        "{}; filename=\"{}\"; filename*=UTF-8''{}",
+        disposition.as_str(),
+        ascii_fallback(file_name),
+        encode_rfc5987(file_name)
+    );
+    // 两种文件名都只含可见 ASCII 字符，构造不会失败
+    HeaderValue::from_str(&value).unwrap_or_else(|_| HeaderValue::from_static("attachment"))
+}
+
+/// 供不支持 `filename*` 的客户端使用：非 ASCII 与引号、反斜杠替换为下划线
+fn ascii_fallback(file_name: &str) -> String {
+    file_name
+        .chars()
+        .map(|ch| match ch {
+            '"' | '\\' => '_',
+            ch if ch.is_ascii_graphic() || ch == ' ' => ch,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// RFC 5987 `value-chars`：`attr-char` 原样保留，其余字节按 UTF-8 百分号编码
+fn encode_rfc5987(file_name: &str) -> String {
+    let mut encoded = String::with_capacity(file_name.len());
+    for byte in file_name.bytes() {
+        match byte {
+            b'a'..=b'z'
+            | b'A'..=b'Z'
+            | b'0'..=b'9'
+            | b'!'
+            | b'#'
+            | b'$'
+            | b'&'
+            | b'+'
+            | b'-'
+            | b'.'
+            | b'^'
+            | b'_'
+            | b'`'
+            | b'|'
+            | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_non_ascii_file_names() {
+        let header = content_disposition(Disposition::Attachment, "季度 报告\".pdf");
+        assert_eq!(
+            header.to_str().unwrap(),
+            "attachment; filename=\"__ ___.pdf\"; \
+             filename*=UTF-8''%E5%AD%A3%E5%BA%A6%20%E6%8A%A5%E5%91%8A%22.pdf"
+        );
+    }
+
+    #[test]
+    fn inline_only_for_safe_types() {
+        assert_eq!(
+            Disposition::Inline.resolve("image/png"),
+            Disposition::Inline
+        );
+        assert_eq!(
+            Disposition::Inline.resolve("text/plain; charset=utf-8"),
+            Disposition::Inline
+        );
+        assert_eq!(
+            Disposition::Inline.resolve("video/mp4"),
+            Disposition::Inline
+        );
+        assert_eq!(
+            Disposition::Inline.resolve("image/svg+xml"),
+            Disposition::Attachment
+        );
+        assert_eq!(
+            Disposition::Inline.resolve("text/html"),
+            Disposition::Attachment
+        );
+        assert_eq!(
+            Disposition::Inline.resolve("application/octet-stream"),
+            Disposition::Attachment
+        );
+        assert_eq!(
+            Disposition::Attachment.resolve("image/png"),
+            Disposition::Attachment
+        );
+    }
+}
@@ -2,34 +2,64 @@ use std::path::Path;
 use std::sync::Arc;
 
 use axum::body::Body;
-use axum::extract::{Path as AxumPath, State};
-use axum::http::HeaderValue;
-use axum::http::header::{CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_TYPE};
+use axum::extract::{Path as AxumPath, Query, State};
+use axum::http::header::{
+    ACCEPT_RANGES, CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_SECURITY_POLICY,
+    CONTENT_TYPE, IF_RANGE, RANGE, X_CONTENT_TYPE_OPTIONS,
+};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
 use axum::response::Response;
-use tokio::fs;
-use tokio_util::io::ReaderStream;
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use serde::Deserialize;
+use utoipa::IntoParams;
 
 use crate::errors::AppError;
 use crate::handlers::{AuthToken, verify_room_token_by_id};
 use crate::models::content::RoomContent;
 use crate::repository::{IRoomContentRepository, RoomContentRepository};
 use crate::state::AppState;
+use crate::storage::sniff;
 use crate::validation::TokenValidator;
 
-use super::{ContentPermission, ensure_permission};
+use super::conditional::{
+    content_etag, header_str, insert_validators, is_not_modified, not_modified,
+};
+use super::disposition::{Disposition, INLINE_CONTENT_SECURITY_POLICY, content_disposition};
+use super::range::{RangeRequest, if_range_matches, parse_range};
+use super::{ContentPermission, ensure_permission, ensure_scan_clean, map_read_error};
+
+const REPR_DIGEST: HeaderName = HeaderName::from_static("repr-digest");
+const DIGEST: HeaderName = HeaderName::from_static("digest");
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct DownloadQuery {
+    /// `inline` 时在浏览器内预览；仅图片、PDF、纯文本与音视频生效，其余类型仍作为附件
+    #[param(inline)]
+    pub disposition: Option<Disposition>,
+}
 
 #[utoipa::path(
     get,
     path = "/api/v1/contents/{content_id}",
     params(
         ("content_id" = i64, Path, description = "内容 id"),
-        ("token" = String, Query, description = "有效的房间 token")
+        ("token" = String, Query, description = "有效的房间 token"),
+        DownloadQuery,
+        ("Range" = Option<String>, Header, description = "单个字节区间，如 bytes=0-1023"),
+        ("If-Range" = Option<String>, Header, description = "ETag 或 Last-Modified，不匹配时返回完整内容"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag 匹配时返回 304"),
+        ("If-Modified-Since" = Option<String>, Header, description = "未修改时返回 304")
     ),
     responses(
-        (status = 200, description = "文件内容"),
+        (status = 200, description = "文件内容，附带 Repr-Digest/Digest（SHA-256）"),
+        (status = 206, description = "部分文件内容，摘要仍针对完整文件"),
+        (status = 304, description = "内容未修改"),
         (status = 401, description = "token 无效"),
-        (status = 403, description = "无访问权限"),
-        (status = 404, description = "文件不存在")
+        (status = 403, description = "无访问权限，或文件未通过病毒扫描"),
+        (status = 404, description = "文件不存在"),
+        (status = 409, description = "文件正在等待病毒扫描"),
+        (status = 416, description = "请求区间不可满足或包含多个区间")
     ),
     tag = "content"
 )]
@@ -37,6 +67,8 @@ pub async fn download_content_global(
     AxumPath(content_id): AxumPath<i64>,
     AuthToken(token): AuthToken,
     State(app_state): State<Arc<AppState>>,
+    Query(query): Query<DownloadQuery>,
+    headers: HeaderMap,
 ) -> Result<Response, AppError> {
     TokenValidator::validate_token_format(&token)?;
 
@@ -53,18 +85,56 @@ pub async fn download_content_global(
         verified.room.permission.can_view(),
         ContentPermission::View,
     )?;
+    ensure_scan_clean(&content)?;
 
-    serve_content_stream(content).await
+    let disposition = query.disposition.unwrap_or_default();
+    serve_content_stream(&app_state, content, disposition, &headers).await
 }
 
-async fn serve_content_stream(content: RoomContent) -> Result<Response, AppError> {
+async fn serve_content_stream(
+    app_state: &AppState,
+    content: RoomContent,
+    disposition: Disposition,
+    headers: &HeaderMap,
+) -> Result<Response, AppError> {
+    let etag = content_etag(&content);
+    let last_modified = content.updated_at.and_utc();
     let path = content
         .path
         .ok_or_else(|| AppError::not_found("Content not stored on disk"))?;
 
-    let file = fs::File::open(&path)
-        .await
-        .map_err(|_| AppError::not_found("File missing on disk"))?;
+    if is_not_modified(headers, &etag, Some(last_modified)) {
+        return Ok(not_modified(&etag, Some(last_modified)));
+    }
+
+    let storage = app_state.storage();
+    let key = app_state.storage_key(&path);
+    let meta = storage.stat(&key).await.map_err(map_read_error)?;
+
+    let range = match header_str(headers, &IF_RANGE) {
+        Some(if_range) if !if_range_matches(if_range, Some(&etag), Some(last_modified)) => {
+            RangeRequest::Full
+        }
+        _ => parse_range(header_str(headers, &RANGE), meta.size),
+    };
+    let (status, stream, length, content_range) = match range {
+        RangeRequest::Full => (
+            StatusCode::OK,
+            storage.reader(&key).await.map_err(map_read_error)?,
+            meta.size,
+            None,
+        ),
+        RangeRequest::Partial(range) => (
+            StatusCode::PARTIAL_CONTENT,
+            storage
+                .reader_range(&key, range.start..range.end + 1)
+                .await
+                .map_err(map_read_error)?,
+            range.len(),
+            Some(range.content_range(meta.size)),
+        ),
+        RangeRequest::Unsatisfiable => return Ok(range_not_satisfiable(meta.size)),
+    };
 
     let file_name = content.file_name.clone().unwrap_or_else(|| {
         Path::new(&path)
@@ -73,26 +143,77 @@ async fn serve_content_stream(content: RoomContent) -> Result<Response, AppError
             .unwrap_or_else(|| "download.bin".to_string())
     });
 
-    let stream = ReaderStream::new(file);
-    let body = Body::from_stream(stream);
-    let mut response = Response::new(body);
-    let disposition = HeaderValue::from_str(&format!("attachment; filename=\"{file_name}\""))
-        .map_err(|_| AppError::internal("Failed to build response headers"))?;
+    let mut response = Response::new(Body::from_stream(stream));
+    *response.status_mut() = status;
+
     response
         .headers_mut()
-        .insert(CONTENT_DISPOSITION, disposition);
-
-    if let Some(size) = content.size
-        && let Ok(value) = HeaderValue::from_str(&size.to_string())
+        .insert(CONTENT_LENGTH, HeaderValue::from(length));
+    response
+        .headers_mut()
+        .insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    insert_validators(response.headers_mut(), &etag, Some(last_modified));
+    if let Some(content_range) = content_range
+        && let Ok(value) = HeaderValue::from_str(&content_range)
     {
-        response.headers_mut().insert(CONTENT_LENGTH, value);
+        response.headers_mut().insert(CONTENT_RANGE, value);
     }
 
-    if let Some(mime) = content.mime_type
-        && let Ok(value) = HeaderValue::from_str(&mime)
-    {
-        response.headers_mut().insert(CONTENT_TYPE, value);
+    // 以检测到的类型为准，HTML/SVG/JS 等活动类型一律按二进制下发，并禁止浏览器再次嗅探
+    let mime = sniff::served_mime_type(
+        content.mime_type.as_deref(),
+        content.detected_mime_type.as_deref(),
+    );
+    let disposition = disposition.resolve(&mime);
+    let mime = HeaderValue::from_str(&mime)
+        .unwrap_or_else(|_| HeaderValue::from_static(sniff::FALLBACK_MIME_TYPE));
+    response.headers_mut().insert(CONTENT_TYPE, mime);
+    response
+        .headers_mut()
+        .insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    response.headers_mut().insert(
+        CONTENT_DISPOSITION,
+        content_disposition(disposition, &file_name),
+    );
+    if disposition == Disposition::Inline {
+        response.headers_mut().insert(
+            CONTENT_SECURITY_POLICY,
+            HeaderValue::from_static(INLINE_CONTENT_SECURITY_POLICY),
+        );
+    }
+
+    if let Some(hash) = content.hash.as_deref() {
+        insert_digest(response.headers_mut(), hash);
     }
 
     Ok(response)
 }
+
+/// 写入完整文件的 SHA-256 摘要
+///
+/// `Repr-Digest`（RFC 9530）描述完整表示，206 响应同样适用；
+/// `Digest`（RFC 3230）供尚未支持新头的客户端使用。
+fn insert_digest(headers: &mut HeaderMap, hex_hash: &str) {
+    let Ok(raw) = hex::decode(hex_hash) else {
+        return;
+    };
+    let encoded = BASE64_STANDARD.encode(raw);
+    if let Ok(value) = HeaderValue::from_str(&format!("sha-256=:{encoded}:")) {
+        headers.insert(REPR_DIGEST, value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&format!("SHA-256={encoded}")) {
+        headers.insert(DIGEST, value);
+    }
+}
+
+fn range_not_satisfiable(size: u64) -> Response {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+    if let Ok(value) = HeaderValue::from_str(&format!("bytes */{size}")) {
+        response.headers_mut().insert(CONTENT_RANGE, value);
+    }
+    response
+        .headers_mut()
+        .insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    response
+}
@@ -0,0 +1,173 @@
+//! HTTP `Range` 请求解析
+//!
+//! 仅支持单个 `bytes` 区间；多区间请求按不可满足处理（416），
+//! 浏览器断点续传与音视频拖动只会发送单区间。
+
+use chrono::{DateTime, Utc};
+
+/// 闭区间字节范围 `[start, end]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    pub fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// `Content-Range` 头的值
+    pub fn content_range(&self, size: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, size)
+    }
+}
+
+/// `Range` 头的解析结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RangeRequest {
+    /// 无 `Range` 头，或按 RFC 9110 应忽略的 `Range` 头（未知单位、语法错误）
+    Full,
+    /// 单个可满足的区间
+    Partial(ByteRange),
+    /// 区间不可满足或包含多个区间
+    Unsatisfiable,
+}
+
+/// 解析 `Range` 头，`size` 为资源总长度
+pub(crate) fn parse_range(header: Option<&str>, size: u64) -> RangeRequest {
+    let Some(header) = header else {
+        return RangeRequest::Full;
+    };
+    let Some((unit, spec)) = header.trim().split_once('=') else {
+        return RangeRequest::Full;
+    };
+    if !unit.trim().eq_ignore_ascii_case("bytes") {
+        return RangeRequest::Full;
+    }
+    if spec.contains(',') {
+        return RangeRequest::Unsatisfiable;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return RangeRequest::Full;
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    let range = if start.is_empty() {
+        // 后缀区间：最后 N 个字节
+        let Ok(suffix) = end.parse::<u64>() else {
+            return RangeRequest::Full;
+        };
+        if suffix == 0 || size == 0 {
+            return RangeRequest::Unsatisfiable;
+        }
+        ByteRange {
+            start: size.saturating_sub(suffix),
+            end: size - 1,
+        }
+    } else {
+        let Ok(start) = start.parse::<u64>() else {
+            return RangeRequest::Full;
+        };
+        let end = if end.is_empty() {
+            u64::MAX
+        } else {
+            match end.parse::<u64>() {
+                Ok(end) if end >= start => end,
+                _ => return RangeRequest::Full,
+            }
+        };
+        if start >= size {
+            return RangeRequest::Unsatisfiable;
+        }
+        ByteRange {
+            start,
+            end: end.min(size - 1),
+        }
+    };
+
+    RangeRequest::Partial(range)
+}
+
+/// 判断 `If-Range` 条件是否成立；不成立时应忽略 `Range` 返回完整内容
+///
+/// 实体标签须强比较；日期须与 `Last-Modified` 完全一致（秒级精度）。
+pub(crate) fn if_range_matches(
+    header: &str,
+    etag: Option<&str>,
+    last_modified: Option<DateTime<Utc>>,
+) -> bool {
+    let header = header.trim();
+    if header.starts_with('"') || header.starts_with("W/") {
+        return !header.starts_with("W/") && etag.is_some_and(|etag| etag == header);
+    }
+    let Ok(date) = DateTime::parse_from_rfc2822(header) else {
+        return false;
+    };
+    last_modified.is_some_and(|modified| modified.timestamp() == date.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partial(start: u64, end: u64) -> RangeRequest {
+        RangeRequest::Partial(ByteRange { start, end })
+    }
+
+    #[test]
+    fn parses_single_ranges() {
+        assert_eq!(parse_range(None, 100), RangeRequest::Full);
+        assert_eq!(parse_range(Some("bytes=0-9"), 100), partial(0, 9));
+        assert_eq!(parse_range(Some("bytes=90-"), 100), partial(90, 99));
+        assert_eq!(parse_range(Some("bytes=90-500"), 100), partial(90, 99));
+        assert_eq!(parse_range(Some("bytes=-10"), 100), partial(90, 99));
+        assert_eq!(parse_range(Some("bytes=-500"), 100), partial(0, 99));
+        assert_eq!(parse_range(Some(" Bytes = 5 - 5 "), 100), partial(5, 5));
+    }
+
+    #[test]
+    fn ignores_invalid_and_rejects_unsatisfiable_ranges() {
+        assert_eq!(parse_range(Some("items=0-1"), 100), RangeRequest::Full);
+        assert_eq!(parse_range(Some("bytes=a-b"), 100), RangeRequest::Full);
+        assert_eq!(parse_range(Some("bytes=9-1"), 100), RangeRequest::Full);
+        assert_eq!(
+            parse_range(Some("bytes=100-"), 100),
+            RangeRequest::Unsatisfiable
+        );
+        assert_eq!(
+            parse_range(Some("bytes=-0"), 100),
+            RangeRequest::Unsatisfiable
+        );
+        assert_eq!(
+            parse_range(Some("bytes=0-"), 0),
+            RangeRequest::Unsatisfiable
+        );
+        assert_eq!(
+            parse_range(Some("bytes=0-1,5-6"), 100),
+            RangeRequest::Unsatisfiable
+        );
+    }
+
+    #[test]
+    fn if_range_compares_strong_etag_and_exact_date() {
+        let modified = DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert!(if_range_matches("\"abc\"", Some("\"abc\""), None));
+        assert!(!if_range_matches("\"abc\"", Some("\"def\""), None));
+        assert!(!if_range_matches("W/\"abc\"", Some("W/\"abc\""), None));
+        assert!(if_range_matches(
+            "Wed, 21 Oct 2015 07:28:00 GMT",
+            None,
+            Some(modified)
+        ));
+        assert!(!if_range_matches(
+            "Wed, 21 Oct 2015 07:29:00 GMT",
+            None,
+            Some(modified)
+        ));
+        assert!(!if_range_matches("not a date", None, Some(modified)));
+    }
+}
@@ -1,10 +1,11 @@
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 use axum::Json;
-use tokio::fs;
 
 use crate::errors::AppError;
+use crate::models::content::{RoomContent, ScanStatus};
 use crate::services::RoomTokenClaims;
+use crate::storage::{StorageBackend, StorageError, key};
 
 pub(crate) type HandlerResult<T> = Result<Json<T>, AppError>;
 
@@ -38,14 +39,67 @@ pub(crate) fn ensure_permission(
     Ok(())
 }
 
-/// 确保房间存储目录存在，使用 room_id 作为目录名
-pub(crate) async fn ensure_room_storage(
-    base_dir: &Path,
+/// 文件扫描通过前拒绝下载：扫描中返回 409，感染或无法扫描返回 403
+pub(crate) fn ensure_scan_clean(content: &RoomContent) -> Result<(), AppError> {
+    match content.scan_status {
+        ScanStatus::Clean => Ok(()),
+        ScanStatus::Pending => Err(AppError::conflict(
+            "Content is pending a security scan, try again later",
+        )),
+        ScanStatus::Infected => Err(AppError::permission_denied(
+            "Content is quarantined: malware detected",
+        )),
+        ScanStatus::Failed => Err(AppError::permission_denied(
+            "Content is quarantined: security scan failed",
+        )),
+    }
+}
+
+/// 读取存储对象失败时的错误映射，对象缺失返回 404
+pub(crate) fn map_read_error(error: StorageError) -> AppError {
+    match error {
+        StorageError::NotFound(_) => AppError::not_found("File missing on disk"),
+        other => AppError::internal(format!("Read file failed: {other}")),
+    }
+}
+
+/// 为房间内的文件生成不冲突的对象键，使用 room_id 作为目录名
+///
+/// 同名文件依次追加 `(1)`、`(2)` … 后缀。
+pub(crate) async fn unique_storage_key(
+    storage: &dyn StorageBackend,
     room_id: i64,
-) -> Result<PathBuf, std::io::Error> {
-    let dir = base_dir.join(room_id.to_string());
-    fs::create_dir_all(&dir).await?;
-    Ok(dir)
+    file_name: &str,
+) -> Result<String, AppError> {
+    let safe_file_name = sanitize_filename::sanitize(file_name);
+    let path = Path::new(&safe_file_name);
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(&safe_file_name);
+    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+
+    let mut object_key = key::room_object_key(room_id, &safe_file_name);
+    let mut counter = 1;
+    while storage
+        .exists(&object_key)
+        .await
+        .map_err(|e| AppError::internal(format!("Check storage failed: {e}")))?
+    {
+        let candidate = if extension.is_empty() {
+            format!("{}({})", stem, counter)
+        } else {
+            format!("{}({}).{}", stem, counter, extension)
+        };
+        object_key = key::room_object_key(room_id, &candidate);
+        counter += 1;
+
+        if counter > 1000 {
+            return Err(AppError::internal("Too many files with the same name"));
+        }
+    }
+
+    Ok(object_key)
 }
 
 pub(crate) fn room_id_or_error(claims: &RoomTokenClaims) -> Result<i64, AppError> {
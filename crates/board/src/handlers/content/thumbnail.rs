@@ -0,0 +1,117 @@
+//! 图片缩略图下载
+//!
+//! 缩略图由后台任务生成，宽度按配置档位取整；配置变化后重新生成期间使用已有的最接近的宽度。
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::extract::{Path as AxumPath, Query, State};
+use axum::http::header::{
+    CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS,
+};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::Response;
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::errors::AppError;
+use crate::handlers::{AuthToken, verify_room_token_by_id};
+use crate::models::content::{RoomContent, ThumbnailStatus};
+use crate::repository::{IRoomContentRepository, RoomContentRepository};
+use crate::state::AppState;
+use crate::validation::TokenValidator;
+
+use super::conditional::{content_etag, insert_validators, is_not_modified, not_modified};
+use super::{ContentPermission, ensure_permission, ensure_scan_clean, map_read_error};
+
+#[derive(Debug, Default, Deserialize, IntoParams, ToSchema)]
+pub struct ThumbnailQuery {
+    /// 期望宽度（像素），返回不小于该值的最小档位，省略时返回最小档位
+    pub width: Option<u32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/contents/{content_id}/thumbnail",
+    params(
+        ("content_id" = i64, Path, description = "内容 id"),
+        ("token" = String, Query, description = "有效的房间 token"),
+        ThumbnailQuery,
+        ("If-None-Match" = Option<String>, Header, description = "ETag 匹配时返回 304")
+    ),
+    responses(
+        (status = 200, description = "缩略图（JPEG 或 WebP）"),
+        (status = 304, description = "缩略图未修改"),
+        (status = 401, description = "token 无效"),
+        (status = 403, description = "无访问权限"),
+        (status = 404, description = "内容不存在或缩略图尚未生成")
+    ),
+    tag = "content"
+)]
+pub async fn download_thumbnail(
+    AxumPath(content_id): AxumPath<i64>,
+    AuthToken(token): AuthToken,
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<ThumbnailQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    TokenValidator::validate_token_format(&token)?;
+
+    let repository = RoomContentRepository::new(app_state.db_pool.clone());
+    let content = repository
+        .find_by_id(content_id)
+        .await
+        .map_err(|e| AppError::internal(format!("Query failed: {e}")))?
+        .ok_or_else(|| AppError::not_found("Content not found"))?;
+
+    let verified = verify_room_token_by_id(app_state.clone(), content.room_id, &token).await?;
+    ensure_permission(
+        &verified.claims,
+        verified.room.permission.can_view(),
+        ContentPermission::View,
+    )?;
+
+    ensure_scan_clean(&content)?;
+    // 配置变化后重新生成期间状态为 `Pending`，仍可使用已有的缩略图
+    if !matches!(
+        content.thumbnail_status,
+        ThumbnailStatus::Ready | ThumbnailStatus::Pending
+    ) {
+        return Err(AppError::not_found("Thumbnail not available"));
+    }
+    let thumbnails = &app_state.services.thumbnails;
+    let (width, key) = thumbnails
+        .resolve(&content, query.width)
+        .await
+        .map_err(|e| AppError::internal(format!("Thumbnail lookup failed: {e:#}")))?
+        .ok_or_else(|| AppError::not_found("Thumbnail not available"))?;
+
+    let etag = thumbnail_etag(&content, width, thumbnails.format().extension());
+    let last_modified = content.updated_at.and_utc();
+    if is_not_modified(&headers, &etag, Some(last_modified)) {
+        return Ok(not_modified(&etag, Some(last_modified)));
+    }
+
+    let storage = app_state.storage();
+    let meta = storage.stat(&key).await.map_err(map_read_error)?;
+    let stream = storage.reader(&key).await.map_err(map_read_error)?;
+
+    let mut response = Response::new(Body::from_stream(stream));
+    *response.status_mut() = StatusCode::OK;
+    let response_headers = response.headers_mut();
+    response_headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static(thumbnails.format().mime_type()),
+    );
+    response_headers.insert(CONTENT_LENGTH, HeaderValue::from(meta.size));
+    response_headers.insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    response_headers.insert(CONTENT_DISPOSITION, HeaderValue::from_static("inline"));
+    insert_validators(response_headers, &etag, Some(last_modified));
+    Ok(response)
+}
+
+/// 在内容 ETag 的基础上区分宽度档位与输出格式，切换格式后旧的缓存不再命中
+fn thumbnail_etag(content: &RoomContent, width: u32, extension: &str) -> String {
+    let etag = content_etag(content);
+    format!("{}-w{width}.{extension}\"", etag.trim_end_matches('"'))
+}
@@ -3,33 +3,48 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use axum::Json;
+use axum::body::Body;
 use axum::extract::{Multipart, Path as AxumPath, Query, State, multipart::Field};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderMap, HeaderValue};
+use axum::response::Response;
+use bytes::Bytes;
 use futures::StreamExt;
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
+use tokio_util::io::ReaderStream;
 use utoipa::ToSchema;
 
+use crate::constants::upload::MAX_METADATA_STRIP_SIZE;
+
 use crate::dto::content::{
     RoomContentView, UploadContentResponse, UploadPreparationRequest, UploadPreparationResponse,
 };
 use crate::errors::AppError;
 use crate::models::{
     UploadFileDescriptor,
-    content::{ContentType, RoomContent},
+    content::{ContentType, IntegrityStatus, RoomContent, ScanStatus, ThumbnailStatus},
 };
 use crate::repository::{
     IRoomContentRepository, IRoomUploadReservationRepository, RoomContentRepository,
     RoomUploadReservationRepository,
 };
+use crate::services::{METADATA_STRIP_MIME_TYPES, strip_uploaded_image};
 use crate::state::AppState;
+use crate::storage::{StorageWriter, sniff};
 use crate::validation::RoomNameValidator;
 
+use super::conditional::{body_etag, insert_validators, is_not_modified, not_modified};
 use super::{
-    ContentPermission, HandlerResult, ensure_permission, ensure_room_storage, room_id_or_error,
+    ContentPermission, HandlerResult, ensure_permission, room_id_or_error, unique_storage_key,
 };
 use crate::handlers::{AuthToken, verify_room_token};
 
+/// 暂存文件拷贝到存储后端时的读取缓冲大小
+const TEMP_COPY_BUFFER_SIZE: usize = 64 * 1024;
+
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct UploadReservationQuery {
     pub reservation_id: i64,
@@ -37,9 +52,11 @@ pub struct UploadReservationQuery {
 
 struct TempUpload {
     original_name: String,
-    path: PathBuf,
+    key: String,
     size: i64,
     mime: Option<String>,
+    detected_mime: Option<&'static str>,
+    hash: String,
 }
 
 #[utoipa::path(
@@ -47,10 +64,12 @@ struct TempUpload {
     path = "/api/v1/rooms/{name}/contents",
     params(
         ("name" = String, Path, description = "房间名称"),
-        ("token" = String, Query, description = "有效的房间 token")
+        ("token" = String, Query, description = "有效的房间 token"),
+        ("If-None-Match" = Option<String>, Header, description = "列表 ETag 匹配时返回 304")
     ),
     responses(
         (status = 200, description = "房间文件列表", body = [RoomContentView]),
+        (status = 304, description = "列表未变化"),
         (status = 401, description = "token 无效"),
         (status = 404, description = "房间不存在")
     ),
@@ -60,7 +79,8 @@ pub async fn list_contents(
     AxumPath(name): AxumPath<String>,
     AuthToken(token): AuthToken,
     State(app_state): State<Arc<AppState>>,
-) -> HandlerResult<Vec<RoomContentView>> {
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
     // Validate room name using the new validation framework
     RoomNameValidator::validate_identifier(&name)?;
 
@@ -80,9 +100,22 @@ pub async fn list_contents(
         .await
         .map_err(|e| AppError::internal(format!("Failed to list contents: {e}")))?;
 
-    Ok(Json(
-        contents.into_iter().map(RoomContentView::from).collect(),
-    ))
+    let views: Vec<RoomContentView> = contents.into_iter().map(RoomContentView::from).collect();
+    let body = serde_json::to_vec(&views)
+        .map_err(|e| AppError::internal(format!("Failed to serialize contents: {e}")))?;
+
+    // 删除内容不会推进任何 updated_at，因此列表只使用基于响应体的 ETag
+    let etag = body_etag(&body);
+    if is_not_modified(&headers, &etag, None) {
+        return Ok(not_modified(&etag, None));
+    }
+
+    let mut response = Response::new(Body::from(body));
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    insert_validators(response.headers_mut(), &etag, None);
+    Ok(response)
 }
 
 #[utoipa::path(
@@ -255,11 +288,29 @@ pub async fn upload_contents(
 
     let expected_map = build_expected_manifest(expected_files)?;
 
-    let storage_dir = ensure_room_storage(app_state.storage_root().as_ref(), room_id)
+    let strip_metadata = app_state.strip_image_metadata(&verified.room);
+    let staged = stage_multipart_uploads(
+        multipart,
+        &expected_map,
+        &app_state,
+        room_id,
+        query.reservation_id,
+        strip_metadata,
+    )
+    .await;
+    // 去重模式下的本地暂存文件在写入存储后即可删除
+    if let Err(e) = app_state
+        .chunk_staging()
+        .remove_reservation(query.reservation_id)
         .await
-        .map_err(|e| AppError::internal(format!("Failed to prepare storage directory: {e}")))?;
-
-    let staged = stage_multipart_uploads(multipart, &expected_map, &storage_dir).await?;
+    {
+        log::warn!(
+            "Failed to remove staged uploads of reservation {}: {:#}",
+            query.reservation_id,
+            e
+        );
+    }
+    let staged = staged?;
 
     let repository = RoomContentRepository::new(app_state.db_pool.clone());
     let (uploaded, actual_total) =
@@ -297,8 +348,12 @@ fn build_expected_manifest(
 async fn stage_multipart_uploads(
     mut multipart: Multipart,
     expected_map: &HashMap<String, UploadFileDescriptor>,
-    storage_dir: &Path,
+    app_state: &AppState,
+    room_id: i64,
+    reservation_id: i64,
+    strip_metadata: bool,
 ) -> Result<Vec<TempUpload>, AppError> {
+    let staging_dir = app_state.chunk_staging().reservation_dir(reservation_id);
     let mut staged = Vec::new();
     let mut seen = HashSet::new();
 
@@ -307,10 +362,21 @@ async fn stage_multipart_uploads(
         .await
         .map_err(|e| AppError::validation(format!("Invalid multipart data: {e}")))?
     {
-        match stage_upload_field(field, expected_map, storage_dir, &mut seen).await {
+        let temp_path = staging_dir.join(format!("upload-{}", seen.len()));
+        match stage_upload_field(
+            field,
+            expected_map,
+            app_state,
+            room_id,
+            strip_metadata,
+            temp_path,
+            &mut seen,
+        )
+        .await
+        {
             Ok(temp_upload) => staged.push(temp_upload),
             Err(error) => {
-                cleanup_staged_uploads(&staged).await;
+                cleanup_staged_uploads(app_state, &staged).await;
                 return Err(error);
             }
         }
@@ -321,7 +387,7 @@ async fn stage_multipart_uploads(
     }
 
     if staged.len() != expected_map.len() {
-        cleanup_staged_uploads(&staged).await;
+        cleanup_staged_uploads(app_state, &staged).await;
         return Err(AppError::validation(
             "Uploaded file count mismatch reservation",
         ));
@@ -333,7 +399,10 @@ async fn stage_multipart_uploads(
 async fn stage_upload_field(
     mut field: Field<'_>,
     expected_map: &HashMap<String, UploadFileDescriptor>,
-    storage_dir: &Path,
+    app_state: &AppState,
+    room_id: i64,
+    strip_metadata: bool,
+    temp_path: PathBuf,
     seen: &mut HashSet<String>,
 ) -> Result<TempUpload, AppError> {
     let file_name = field
@@ -351,80 +420,341 @@ async fn stage_upload_field(
         )));
     }
 
-    let file_path = unique_upload_path(storage_dir, &file_name)?;
-    let size = write_field_to_file(&mut field, &file_path).await?;
-
-    if size != expected.size {
-        fs::remove_file(&file_path).await.ok();
-        return Err(AppError::validation(format!(
-            "File size mismatch for {file_name}"
-        )));
-    }
-
-    let mime = mime_guess::from_path(&file_path)
-        .first_raw()
-        .map(|m| m.to_string());
+    let mime = declared_mime_type(&field, &file_name);
+    let mut receiver = FieldReceiver {
+        app_state,
+        room_id,
+        file_name: &file_name,
+        expected_size: expected.size,
+        strip_metadata,
+        temp_path,
+        hasher: Sha256::new(),
+        size: 0,
+        head: Vec::new(),
+        detected_mime: None,
+        sink: None,
+    };
+    let received = match receiver.receive(&mut field).await {
+        Ok(()) => receiver.finish().await,
+        Err(error) => Err(error),
+    };
+    let stored = match received {
+        Ok(stored) => stored,
+        Err(error) => {
+            receiver.abort().await;
+            return Err(error);
+        }
+    };
 
     Ok(TempUpload {
         original_name: file_name,
-        path: file_path,
-        size,
+        key: stored.key,
+        size: stored.size,
         mime,
+        detected_mime: stored.detected_mime,
+        hash: stored.hash,
     })
 }
 
-fn unique_upload_path(storage_dir: &Path, file_name: &str) -> Result<PathBuf, AppError> {
-    let safe_file_name = sanitize_filename::sanitize(file_name);
-    let mut final_filename = safe_file_name.clone();
-    let mut counter = 1;
-    let mut file_path = storage_dir.join(&final_filename);
-
-    while file_path.exists() {
-        let path = Path::new(&safe_file_name);
-        let stem = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or(&safe_file_name);
-        let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("");
-
-        final_filename = if extension.is_empty() {
-            format!("{}({})", stem, counter)
+/// 客户端声明的类型：优先使用 multipart 字段头，缺失或为通用二进制时按扩展名推断
+///
+/// 声明类型仅用于展示，下载与分类以 [`sniff::detect`] 的检测结果为准。
+fn declared_mime_type(field: &Field<'_>, file_name: &str) -> Option<String> {
+    field
+        .content_type()
+        .map(str::trim)
+        .filter(|mime| !mime.is_empty() && *mime != sniff::FALLBACK_MIME_TYPE)
+        .map(str::to_string)
+        .or_else(|| {
+            mime_guess::from_path(file_name)
+                .first_raw()
+                .map(|m| m.to_string())
+        })
+}
+
+/// 写入上传内容并返回对象键；启用去重时写入共享 blob，已存在则只增加引用
+async fn store_upload_data(
+    app_state: &AppState,
+    room_id: i64,
+    file_name: &str,
+    hash: &str,
+    data: Vec<u8>,
+) -> Result<String, AppError> {
+    let storage = app_state.storage();
+    let blob_store = app_state.blob_store();
+    if !blob_store.enabled() {
+        let key = unique_storage_key(storage.as_ref(), room_id, file_name).await?;
+        storage
+            .put(&key, data)
+            .await
+            .map_err(|e| AppError::internal(format!("Write file failed: {e}")))?;
+        return Ok(key);
+    }
+
+    let size = data.len() as i64;
+    let lease = blob_store
+        .acquire(hash, size)
+        .await
+        .map_err(|e| AppError::internal(format!("Acquire blob failed: {e:#}")))?;
+    if lease.needs_upload {
+        blob_store
+            .upload(&lease, storage.put(&lease.key, data))
+            .await
+            .map_err(|e| AppError::internal(format!("Write file failed: {e}")))?;
+    }
+    Ok(lease.key)
+}
+
+/// 接收中的文件内容去向
+enum UploadSink {
+    /// 需要移除元数据的图片，大小不超过 [`MAX_METADATA_STRIP_SIZE`]
+    Memory(Vec<u8>),
+    /// 未启用去重时直接写入房间下的对象
+    Storage {
+        key: String,
+        writer: Box<dyn StorageWriter>,
+    },
+    /// 启用去重时先写入本地暂存文件，得到哈希后再决定是否写入 blob
+    TempFile(fs::File),
+}
+
+/// 已写入存储的上传文件
+struct StoredUpload {
+    key: String,
+    size: i64,
+    hash: String,
+    detected_mime: Option<&'static str>,
+}
+
+/// 流式接收一个 multipart 文件字段
+///
+/// 边读边计算 SHA-256，读到开头 [`sniff::SNIFF_LEN`] 字节后检测类型并选择去向，
+/// 只有需要移除元数据的小图片会完整缓存在内存中。
+struct FieldReceiver<'a> {
+    app_state: &'a AppState,
+    room_id: i64,
+    file_name: &'a str,
+    expected_size: i64,
+    strip_metadata: bool,
+    temp_path: PathBuf,
+    hasher: Sha256,
+    size: i64,
+    head: Vec<u8>,
+    detected_mime: Option<&'static str>,
+    sink: Option<UploadSink>,
+}
+
+impl FieldReceiver<'_> {
+    async fn receive(&mut self, field: &mut Field<'_>) -> Result<(), AppError> {
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk
+                .map_err(|e| AppError::validation(format!("Read upload chunk failed: {e}")))?;
+            self.size += chunk.len() as i64;
+            if self.size > self.expected_size {
+                return Err(self.size_mismatch());
+            }
+            self.hasher.update(&chunk);
+            if self.sink.is_some() {
+                self.write(chunk).await?;
+                continue;
+            }
+            self.head.extend_from_slice(&chunk);
+            if self.head.len() >= sniff::SNIFF_LEN {
+                self.open_sink().await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn finish(&mut self) -> Result<StoredUpload, AppError> {
+        if self.sink.is_none() {
+            self.open_sink().await?;
+        }
+        if self.size != self.expected_size {
+            return Err(self.size_mismatch());
+        }
+
+        let hash = hex::encode(std::mem::take(&mut self.hasher).finalize());
+        let (key, size, hash) = match self.sink.take() {
+            Some(UploadSink::Memory(data)) => {
+                // 移除元数据后以实际写入的大小计入房间容量
+                let data = strip_uploaded_image(data, self.detected_mime);
+                let size = data.len() as i64;
+                let hash = hex::encode(Sha256::digest(&data));
+                let key =
+                    store_upload_data(self.app_state, self.room_id, self.file_name, &hash, data)
+                        .await?;
+                (key, size, hash)
+            }
+            Some(UploadSink::Storage { key, mut writer }) => {
+                if let Err(e) = writer.close().await {
+                    let _ = writer.abort().await;
+                    return Err(AppError::internal(format!("Write file failed: {e}")));
+                }
+                (key, self.size, hash)
+            }
+            Some(UploadSink::TempFile(mut file)) => {
+                file.flush()
+                    .await
+                    .map_err(|e| AppError::internal(format!("Write file failed: {e}")))?;
+                drop(file);
+                let key =
+                    store_temp_upload(self.app_state, &self.temp_path, &hash, self.size).await?;
+                fs::remove_file(&self.temp_path).await.ok();
+                (key, self.size, hash)
+            }
+            None => return Err(AppError::internal("Upload sink missing")),
+        };
+
+        Ok(StoredUpload {
+            key,
+            size,
+            hash,
+            detected_mime: self.detected_mime,
+        })
+    }
+
+    /// 丢弃已写入的部分内容
+    async fn abort(&mut self) {
+        match self.sink.take() {
+            Some(UploadSink::Storage { mut writer, .. }) => {
+                let _ = writer.abort().await;
+            }
+            Some(UploadSink::TempFile(file)) => {
+                drop(file);
+                fs::remove_file(&self.temp_path).await.ok();
+            }
+            Some(UploadSink::Memory(_)) | None => {}
+        }
+    }
+
+    async fn open_sink(&mut self) -> Result<(), AppError> {
+        self.detected_mime = sniff::detect(&self.head);
+        let strippable = self.strip_metadata
+            && self
+                .detected_mime
+                .is_some_and(|mime| METADATA_STRIP_MIME_TYPES.contains(&mime));
+        let sink = if strippable
+            && u64::try_from(self.expected_size).is_ok_and(|size| size <= MAX_METADATA_STRIP_SIZE)
+        {
+            UploadSink::Memory(Vec::new())
         } else {
-            format!("{}({}).{}", stem, counter, extension)
+            if strippable {
+                log::warn!(
+                    "Keeping metadata of {}: {} bytes exceeds the strip limit",
+                    self.file_name,
+                    self.expected_size
+                );
+            }
+            open_stream_sink(
+                self.app_state,
+                self.room_id,
+                self.file_name,
+                &self.temp_path,
+            )
+            .await?
         };
+        self.sink = Some(sink);
 
-        file_path = storage_dir.join(&final_filename);
-        counter += 1;
+        let head = std::mem::take(&mut self.head);
+        self.write(Bytes::from(head)).await
+    }
 
-        if counter > 1000 {
-            return Err(AppError::internal("Too many files with the same name"));
+    async fn write(&mut self, data: Bytes) -> Result<(), AppError> {
+        match self.sink.as_mut() {
+            Some(UploadSink::Memory(buffer)) => buffer.extend_from_slice(&data),
+            Some(UploadSink::Storage { writer, .. }) => writer
+                .write(data)
+                .await
+                .map_err(|e| AppError::internal(format!("Write file failed: {e}")))?,
+            Some(UploadSink::TempFile(file)) => file
+                .write_all(&data)
+                .await
+                .map_err(|e| AppError::internal(format!("Write staging file failed: {e}")))?,
+            None => return Err(AppError::internal("Upload sink missing")),
         }
+        Ok(())
     }
 
-    Ok(file_path)
+    fn size_mismatch(&self) -> AppError {
+        AppError::validation(format!("File size mismatch for {}", self.file_name))
+    }
 }
 
-async fn write_field_to_file(field: &mut Field<'_>, file_path: &Path) -> Result<i64, AppError> {
-    let mut temp_file = fs::File::create(file_path)
-        .await
-        .map_err(|e| AppError::internal(format!("Cannot create file: {e}")))?;
-
-    let mut size: i64 = 0;
-    while let Some(chunk) = field.next().await {
-        let chunk =
-            chunk.map_err(|e| AppError::validation(format!("Read upload chunk failed: {e}")))?;
-        size += chunk.len() as i64;
-        temp_file
-            .write_all(&chunk)
+/// 未启用去重时直接写入房间下的对象，否则写入本地暂存文件
+async fn open_stream_sink(
+    app_state: &AppState,
+    room_id: i64,
+    file_name: &str,
+    temp_path: &Path,
+) -> Result<UploadSink, AppError> {
+    if !app_state.blob_store().enabled() {
+        let storage = app_state.storage();
+        let key = unique_storage_key(storage.as_ref(), room_id, file_name).await?;
+        let writer = storage
+            .writer(&key)
             .await
             .map_err(|e| AppError::internal(format!("Write file failed: {e}")))?;
+        return Ok(UploadSink::Storage { key, writer });
+    }
+
+    if let Some(dir) = temp_path.parent() {
+        fs::create_dir_all(dir)
+            .await
+            .map_err(|e| AppError::internal(format!("Create staging dir failed: {e}")))?;
+    }
+    let file = fs::File::create(temp_path)
+        .await
+        .map_err(|e| AppError::internal(format!("Create staging file failed: {e}")))?;
+    Ok(UploadSink::TempFile(file))
+}
+
+/// 将去重模式下的暂存文件登记为 blob，对象尚不存在时以流式写入
+async fn store_temp_upload(
+    app_state: &AppState,
+    temp_path: &Path,
+    hash: &str,
+    size: i64,
+) -> Result<String, AppError> {
+    let blob_store = app_state.blob_store();
+    let lease = blob_store
+        .acquire(hash, size)
+        .await
+        .map_err(|e| AppError::internal(format!("Acquire blob failed: {e:#}")))?;
+    if lease.needs_upload {
+        blob_store
+            .upload(&lease, copy_temp_file(app_state, temp_path, &lease.key))
+            .await?;
     }
-    temp_file
-        .flush()
+    Ok(lease.key)
+}
+
+async fn copy_temp_file(app_state: &AppState, temp_path: &Path, key: &str) -> Result<(), AppError> {
+    let file = fs::File::open(temp_path)
         .await
-        .map_err(|e| AppError::internal(format!("Flush file failed: {e}")))?;
+        .map_err(|e| AppError::internal(format!("Read staging file failed: {e}")))?;
+    let mut writer = app_state
+        .storage()
+        .writer(key)
+        .await
+        .map_err(|e| AppError::internal(format!("Write file failed: {e}")))?;
+
+    let mut chunks = ReaderStream::with_capacity(file, TEMP_COPY_BUFFER_SIZE);
+    while let Some(chunk) = chunks.next().await {
+        let result = match chunk {
+            Ok(chunk) => writer.write(chunk).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            let _ = writer.abort().await;
+            return Err(AppError::internal(format!("Write file failed: {e}")));
+        }
+    }
 
-    Ok(size)
+    writer
+        .close()
+        .await
+        .map_err(|e| AppError::internal(format!("Write file failed: {e}")))
 }
 
 async fn persist_staged_uploads(
@@ -438,10 +768,13 @@ async fn persist_staged_uploads(
     let mut actual_total: i64 = 0;
 
     for temp in staged {
-        let saved = match repository.create(&build_file_content(room_id, temp)).await {
+        let mut content = build_file_content(room_id, app_state.storage_path(&temp.key), temp);
+        content.thumbnail_status = app_state.services.thumbnails.initial_status(&content);
+        content.scan_status = app_state.services.content_scan.initial_status(&content);
+        let saved = match repository.create(&content).await {
             Ok(value) => value,
             Err(e) => {
-                cleanup_staged_uploads(staged).await;
+                cleanup_staged_uploads(app_state, staged).await;
                 return Err(AppError::internal(format!("Persist content failed: {e}")));
             }
         };
@@ -456,7 +789,7 @@ async fn persist_staged_uploads(
     Ok((uploaded, actual_total))
 }
 
-fn build_file_content(room_id: i64, temp: &TempUpload) -> RoomContent {
+fn build_file_content(room_id: i64, stored_path: String, temp: &TempUpload) -> RoomContent {
     let now = chrono::Utc::now().naive_utc();
     let mut content = RoomContent {
         id: None,
@@ -468,17 +801,22 @@ fn build_file_content(room_id: i64, temp: &TempUpload) -> RoomContent {
         file_name: Some(temp.original_name.clone()),
         size: None,
         mime_type: None,
+        detected_mime_type: temp.detected_mime.map(str::to_string),
+        hash: Some(temp.hash.clone()),
         sequence_number: 0,
+        thumbnail_status: ThumbnailStatus::None,
+        scan_status: ScanStatus::Clean,
+        integrity_status: IntegrityStatus::Ok,
         created_at: now,
         updated_at: now,
     };
     content.set_path(
-        temp.path.to_string_lossy().to_string(),
-        ContentType::File,
+        stored_path,
+        sniff::content_type_for(temp.detected_mime),
         temp.size,
         temp.mime
             .clone()
-            .unwrap_or_else(|| "application/octet-stream".to_string()),
+            .unwrap_or_else(|| sniff::FALLBACK_MIME_TYPE.to_string()),
     );
     content
 }
@@ -533,14 +871,10 @@ fn actual_manifest(staged: &[TempUpload]) -> Vec<UploadFileDescriptor> {
         .collect()
 }
 
-async fn cleanup_staged_uploads(staged: &[TempUpload]) {
+async fn cleanup_staged_uploads(app_state: &AppState, staged: &[TempUpload]) {
     for item in staged {
-        if let Err(err) = fs::remove_file(&item.path).await {
-            log::warn!(
-                "Failed to remove temp file {}: {}",
-                item.path.display(),
-                err
-            );
+        if let Err(err) = app_state.blob_store().release_object(&item.key).await {
+            log::warn!("Failed to remove staged file {}: {:#}", item.key, err);
         }
     }
 }
@@ -114,22 +114,26 @@ async fn broadcast_permission_update(app_state: &AppState, old_slug: &str, updat
     let broadcaster = app_state.broadcaster.clone();
     let room_info = room_info_from_room(updated_room);
     let new_slug = updated_room.slug.clone();
-    let update_reason = if new_slug != old_slug {
-        RoomUpdateReason::AddressChanged
-    } else {
-        RoomUpdateReason::PermissionsChanged
-    };
+
+    if new_slug == old_slug {
+        if let Err(e) = broadcaster
+            .broadcast_room_update(old_slug, &room_info, RoomUpdateReason::PermissionsChanged)
+            .await
+        {
+            log::warn!("Failed to broadcast room update event: {}", e);
+        }
+        return;
+    }
 
     if let Err(e) = broadcaster
-        .broadcast_room_update(old_slug, &room_info, update_reason.clone())
+        .broadcast_address_changed(old_slug, &room_info)
         .await
     {
         log::warn!("Failed to broadcast room update event (old slug): {}", e);
     }
-    if new_slug != old_slug
-        && let Err(e) = broadcaster
-            .broadcast_room_update(&new_slug, &room_info, update_reason)
-            .await
+    if let Err(e) = broadcaster
+        .broadcast_room_update(&new_slug, &room_info, RoomUpdateReason::AddressChanged)
+        .await
     {
         log::warn!("Failed to broadcast room update event (new slug): {}", e);
     }
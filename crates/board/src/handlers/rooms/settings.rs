@@ -176,6 +176,10 @@ fn apply_settings_payload(
     if let Some(max_size) = payload.max_size {
         room.max_size = max_size;
     }
+
+    if let Some(strip) = payload.strip_image_metadata {
+        room.strip_image_metadata = Some(strip);
+    }
     Ok(())
 }
 
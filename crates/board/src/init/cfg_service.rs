@@ -82,6 +82,20 @@ fn merge_config_with_cli_args(
     merge_cli_arg!(cfg.app.jwt.secret, args.jwt_secret.clone());
     merge_cli_arg!(cfg.app.database.url, args.db_url.clone());
 
+    merge_cli_arg!(cfg.app.storage.backend, args.storage_backend.clone());
+    merge_cli_arg!(cfg.app.storage.root, args.storage_root.clone());
+    merge_cli_arg!(cfg.app.storage.s3.endpoint, args.s3_endpoint.clone());
+    merge_cli_arg!(cfg.app.storage.s3.bucket, args.s3_bucket.clone());
+    merge_cli_arg!(
+        cfg.app.storage.s3.access_key_id,
+        args.s3_access_key_id.clone()
+    );
+    merge_cli_arg!(
+        cfg.app.storage.s3.secret_access_key,
+        args.s3_secret_access_key.clone()
+    );
+    merge_cli_arg!(cfg.app.storage.s3.region, args.s3_region.clone(), Some);
+
     let legacy_normalized_db_url = normalize_database_url_compat(&cfg.app.database.url);
     if legacy_normalized_db_url != cfg.app.database.url {
         log::warn!(
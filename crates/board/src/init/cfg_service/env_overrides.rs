@@ -14,8 +14,10 @@ macro_rules! apply_env {
 pub(super) fn apply_program_env_overrides(cfg: &mut configrs::Config) {
     apply_basic_env_overrides(cfg);
     apply_jwt_env_overrides(cfg);
+    apply_storage_env_overrides(cfg);
     apply_room_env_overrides(cfg);
     apply_gc_env_overrides(cfg);
+    apply_websocket_env_overrides(cfg);
     apply_middleware_env_overrides(cfg);
 }
 
@@ -64,6 +66,55 @@ fn apply_jwt_env_overrides(cfg: &mut configrs::Config) {
     );
 }
 
+fn apply_storage_env_overrides(cfg: &mut configrs::Config) {
+    apply_env!(env_string, "STORAGE_BACKEND", cfg.app.storage.backend);
+    apply_env!(env_string, "STORAGE_ROOT", cfg.app.storage.root);
+    apply_env!(env_bool, "STORAGE_DEDUP", cfg.app.storage.dedup);
+    apply_env!(
+        env_bool,
+        "STORAGE_THUMBNAILS_ENABLED",
+        cfg.app.storage.thumbnails.enabled
+    );
+    apply_env!(
+        env_bool,
+        "STORAGE_SCAN_ENABLED",
+        cfg.app.storage.scan.enabled
+    );
+    apply_env!(
+        env_string,
+        "STORAGE_SCAN_CLAMD_ADDRESS",
+        cfg.app.storage.scan.clamd_address
+    );
+    apply_env!(
+        env_bool,
+        "STORAGE_RECONCILE_ENABLED",
+        cfg.app.storage.reconcile.enabled
+    );
+    apply_env!(
+        env_bool,
+        "STORAGE_RECONCILE_DELETE_ORPHANS",
+        cfg.app.storage.reconcile.delete_orphans
+    );
+    apply_env!(
+        env_bool,
+        "STORAGE_SCRUB_ENABLED",
+        cfg.app.storage.scrub.enabled
+    );
+    apply_env!(env_string, "S3_ENDPOINT", cfg.app.storage.s3.endpoint);
+    apply_env!(env_string, "S3_BUCKET", cfg.app.storage.s3.bucket);
+    apply_env!(
+        env_string,
+        "S3_ACCESS_KEY_ID",
+        cfg.app.storage.s3.access_key_id
+    );
+    apply_env!(
+        env_string,
+        "S3_SECRET_ACCESS_KEY",
+        cfg.app.storage.s3.secret_access_key
+    );
+    apply_env!(env_string, "S3_REGION", cfg.app.storage.s3.region, Some);
+}
+
 fn apply_room_env_overrides(cfg: &mut configrs::Config) {
     apply_env!(
         env_byte_size,
@@ -123,6 +174,21 @@ fn apply_room_env_overrides(cfg: &mut configrs::Config) {
         "UPLOAD_RESERVATION_TTL_SECONDS",
         cfg.app.upload.reservation_ttl_seconds
     );
+    apply_env!(
+        env_bool,
+        "UPLOAD_STRIP_IMAGE_METADATA",
+        cfg.app.upload.strip_image_metadata
+    );
+    apply_env!(
+        env_string,
+        "UPLOAD_CHUNK_STAGING_BACKEND",
+        cfg.app.upload.chunk_staging_backend
+    );
+    apply_env!(
+        env_string,
+        "UPLOAD_CHUNK_STAGING_DIR",
+        cfg.app.upload.chunk_staging_dir
+    );
 }
 
 fn apply_gc_env_overrides(cfg: &mut configrs::Config) {
@@ -130,6 +196,34 @@ fn apply_gc_env_overrides(cfg: &mut configrs::Config) {
     apply_env!(env_u32, "GC_BATCH_LIMIT", cfg.app.gc.batch_limit);
 }
 
+fn apply_websocket_env_overrides(cfg: &mut configrs::Config) {
+    apply_env!(
+        env_string,
+        "WEBSOCKET_EVENT_BUS",
+        cfg.app.websocket.event_bus
+    );
+    apply_env!(
+        env_string,
+        "WEBSOCKET_EVENT_CHANNEL",
+        cfg.app.websocket.event_channel
+    );
+    apply_env!(
+        env_u32,
+        "WEBSOCKET_EVENT_LOG_CAPACITY",
+        cfg.app.websocket.event_log_capacity
+    );
+    apply_env!(
+        env_u32,
+        "WEBSOCKET_SEND_QUEUE_DEPTH",
+        cfg.app.websocket.send_queue_depth
+    );
+    apply_env!(
+        env_string,
+        "WEBSOCKET_OVERFLOW_POLICY",
+        cfg.app.websocket.overflow_policy
+    );
+}
+
 fn apply_middleware_env_overrides(cfg: &mut configrs::Config) {
     apply_tracing_env_overrides(cfg);
     apply_request_id_env_overrides(cfg);
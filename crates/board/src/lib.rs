@@ -1,4 +1,5 @@
 #![allow(unused_imports, unused_variables, dead_code)]
+mod checksum;
 mod chunk_temp_storage;
 pub mod cmd;
 pub mod config;
@@ -25,7 +26,6 @@ pub mod websocket;
 mod tests;
 
 use std::net::SocketAddr;
-use std::path::PathBuf;
 use std::sync::Arc;
 
 use axum::body::Body;
@@ -38,9 +38,8 @@ use clap::Parser;
 use shadow_rs::shadow;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
-use crate::config::{AppConfig, AuthConfig, RoomConfig, ServerConfig, StorageConfig};
-use crate::constants::{
-    storage::DEFAULT_STORAGE_ROOT, upload::DEFAULT_UPLOAD_RESERVATION_TTL_SECONDS,
+use crate::config::{
+    AppConfig, AuthConfig, RoomConfig, ServerConfig, StorageConfig, WebsocketConfig,
 };
 use crate::db::{
     DbKind, DbPoolSettings, backup_sqlite_before_migrations, has_pending_sqlite_migrations,
@@ -55,7 +54,10 @@ use crate::repository::room_refresh_token_repository::{
 use crate::scheduler::{SchedulerHandle, TaskRegistration, TaskScheduler};
 use crate::services::{RoomTokenService, refresh_token_service::RefreshTokenService};
 use crate::state::AppState;
-use crate::tasks::{RoomLifecycleTask, TokenCleanupTask, UploadCleanupTask};
+use crate::tasks::{
+    ContentScanTask, IntegrityScrubTask, RoomLifecycleTask, StorageReconcileTask, ThumbnailTask,
+    TokenCleanupTask, UploadCleanupTask,
+};
 use configrs::Config;
 use sqlx::sqlite::SqliteJournalMode;
 
@@ -72,6 +74,24 @@ pub async fn run() -> anyhow::Result<()> {
             let cfg = cfg_service::init(&args)?;
             start_server(&cfg).await?
         }
+        cmd::Cli::Export { args, room, output } => {
+            let cfg = cfg_service::init(&args)?;
+            log_service::init(&cfg);
+            let app_state = init_app_state(&cfg).await?;
+            let output = cmd::export_room(&app_state, &room, output).await?;
+            println!("Exported room '{room}' to {}", output.display());
+        }
+        cmd::Cli::Import {
+            args,
+            archive,
+            slug,
+        } => {
+            let cfg = cfg_service::init(&args)?;
+            log_service::init(&cfg);
+            let app_state = init_app_state(&cfg).await?;
+            let slug = cmd::import_room(&app_state, &archive, slug).await?;
+            println!("Imported room '{slug}' from {}", archive.display());
+        }
         #[cfg(feature = "completions")]
         cmd::Cli::Completions { shell } => cmd::output_completions(shell)?,
     }
@@ -83,7 +103,41 @@ async fn start_server(cfg: &Config) -> anyhow::Result<()> {
     // Config Debug redacts jwt.secret, room passwords, and DB credentials.
     log_service::init(cfg);
     log::info!("Starting server with config: {cfg:#?}");
+    let app_state = init_app_state(cfg).await?;
+
+    let addr: SocketAddr = format!("{}:{}", cfg.app.server.addr, cfg.app.server.port).parse()?;
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let actual_addr = listener.local_addr()?;
+
+    let (scalar_path, router, middleware_tasks) = build_api_router(app_state.clone(), cfg)?;
+    // 多实例部署时接收其他实例发布的房间事件
+    let event_bus = app_state.broadcaster.event_bus().clone();
+    let scheduler = start_scheduler(app_state, cfg, middleware_tasks)?;
+    let scheduler_cancellation = scheduler.cancellation_token();
+    let event_listener = tokio::spawn({
+        let cancellation = scheduler_cancellation.clone();
+        async move { event_bus.run(cancellation).await }
+    });
+
+    println!("Server listening on http://{actual_addr}");
+    println!("Scalar listening on http://{actual_addr}{}", scalar_path);
+
+    let result = axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal(scheduler_cancellation))
+    .await
+    .map_err(anyhow::Error::new);
+    scheduler.shutdown().await;
+    if let Err(error) = event_listener.await {
+        log::warn!("Room event listener stopped abnormally: {error}");
+    }
+    result
+}
 
+/// 初始化数据库、执行迁移并构建应用状态，供服务启动与离线子命令共用
+async fn init_app_state(cfg: &Config) -> anyhow::Result<Arc<AppState>> {
     // 初始化数据库
     let db_url = cfg.app.database.url.clone();
     let sqlite_journal = parse_sqlite_journal_mode(cfg.app.database.journal_mode.as_str());
@@ -112,18 +166,7 @@ async fn start_server(cfg: &Config) -> anyhow::Result<()> {
             min_connections: cfg.app.database.min_connections.unwrap_or(1),
             journal_mode: cfg.app.database.journal_mode.clone(),
         },
-        storage: StorageConfig {
-            root: if cfg.app.storage.root.trim().is_empty() {
-                PathBuf::from(DEFAULT_STORAGE_ROOT)
-            } else {
-                PathBuf::from(&cfg.app.storage.root)
-            },
-            upload_reservation_ttl_seconds: if cfg.app.upload.reservation_ttl_seconds <= 0 {
-                DEFAULT_UPLOAD_RESERVATION_TTL_SECONDS
-            } else {
-                cfg.app.upload.reservation_ttl_seconds
-            },
-        },
+        storage: StorageConfig::try_from(&cfg.app)?,
         room: RoomConfig::try_from(&cfg.app.room)?,
         auth: AuthConfig::new(cfg.app.jwt.secret.clone())
             .map_err(|e| anyhow::anyhow!("Invalid JWT config: {}", e))?
@@ -134,10 +177,19 @@ async fn start_server(cfg: &Config) -> anyhow::Result<()> {
                 cfg.app.jwt.cleanup_interval_seconds,
                 cfg.app.jwt.enable_refresh_token_rotation,
             ),
+        websocket: WebsocketConfig::try_from(&cfg.app.websocket)?,
     };
 
     // 创建应用状态
     let app_state = Arc::new(AppState::new(app_config, db_pool)?);
+    // 启动前确认存储后端可达（S3 时即 endpoint 可连通、bucket 存在且凭据有效）
+    app_state.storage().check().await.map_err(|e| {
+        anyhow::anyhow!(
+            "Storage backend '{}' is not reachable: {}",
+            app_state.config.storage.backend,
+            e
+        )
+    })?;
     let migrated_passwords = crate::services::migrate_legacy_room_passwords(
         &app_state.db_pool,
         app_state.room_password_service(),
@@ -150,26 +202,7 @@ async fn start_server(cfg: &Config) -> anyhow::Result<()> {
         );
     }
 
-    let addr: SocketAddr = format!("{}:{}", cfg.app.server.addr, cfg.app.server.port).parse()?;
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    let actual_addr = listener.local_addr()?;
-
-    let (scalar_path, router, middleware_tasks) = build_api_router(app_state.clone(), cfg)?;
-    let scheduler = start_scheduler(app_state, cfg, middleware_tasks)?;
-    let scheduler_cancellation = scheduler.cancellation_token();
-
-    println!("Server listening on http://{actual_addr}");
-    println!("Scalar listening on http://{actual_addr}{}", scalar_path);
-
-    let result = axum::serve(
-        listener,
-        router.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .with_graceful_shutdown(shutdown_signal(scheduler_cancellation))
-    .await
-    .map_err(anyhow::Error::new);
-    scheduler.shutdown().await;
-    result
+    Ok(app_state)
 }
 
 fn parse_sqlite_journal_mode(value: &str) -> SqliteJournalMode {
@@ -375,9 +408,59 @@ fn start_scheduler(
         TaskRegistration {
             interval: room_interval,
             timeout,
-            task: Arc::new(UploadCleanupTask::new(upload_repository)),
+            task: Arc::new(UploadCleanupTask::new(
+                upload_repository,
+                app_state.services.chunk_staging.clone(),
+            )),
         },
     ];
+    let thumbnails = &app_state.config.storage.thumbnails;
+    if thumbnails.enabled {
+        registrations.push(TaskRegistration {
+            interval: std::time::Duration::from_secs(thumbnails.interval_seconds),
+            timeout,
+            task: Arc::new(ThumbnailTask::new(
+                app_state.services.thumbnails.clone(),
+                app_state.services.room_repository.clone(),
+                app_state.broadcaster.clone(),
+            )),
+        });
+    }
+    let scan = &app_state.config.storage.scan;
+    if scan.enabled {
+        // 每个文件都可能用满扫描超时，整轮超时按批量放大
+        let per_batch =
+            std::time::Duration::from_secs(scan.timeout_seconds * u64::from(scan.batch_limit));
+        registrations.push(TaskRegistration {
+            interval: std::time::Duration::from_secs(scan.interval_seconds),
+            timeout: timeout.max(per_batch),
+            task: Arc::new(ContentScanTask::new(
+                app_state.services.content_scan.clone(),
+                app_state.services.room_repository.clone(),
+                app_state.broadcaster.clone(),
+            )),
+        });
+    }
+    let reconcile = &app_state.config.storage.reconcile;
+    if reconcile.enabled {
+        registrations.push(TaskRegistration {
+            interval: std::time::Duration::from_secs(reconcile.interval_seconds),
+            timeout,
+            task: Arc::new(StorageReconcileTask::new(
+                app_state.services.storage_reconcile.clone(),
+            )),
+        });
+    }
+    let scrub = &app_state.config.storage.scrub;
+    if scrub.enabled {
+        registrations.push(TaskRegistration {
+            interval: std::time::Duration::from_secs(scrub.interval_seconds),
+            timeout,
+            task: Arc::new(IntegrityScrubTask::new(
+                app_state.services.integrity_scrub.clone(),
+            )),
+        });
+    }
     registrations.extend(
         middleware_tasks
             .into_iter()
@@ -1,9 +1,24 @@
 use axum::Router;
+use axum::http::HeaderName;
 use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, AllowPrivateNetwork, CorsLayer};
 
 // Re-export CorsConfig from configrs
 pub use configrs::CorsConfig;
 
+/// Response headers browsers must be allowed to read for tus uploads,
+/// exposed in addition to the configured `expose_headers`
+const TUS_EXPOSED_HEADERS: [HeaderName; 9] = [
+    HeaderName::from_static("location"),
+    HeaderName::from_static("tus-resumable"),
+    HeaderName::from_static("tus-version"),
+    HeaderName::from_static("tus-extension"),
+    HeaderName::from_static("tus-checksum-algorithm"),
+    HeaderName::from_static("upload-offset"),
+    HeaderName::from_static("upload-length"),
+    HeaderName::from_static("upload-expires"),
+    HeaderName::from_static("content-location"),
+];
+
 /// Apply CORS middleware to the router
 pub fn apply_cors_layer<S>(config: &CorsConfig, router: Router<S>) -> Router<S>
 where
@@ -67,6 +82,14 @@ where
         }
     }
 
+    let mut exposed: Vec<HeaderName> = config
+        .expose_headers
+        .iter()
+        .filter_map(|h| h.parse().ok())
+        .collect();
+    exposed.extend(TUS_EXPOSED_HEADERS);
+    cors = cors.expose_headers(exposed);
+
     // Configure other settings
     cors = cors
         .allow_credentials(config.allow_credentials)
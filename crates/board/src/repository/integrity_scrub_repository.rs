@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+
+use crate::db::DbPool;
+use crate::models::content::IntegrityStatus;
+use crate::models::room::row_utils::format_naive_datetime;
+
+/// 等待校验的存储文件
+#[derive(Debug, Clone)]
+pub struct ScrubCandidate {
+    pub id: i64,
+    pub path: String,
+    /// 基准摘要，为空表示首次遇到
+    pub hash: Option<String>,
+    pub integrity_status: IntegrityStatus,
+}
+
+/// 文件完整性巡检使用的查询
+#[derive(Clone)]
+pub struct IntegrityScrubRepository {
+    pool: Arc<DbPool>,
+}
+
+impl IntegrityScrubRepository {
+    pub fn new(pool: Arc<DbPool>) -> Self {
+        Self { pool }
+    }
+
+    /// 从未校验或上次校验早于 `verified_before` 的文件，最久未校验的排在前面
+    ///
+    /// 已标记为 `Missing` 的内容等对账任务在文件恢复后清除标记，这里跳过。
+    pub async fn list_due(
+        &self,
+        verified_before: NaiveDateTime,
+        limit: u32,
+    ) -> Result<Vec<ScrubCandidate>> {
+        let rows: Vec<(i64, String, Option<String>, IntegrityStatus)> = sqlx::query_as(
+            r#"
+            SELECT id, path, hash, integrity_status
+            FROM room_contents
+            WHERE path IS NOT NULL
+              AND integrity_status <> $1
+              AND (verified_at IS NULL OR verified_at <= $2)
+            ORDER BY verified_at IS NOT NULL, verified_at, id
+            LIMIT $3
+            "#,
+        )
+        .bind(IntegrityStatus::Missing)
+        .bind(format_naive_datetime(verified_before))
+        .bind(i64::from(limit))
+        .fetch_all(&*self.pool)
+        .await
+        .context("failed to list contents due for integrity scrub")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, path, hash, integrity_status)| ScrubCandidate {
+                id,
+                path,
+                hash,
+                integrity_status,
+            })
+            .collect())
+    }
+
+    /// 为没有摘要的内容记录基准摘要
+    ///
+    /// 期间文件被替换（`path` 已变化）或已有摘要时返回 `false`。
+    pub async fn record_baseline(
+        &self,
+        content_id: i64,
+        path: &str,
+        hash: &str,
+        now: NaiveDateTime,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE room_contents
+            SET hash = $1, verified_at = $2
+            WHERE id = $3 AND path = $4 AND hash IS NULL
+            "#,
+        )
+        .bind(hash)
+        .bind(format_naive_datetime(now))
+        .bind(content_id)
+        .bind(path)
+        .execute(&*self.pool)
+        .await
+        .context("failed to record baseline hash")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 记录一次校验结果，状态变化时同时更新 `updated_at`
+    ///
+    /// 期间文件被替换或摘要被改写时返回 `false`。
+    pub async fn finish_verification(
+        &self,
+        content_id: i64,
+        path: &str,
+        hash: &str,
+        status: IntegrityStatus,
+        now: NaiveDateTime,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE room_contents
+            SET integrity_status = $1,
+                verified_at = $2,
+                updated_at = CASE WHEN integrity_status = $1 THEN updated_at ELSE $2 END
+            WHERE id = $3 AND path = $4 AND hash = $5 AND integrity_status <> $6
+            "#,
+        )
+        .bind(status)
+        .bind(format_naive_datetime(now))
+        .bind(content_id)
+        .bind(path)
+        .bind(hash)
+        .bind(IntegrityStatus::Missing)
+        .execute(&*self.pool)
+        .await
+        .context("failed to record integrity verification")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 巡检时发现文件不存在，标记为 `Missing` 并记录校验时间
+    ///
+    /// 期间文件被替换或已被标记时返回 `false`。
+    pub async fn mark_missing(
+        &self,
+        content_id: i64,
+        path: &str,
+        now: NaiveDateTime,
+    ) -> Result<bool> {
+        let now = format_naive_datetime(now);
+        let result = sqlx::query(
+            r#"
+            UPDATE room_contents
+            SET integrity_status = $1, verified_at = $2, updated_at = $2
+            WHERE id = $3 AND path = $4 AND integrity_status <> $1
+            "#,
+        )
+        .bind(IntegrityStatus::Missing)
+        .bind(now)
+        .bind(content_id)
+        .bind(path)
+        .execute(&*self.pool)
+        .await
+        .context("failed to mark content file as missing")?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -1,17 +1,25 @@
+pub mod integrity_scrub_repository;
 pub mod room_access_repository;
 pub mod room_chunk_upload_repository;
 pub mod room_content_repository;
+pub mod room_event_repository;
 pub mod room_lifecycle_repository;
 pub mod room_refresh_token_repository;
 pub mod room_repository;
 pub mod room_token_repository;
 pub mod room_upload_reservation_repository;
+pub mod storage_blob_repository;
+pub mod storage_reconcile_repository;
 
+pub use integrity_scrub_repository::*;
 pub use room_access_repository::*;
 pub use room_chunk_upload_repository::*;
 pub use room_content_repository::*;
+pub use room_event_repository::*;
 pub use room_lifecycle_repository::*;
 pub use room_refresh_token_repository::*;
 pub use room_repository::*;
 pub use room_token_repository::*;
 pub use room_upload_reservation_repository::*;
+pub use storage_blob_repository::*;
+pub use storage_reconcile_repository::*;
@@ -15,6 +15,7 @@ const SELECT_BASE: &str = r#"
         chunk_size,
         chunk_hash,
         upload_status,
+        hash_algorithm,
         CAST(created_at AS TEXT) as created_at,
         CAST(updated_at AS TEXT) as updated_at
     FROM room_chunk_uploads
@@ -85,9 +86,10 @@ impl IRoomChunkUploadRepository for RoomChunkUploadRepository {
                 chunk_size,
                 chunk_hash,
                 upload_status,
+                hash_algorithm,
                 created_at,
                 updated_at
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
             RETURNING id
             "#,
         )
@@ -96,6 +98,7 @@ impl IRoomChunkUploadRepository for RoomChunkUploadRepository {
         .bind(upload.chunk_size)
         .bind(&upload.chunk_hash)
         .bind(upload.upload_status.to_string())
+        .bind(upload.hash_algorithm.to_string())
         .bind(created_at)
         .bind(updated_at)
         .fetch_one(&mut *tx)
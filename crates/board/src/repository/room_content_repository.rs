@@ -2,12 +2,15 @@ use anyhow::{Result, anyhow};
 use async_trait::async_trait;
 use chrono::Utc;
 use sqlx::Any;
+use std::path::Path;
 use std::sync::Arc;
 
 use crate::models::room::row_utils::format_naive_datetime;
+use crate::repository::{ReleasedObjects, StorageBlobRepository};
+use crate::storage::key;
 use crate::{
     db::DbPool,
-    models::content::{ContentType, RoomContent},
+    models::content::{ContentType, RoomContent, ScanStatus, ThumbnailStatus},
 };
 
 const CONTENT_SELECT_BASE: &str = r#"
@@ -21,7 +24,12 @@ const CONTENT_SELECT_BASE: &str = r#"
         file_name,
         size,
         mime_type,
+        detected_mime_type,
+        hash,
         sequence_number,
+        thumbnail_status,
+        scan_status,
+        integrity_status,
         CAST(created_at AS TEXT) as created_at,
         CAST(updated_at AS TEXT) as updated_at
     FROM room_contents
@@ -68,6 +76,46 @@ impl RoomContentRepository {
         Self { pool }
     }
 
+    /// 删除指定内容，并在同一事务中释放其存储对象的引用
+    ///
+    /// 返回实际删除的内容 id 与提交后需要删除的对象；并发删除时已被他人删除的行不会重复释放。
+    pub async fn delete_releasing(
+        &self,
+        room_id: i64,
+        content_ids: &[i64],
+        storage_root: &Path,
+    ) -> Result<(Vec<i64>, ReleasedObjects)> {
+        if content_ids.is_empty() {
+            return Ok((Vec::new(), ReleasedObjects::default()));
+        }
+
+        let mut sql = String::from("DELETE FROM room_contents WHERE room_id = $1 AND id IN (");
+        for i in 0..content_ids.len() {
+            if i > 0 {
+                sql.push_str(", ");
+            }
+            sql.push('$');
+            sql.push_str(&(i + 2).to_string());
+        }
+        sql.push_str(") RETURNING id, path");
+
+        let mut tx = self.pool.begin().await?;
+        let mut query = sqlx::query_as::<_, (i64, Option<String>)>(&sql).bind(room_id);
+        for id in content_ids {
+            query = query.bind(id);
+        }
+        let rows = query.fetch_all(&mut *tx).await?;
+        let object_keys = rows
+            .iter()
+            .filter_map(|(_, path)| path.as_deref())
+            .map(|path| key::object_key(storage_root, path))
+            .collect();
+        let released = StorageBlobRepository::release_keys(&mut tx, object_keys).await?;
+        tx.commit().await?;
+
+        Ok((rows.into_iter().map(|(id, _)| id).collect(), released))
+    }
+
     async fn fetch_optional_by_id<'e, E>(
         executor: E,
         content_id: i64,
@@ -91,6 +139,104 @@ impl RoomContentRepository {
             .await?
             .ok_or_else(|| anyhow!("room content not found for id {}", content_id))
     }
+
+    /// 在单个事务中批量写入内容，保留记录自带的 `created_at` / `updated_at`
+    ///
+    /// 用于房间导入，任一条失败时整体回滚。
+    pub async fn create_batch(&self, contents: &[RoomContent]) -> Result<Vec<RoomContent>> {
+        let mut tx = self.pool.begin().await?;
+        let mut created = Vec::with_capacity(contents.len());
+        for room_content in contents {
+            let id: i64 = sqlx::query_scalar(
+                r#"
+                INSERT INTO room_contents
+                    (room_id, content_type, text, url, path, file_name, size, mime_type, detected_mime_type, hash, sequence_number, thumbnail_status, scan_status, integrity_status, created_at, updated_at)
+                VALUES
+                    ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+                RETURNING id
+                "#,
+            )
+            .bind(room_content.room_id)
+            .bind(room_content.content_type)
+            .bind(&room_content.text)
+            .bind(&room_content.url)
+            .bind(&room_content.path)
+            .bind(&room_content.file_name)
+            .bind(room_content.size)
+            .bind(&room_content.mime_type)
+            .bind(&room_content.detected_mime_type)
+            .bind(&room_content.hash)
+            .bind(room_content.sequence_number)
+            .bind(room_content.thumbnail_status)
+            .bind(room_content.scan_status)
+            .bind(room_content.integrity_status)
+            .bind(format_naive_datetime(room_content.created_at))
+            .bind(format_naive_datetime(room_content.updated_at))
+            .fetch_one(&mut *tx)
+            .await?;
+            created.push(Self::fetch_by_id_or_err(&mut *tx, id).await?);
+        }
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    /// 按 id 顺序列出等待生成缩略图的内容，未通过扫描的文件不解码
+    pub async fn list_pending_thumbnails(&self, limit: u32) -> Result<Vec<RoomContent>> {
+        let sql = format!(
+            "{CONTENT_SELECT_BASE} WHERE thumbnail_status = $1 AND scan_status = $2 ORDER BY id ASC LIMIT $3"
+        );
+        let rows = sqlx::query_as::<_, RoomContent>(&sql)
+            .bind(ThumbnailStatus::Pending)
+            .bind(ScanStatus::Clean)
+            .bind(i64::from(limit))
+            .fetch_all(&*self.pool)
+            .await?;
+        Ok(rows)
+    }
+
+    /// 更新缩略图状态，内容已被删除时返回 `false`
+    pub async fn set_thumbnail_status(
+        &self,
+        content_id: i64,
+        status: ThumbnailStatus,
+    ) -> Result<bool> {
+        let now = format_naive_datetime(Utc::now().naive_utc());
+        let result = sqlx::query(
+            "UPDATE room_contents SET thumbnail_status = $1, updated_at = $2 WHERE id = $3",
+        )
+        .bind(status)
+        .bind(now)
+        .bind(content_id)
+        .execute(&*self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 按 id 顺序列出等待扫描的内容
+    pub async fn list_pending_scans(&self, limit: u32) -> Result<Vec<RoomContent>> {
+        let sql = format!("{CONTENT_SELECT_BASE} WHERE scan_status = $1 ORDER BY id ASC LIMIT $2");
+        let rows = sqlx::query_as::<_, RoomContent>(&sql)
+            .bind(ScanStatus::Pending)
+            .bind(i64::from(limit))
+            .fetch_all(&*self.pool)
+            .await?;
+        Ok(rows)
+    }
+
+    /// 记录扫描结论，仅更新仍处于 `Pending` 的内容；已被删除时返回 `false`
+    pub async fn finish_scan(&self, content_id: i64, status: ScanStatus) -> Result<bool> {
+        let now = format_naive_datetime(Utc::now().naive_utc());
+        let result = sqlx::query(
+            "UPDATE room_contents SET scan_status = $1, updated_at = $2 WHERE id = $3 AND scan_status = $4",
+        )
+        .bind(status)
+        .bind(now)
+        .bind(content_id)
+        .bind(ScanStatus::Pending)
+        .execute(&*self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
 }
 
 #[async_trait]
@@ -113,9 +259,9 @@ impl IRoomContentRepository for RoomContentRepository {
         let id: i64 = sqlx::query_scalar(
             r#"
             INSERT INTO room_contents
-                (room_id, content_type, text, url, path, file_name, size, mime_type, sequence_number, created_at, updated_at)
+                (room_id, content_type, text, url, path, file_name, size, mime_type, detected_mime_type, hash, sequence_number, thumbnail_status, scan_status, integrity_status, created_at, updated_at)
             VALUES
-                ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
             RETURNING id
             "#,
         )
@@ -127,7 +273,12 @@ impl IRoomContentRepository for RoomContentRepository {
         .bind(&room_content.file_name)
         .bind(room_content.size)
         .bind(&room_content.mime_type)
+        .bind(&room_content.detected_mime_type)
+        .bind(&room_content.hash)
         .bind(room_content.sequence_number)
+        .bind(room_content.thumbnail_status)
+        .bind(room_content.scan_status)
+        .bind(room_content.integrity_status)
         .bind(now_str.clone())
         .bind(now_str)
         .fetch_one(&mut *tx)
@@ -154,8 +305,10 @@ impl IRoomContentRepository for RoomContentRepository {
             UPDATE room_contents SET
                 room_id = $1, content_type = $2, text = $3,
                 url = $4, path = $5, file_name = $6, size = $7, mime_type = $8,
-                sequence_number = $9, updated_at = $10
-            WHERE id = $11
+                detected_mime_type = $9, hash = $10, sequence_number = $11,
+                thumbnail_status = $12, scan_status = $13, integrity_status = $14,
+                updated_at = $15
+            WHERE id = $16
             "#,
         )
         .bind(room_content.room_id)
@@ -166,7 +319,12 @@ impl IRoomContentRepository for RoomContentRepository {
         .bind(&room_content.file_name)
         .bind(room_content.size)
         .bind(&room_content.mime_type)
+        .bind(&room_content.detected_mime_type)
+        .bind(&room_content.hash)
         .bind(room_content.sequence_number)
+        .bind(room_content.thumbnail_status)
+        .bind(room_content.scan_status)
+        .bind(room_content.integrity_status)
         .bind(now_str)
         .bind(content_id)
         .execute(&mut *tx)
@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+
+use crate::db::DbPool;
+use crate::models::room::row_utils::format_naive_datetime;
+
+/// 分配事件 ID 时遇到并发冲突的重试次数
+const APPEND_ATTEMPTS: usize = 5;
+
+/// `room_events` 房间事件日志
+#[derive(Clone)]
+pub struct RoomEventRepository {
+    pool: Arc<DbPool>,
+}
+
+impl RoomEventRepository {
+    pub fn new(pool: Arc<DbPool>) -> Self {
+        Self { pool }
+    }
+
+    /// 追加一条事件，返回分配的事件 ID
+    ///
+    /// ID 为该房间当前最大 ID 加一；多个实例同时写入时主键冲突，重新分配即可。
+    pub async fn append(&self, room_name: &str, message: &str) -> Result<i64> {
+        let now = format_naive_datetime(Utc::now().naive_utc());
+        for _ in 0..APPEND_ATTEMPTS {
+            let result = sqlx::query_scalar(
+                r#"
+                INSERT INTO room_events (room_name, event_id, message, created_at)
+                SELECT $1, COALESCE(MAX(event_id), 0) + 1, $2, $3
+                FROM room_events
+                WHERE room_name = $1
+                RETURNING event_id
+                "#,
+            )
+            .bind(room_name)
+            .bind(message)
+            .bind(&now)
+            .fetch_one(&*self.pool)
+            .await;
+            match result {
+                Ok(event_id) => return Ok(event_id),
+                Err(sqlx::Error::Database(error)) if error.is_unique_violation() => continue,
+                Err(error) => return Err(error).context("failed to append room event"),
+            }
+        }
+        anyhow::bail!("failed to allocate an event id for room {room_name}")
+    }
+
+    /// 删除 ID 不大于 `event_id` 的事件
+    pub async fn prune(&self, room_name: &str, event_id: i64) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM room_events WHERE room_name = $1 AND event_id <= $2")
+            .bind(room_name)
+            .bind(event_id)
+            .execute(&*self.pool)
+            .await
+            .context("failed to prune room events")?;
+        Ok(result.rows_affected())
+    }
+
+    /// 日志中最新的事件 ID，没有事件时为 `None`
+    pub async fn latest_event_id(&self, room_name: &str) -> Result<Option<i64>> {
+        sqlx::query_scalar("SELECT MAX(event_id) FROM room_events WHERE room_name = $1")
+            .bind(room_name)
+            .fetch_one(&*self.pool)
+            .await
+            .context("failed to load latest room event id")
+    }
+
+    /// ID 大于 `event_id` 的事件，按 ID 升序
+    pub async fn list_after(&self, room_name: &str, event_id: i64) -> Result<Vec<(i64, String)>> {
+        sqlx::query_as(
+            "SELECT event_id, message FROM room_events WHERE room_name = $1 AND event_id > $2 ORDER BY event_id",
+        )
+        .bind(room_name)
+        .bind(event_id)
+        .fetch_all(&*self.pool)
+        .await
+        .context("failed to list room events")
+    }
+}
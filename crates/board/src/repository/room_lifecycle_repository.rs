@@ -1,3 +1,4 @@
+use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
@@ -7,6 +8,8 @@ use sqlx::Row;
 use crate::db::DbPool;
 use crate::models::Room;
 use crate::models::room::row_utils::{format_naive_datetime, parse_any_timestamp};
+use crate::repository::{ReleasedObjects, StorageBlobRepository};
+use crate::storage::key;
 
 #[derive(Debug, Clone)]
 pub struct RoomLifecycleCandidate {
@@ -203,7 +206,7 @@ impl RoomLifecycleRepository {
                    CAST(expire_at AS TEXT) AS expire_at,
                    CAST(created_at AS TEXT) AS created_at,
                    CAST(updated_at AS TEXT) AS updated_at,
-                   permission
+                   permission, strip_image_metadata
             FROM rooms WHERE id = $1
             "#,
         )
@@ -213,22 +216,38 @@ impl RoomLifecycleRepository {
         .context("failed to reload room lifecycle candidate")
     }
 
-    pub async fn list_content_paths(&self, room_id: i64) -> Result<Vec<String>> {
-        sqlx::query_scalar("SELECT path FROM room_contents WHERE room_id = $1 AND path IS NOT NULL")
-            .bind(room_id)
-            .fetch_all(&*self.pool)
-            .await
-            .context("failed to list room storage paths")
-    }
-
-    pub async fn delete_room_graph(&self, room_id: i64) -> Result<bool> {
+    /// 删除房间及其全部记录，并在同一事务中释放内容持有的存储对象
+    ///
+    /// 返回房间是否存在，以及提交后需要删除的对象；事务失败时引用计数不变，可以安全重试。
+    pub async fn delete_room_graph(
+        &self,
+        room_id: i64,
+        storage_root: &Path,
+    ) -> Result<(bool, ReleasedObjects)> {
         let mut tx = self.pool.begin().await?;
+        let paths: Vec<String> = sqlx::query_scalar(
+            "DELETE FROM room_contents WHERE room_id = $1 AND path IS NOT NULL RETURNING path",
+        )
+        .bind(room_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let object_keys = paths
+            .iter()
+            .map(|path| key::object_key(storage_root, path))
+            .collect();
+        let released = StorageBlobRepository::release_keys(&mut tx, object_keys).await?;
         sqlx::query(
             "DELETE FROM room_chunk_uploads WHERE reservation_id IN (SELECT id FROM room_upload_reservations WHERE room_id = $1)",
         )
         .bind(room_id)
         .execute(&mut *tx)
         .await?;
+        sqlx::query(
+            "DELETE FROM room_events WHERE room_name = (SELECT slug FROM rooms WHERE id = $1)",
+        )
+        .bind(room_id)
+        .execute(&mut *tx)
+        .await?;
         for table in [
             "room_upload_reservations",
             "room_refresh_tokens",
@@ -246,7 +265,7 @@ impl RoomLifecycleRepository {
             .rows_affected()
             > 0;
         tx.commit().await?;
-        Ok(deleted)
+        Ok((deleted, released))
     }
 
     pub async fn release_private_names(
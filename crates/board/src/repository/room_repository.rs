@@ -24,7 +24,8 @@ const ROOM_SELECT_BASE: &str = r#"
         CAST(expire_at AS TEXT) as expire_at,
         CAST(created_at AS TEXT) as created_at,
         CAST(updated_at AS TEXT) as updated_at,
-        permission
+        permission,
+        strip_image_metadata
     FROM rooms
 "#;
 
@@ -127,14 +128,16 @@ impl RoomRepository {
                 max_size = $2,
                 max_times_entered = $3,
                 expire_at = $4,
-                updated_at = $5
-            WHERE id = $6
+                strip_image_metadata = $5,
+                updated_at = $6
+            WHERE id = $7
             "#,
         )
         .bind(&room.password)
         .bind(room.max_size)
         .bind(room.max_times_entered)
         .bind(format_optional_naive_datetime(room.expire_at))
+        .bind(room.strip_image_metadata.map(i64::from))
         .bind(now)
         .bind(room_id)
         .execute(&mut *tx)
@@ -149,6 +152,22 @@ impl RoomRepository {
             .id
             .ok_or_else(|| anyhow!("room id is required for permission update"))?;
         let mut tx = self.pool.begin().await?;
+        let old_slug: String = sqlx::query_scalar("SELECT slug FROM rooms WHERE id = $1")
+            .bind(room_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if old_slug != room.slug {
+            // 事件日志按地址记录，随房间一起迁移到新地址
+            sqlx::query("DELETE FROM room_events WHERE room_name = $1")
+                .bind(&room.slug)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("UPDATE room_events SET room_name = $1 WHERE room_name = $2")
+                .bind(&room.slug)
+                .bind(&old_slug)
+                .execute(&mut *tx)
+                .await?;
+        }
         let now = format_naive_datetime(Utc::now().naive_utc());
         sqlx::query("UPDATE rooms SET permission = $1, slug = $2, updated_at = $3 WHERE id = $4")
             .bind(i64::from(room.permission.bits()))
@@ -210,8 +229,8 @@ impl IRoomRepository for RoomRepository {
             INSERT INTO rooms (
                 name, slug, password, status, max_size, current_size,
                 max_times_entered, current_times_entered, expire_at,
-                created_at, updated_at, permission
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                created_at, updated_at, permission, strip_image_metadata
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
             ON CONFLICT DO NOTHING
             RETURNING id
             "#,
@@ -228,6 +247,7 @@ impl IRoomRepository for RoomRepository {
         .bind(now_str.clone())
         .bind(now_str.clone())
         .bind(i64::from(room.permission.bits()))
+        .bind(room.strip_image_metadata.map(i64::from))
         .fetch_optional(&mut *tx)
         .await?;
 
@@ -266,8 +286,8 @@ impl IRoomRepository for RoomRepository {
             UPDATE rooms SET
                 password = $1, status = $2, max_size = $3, current_size = $4,
                 max_times_entered = $5, current_times_entered = $6, expire_at = $7,
-                updated_at = $8, permission = $9, slug = $10
-            WHERE id = $11
+                updated_at = $8, permission = $9, slug = $10, strip_image_metadata = $11
+            WHERE id = $12
             "#,
         )
         .bind(&room.password)
@@ -280,6 +300,7 @@ impl IRoomRepository for RoomRepository {
         .bind(now_str)
         .bind(i64::from(room.permission.bits()))
         .bind(&room.slug)
+        .bind(room.strip_image_metadata.map(i64::from))
         .bind(room_id)
         .execute(&mut *tx)
         .await?;
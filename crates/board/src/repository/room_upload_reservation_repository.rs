@@ -1,6 +1,6 @@
 use anyhow::{Result, anyhow};
 use async_trait::async_trait;
-use chrono::{Duration, Utc};
+use chrono::{Duration, NaiveDateTime, Utc};
 use sqlx::Any;
 use std::sync::Arc;
 
@@ -31,7 +31,8 @@ const SELECT_BASE: &str = r#"
         uploaded_chunks,
         file_hash,
         chunk_size,
-        upload_status
+        upload_status,
+        hash_algorithm
     FROM room_upload_reservations
 "#;
 
@@ -77,6 +78,21 @@ pub trait IRoomUploadReservationRepository: Send + Sync {
     async fn delete(&self, reservation_id: i64) -> Result<bool>;
     async fn list_expired_chunked_ids(&self) -> Result<Vec<i64>>;
     async fn purge_expired(&self) -> Result<u64>;
+    /// 在没有其他未过期租约时占用预留的写入租约，成功返回 `true`
+    async fn acquire_write_lease(
+        &self,
+        reservation_id: i64,
+        lease: &str,
+        until: NaiveDateTime,
+    ) -> Result<bool>;
+    /// 延长仍由 `lease` 持有的租约，租约已被取代时返回 `false`
+    async fn renew_write_lease(
+        &self,
+        reservation_id: i64,
+        lease: &str,
+        until: NaiveDateTime,
+    ) -> Result<bool>;
+    async fn release_write_lease(&self, reservation_id: i64, lease: &str) -> Result<()>;
 }
 
 pub struct RoomUploadReservationRepository {
@@ -131,7 +147,8 @@ impl RoomUploadReservationRepository {
                 CAST(expire_at AS TEXT) as expire_at,
                 CAST(created_at AS TEXT) as created_at,
                 CAST(updated_at AS TEXT) as updated_at,
-                permission
+                permission,
+                strip_image_metadata
             FROM rooms
             WHERE id = $1
             "#,
@@ -174,8 +191,9 @@ impl IRoomUploadReservationRepository for RoomUploadReservationRepository {
                 uploaded_chunks,
                 file_hash,
                 chunk_size,
-                upload_status
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+                upload_status,
+                hash_algorithm
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
             RETURNING id
             "#,
         )
@@ -195,6 +213,7 @@ impl IRoomUploadReservationRepository for RoomUploadReservationRepository {
         .bind(&reservation.file_hash)
         .bind(reservation.chunk_size)
         .bind(upload_status)
+        .bind(reservation.hash_algorithm)
         .fetch_one(&mut *tx)
         .await?;
 
@@ -595,4 +614,64 @@ impl IRoomUploadReservationRepository for RoomUploadReservationRepository {
         tx.commit().await?;
         Ok(result.rows_affected())
     }
+
+    async fn acquire_write_lease(
+        &self,
+        reservation_id: i64,
+        lease: &str,
+        until: NaiveDateTime,
+    ) -> Result<bool> {
+        let now = format_naive_datetime(Utc::now().naive_utc());
+        let result = sqlx::query(
+            r#"
+            UPDATE room_upload_reservations
+            SET write_lease = $1, write_lease_until = $2
+            WHERE id = $3
+              AND (write_lease IS NULL OR write_lease_until <= $4)
+            "#,
+        )
+        .bind(lease)
+        .bind(format_naive_datetime(until))
+        .bind(reservation_id)
+        .bind(now)
+        .execute(&*self.pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn renew_write_lease(
+        &self,
+        reservation_id: i64,
+        lease: &str,
+        until: NaiveDateTime,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE room_upload_reservations
+            SET write_lease_until = $1
+            WHERE id = $2 AND write_lease = $3
+            "#,
+        )
+        .bind(format_naive_datetime(until))
+        .bind(reservation_id)
+        .bind(lease)
+        .execute(&*self.pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn release_write_lease(&self, reservation_id: i64, lease: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE room_upload_reservations
+            SET write_lease = NULL, write_lease_until = NULL
+            WHERE id = $1 AND write_lease = $2
+            "#,
+        )
+        .bind(reservation_id)
+        .bind(lease)
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
 }
@@ -0,0 +1,198 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use sqlx::AnyConnection;
+
+use crate::db::DbPool;
+use crate::models::room::row_utils::format_naive_datetime;
+use crate::storage::key;
+
+/// 内容记录删除后需要从存储中删除的对象
+///
+/// 在删除记录的同一事务中由 [`StorageBlobRepository::release_keys`] 生成，
+/// 事务提交后交给 `BlobStore::delete_released` 删除。
+#[derive(Debug, Default)]
+pub struct ReleasedObjects {
+    /// 房间内的普通对象
+    pub objects: Vec<String>,
+    /// 最后一个引用已释放、已标记为删除中的 blob 对象
+    pub blobs: Vec<String>,
+}
+
+/// `storage_blobs` 引用计数表
+#[derive(Clone)]
+pub struct StorageBlobRepository {
+    pool: Arc<DbPool>,
+}
+
+impl StorageBlobRepository {
+    pub fn new(pool: Arc<DbPool>) -> Self {
+        Self { pool }
+    }
+
+    /// 为 `hash` 增加一次引用，不存在时创建
+    ///
+    /// 返回对象是否正在被上一个引用的释放者删除，此时调用方须等待删除结束后再检查对象。
+    pub async fn acquire(&self, hash: &str, size: i64) -> Result<bool> {
+        let now = format_naive_datetime(Utc::now().naive_utc());
+        let deleting: i64 = sqlx::query_scalar(
+            r#"
+            INSERT INTO storage_blobs (hash, size, ref_count, created_at, updated_at)
+            VALUES ($1, $2, 1, $3, $3)
+            ON CONFLICT (hash) DO UPDATE SET
+                ref_count = storage_blobs.ref_count + 1,
+                updated_at = excluded.updated_at
+            RETURNING deleting
+            "#,
+        )
+        .bind(hash)
+        .bind(size)
+        .bind(now)
+        .fetch_one(&*self.pool)
+        .await
+        .context("failed to acquire storage blob reference")?;
+        Ok(deleting != 0)
+    }
+
+    /// 释放一次引用
+    ///
+    /// 最后一个引用释放时把记录标记为删除中并返回 `true`，调用方删除对象后须调用
+    /// [`Self::finish_release`]；删除期间新的引用会等待标记清除。
+    pub async fn release(&self, hash: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let claimed = Self::release_in(&mut tx, hash).await?;
+        tx.commit().await?;
+        Ok(claimed)
+    }
+
+    /// 在调用方的事务中释放被删除内容记录持有的对象
+    ///
+    /// 每个 blob 键释放一次引用；普通对象和最后一个引用已释放的 blob 放入返回值，
+    /// 须在事务提交后删除，事务回滚时引用计数随之恢复。
+    pub async fn release_keys(
+        conn: &mut AnyConnection,
+        object_keys: Vec<String>,
+    ) -> Result<ReleasedObjects> {
+        let mut released = ReleasedObjects::default();
+        for object_key in object_keys {
+            match key::blob_hash(&object_key) {
+                Some(hash) => {
+                    if Self::release_in(conn, hash).await? {
+                        released.blobs.push(object_key);
+                    }
+                }
+                None => released.objects.push(object_key),
+            }
+        }
+        Ok(released)
+    }
+
+    async fn release_in(conn: &mut AnyConnection, hash: &str) -> Result<bool> {
+        let now = format_naive_datetime(Utc::now().naive_utc());
+        sqlx::query(
+            "UPDATE storage_blobs SET ref_count = ref_count - 1, updated_at = $1 WHERE hash = $2 AND ref_count > 0",
+        )
+        .bind(now)
+        .bind(hash)
+        .execute(&mut *conn)
+        .await
+        .context("failed to release storage blob reference")?;
+        let claimed = sqlx::query(
+            "UPDATE storage_blobs SET deleting = 1 WHERE hash = $1 AND ref_count = 0 AND deleting = 0",
+        )
+        .bind(hash)
+        .execute(&mut *conn)
+        .await
+        .context("failed to mark storage blob for deletion")?
+        .rows_affected()
+            > 0;
+        Ok(claimed)
+    }
+
+    /// 结束 [`Self::release`] 开始的删除
+    ///
+    /// 期间没有新引用时删除记录；有新引用时只清除标记，由新引用方重新写入对象。
+    /// 对象删除失败时同样删除记录，残留的对象之后被复用或由存储对账回收。
+    pub async fn finish_release(&self, hash: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM storage_blobs WHERE hash = $1 AND ref_count = 0 AND deleting = 1")
+            .bind(hash)
+            .execute(&mut *tx)
+            .await
+            .context("failed to remove unreferenced storage blob")?;
+        sqlx::query("UPDATE storage_blobs SET deleting = 0 WHERE hash = $1")
+            .bind(hash)
+            .execute(&mut *tx)
+            .await
+            .context("failed to clear storage blob deletion mark")?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// 对象是否正在被删除
+    pub async fn is_deleting(&self, hash: &str) -> Result<bool> {
+        let deleting: Option<i64> =
+            sqlx::query_scalar("SELECT deleting FROM storage_blobs WHERE hash = $1")
+                .bind(hash)
+                .fetch_optional(&*self.pool)
+                .await
+                .context("failed to load storage blob state")?;
+        Ok(deleting.is_some_and(|deleting| deleting != 0))
+    }
+
+    /// 清除删除中标记，用于接管中途退出的删除
+    pub async fn clear_deleting(&self, hash: &str) -> Result<()> {
+        sqlx::query("UPDATE storage_blobs SET deleting = 0 WHERE hash = $1")
+            .bind(hash)
+            .execute(&*self.pool)
+            .await
+            .context("failed to clear storage blob deletion mark")?;
+        Ok(())
+    }
+
+    /// 认领对象的写入，已被其他调用方认领且认领未超过 `stale_after` 时返回 `false`
+    ///
+    /// 认领期间只有持有方检查并写入对象，结束后须调用 [`Self::finish_upload`]。
+    pub async fn claim_upload(&self, hash: &str, stale_after: Duration) -> Result<bool> {
+        let now = Utc::now().naive_utc();
+        let stale_before = now - chrono::Duration::from_std(stale_after)?;
+        let claimed = sqlx::query(
+            r#"
+            UPDATE storage_blobs
+            SET upload_started_at = $1
+            WHERE hash = $2
+              AND (upload_started_at IS NULL OR upload_started_at < $3)
+            "#,
+        )
+        .bind(format_naive_datetime(now))
+        .bind(hash)
+        .bind(format_naive_datetime(stale_before))
+        .execute(&*self.pool)
+        .await
+        .context("failed to claim storage blob upload")?
+        .rows_affected()
+            > 0;
+        Ok(claimed)
+    }
+
+    /// 交还 [`Self::claim_upload`] 取得的写入认领
+    pub async fn finish_upload(&self, hash: &str) -> Result<()> {
+        sqlx::query("UPDATE storage_blobs SET upload_started_at = NULL WHERE hash = $1")
+            .bind(hash)
+            .execute(&*self.pool)
+            .await
+            .context("failed to clear storage blob upload claim")?;
+        Ok(())
+    }
+
+    /// 当前引用计数，记录不存在时为 `None`
+    pub async fn ref_count(&self, hash: &str) -> Result<Option<i64>> {
+        sqlx::query_scalar("SELECT ref_count FROM storage_blobs WHERE hash = $1")
+            .bind(hash)
+            .fetch_optional(&*self.pool)
+            .await
+            .context("failed to load storage blob reference count")
+    }
+}
@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+
+use crate::db::DbPool;
+use crate::models::content::IntegrityStatus;
+use crate::models::room::row_utils::format_naive_datetime;
+
+/// 指向存储文件的内容记录
+#[derive(Debug, Clone)]
+pub struct StoredContentFile {
+    pub id: i64,
+    pub room_id: i64,
+    pub path: String,
+    pub integrity_status: IntegrityStatus,
+}
+
+/// 存储对账使用的查询
+#[derive(Clone)]
+pub struct StorageReconcileRepository {
+    pool: Arc<DbPool>,
+}
+
+impl StorageReconcileRepository {
+    pub fn new(pool: Arc<DbPool>) -> Self {
+        Self { pool }
+    }
+
+    /// 所有带存储文件的内容记录
+    pub async fn list_content_files(&self) -> Result<Vec<StoredContentFile>> {
+        let rows: Vec<(i64, i64, String, IntegrityStatus)> = sqlx::query_as(
+            r#"
+            SELECT id, room_id, path, integrity_status
+            FROM room_contents
+            WHERE path IS NOT NULL
+            ORDER BY id
+            "#,
+        )
+        .fetch_all(&*self.pool)
+        .await
+        .context("failed to list stored content files")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, room_id, path, integrity_status)| StoredContentFile {
+                id,
+                room_id,
+                path,
+                integrity_status,
+            })
+            .collect())
+    }
+
+    /// `storage_blobs` 中登记的全部哈希
+    pub async fn list_blob_hashes(&self) -> Result<Vec<String>> {
+        sqlx::query_scalar("SELECT hash FROM storage_blobs")
+            .fetch_all(&*self.pool)
+            .await
+            .context("failed to list storage blobs")
+    }
+
+    /// 更新内容的一致性状态，内容已被删除时返回 `false`
+    pub async fn set_integrity_status(
+        &self,
+        content_id: i64,
+        status: IntegrityStatus,
+    ) -> Result<bool> {
+        let now = format_naive_datetime(Utc::now().naive_utc());
+        let result = sqlx::query(
+            "UPDATE room_contents SET integrity_status = $1, updated_at = $2 WHERE id = $3",
+        )
+        .bind(status)
+        .bind(now)
+        .bind(content_id)
+        .execute(&*self.pool)
+        .await
+        .context("failed to update content integrity status")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 所有房间的 id 与记录的已用空间
+    pub async fn list_room_sizes(&self) -> Result<Vec<(i64, i64)>> {
+        sqlx::query_as("SELECT id, current_size FROM rooms ORDER BY id")
+            .fetch_all(&*self.pool)
+            .await
+            .context("failed to list room sizes")
+    }
+
+    /// 将房间已用空间改写为内容大小与未消费上传预留之和，返回改写后的值，无需改写时返回 `None`
+    ///
+    /// 已过期但尚未被 `purge_expired` 释放的预留仍计入，释放时才从已用空间中扣除。
+    /// 实际大小在同一条语句中计算，不会用读取后过时的值覆盖并发上传的更新。
+    pub async fn correct_current_size(&self, room_id: i64) -> Result<Option<i64>> {
+        let now = format_naive_datetime(Utc::now().naive_utc());
+        sqlx::query_scalar(
+            r#"
+            UPDATE rooms
+            SET current_size = sizes.actual, updated_at = $1
+            FROM (
+                SELECT
+                    (SELECT COALESCE(SUM(size), 0) FROM room_contents WHERE room_id = $2)
+                    + (
+                        SELECT COALESCE(SUM(reserved_size), 0)
+                        FROM room_upload_reservations
+                        WHERE room_id = $2
+                          AND consumed_at IS NULL
+                          AND upload_status IN ('pending', 'uploading')
+                    ) AS actual
+            ) AS sizes
+            WHERE rooms.id = $2 AND rooms.current_size <> sizes.actual
+            RETURNING rooms.current_size
+            "#,
+        )
+        .bind(now)
+        .bind(room_id)
+        .fetch_optional(&*self.pool)
+        .await
+        .context("failed to correct room size")
+    }
+}
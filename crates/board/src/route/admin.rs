@@ -8,5 +8,7 @@ pub fn api_router(app_state: Arc<AppState>) -> OpenApiRouter {
     OpenApiRouter::new()
         .routes(routes!(crate::handlers::admin::list_full_unbounded_rooms))
         .routes(routes!(crate::handlers::admin::run_room_gc))
+        .routes(routes!(crate::handlers::admin::export_room))
+        .routes(routes!(crate::handlers::admin::import_room))
         .with_state(app_state)
 }
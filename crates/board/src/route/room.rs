@@ -1,5 +1,9 @@
 use std::sync::Arc;
 
+use axum::{http::HeaderValue, middleware};
+use tower_http::set_header::SetResponseHeaderLayer;
+
+use crate::handlers::chunked_upload::tus;
 use crate::state::AppState;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
@@ -27,6 +31,12 @@ pub fn api_router(app_state: Arc<AppState>) -> OpenApiRouter {
         .routes(routes!(
             crate::handlers::content::download::download_content_global
         ))
+        .routes(routes!(
+            crate::handlers::content::thumbnail::download_thumbnail
+        ))
+        .routes(routes!(
+            crate::handlers::content::archive::download_room_archive
+        ))
         .routes(routes!(crate::handlers::content::update::update_content))
         .routes(routes!(crate::handlers::content::url::create_url_content))
         .routes(routes!(crate::handlers::content::message::create_message))
@@ -44,5 +54,22 @@ pub fn api_router(app_state: Arc<AppState>) -> OpenApiRouter {
         .routes(routes!(
             crate::handlers::chunked_upload::cancel_chunked_upload
         ))
+        .merge(tus_router())
         .with_state(app_state)
 }
+
+// tus 协议路由，所有响应（含错误）都带 Tus-Resumable
+fn tus_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new()
+        .routes(routes!(tus::tus_options, tus::create_tus_upload))
+        .routes(routes!(
+            tus::get_tus_offset,
+            tus::patch_tus_upload,
+            tus::terminate_tus_upload
+        ))
+        .route_layer(middleware::from_fn(tus::require_tus_resumable))
+        .layer(SetResponseHeaderLayer::overriding(
+            tus::TUS_RESUMABLE,
+            HeaderValue::from_static(tus::TUS_VERSION),
+        ))
+}
@@ -20,6 +20,10 @@ pub enum ScheduledTaskId {
     AuthTokenCleanup,
     UploadReservationCleanup,
     RateLimitCleanup,
+    ThumbnailGeneration,
+    ContentScan,
+    StorageReconcile,
+    IntegrityScrub,
 }
 
 impl std::fmt::Display for ScheduledTaskId {
@@ -29,6 +33,10 @@ impl std::fmt::Display for ScheduledTaskId {
             Self::AuthTokenCleanup => "auth_token_cleanup",
             Self::UploadReservationCleanup => "upload_reservation_cleanup",
             Self::RateLimitCleanup => "rate_limit_cleanup",
+            Self::ThumbnailGeneration => "thumbnail_generation",
+            Self::ContentScan => "content_scan",
+            Self::StorageReconcile => "storage_reconcile",
+            Self::IntegrityScrub => "integrity_scrub",
         };
         formatter.write_str(value)
     }
@@ -0,0 +1,169 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+use crate::repository::{ReleasedObjects, StorageBlobRepository};
+use crate::storage::{StorageBackend, key};
+
+/// 等待其他释放者删除对象时的轮询间隔
+const DELETE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// 删除标记保持超过该时长时视为释放者已中途退出，由等待方接管
+const DELETE_STALE_AFTER: Duration = Duration::from_secs(300);
+/// 等待其他调用方写入对象时的轮询间隔
+const UPLOAD_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// 写入认领保持超过该时长时视为写入方已中途退出，由等待方接管
+const UPLOAD_STALE_AFTER: Duration = Duration::from_secs(3600);
+
+/// 登记引用后得到的 blob 对象
+#[derive(Debug, Clone)]
+pub struct BlobLease {
+    pub key: String,
+    /// 对象尚不存在于存储中，需要调用方通过 [`BlobStore::upload`] 写入
+    pub needs_upload: bool,
+}
+
+/// 内容寻址的去重存储
+///
+/// 启用后相同 SHA-256 的文件共享 `blobs/` 下的同一对象，
+/// 每条 `room_contents` 记录持有一次引用，最后一个引用释放时删除对象。
+/// 房间配额仍按每条内容的逻辑大小计算。
+#[derive(Clone)]
+pub struct BlobStore {
+    repository: Arc<StorageBlobRepository>,
+    storage: Arc<dyn StorageBackend>,
+    enabled: bool,
+}
+
+impl BlobStore {
+    pub fn new(
+        repository: Arc<StorageBlobRepository>,
+        storage: Arc<dyn StorageBackend>,
+        enabled: bool,
+    ) -> Self {
+        Self {
+            repository,
+            storage,
+            enabled,
+        }
+    }
+
+    /// 新上传是否写入去重存储
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// 为 `hash` 登记一次引用
+    ///
+    /// 对象正被上一个引用的释放者删除时，等删除结束后再判断是否需要写入。
+    /// 同一对象同时只有一个调用方得到 `needs_upload`，其余调用方等它写入结束，
+    /// 写入失败时由等待方接手写入。
+    pub async fn acquire(&self, hash: &str, size: i64) -> Result<BlobLease> {
+        let key = key::blob_key(hash);
+        if self.repository.acquire(hash, size).await? {
+            self.wait_for_delete(hash).await?;
+        }
+        while !self
+            .repository
+            .claim_upload(hash, UPLOAD_STALE_AFTER)
+            .await?
+        {
+            tokio::time::sleep(UPLOAD_POLL_INTERVAL).await;
+        }
+
+        let exists = self
+            .storage
+            .exists(&key)
+            .await
+            .with_context(|| format!("failed to check {key}"));
+        if !matches!(exists, Ok(false)) {
+            self.repository.finish_upload(hash).await?;
+        }
+        Ok(BlobLease {
+            key,
+            needs_upload: !exists?,
+        })
+    }
+
+    /// 写入 [`Self::acquire`] 要求上传的对象，结束后交还写入认领
+    ///
+    /// 写入失败时同时归还引用。
+    pub async fn upload<E>(
+        &self,
+        lease: &BlobLease,
+        write: impl Future<Output = std::result::Result<(), E>>,
+    ) -> std::result::Result<(), E> {
+        let result = write.await;
+        if let Some(hash) = key::blob_hash(&lease.key)
+            && let Err(e) = self.repository.finish_upload(hash).await
+        {
+            log::warn!("Failed to finish upload of blob {}: {:#}", lease.key, e);
+        }
+        if result.is_err()
+            && let Err(e) = self.release_object(&lease.key).await
+        {
+            log::warn!("Failed to release blob {}: {:#}", lease.key, e);
+        }
+        result
+    }
+
+    /// 删除一条内容对应的存储对象
+    ///
+    /// blob 仅在最后一个引用释放时删除，房间内的普通对象直接删除。
+    /// 去重关闭后历史 blob 依旧按引用计数回收。
+    pub async fn release_object(&self, object_key: &str) -> Result<()> {
+        let Some(hash) = key::blob_hash(object_key) else {
+            return self
+                .storage
+                .delete(object_key)
+                .await
+                .with_context(|| format!("failed to remove {object_key}"));
+        };
+        if self.repository.release(hash).await? {
+            // 删除对象后再根据引用计数决定是否删除记录，期间的新引用会等待并重新写入
+            let deleted = self
+                .storage
+                .delete(object_key)
+                .await
+                .with_context(|| format!("failed to remove {object_key}"));
+            self.repository.finish_release(hash).await?;
+            deleted?;
+        }
+        Ok(())
+    }
+
+    /// 删除内容记录的事务提交后，删除不再被引用的对象
+    ///
+    /// 逐个删除，失败的对象记录日志后留给存储对账回收，不影响其余对象。
+    pub async fn delete_released(&self, released: ReleasedObjects) -> Result<()> {
+        for object_key in released.objects {
+            if let Err(e) = self.storage.delete(&object_key).await {
+                log::warn!("Failed to remove stored file {}: {:#}", object_key, e);
+            }
+        }
+        for object_key in released.blobs {
+            let Some(hash) = key::blob_hash(&object_key) else {
+                continue;
+            };
+            if let Err(e) = self.storage.delete(&object_key).await {
+                log::warn!("Failed to remove stored blob {}: {:#}", object_key, e);
+            }
+            self.repository.finish_release(hash).await?;
+        }
+        Ok(())
+    }
+
+    async fn wait_for_delete(&self, hash: &str) -> Result<()> {
+        let started = Instant::now();
+        while self.repository.is_deleting(hash).await? {
+            if started.elapsed() >= DELETE_STALE_AFTER {
+                log::warn!("Taking over stale deletion of storage blob {hash}");
+                self.repository.clear_deleting(hash).await?;
+                break;
+            }
+            tokio::time::sleep(DELETE_POLL_INTERVAL).await;
+        }
+        Ok(())
+    }
+}
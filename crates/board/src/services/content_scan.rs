@@ -0,0 +1,297 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::StreamExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UnixStream};
+
+use crate::config::{ClamdAddress, ScanConfig};
+use crate::models::content::{RoomContent, ScanStatus};
+use crate::repository::RoomContentRepository;
+use crate::storage::{ByteStream, StorageBackend, StorageError, key};
+
+/// 单个 INSTREAM 数据块的最大长度
+const INSTREAM_CHUNK_SIZE: usize = 64 * 1024;
+/// clamd 回复的最大长度，超过时视为协议错误
+const MAX_REPLY_LEN: u64 = 4 * 1024;
+
+/// 扫描器对一个文件给出的结论
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanVerdict {
+    Clean,
+    /// 命中的病毒签名
+    Infected(String),
+    /// 扫描器无法处理该文件（如超出大小限制），重试也不会有结果
+    Failed(String),
+}
+
+impl ScanVerdict {
+    pub fn status(&self) -> ScanStatus {
+        match self {
+            Self::Clean => ScanStatus::Clean,
+            Self::Infected(_) => ScanStatus::Infected,
+            Self::Failed(_) => ScanStatus::Failed,
+        }
+    }
+}
+
+/// 内容扫描器
+///
+/// 返回 `Err` 表示扫描器暂时不可用（连接失败、超时等），内容保持 `Pending` 等待下一轮。
+#[async_trait]
+pub trait ContentScanner: Send + Sync {
+    async fn scan(&self, stream: ByteStream) -> Result<ScanVerdict>;
+}
+
+/// 通过 clamd `INSTREAM` 协议扫描，支持 TCP 与 Unix socket
+pub struct ClamdScanner {
+    address: ClamdAddress,
+    timeout: Duration,
+}
+
+impl ClamdScanner {
+    pub fn new(address: ClamdAddress, timeout: Duration) -> Self {
+        Self { address, timeout }
+    }
+}
+
+#[async_trait]
+impl ContentScanner for ClamdScanner {
+    async fn scan(&self, stream: ByteStream) -> Result<ScanVerdict> {
+        let reply = tokio::time::timeout(self.timeout, async {
+            match &self.address {
+                ClamdAddress::Tcp(address) => {
+                    let connection = TcpStream::connect(address)
+                        .await
+                        .with_context(|| format!("failed to connect to clamd at {address}"))?;
+                    instream(connection, stream).await
+                }
+                ClamdAddress::Unix(path) => {
+                    let connection = UnixStream::connect(path).await.with_context(|| {
+                        format!("failed to connect to clamd at {}", path.display())
+                    })?;
+                    instream(connection, stream).await
+                }
+            }
+        })
+        .await
+        .context("clamd scan timed out")??;
+        parse_reply(&reply)
+    }
+}
+
+/// 发送 `zINSTREAM` 命令与长度前缀的数据块，返回去掉结尾 NUL 的回复
+async fn instream<S>(mut connection: S, mut stream: ByteStream) -> Result<String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let sent = async {
+        connection.write_all(b"zINSTREAM\0").await?;
+        while let Some(chunk) = stream.next().await {
+            for piece in chunk?.chunks(INSTREAM_CHUNK_SIZE) {
+                connection
+                    .write_all(&(piece.len() as u32).to_be_bytes())
+                    .await?;
+                connection.write_all(piece).await?;
+            }
+        }
+        connection.write_all(&0u32.to_be_bytes()).await?;
+        connection.flush().await
+    }
+    .await;
+
+    // 超出 StreamMaxLength 时 clamd 会先回复错误再断开，写入失败后仍尝试读取回复
+    let mut reply = Vec::new();
+    let read = (&mut connection)
+        .take(MAX_REPLY_LEN)
+        .read_to_end(&mut reply)
+        .await;
+    if reply.is_empty() {
+        sent.context("failed to stream content to clamd")?;
+        read.context("failed to read clamd reply")?;
+        anyhow::bail!("clamd closed the connection without a reply");
+    }
+    let end = reply
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(reply.len());
+    Ok(String::from_utf8_lossy(&reply[..end]).trim().to_string())
+}
+
+/// 解析 clamd 回复：`stream: OK`、`stream: <签名> FOUND` 或 `<原因> ERROR`
+pub fn parse_reply(reply: &str) -> Result<ScanVerdict> {
+    let result = reply
+        .split_once(": ")
+        .map_or(reply, |(_, result)| result)
+        .trim();
+    if result == "OK" {
+        return Ok(ScanVerdict::Clean);
+    }
+    if let Some(signature) = result.strip_suffix(" FOUND") {
+        return Ok(ScanVerdict::Infected(signature.trim().to_string()));
+    }
+    if let Some(reason) = result.strip_suffix(" ERROR") {
+        return Ok(ScanVerdict::Failed(reason.trim().to_string()));
+    }
+    anyhow::bail!("unexpected clamd reply: {reply}")
+}
+
+/// 一轮扫描的结果
+#[derive(Debug, Default)]
+pub struct ScanRunReport {
+    pub examined: u64,
+    /// 本轮得出结论的内容，`scan_status` 已更新
+    pub finished: Vec<RoomContent>,
+}
+
+/// 上传内容扫描服务
+///
+/// 上传或合并完成时仅把文件标记为 `Pending`，由后台任务逐个交给扫描器。
+/// 扫描通过前文件不可下载、不生成缩略图，也不会写入房间归档。
+pub struct ContentScanService {
+    repository: RoomContentRepository,
+    storage: Arc<dyn StorageBackend>,
+    storage_root: PathBuf,
+    scanner: Arc<dyn ContentScanner>,
+    config: ScanConfig,
+}
+
+impl ContentScanService {
+    pub fn new(
+        repository: RoomContentRepository,
+        storage: Arc<dyn StorageBackend>,
+        storage_root: PathBuf,
+        scanner: Arc<dyn ContentScanner>,
+        config: ScanConfig,
+    ) -> Self {
+        Self {
+            repository,
+            storage,
+            storage_root,
+            scanner,
+            config,
+        }
+    }
+
+    /// 新内容写入数据库前的初始状态
+    pub fn initial_status(&self, content: &RoomContent) -> ScanStatus {
+        if self.config.enabled && content.path.is_some() {
+            ScanStatus::Pending
+        } else {
+            ScanStatus::Clean
+        }
+    }
+
+    /// 扫描一批等待中的文件
+    ///
+    /// 扫描器不可用时停止本轮，剩余内容保持 `Pending`。
+    pub async fn run_pending(&self) -> Result<ScanRunReport> {
+        let pending = self
+            .repository
+            .list_pending_scans(self.config.batch_limit)
+            .await?;
+        let mut report = ScanRunReport {
+            examined: pending.len() as u64,
+            ..Default::default()
+        };
+
+        for mut content in pending {
+            let Some(content_id) = content.id else {
+                continue;
+            };
+            let verdict = match self.scan(&content).await {
+                Ok(verdict) => verdict,
+                Err(error) => {
+                    log::warn!("Content scan of {content_id} postponed: {error:#}");
+                    break;
+                }
+            };
+            match &verdict {
+                ScanVerdict::Clean => {}
+                ScanVerdict::Infected(signature) => {
+                    log::warn!("Content {content_id} is infected: {signature}");
+                }
+                ScanVerdict::Failed(reason) => {
+                    log::warn!("Content {content_id} could not be scanned: {reason}");
+                }
+            }
+
+            let status = verdict.status();
+            if self.repository.finish_scan(content_id, status).await? {
+                content.scan_status = status;
+                report.finished.push(content);
+            }
+        }
+
+        Ok(report)
+    }
+
+    async fn scan(&self, content: &RoomContent) -> Result<ScanVerdict> {
+        let Some(path) = content.path.as_deref() else {
+            return Ok(ScanVerdict::Clean);
+        };
+        let source_key = key::object_key(&self.storage_root, path);
+        let stream = match self.storage.reader(&source_key).await {
+            Ok(stream) => stream,
+            Err(StorageError::NotFound(_)) => {
+                return Ok(ScanVerdict::Failed(format!("{source_key} is missing")));
+            }
+            Err(error) => {
+                return Err(error).with_context(|| format!("failed to read {source_key}"));
+            }
+        };
+        self.scanner.scan(stream).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_clamd_replies() {
+        assert_eq!(parse_reply("stream: OK").unwrap(), ScanVerdict::Clean);
+        assert_eq!(
+            parse_reply("stream: Win.Test.EICAR_HDB-1 FOUND").unwrap(),
+            ScanVerdict::Infected("Win.Test.EICAR_HDB-1".into())
+        );
+        assert_eq!(
+            parse_reply("INSTREAM size limit exceeded. ERROR").unwrap(),
+            ScanVerdict::Failed("INSTREAM size limit exceeded.".into())
+        );
+        assert!(parse_reply("UNKNOWN COMMAND").is_err());
+    }
+
+    #[tokio::test]
+    async fn streams_chunks_to_clamd() {
+        let (client, mut server) = tokio::io::duplex(1024 * 1024);
+        let data = vec![7u8; INSTREAM_CHUNK_SIZE + 10];
+        let stream: ByteStream = futures::stream::iter(vec![Ok(bytes::Bytes::from(data))]).boxed();
+
+        let server = tokio::spawn(async move {
+            let mut command = [0u8; 10];
+            server.read_exact(&mut command).await.unwrap();
+            assert_eq!(&command, b"zINSTREAM\0");
+            let mut lengths = Vec::new();
+            loop {
+                let length = server.read_u32().await.unwrap() as usize;
+                lengths.push(length);
+                if length == 0 {
+                    break;
+                }
+                let mut chunk = vec![0u8; length];
+                server.read_exact(&mut chunk).await.unwrap();
+            }
+            server.write_all(b"stream: OK\0").await.unwrap();
+            server.shutdown().await.unwrap();
+            lengths
+        });
+
+        let reply = instream(client, stream).await.unwrap();
+        assert_eq!(reply, "stream: OK");
+        assert_eq!(server.await.unwrap(), vec![INSTREAM_CHUNK_SIZE, 10, 0]);
+    }
+}
@@ -0,0 +1,316 @@
+use anyhow::Result;
+use image::metadata::Orientation;
+use img_parts::jpeg::{Jpeg, JpegSegment, markers};
+use img_parts::png::{Png, PngChunk};
+use img_parts::riff::{RiffChunk, RiffContent};
+use img_parts::webp::{CHUNK_EXIF, CHUNK_VP8X, CHUNK_XMP, WebP};
+use img_parts::{Bytes, ImageEXIF};
+
+/// 支持移除元数据的图片类型
+pub const METADATA_STRIP_MIME_TYPES: &[&str] = &["image/jpeg", "image/png", "image/webp"];
+
+const EXIF_PREFIX: &[u8] = b"Exif\0\0";
+/// JPEG APP1/APP13 中携带 EXIF、XMP 与 IPTC 的段前缀
+const JPEG_METADATA_PREFIXES: &[(u8, &[u8])] = &[
+    (markers::APP1, EXIF_PREFIX),
+    (markers::APP1, b"http://ns.adobe.com/xap/1.0/\0"),
+    (markers::APP1, b"http://ns.adobe.com/xmp/extension/\0"),
+    (markers::APP13, b"Photoshop 3.0\0"),
+];
+const PNG_CHUNK_EXIF: [u8; 4] = *b"eXIf";
+const PNG_TEXT_CHUNKS: &[[u8; 4]] = &[*b"tEXt", *b"zTXt", *b"iTXt"];
+/// PNG 文本块中承载 XMP 与原始 EXIF/IPTC 的关键字
+const PNG_METADATA_KEYWORDS: &[&[u8]] = &[
+    b"XML:com.adobe.xmp",
+    b"Raw profile type exif",
+    b"Raw profile type APP1",
+    b"Raw profile type xmp",
+    b"Raw profile type iptc",
+];
+/// VP8X 头中 EXIF 与 XMP 存在标志位
+const VP8X_EXIF_FLAG: u8 = 0b0000_1000;
+const VP8X_XMP_FLAG: u8 = 0b0000_0100;
+
+/// 移除 JPEG/PNG/WebP 中的 EXIF、XMP 等元数据（含 GPS 位置），仅保留方向信息
+///
+/// 只改写容器结构，不重新编码像素数据。类型不支持或没有可移除的元数据时返回 `None`，
+/// 调用方保留原始内容。
+pub fn strip_image_metadata(data: &[u8], mime: &str) -> Result<Option<Vec<u8>>> {
+    let data = Bytes::copy_from_slice(data);
+    match mime {
+        "image/jpeg" => strip_jpeg(data),
+        "image/png" => strip_png(data),
+        "image/webp" => strip_webp(data),
+        _ => Ok(None),
+    }
+}
+
+/// 上传流程使用：仅处理支持的类型，解析失败时记录日志并保留原始内容
+pub fn strip_uploaded_image(data: Vec<u8>, detected_mime: Option<&str>) -> Vec<u8> {
+    let Some(mime) = detected_mime.filter(|mime| METADATA_STRIP_MIME_TYPES.contains(mime)) else {
+        return data;
+    };
+    match strip_image_metadata(&data, mime) {
+        Ok(Some(stripped)) => stripped,
+        Ok(None) => data,
+        Err(error) => {
+            log::warn!("Failed to strip {mime} metadata, keeping original: {error:#}");
+            data
+        }
+    }
+}
+
+fn strip_jpeg(data: Bytes) -> Result<Option<Vec<u8>>> {
+    let mut jpeg = Jpeg::from_bytes(data)?;
+    let orientation = jpeg.exif().and_then(|exif| rotation(&exif));
+    let before = jpeg.segments().len();
+    jpeg.segments_mut().retain(|segment| {
+        !JPEG_METADATA_PREFIXES.iter().any(|(marker, prefix)| {
+            segment.marker() == *marker && segment.contents().starts_with(prefix)
+        })
+    });
+    if jpeg.segments().len() == before {
+        return Ok(None);
+    }
+
+    if let Some(orientation) = orientation {
+        let mut contents = EXIF_PREFIX.to_vec();
+        contents.extend_from_slice(&orientation_exif(orientation));
+        let position = jpeg
+            .segments()
+            .iter()
+            .take_while(|segment| segment.marker() == markers::APP0)
+            .count();
+        jpeg.segments_mut().insert(
+            position,
+            JpegSegment::new_with_contents(markers::APP1, contents.into()),
+        );
+    }
+    Ok(Some(jpeg.encoder().bytes().to_vec()))
+}
+
+fn strip_png(data: Bytes) -> Result<Option<Vec<u8>>> {
+    let mut png = Png::from_bytes(data)?;
+    let orientation = png.exif().and_then(|exif| rotation(&exif));
+    let before = png.chunks().len();
+    png.chunks_mut().retain(|chunk| {
+        let kind = chunk.kind();
+        if kind == PNG_CHUNK_EXIF {
+            return false;
+        }
+        !(PNG_TEXT_CHUNKS.contains(&kind)
+            && PNG_METADATA_KEYWORDS.iter().any(|keyword| {
+                chunk
+                    .contents()
+                    .strip_prefix(*keyword)
+                    .is_some_and(|rest| rest.first() == Some(&0))
+            }))
+    });
+    if png.chunks().len() == before {
+        return Ok(None);
+    }
+
+    if let Some(orientation) = orientation {
+        // 解码器只读取图像数据之前的 eXIf，紧跟 IHDR 写入
+        png.chunks_mut().insert(
+            1,
+            PngChunk::new(PNG_CHUNK_EXIF, orientation_exif(orientation).into()),
+        );
+    }
+    Ok(Some(png.encoder().bytes().to_vec()))
+}
+
+fn strip_webp(data: Bytes) -> Result<Option<Vec<u8>>> {
+    let mut webp = WebP::from_bytes(data)?;
+    // 只有扩展格式（VP8X）可以携带元数据块
+    let Some(header) = webp
+        .chunk_by_id(CHUNK_VP8X)
+        .and_then(|chunk| chunk.content().data())
+        .filter(|header| !header.is_empty())
+        .cloned()
+    else {
+        return Ok(None);
+    };
+    if !webp.has_chunk(CHUNK_EXIF) && !webp.has_chunk(CHUNK_XMP) {
+        return Ok(None);
+    }
+
+    let orientation = webp
+        .chunk_by_id(CHUNK_EXIF)
+        .and_then(|chunk| chunk.content().data())
+        .and_then(|exif| rotation(exif.strip_prefix(EXIF_PREFIX).unwrap_or(exif)));
+    webp.remove_chunks_by_id(CHUNK_EXIF);
+    webp.remove_chunks_by_id(CHUNK_XMP);
+
+    let mut header = header.to_vec();
+    header[0] &= !(VP8X_EXIF_FLAG | VP8X_XMP_FLAG);
+    if let Some(orientation) = orientation {
+        header[0] |= VP8X_EXIF_FLAG;
+        webp.chunks_mut().push(RiffChunk::new(
+            CHUNK_EXIF,
+            RiffContent::Data(orientation_exif(orientation).into()),
+        ));
+    }
+    if let Some(vp8x) = webp
+        .chunks_mut()
+        .iter_mut()
+        .find(|chunk| chunk.id() == CHUNK_VP8X)
+    {
+        *vp8x = RiffChunk::new(CHUNK_VP8X, RiffContent::Data(header.into()));
+    }
+    Ok(Some(webp.encoder().bytes().to_vec()))
+}
+
+/// 需要保留的方向信息；无旋转时不保留
+fn rotation(exif: &[u8]) -> Option<Orientation> {
+    Orientation::from_exif_chunk(exif)
+        .filter(|orientation| *orientation != Orientation::NoTransforms)
+}
+
+/// 仅包含 Orientation 标签的最小 EXIF（小端 TIFF）
+fn orientation_exif(orientation: Orientation) -> Vec<u8> {
+    let mut exif = Vec::with_capacity(26);
+    exif.extend_from_slice(b"II*\0");
+    exif.extend_from_slice(&8u32.to_le_bytes());
+    exif.extend_from_slice(&1u16.to_le_bytes());
+    exif.extend_from_slice(&0x0112u16.to_le_bytes());
+    exif.extend_from_slice(&3u16.to_le_bytes());
+    exif.extend_from_slice(&1u32.to_le_bytes());
+    exif.extend_from_slice(&u16::from(orientation.to_exif()).to_le_bytes());
+    exif.extend_from_slice(&[0, 0]);
+    exif.extend_from_slice(&0u32.to_le_bytes());
+    exif
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+
+    /// 带 GPS 信息与方向标签的 EXIF
+    fn exif_with_gps() -> Vec<u8> {
+        let mut exif = orientation_exif(Orientation::Rotate90);
+        exif.extend_from_slice(b"GPS 31.2304N 121.4737E");
+        exif
+    }
+
+    fn encoded(format: ImageFormat) -> Bytes {
+        let mut buffer = Cursor::new(Vec::new());
+        DynamicImage::new_rgb8(4, 2)
+            .write_to(&mut buffer, format)
+            .expect("encode image");
+        buffer.into_inner().into()
+    }
+
+    fn orientation_of(data: &[u8]) -> Orientation {
+        let mut decoder = ImageReader::new(Cursor::new(data))
+            .with_guessed_format()
+            .unwrap()
+            .into_decoder()
+            .unwrap();
+        decoder.orientation().unwrap()
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    #[test]
+    fn jpeg_keeps_only_orientation() {
+        let mut jpeg = Jpeg::from_bytes(encoded(ImageFormat::Jpeg)).unwrap();
+        jpeg.set_exif(Some(exif_with_gps().into()));
+        jpeg.segments_mut().insert(
+            1,
+            JpegSegment::new_with_contents(
+                markers::APP1,
+                Bytes::from_static(b"http://ns.adobe.com/xap/1.0/\0<x:xmpmeta/>"),
+            ),
+        );
+        let source = jpeg.encoder().bytes();
+
+        let stripped = strip_image_metadata(&source, "image/jpeg")
+            .unwrap()
+            .unwrap();
+        assert!(stripped.len() < source.len());
+        assert!(!contains(&stripped, b"GPS"));
+        assert!(!contains(&stripped, b"xmpmeta"));
+        assert_eq!(orientation_of(&stripped), Orientation::Rotate90);
+        image::load_from_memory(&stripped).expect("still decodes");
+    }
+
+    #[test]
+    fn png_removes_exif_and_xmp() {
+        let mut png = Png::from_bytes(encoded(ImageFormat::Png)).unwrap();
+        png.set_exif(Some(exif_with_gps().into()));
+        let position = png.chunks().len() - 1;
+        png.chunks_mut().insert(
+            position,
+            PngChunk::new(
+                *b"iTXt",
+                Bytes::from_static(b"XML:com.adobe.xmp\0\0\0\0\0<x:xmpmeta/>"),
+            ),
+        );
+        let source = png.encoder().bytes();
+
+        let stripped = strip_image_metadata(&source, "image/png").unwrap().unwrap();
+        assert!(!contains(&stripped, b"GPS"));
+        assert!(!contains(&stripped, b"xmpmeta"));
+        assert_eq!(orientation_of(&stripped), Orientation::Rotate90);
+        image::load_from_memory(&stripped).expect("still decodes");
+    }
+
+    #[test]
+    fn webp_keeps_only_orientation() {
+        let mut webp = WebP::from_bytes(encoded(ImageFormat::WebP)).unwrap();
+        // 4x2 画布，宽高按减一后的 24 位小端存储
+        let header = [VP8X_EXIF_FLAG | VP8X_XMP_FLAG, 0, 0, 0, 3, 0, 0, 1, 0, 0];
+        webp.chunks_mut().insert(
+            0,
+            RiffChunk::new(CHUNK_VP8X, RiffContent::Data(header.to_vec().into())),
+        );
+        webp.chunks_mut().push(RiffChunk::new(
+            CHUNK_EXIF,
+            RiffContent::Data(exif_with_gps().into()),
+        ));
+        webp.chunks_mut().push(RiffChunk::new(
+            CHUNK_XMP,
+            RiffContent::Data(Bytes::from_static(b"<x:xmpmeta/>")),
+        ));
+        let source = webp.encoder().bytes();
+
+        let stripped = strip_image_metadata(&source, "image/webp")
+            .unwrap()
+            .unwrap();
+        assert!(!contains(&stripped, b"GPS"));
+        assert!(!contains(&stripped, b"xmpmeta"));
+        let flags = WebP::from_bytes(stripped.clone().into())
+            .unwrap()
+            .chunk_by_id(CHUNK_VP8X)
+            .and_then(|chunk| chunk.content().data())
+            .map(|header| header[0])
+            .unwrap();
+        assert_eq!(flags & (VP8X_EXIF_FLAG | VP8X_XMP_FLAG), VP8X_EXIF_FLAG);
+        assert_eq!(orientation_of(&stripped), Orientation::Rotate90);
+        image::load_from_memory(&stripped).expect("still decodes");
+    }
+
+    #[test]
+    fn clean_images_are_left_untouched() {
+        let source = encoded(ImageFormat::Png);
+        assert!(
+            strip_image_metadata(&source, "image/png")
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            strip_image_metadata(&source, "image/gif")
+                .unwrap()
+                .is_none()
+        );
+        assert!(strip_image_metadata(b"not a jpeg", "image/jpeg").is_err());
+    }
+}
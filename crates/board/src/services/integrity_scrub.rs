@@ -0,0 +1,165 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+
+use crate::config::ScrubConfig;
+use crate::models::content::IntegrityStatus;
+use crate::repository::{IntegrityScrubRepository, ScrubCandidate};
+use crate::storage::{StorageBackend, StorageError, key};
+
+/// 一轮巡检的结果
+#[derive(Debug, Default)]
+pub struct ScrubReport {
+    pub examined: u64,
+    /// 首次遇到、记录了基准摘要的文件数
+    pub baselines_recorded: u64,
+    /// 摘要与基准一致的文件数
+    pub verified: u64,
+    /// 本轮新标记为 `Corrupted` 的内容
+    pub corrupted: Vec<i64>,
+    /// 摘要重新与基准一致、恢复为 `Ok` 的内容数
+    pub restored: u64,
+    /// 本轮发现文件不存在、标记为 `Missing` 的内容
+    pub missing: Vec<i64>,
+}
+
+impl ScrubReport {
+    pub fn changed(&self) -> u64 {
+        self.baselines_recorded
+            + self.corrupted.len() as u64
+            + self.restored
+            + self.missing.len() as u64
+    }
+}
+
+/// 文件完整性巡检服务
+///
+/// 廉价磁盘可能静默损坏数据。每轮只取少量最久未校验的文件重新计算 SHA-256，
+/// 与上传时记录的摘要比对；没有摘要的旧内容先记录一次基准。文件不存在时标记为 `Missing`，
+/// 不再参与巡检，由对账任务在文件恢复后清除标记。
+pub struct IntegrityScrubService {
+    repository: IntegrityScrubRepository,
+    storage: Arc<dyn StorageBackend>,
+    storage_root: PathBuf,
+    config: ScrubConfig,
+}
+
+impl IntegrityScrubService {
+    pub fn new(
+        repository: IntegrityScrubRepository,
+        storage: Arc<dyn StorageBackend>,
+        storage_root: PathBuf,
+        config: ScrubConfig,
+    ) -> Self {
+        Self {
+            repository,
+            storage,
+            storage_root,
+            config,
+        }
+    }
+
+    pub async fn run(&self) -> Result<ScrubReport> {
+        let reverify_after = i64::try_from(self.config.reverify_after_seconds)
+            .ok()
+            .and_then(chrono::Duration::try_seconds)
+            .unwrap_or(chrono::Duration::MAX);
+        let verified_before = Utc::now()
+            .naive_utc()
+            .checked_sub_signed(reverify_after)
+            .unwrap_or(chrono::NaiveDateTime::MIN);
+        let candidates = self
+            .repository
+            .list_due(verified_before, self.config.batch_limit)
+            .await?;
+
+        let mut report = ScrubReport {
+            examined: candidates.len() as u64,
+            ..Default::default()
+        };
+        for candidate in candidates {
+            self.verify(&candidate, &mut report).await?;
+            // 巡检优先级低，每个文件之后让出执行权
+            tokio::task::yield_now().await;
+        }
+        Ok(report)
+    }
+
+    async fn verify(&self, candidate: &ScrubCandidate, report: &mut ScrubReport) -> Result<()> {
+        let object_key = key::object_key(&self.storage_root, &candidate.path);
+        let now = Utc::now().naive_utc();
+        let Some(digest) = self.digest(&object_key).await? else {
+            // 不标记的话该行一直是最久未校验的，会反复占用批次名额
+            if self
+                .repository
+                .mark_missing(candidate.id, &candidate.path, now)
+                .await?
+            {
+                log::warn!("File of content {} is missing", candidate.id);
+                report.missing.push(candidate.id);
+            }
+            return Ok(());
+        };
+
+        let Some(expected) = candidate.hash.as_deref() else {
+            if self
+                .repository
+                .record_baseline(candidate.id, &candidate.path, &digest, now)
+                .await?
+            {
+                report.baselines_recorded += 1;
+            }
+            return Ok(());
+        };
+
+        let status = if digest.eq_ignore_ascii_case(expected) {
+            IntegrityStatus::Ok
+        } else {
+            IntegrityStatus::Corrupted
+        };
+        if !self
+            .repository
+            .finish_verification(candidate.id, &candidate.path, expected, status, now)
+            .await?
+        {
+            return Ok(());
+        }
+        let was_corrupted = candidate.integrity_status == IntegrityStatus::Corrupted;
+        match status {
+            IntegrityStatus::Ok if was_corrupted => {
+                log::info!("File of content {} matches its hash again", candidate.id);
+                report.restored += 1;
+            }
+            IntegrityStatus::Corrupted if !was_corrupted => {
+                log::error!(
+                    "File of content {} is corrupted: expected sha256 {expected}, got {digest}",
+                    candidate.id
+                );
+                report.corrupted.push(candidate.id);
+            }
+            IntegrityStatus::Ok => report.verified += 1,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// 流式计算文件的 SHA-256，文件不存在时返回 `None`
+    async fn digest(&self, object_key: &str) -> Result<Option<String>> {
+        let mut stream = match self.storage.reader(object_key).await {
+            Ok(stream) => stream,
+            Err(StorageError::NotFound(_)) => return Ok(None),
+            Err(error) => {
+                return Err(error).with_context(|| format!("failed to read {object_key}"));
+            }
+        };
+        let mut hasher = Sha256::new();
+        while let Some(chunk) = stream.next().await {
+            hasher.update(chunk.with_context(|| format!("failed to read {object_key}"))?);
+        }
+        Ok(Some(hex::encode(hasher.finalize())))
+    }
+}
@@ -4,25 +4,41 @@ use anyhow::Result;
 /// 集中管理所有应用程序服务
 use std::sync::Arc;
 
+use crate::chunk_temp_storage::ChunkStaging;
 use crate::config::AppConfig;
 use crate::db::DbPool;
 use crate::repository::room_refresh_token_repository::{
     RoomRefreshTokenRepository, TokenBlacklistRepository,
 };
 use crate::repository::room_repository::RoomRepository;
-use crate::repository::{RoomAccessRepository, RoomTokenRepository};
+use crate::repository::{RoomAccessRepository, RoomTokenRepository, StorageBlobRepository};
+use crate::storage::StorageBackend;
 
 pub mod auth_service;
+pub mod blob_store;
+pub mod content_scan;
+pub mod image_metadata;
+pub mod integrity_scrub;
 pub mod refresh_token_service;
 pub mod room_lifecycle;
 pub mod room_password;
+pub mod room_transfer;
+pub mod storage_reconcile;
+pub mod thumbnail;
 pub mod token;
 
 // 重新导出服务类型
 pub use auth_service::*;
+pub use blob_store::*;
+pub use content_scan::*;
+pub use image_metadata::*;
+pub use integrity_scrub::*;
 pub use refresh_token_service::*;
 pub use room_lifecycle::*;
 pub use room_password::*;
+pub use room_transfer::*;
+pub use storage_reconcile::*;
+pub use thumbnail::*;
 pub use token::*;
 
 /// 服务容器，包含所有应用程序服务
@@ -34,11 +50,22 @@ pub struct Services {
     pub room_repository: Arc<RoomRepository>,
     pub room_lifecycle: Arc<RoomLifecycleService>,
     pub room_password: Arc<RoomPasswordService>,
+    pub blob_store: Arc<BlobStore>,
+    pub room_transfer: Arc<RoomTransferService>,
+    pub thumbnails: Arc<ThumbnailService>,
+    pub content_scan: Arc<ContentScanService>,
+    pub storage_reconcile: Arc<StorageReconcileService>,
+    pub integrity_scrub: Arc<IntegrityScrubService>,
+    pub chunk_staging: Arc<ChunkStaging>,
 }
 
 impl Services {
     /// 创建新的服务容器
-    pub fn new(config: &AppConfig, db_pool: Arc<DbPool>) -> Result<Self> {
+    pub fn new(
+        config: &AppConfig,
+        db_pool: Arc<DbPool>,
+        storage: Arc<dyn StorageBackend>,
+    ) -> Result<Self> {
         // 创建令牌服务
         let token_service = Arc::new(RoomTokenService::with_config(
             Arc::new(config.auth.jwt_secret.clone()),
@@ -70,14 +97,66 @@ impl Services {
         // 创建认证服务
         let auth_service = Arc::new(AuthService::new(token_service.clone(), blacklist_repo));
 
+        let blob_store = Arc::new(BlobStore::new(
+            Arc::new(StorageBlobRepository::new(db_pool.clone())),
+            storage.clone(),
+            config.storage.dedup,
+        ));
+
         let room_lifecycle_repository = Arc::new(crate::repository::RoomLifecycleRepository::new(
             db_pool.clone(),
         ));
         let room_lifecycle = Arc::new(RoomLifecycleService::new(
             room_lifecycle_repository,
+            storage.clone(),
+            blob_store.clone(),
             config.storage.root.clone(),
         ));
         let room_password = Arc::new(RoomPasswordService);
+        let thumbnails = Arc::new(ThumbnailService::new(
+            crate::repository::RoomContentRepository::new(db_pool.clone()),
+            storage.clone(),
+            config.storage.root.clone(),
+            config.storage.thumbnails.clone(),
+        ));
+        let scan_config = config.storage.scan.clone();
+        let content_scan = Arc::new(ContentScanService::new(
+            crate::repository::RoomContentRepository::new(db_pool.clone()),
+            storage.clone(),
+            config.storage.root.clone(),
+            Arc::new(ClamdScanner::new(
+                scan_config.clamd_address.clone(),
+                std::time::Duration::from_secs(scan_config.timeout_seconds),
+            )),
+            scan_config,
+        ));
+        let storage_reconcile = Arc::new(StorageReconcileService::new(
+            crate::repository::StorageReconcileRepository::new(db_pool.clone()),
+            storage.clone(),
+            config.storage.root.clone(),
+            config.storage.reconcile.clone(),
+        ));
+        let integrity_scrub = Arc::new(IntegrityScrubService::new(
+            crate::repository::IntegrityScrubRepository::new(db_pool.clone()),
+            storage.clone(),
+            config.storage.root.clone(),
+            config.storage.scrub.clone(),
+        ));
+        let chunk_staging = Arc::new(ChunkStaging::new(
+            config.storage.chunk_staging.clone(),
+            storage.clone(),
+        ));
+        let room_transfer = Arc::new(RoomTransferService::new(
+            db_pool,
+            storage,
+            blob_store.clone(),
+            room_lifecycle.clone(),
+            room_password.clone(),
+            thumbnails.clone(),
+            content_scan.clone(),
+            config.storage.root.clone(),
+            config.room.clone(),
+        ));
 
         Ok(Self {
             auth: auth_service,
@@ -86,6 +165,13 @@ impl Services {
             room_repository,
             room_lifecycle,
             room_password,
+            blob_store,
+            room_transfer,
+            thumbnails,
+            content_scan,
+            storage_reconcile,
+            integrity_scrub,
+            chunk_staging,
         })
     }
 
@@ -122,7 +208,15 @@ mod tests {
         config.auth = AuthConfig::new("test-secret-key-for-unit-testing-123".to_string())?;
 
         // 创建服务
-        let services = Services::new(&config, db_pool)?;
+        let storage_root = tempfile::TempDir::new()?;
+        let storage: Arc<dyn StorageBackend> = Arc::new(crate::storage::OpendalBackend::new(
+            crate::storage::StorageConfig {
+                storage_type: crate::storage::StorageType::Fs,
+                root: storage_root.path().to_string_lossy().into_owned(),
+                s3_config: None,
+            },
+        )?);
+        let services = Services::new(&config, db_pool, storage)?;
 
         // 验证服务创建成功
         assert!(!services.token_service.get_secret().is_empty());
@@ -1,10 +1,12 @@
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use chrono::{Duration, NaiveDateTime, Utc};
 
 use crate::repository::RoomLifecycleRepository;
+use crate::services::BlobStore;
+use crate::storage::{StorageBackend, key};
 use crate::websocket::connection::ConnectionManager;
 
 const FULL_ROOM_TOKEN_GRACE_PERIOD: Duration = Duration::days(1);
@@ -38,13 +40,22 @@ pub struct FullRoomGcStatus {
 #[derive(Clone)]
 pub struct RoomLifecycleService {
     repository: Arc<RoomLifecycleRepository>,
+    storage: Arc<dyn StorageBackend>,
+    blob_store: Arc<BlobStore>,
     storage_root: PathBuf,
 }
 
 impl RoomLifecycleService {
-    pub fn new(repository: Arc<RoomLifecycleRepository>, storage_root: PathBuf) -> Self {
+    pub fn new(
+        repository: Arc<RoomLifecycleRepository>,
+        storage: Arc<dyn StorageBackend>,
+        blob_store: Arc<BlobStore>,
+        storage_root: PathBuf,
+    ) -> Self {
         Self {
             repository,
+            storage,
+            blob_store,
             storage_root,
         }
     }
@@ -173,30 +184,19 @@ impl RoomLifecycleService {
         Ok(cleaned)
     }
 
-    async fn purge_room(&self, room_id: i64) -> Result<bool> {
-        for path in self.repository.list_content_paths(room_id).await? {
-            remove_file_if_present(Path::new(&path)).await?;
-        }
-        remove_dir_if_present(&self.storage_root.join(room_id.to_string())).await?;
-        self.repository
-            .delete_room_graph(room_id)
+    pub(crate) async fn purge_room(&self, room_id: i64) -> Result<bool> {
+        // 先在事务中删除记录并释放引用，提交后再删除对象，失败重试不会重复释放
+        let (deleted, released) = self
+            .repository
+            .delete_room_graph(room_id, &self.storage_root)
             .await
-            .context("failed to delete room persistence graph")
-    }
-}
-
-async fn remove_file_if_present(path: &Path) -> Result<()> {
-    match tokio::fs::remove_file(path).await {
-        Ok(()) => Ok(()),
-        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
-        Err(error) => Err(error).with_context(|| format!("failed to remove {}", path.display())),
-    }
-}
-
-async fn remove_dir_if_present(path: &Path) -> Result<()> {
-    match tokio::fs::remove_dir_all(path).await {
-        Ok(()) => Ok(()),
-        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
-        Err(error) => Err(error).with_context(|| format!("failed to remove {}", path.display())),
+            .context("failed to delete room persistence graph")?;
+        self.blob_store.delete_released(released).await?;
+        let prefix = key::room_prefix(room_id);
+        if let Err(e) = self.storage.delete_all(&prefix).await {
+            // 记录已删除，残留对象由存储对账回收
+            log::warn!("Failed to remove {}: {:#}", prefix, e);
+        }
+        Ok(deleted)
     }
 }
@@ -0,0 +1,691 @@
+//! 房间导出与导入
+//!
+//! 归档为 ZIP：`manifest.json` 描述房间与全部内容记录（含 `sequence_number`），
+//! 文件内容存放在 `files/<序号>/<文件名>` 下。导入时按目标部署的 [`RoomConfig`]
+//! 重新校验容量与权限，文件逐个流式写入存储并以 SHA-256 校验。
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_zip::base::read::seek::ZipFileReader;
+use async_zip::base::write::ZipFileWriter;
+use async_zip::{Compression, ZipDateTime, ZipEntryBuilder};
+use bytes::Bytes;
+use chrono::{NaiveDateTime, Utc};
+use futures::{AsyncReadExt, AsyncWriteExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncWrite, BufReader};
+
+use crate::config::RoomConfig;
+use crate::db::DbPool;
+use crate::models::content::{ContentType, RoomContent};
+use crate::models::permission::RoomPermission;
+use crate::models::{Room, RoomStatus};
+use crate::repository::{
+    IRoomContentRepository, IRoomRepository, RoomContentRepository, RoomRepository,
+};
+use crate::services::{
+    BlobStore, ContentScanService, RoomLifecycleService, RoomPasswordService, ThumbnailService,
+    is_encoded_password,
+};
+use crate::storage::{StorageBackend, StorageError, key, sniff};
+use crate::validation::RoomNameValidator;
+
+/// 归档格式标识，写入 manifest 的 `format` 字段
+pub const ROOM_ARCHIVE_FORMAT: &str = "elizabeth-room";
+/// 当前归档版本，结构不兼容的变更须递增
+pub const ROOM_ARCHIVE_VERSION: u32 = 1;
+const MANIFEST_ENTRY: &str = "manifest.json";
+const FILES_DIR: &str = "files/";
+/// manifest 解压后的大小上限，防止恶意归档耗尽内存
+pub const MAX_MANIFEST_SIZE: u64 = 16 * 1024 * 1024;
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RoomTransferError {
+    #[error("Invalid room archive: {0}")]
+    InvalidArchive(String),
+    #[error("{0}")]
+    Policy(String),
+    #[error("Room content ({size} bytes) exceeds max_size ({max_size} bytes)")]
+    TooLarge { size: i64, max_size: i64 },
+    #[error("Room already exists: {0}")]
+    Conflict(String),
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+type TransferResult<T> = Result<T, RoomTransferError>;
+
+/// `manifest.json` 的内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomArchiveManifest {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: NaiveDateTime,
+    pub room: ArchivedRoom,
+    pub contents: Vec<ArchivedContent>,
+}
+
+/// 归档中的房间设置；容量占用在导入时按内容重新计算
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedRoom {
+    pub name: String,
+    pub slug: String,
+    /// Argon2 编码后的密码，可在实例间直接迁移
+    pub password: Option<String>,
+    pub status: RoomStatus,
+    pub max_size: i64,
+    pub max_times_entered: i64,
+    pub current_times_entered: i64,
+    pub expire_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub permission: u8,
+    /// 旧版归档没有该字段，缺省时沿用目标部署配置
+    #[serde(default)]
+    pub strip_image_metadata: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedContent {
+    pub sequence_number: i32,
+    pub content_type: ContentType,
+    pub text: Option<String>,
+    pub url: Option<String>,
+    pub file_name: Option<String>,
+    pub size: Option<i64>,
+    pub mime_type: Option<String>,
+    pub hash: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// 文件在归档内的条目名，仅文件与图片存在
+    pub file: Option<String>,
+}
+
+/// 导入选项
+#[derive(Debug, Clone, Default)]
+pub struct RoomImportOptions {
+    /// 以新的名称导入；省略时沿用归档中的名称与 slug
+    pub slug: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct RoomTransferService {
+    db_pool: Arc<DbPool>,
+    storage: Arc<dyn StorageBackend>,
+    blob_store: Arc<BlobStore>,
+    room_lifecycle: Arc<RoomLifecycleService>,
+    room_password: Arc<RoomPasswordService>,
+    thumbnails: Arc<ThumbnailService>,
+    content_scan: Arc<ContentScanService>,
+    storage_root: PathBuf,
+    room_config: RoomConfig,
+}
+
+impl RoomTransferService {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        db_pool: Arc<DbPool>,
+        storage: Arc<dyn StorageBackend>,
+        blob_store: Arc<BlobStore>,
+        room_lifecycle: Arc<RoomLifecycleService>,
+        room_password: Arc<RoomPasswordService>,
+        thumbnails: Arc<ThumbnailService>,
+        content_scan: Arc<ContentScanService>,
+        storage_root: PathBuf,
+        room_config: RoomConfig,
+    ) -> Self {
+        Self {
+            db_pool,
+            storage,
+            blob_store,
+            room_lifecycle,
+            room_password,
+            thumbnails,
+            content_scan,
+            storage_root,
+            room_config,
+        }
+    }
+
+    /// 将房间导出为归档写入 `writer`
+    ///
+    /// 存储中已缺失的文件连同其内容记录一起跳过，缺少哈希的旧记录在导出时补算。
+    pub async fn export<W>(&self, room: &Room, writer: W) -> anyhow::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let room_id = room.id.context("room id missing")?;
+        let contents = RoomContentRepository::new(self.db_pool.clone())
+            .list_by_room(room_id)
+            .await
+            .context("failed to list room contents")?;
+
+        let mut zip = ZipFileWriter::with_tokio(writer);
+        let mut archived = Vec::with_capacity(contents.len());
+        for (index, content) in contents.iter().enumerate() {
+            let mut entry = archived_content(content);
+            if let Some(path) = content.path.as_deref().filter(|_| has_file(content)) {
+                let object_key = key::object_key(&self.storage_root, path);
+                let mut stream = match self.storage.reader(&object_key).await {
+                    Ok(stream) => stream,
+                    Err(StorageError::NotFound(_)) => {
+                        log::warn!("Skipping missing file {} in room export", object_key);
+                        continue;
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+
+                let entry_name = file_entry_name(index, content);
+                let builder = ZipEntryBuilder::new(entry_name.clone().into(), Compression::Stored)
+                    .last_modification_date(ZipDateTime::from_chrono(&content.updated_at.and_utc()))
+                    .unix_permissions(0o644);
+                let mut entry_writer = zip.write_entry_stream(builder).await?;
+                let mut hasher = Sha256::new();
+                let mut size = 0i64;
+                while let Some(chunk) = stream.next().await {
+                    let chunk = chunk?;
+                    hasher.update(&chunk);
+                    size += chunk.len() as i64;
+                    entry_writer.write_all(&chunk).await?;
+                }
+                entry_writer.close().await?;
+
+                entry.size = Some(size);
+                entry.hash = Some(hex::encode(hasher.finalize()));
+                entry.file = Some(entry_name);
+            }
+            archived.push(entry);
+        }
+
+        let manifest = RoomArchiveManifest {
+            format: ROOM_ARCHIVE_FORMAT.to_string(),
+            version: ROOM_ARCHIVE_VERSION,
+            exported_at: Utc::now().naive_utc(),
+            room: archived_room(room),
+            contents: archived,
+        };
+        let manifest = serde_json::to_vec_pretty(&manifest)?;
+        let builder = ZipEntryBuilder::new(MANIFEST_ENTRY.into(), Compression::Deflate);
+        zip.write_entry_whole(builder, &manifest).await?;
+
+        let mut inner = zip.close().await?;
+        inner.close().await?;
+        Ok(())
+    }
+
+    /// 从本地归档文件导入房间
+    ///
+    /// 容量与权限按本部署的房间默认配置重新校验：`max_size` 不超过
+    /// `defaults.max_content_size` 且须容纳全部内容，权限不超出 `defaults.permission`。
+    /// 任一步骤失败时已写入的文件与房间记录都会被清理。
+    pub async fn import_file(
+        &self,
+        archive_path: &Path,
+        options: RoomImportOptions,
+    ) -> TransferResult<Room> {
+        let file = tokio::fs::File::open(archive_path)
+            .await
+            .with_context(|| format!("failed to open {}", archive_path.display()))?;
+        let mut reader = ZipFileReader::with_tokio(BufReader::new(file))
+            .await
+            .map_err(|e| RoomTransferError::InvalidArchive(e.to_string()))?;
+
+        let entries: HashMap<String, usize> = reader
+            .file()
+            .entries()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                let name = entry.filename().as_str().ok()?;
+                Some((name.to_string(), index))
+            })
+            .collect();
+        let manifest = read_manifest(&mut reader, &entries).await?;
+        let (room, contents_size) = self.prepare_room(&manifest, options).await?;
+
+        let repository = RoomRepository::new(self.db_pool.clone());
+        let mut room = repository
+            .create_if_absent(&room)
+            .await
+            .context("failed to create room")?
+            .ok_or_else(|| RoomTransferError::Conflict(room.slug.clone()))?;
+        let room_id = room.id.context("room id missing")?;
+
+        let mut stored_keys = Vec::new();
+        let result = self
+            .restore_contents(
+                &mut reader,
+                &entries,
+                &manifest,
+                &mut room,
+                contents_size,
+                &mut stored_keys,
+            )
+            .await;
+        if let Err(e) = result {
+            self.discard(room_id, &stored_keys).await;
+            return Err(e);
+        }
+        Ok(room)
+    }
+
+    /// 按目标部署的策略构造待创建的房间，并返回内容总大小
+    async fn prepare_room(
+        &self,
+        manifest: &RoomArchiveManifest,
+        options: RoomImportOptions,
+    ) -> TransferResult<(Room, i64)> {
+        let archived = &manifest.room;
+        let (name, slug) = match options.slug {
+            Some(slug) => {
+                RoomNameValidator::validate(&slug)
+                    .map_err(|e| RoomTransferError::Policy(e.to_string()))?;
+                (slug.clone(), slug)
+            }
+            None => {
+                RoomNameValidator::validate_identifier(&archived.slug)
+                    .map_err(|e| RoomTransferError::Policy(e.to_string()))?;
+                (archived.name.clone(), archived.slug.clone())
+            }
+        };
+
+        let defaults = &self.room_config.defaults;
+        let contents_size = manifest
+            .contents
+            .iter()
+            .try_fold(0i64, |total, content| {
+                total.checked_add(content.size.unwrap_or(0).max(0))
+            })
+            .ok_or_else(|| {
+                RoomTransferError::InvalidArchive("content sizes overflow".to_string())
+            })?;
+        let max_size = archived.max_size.min(defaults.max_content_size);
+        if max_size <= 0 {
+            return Err(RoomTransferError::Policy(
+                "max_size must be greater than 0".to_string(),
+            ));
+        }
+        if contents_size > max_size {
+            return Err(RoomTransferError::TooLarge {
+                size: contents_size,
+                max_size,
+            });
+        }
+
+        let now = Utc::now().naive_utc();
+        let expire_at = match archived.expire_at {
+            Some(expire_at) if expire_at <= now => {
+                return Err(RoomTransferError::Policy(format!(
+                    "Room expired at {expire_at}"
+                )));
+            }
+            Some(expire_at) => expire_at,
+            None => self
+                .room_config
+                .expiry
+                .default_expire_at(now)
+                .context("default room expiry exceeds supported date range")?,
+        };
+
+        let password = match archived.password.clone() {
+            Some(password) if !is_encoded_password(&password) => Some(
+                self.room_password
+                    .hash(password)
+                    .await
+                    .context("failed to protect room password")?,
+            ),
+            password => password,
+        };
+
+        let mut room = Room::new(name, password);
+        room.slug = slug;
+        room.status = archived.status;
+        room.max_size = max_size;
+        room.max_times_entered = archived.max_times_entered;
+        room.current_times_entered = archived
+            .current_times_entered
+            .clamp(0, archived.max_times_entered.max(0));
+        room.expire_at = Some(expire_at);
+        room.permission = RoomPermission::from_bits_truncate(archived.permission)
+            .intersection(defaults.permission);
+        room.strip_image_metadata = archived.strip_image_metadata;
+        Ok((room, contents_size))
+    }
+
+    async fn restore_contents<R>(
+        &self,
+        reader: &mut ZipFileReader<R>,
+        entries: &HashMap<String, usize>,
+        manifest: &RoomArchiveManifest,
+        room: &mut Room,
+        contents_size: i64,
+        stored_keys: &mut Vec<String>,
+    ) -> TransferResult<()>
+    where
+        R: futures::AsyncBufRead + futures::AsyncSeek + Unpin,
+    {
+        let room_id = room.id.context("room id missing")?;
+        let mut used_names = HashSet::new();
+        let mut contents = Vec::with_capacity(manifest.contents.len());
+        for archived in &manifest.contents {
+            let mut content = RoomContent::builder()
+                .room_id(room_id)
+                .content_type(archived.content_type)
+                .sequence_number(archived.sequence_number)
+                .now(archived.created_at)
+                .build();
+            content.updated_at = archived.updated_at;
+            content.text = archived.text.clone();
+            content.url = archived.url.clone();
+            content.file_name = archived.file_name.clone();
+            content.size = archived.size;
+            content.mime_type = archived.mime_type.clone();
+
+            if let Some(entry_name) = archived.file.as_deref() {
+                let index = *entries.get(entry_name).ok_or_else(|| {
+                    RoomTransferError::InvalidArchive(format!("missing entry {entry_name}"))
+                })?;
+                let file_name = archived.file_name.as_deref().unwrap_or(entry_name);
+                let (object_key, detected_mime) = self
+                    .store_entry(reader, index, room_id, file_name, archived, &mut used_names)
+                    .await?;
+                // 归档中的类型不可信，按内容重新检测
+                content.content_type = sniff::content_type_for(detected_mime);
+                content.detected_mime_type = detected_mime.map(str::to_string);
+                content.path = Some(key::stored_path(&self.storage_root, &object_key));
+                content.hash = archived.hash.clone();
+                stored_keys.push(object_key);
+            }
+            content.thumbnail_status = self.thumbnails.initial_status(&content);
+            content.scan_status = self.content_scan.initial_status(&content);
+            contents.push(content);
+        }
+
+        RoomContentRepository::new(self.db_pool.clone())
+            .create_batch(&contents)
+            .await
+            .context("failed to create room contents")?;
+        // 引用已由内容记录持有，之后的失败由 purge_room 统一释放
+        stored_keys.clear();
+        room.current_size = contents_size;
+        *room = RoomRepository::new(self.db_pool.clone())
+            .update(room)
+            .await
+            .context("failed to update room size")?;
+        Ok(())
+    }
+
+    /// 将一个归档条目写入存储，返回对象 key 与根据内容检测到的类型
+    ///
+    /// 大小与 SHA-256 必须与 manifest 一致。启用去重时先写入房间内的暂存对象，
+    /// 校验通过后再登记 blob 引用，避免不可信数据进入共享对象。
+    async fn store_entry<R>(
+        &self,
+        reader: &mut ZipFileReader<R>,
+        index: usize,
+        room_id: i64,
+        file_name: &str,
+        archived: &ArchivedContent,
+        used_names: &mut HashSet<String>,
+    ) -> TransferResult<(String, Option<&'static str>)>
+    where
+        R: futures::AsyncBufRead + futures::AsyncSeek + Unpin,
+    {
+        let expected_hash = archived
+            .hash
+            .as_deref()
+            .ok_or_else(|| RoomTransferError::InvalidArchive("file without hash".to_string()))?;
+        let expected_size = archived
+            .size
+            .ok_or_else(|| RoomTransferError::InvalidArchive("file without size".to_string()))?;
+        let object_key = key::room_object_key(room_id, &unique_name(used_names, file_name));
+
+        let mut entry = reader
+            .reader_without_entry(index)
+            .await
+            .map_err(|e| RoomTransferError::InvalidArchive(e.to_string()))?;
+        let mut writer = self
+            .storage
+            .writer(&object_key)
+            .await
+            .with_context(|| format!("failed to open {object_key}"))?;
+        let mut hasher = Sha256::new();
+        let mut head = Vec::with_capacity(sniff::SNIFF_LEN);
+        let mut size = 0i64;
+        let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+        loop {
+            let read = match entry.read(&mut buffer).await {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) => {
+                    let _ = writer.abort().await;
+                    return Err(RoomTransferError::InvalidArchive(e.to_string()));
+                }
+            };
+            hasher.update(&buffer[..read]);
+            let take = read.min(sniff::SNIFF_LEN - head.len());
+            head.extend_from_slice(&buffer[..take]);
+            size += read as i64;
+            if size > expected_size {
+                let _ = writer.abort().await;
+                return Err(RoomTransferError::InvalidArchive(format!(
+                    "{file_name} is larger than recorded"
+                )));
+            }
+            if let Err(e) = writer.write(Bytes::copy_from_slice(&buffer[..read])).await {
+                let _ = writer.abort().await;
+                return Err(anyhow::Error::from(e)
+                    .context(format!("failed to write {object_key}"))
+                    .into());
+            }
+        }
+        writer
+            .close()
+            .await
+            .with_context(|| format!("failed to write {object_key}"))?;
+
+        let actual_hash = hex::encode(hasher.finalize());
+        if size != expected_size || actual_hash != expected_hash {
+            let _ = self.storage.delete(&object_key).await;
+            return Err(RoomTransferError::InvalidArchive(format!(
+                "{file_name} does not match its recorded size or hash"
+            )));
+        }
+        let detected_mime = sniff::detect(&head);
+        if !self.blob_store.enabled() {
+            return Ok((object_key, detected_mime));
+        }
+
+        let lease = match self.blob_store.acquire(expected_hash, size).await {
+            Ok(lease) => lease,
+            Err(e) => {
+                let _ = self.storage.delete(&object_key).await;
+                return Err(e.into());
+            }
+        };
+        let moved = if lease.needs_upload {
+            self.blob_store
+                .upload(&lease, self.copy_object(&object_key, &lease.key))
+                .await
+        } else {
+            Ok(())
+        };
+        let _ = self.storage.delete(&object_key).await;
+        moved?;
+        Ok((lease.key, detected_mime))
+    }
+
+    async fn copy_object(&self, from: &str, to: &str) -> anyhow::Result<()> {
+        let mut stream = self.storage.reader(from).await?;
+        let mut writer = self.storage.writer(to).await?;
+        while let Some(chunk) = stream.next().await {
+            let result = match chunk {
+                Ok(chunk) => writer.write(chunk).await.map_err(anyhow::Error::from),
+                Err(e) => Err(anyhow::Error::from(e)),
+            };
+            if let Err(e) = result {
+                let _ = writer.abort().await;
+                return Err(e).with_context(|| format!("failed to copy {from} to {to}"));
+            }
+        }
+        writer
+            .close()
+            .await
+            .with_context(|| format!("failed to copy {from} to {to}"))
+    }
+
+    /// 导入失败时归还尚未写入内容记录的对象并删除房间
+    async fn discard(&self, room_id: i64, stored_keys: &[String]) {
+        for object_key in stored_keys {
+            if let Err(e) = self.blob_store.release_object(object_key).await {
+                log::warn!(
+                    "Failed to release {} after import error: {:#}",
+                    object_key,
+                    e
+                );
+            }
+        }
+        if let Err(e) = self.room_lifecycle.purge_room(room_id).await {
+            log::warn!(
+                "Failed to remove room {} after import error: {:#}",
+                room_id,
+                e
+            );
+        }
+    }
+}
+
+async fn read_manifest<R>(
+    reader: &mut ZipFileReader<R>,
+    entries: &HashMap<String, usize>,
+) -> TransferResult<RoomArchiveManifest>
+where
+    R: futures::AsyncBufRead + futures::AsyncSeek + Unpin,
+{
+    let index = *entries
+        .get(MANIFEST_ENTRY)
+        .ok_or_else(|| RoomTransferError::InvalidArchive(format!("missing {MANIFEST_ENTRY}")))?;
+    let entry = reader
+        .reader_without_entry(index)
+        .await
+        .map_err(|e| RoomTransferError::InvalidArchive(e.to_string()))?;
+    let mut raw = Vec::new();
+    entry
+        .take(MAX_MANIFEST_SIZE + 1)
+        .read_to_end(&mut raw)
+        .await
+        .map_err(|e| RoomTransferError::InvalidArchive(e.to_string()))?;
+    if raw.len() as u64 > MAX_MANIFEST_SIZE {
+        return Err(RoomTransferError::InvalidArchive(format!(
+            "{MANIFEST_ENTRY} is too large"
+        )));
+    }
+
+    let manifest: RoomArchiveManifest = serde_json::from_slice(&raw)
+        .map_err(|e| RoomTransferError::InvalidArchive(format!("{MANIFEST_ENTRY}: {e}")))?;
+    if manifest.format != ROOM_ARCHIVE_FORMAT {
+        return Err(RoomTransferError::InvalidArchive(format!(
+            "unknown format {}",
+            manifest.format
+        )));
+    }
+    if manifest.version == 0 || manifest.version > ROOM_ARCHIVE_VERSION {
+        return Err(RoomTransferError::InvalidArchive(format!(
+            "unsupported version {}",
+            manifest.version
+        )));
+    }
+    Ok(manifest)
+}
+
+fn has_file(content: &RoomContent) -> bool {
+    matches!(content.content_type, ContentType::File | ContentType::Image)
+}
+
+fn file_entry_name(index: usize, content: &RoomContent) -> String {
+    let name = content
+        .file_name
+        .as_deref()
+        .map(sanitize_filename::sanitize)
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "file".to_string());
+    format!("{FILES_DIR}{index}/{name}")
+}
+
+/// 房间内的对象名去重，同名文件依次追加 `(1)`、`(2)` … 后缀
+fn unique_name(used: &mut HashSet<String>, file_name: &str) -> String {
+    let safe = sanitize_filename::sanitize(file_name);
+    let safe = if safe.is_empty() {
+        "file".to_string()
+    } else {
+        safe
+    };
+    if used.insert(safe.clone()) {
+        return safe;
+    }
+
+    let path = Path::new(&safe);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or(&safe);
+    let extension = path.extension().and_then(|s| s.to_str());
+    let mut counter = 1;
+    loop {
+        let candidate = match extension {
+            Some(extension) => format!("{stem}({counter}).{extension}"),
+            None => format!("{stem}({counter})"),
+        };
+        if used.insert(candidate.clone()) {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+fn archived_room(room: &Room) -> ArchivedRoom {
+    ArchivedRoom {
+        name: room.name.clone(),
+        slug: room.slug.clone(),
+        password: room.password.clone(),
+        status: room.status,
+        max_size: room.max_size,
+        max_times_entered: room.max_times_entered,
+        current_times_entered: room.current_times_entered,
+        expire_at: room.expire_at,
+        created_at: room.created_at,
+        permission: room.permission.bits(),
+        strip_image_metadata: room.strip_image_metadata,
+    }
+}
+
+fn archived_content(content: &RoomContent) -> ArchivedContent {
+    ArchivedContent {
+        sequence_number: content.sequence_number,
+        content_type: content.content_type,
+        text: content.text.clone(),
+        url: content.url.clone(),
+        file_name: content.file_name.clone(),
+        size: content.size,
+        mime_type: content.mime_type.clone(),
+        hash: content.hash.clone(),
+        created_at: content.created_at,
+        updated_at: content.updated_at,
+        file: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_name_appends_counter() {
+        let mut used = HashSet::new();
+        assert_eq!(unique_name(&mut used, "a.txt"), "a.txt");
+        assert_eq!(unique_name(&mut used, "a.txt"), "a(1).txt");
+        assert_eq!(unique_name(&mut used, "../a.txt"), "..a.txt");
+        assert_eq!(unique_name(&mut used, ""), "file");
+    }
+}
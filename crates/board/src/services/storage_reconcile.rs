@@ -0,0 +1,175 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+
+use crate::config::ReconcileConfig;
+use crate::models::content::IntegrityStatus;
+use crate::repository::StorageReconcileRepository;
+use crate::storage::{StorageBackend, StorageError, key};
+
+/// 一轮对账的结果
+#[derive(Debug, Default)]
+pub struct ReconcileReport {
+    pub files_examined: u64,
+    pub contents_examined: u64,
+    /// 超过宽限期且没有内容记录的文件
+    pub orphan_files: Vec<String>,
+    pub orphans_deleted: u64,
+    /// 本轮新标记为 `Missing` 的内容
+    pub missing_contents: Vec<i64>,
+    /// 文件重新出现、恢复为 `Ok` 的内容数
+    pub restored_contents: u64,
+    pub rooms_corrected: u64,
+}
+
+impl ReconcileReport {
+    pub fn changed(&self) -> u64 {
+        self.orphans_deleted
+            + self.missing_contents.len() as u64
+            + self.restored_contents
+            + self.rooms_corrected
+    }
+}
+
+/// 存储与数据库对账服务
+///
+/// 上传或合并中途崩溃会留下没有 `room_contents` 记录的文件，手工清理磁盘则会留下
+/// 指向不存在文件的记录。对账先读取数据库再遍历存储，新写入的文件受宽限期保护，
+/// 不会被误判为孤儿。
+pub struct StorageReconcileService {
+    repository: StorageReconcileRepository,
+    storage: Arc<dyn StorageBackend>,
+    storage_root: PathBuf,
+    config: ReconcileConfig,
+}
+
+impl StorageReconcileService {
+    pub fn new(
+        repository: StorageReconcileRepository,
+        storage: Arc<dyn StorageBackend>,
+        storage_root: PathBuf,
+        config: ReconcileConfig,
+    ) -> Self {
+        Self {
+            repository,
+            storage,
+            storage_root,
+            config,
+        }
+    }
+
+    pub async fn run(&self) -> Result<ReconcileReport> {
+        let rows = self.repository.list_content_files().await?;
+        let blob_hashes: HashSet<String> = self
+            .repository
+            .list_blob_hashes()
+            .await?
+            .into_iter()
+            .collect();
+        let files: HashSet<String> = self
+            .storage
+            .list_recursive("")
+            .await
+            .context("failed to list storage")?
+            .into_iter()
+            .collect();
+
+        let mut report = ReconcileReport {
+            files_examined: files.len() as u64,
+            contents_examined: rows.len() as u64,
+            ..Default::default()
+        };
+
+        let referenced: HashSet<String> = rows
+            .iter()
+            .map(|row| key::object_key(&self.storage_root, &row.path))
+            .collect();
+        let owners: HashSet<(i64, i64)> = rows.iter().map(|row| (row.room_id, row.id)).collect();
+        for file in &files {
+            // 暂存分块属于上传预留，由上传清理任务回收
+            let owned = file.starts_with(key::CHUNK_PREFIX)
+                || referenced.contains(file)
+                || key::blob_hash(file).is_some_and(|hash| blob_hashes.contains(hash))
+                || key::thumbnail_owner(file).is_some_and(|owner| owners.contains(&owner));
+            if !owned {
+                self.handle_orphan(file, &mut report).await?;
+            }
+        }
+
+        for row in &rows {
+            let object_key = key::object_key(&self.storage_root, &row.path);
+            // 列表之后才写入的文件不在 `files` 中，标记前再确认一次
+            let exists = files.contains(&object_key)
+                || self
+                    .storage
+                    .exists(&object_key)
+                    .await
+                    .with_context(|| format!("failed to check {object_key}"))?;
+            let status = if exists {
+                IntegrityStatus::Ok
+            } else {
+                IntegrityStatus::Missing
+            };
+            // 文件仍在时保留完整性巡检给出的损坏标记
+            let unchanged = if exists {
+                row.integrity_status != IntegrityStatus::Missing
+            } else {
+                row.integrity_status == IntegrityStatus::Missing
+            };
+            if unchanged || !self.repository.set_integrity_status(row.id, status).await? {
+                continue;
+            }
+            if exists {
+                log::info!("File of content {} is back at {object_key}", row.id);
+                report.restored_contents += 1;
+            } else {
+                log::warn!("File of content {} is missing: {object_key}", row.id);
+                report.missing_contents.push(row.id);
+            }
+        }
+
+        for (room_id, recorded) in self.repository.list_room_sizes().await? {
+            if let Some(actual) = self.repository.correct_current_size(room_id).await? {
+                log::warn!("Corrected size of room {room_id} from {recorded} to {actual} bytes");
+                report.rooms_corrected += 1;
+            }
+        }
+
+        Ok(report)
+    }
+
+    async fn handle_orphan(&self, file: &str, report: &mut ReconcileReport) -> Result<()> {
+        let meta = match self.storage.stat(file).await {
+            Ok(meta) => meta,
+            Err(StorageError::NotFound(_)) => return Ok(()),
+            Err(error) => return Err(error).with_context(|| format!("failed to stat {file}")),
+        };
+        let grace = i64::try_from(self.config.orphan_grace_seconds)
+            .ok()
+            .and_then(chrono::Duration::try_seconds)
+            .unwrap_or(chrono::Duration::MAX);
+        // 无法得知修改时间的文件按新文件处理
+        let expired = meta
+            .last_modified
+            .is_some_and(|modified| Utc::now().signed_duration_since(modified) >= grace);
+        if !expired {
+            return Ok(());
+        }
+
+        if self.config.delete_orphans {
+            self.storage
+                .delete(file)
+                .await
+                .with_context(|| format!("failed to delete orphan {file}"))?;
+            log::warn!("Deleted orphan file {file} ({} bytes)", meta.size);
+            report.orphans_deleted += 1;
+        } else {
+            log::warn!("Found orphan file {file} ({} bytes)", meta.size);
+        }
+        report.orphan_files.push(file.to_string());
+        Ok(())
+    }
+}
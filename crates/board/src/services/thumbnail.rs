@@ -0,0 +1,355 @@
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ExtendedColorType, ImageDecoder, ImageReader, Limits};
+
+use crate::config::{ThumbnailConfig, ThumbnailFormat};
+use crate::models::content::{RoomContent, ThumbnailStatus};
+use crate::repository::RoomContentRepository;
+use crate::storage::{StorageBackend, StorageError, key};
+
+/// 原图允许的最大边长（像素），超过时放弃解码
+const MAX_SOURCE_DIMENSION: u32 = 16_384;
+/// 解码时允许分配的最大内存
+const MAX_DECODE_ALLOC: u64 = 512 * 1024 * 1024;
+/// 缩略图高度最多为宽度的倍数，避免超长图生成巨大的缩略图
+const MAX_ASPECT_RATIO: u32 = 4;
+
+/// 一轮缩略图生成的结果
+#[derive(Debug, Default)]
+pub struct ThumbnailRunReport {
+    pub examined: u64,
+    /// 本轮生成完毕的内容，状态已更新为 `Ready`
+    pub ready: Vec<RoomContent>,
+    pub failed: u64,
+}
+
+/// 图片缩略图服务
+///
+/// 上传时仅把图片标记为 `Pending`，由后台任务逐批解码、缩放并写回存储层。
+/// 缩略图存放在房间前缀下，随内容删除或房间回收一起清理。
+pub struct ThumbnailService {
+    repository: RoomContentRepository,
+    storage: Arc<dyn StorageBackend>,
+    storage_root: PathBuf,
+    config: ThumbnailConfig,
+}
+
+impl ThumbnailService {
+    pub fn new(
+        repository: RoomContentRepository,
+        storage: Arc<dyn StorageBackend>,
+        storage_root: PathBuf,
+        config: ThumbnailConfig,
+    ) -> Self {
+        Self {
+            repository,
+            storage,
+            storage_root,
+            config,
+        }
+    }
+
+    pub fn format(&self) -> ThumbnailFormat {
+        self.config.format
+    }
+
+    /// 新内容写入数据库前的初始状态
+    pub fn initial_status(&self, content: &RoomContent) -> ThumbnailStatus {
+        if self.config.enabled && content.supports_thumbnail() {
+            ThumbnailStatus::Pending
+        } else {
+            ThumbnailStatus::None
+        }
+    }
+
+    /// 选取不小于请求宽度的最小档位，未指定时返回最小档位
+    pub fn select_width(&self, requested: Option<u32>) -> Option<u32> {
+        nearest_width(&self.config.widths, requested)
+    }
+
+    /// 查找可供下载的缩略图，返回实际宽度与对象 key
+    ///
+    /// 修改宽度档位或格式后，旧内容只有按旧配置生成的缩略图：此时退回到当前格式下已有的
+    /// 最接近的宽度，并把内容放回队列按当前配置重新生成。一张可用的都没有时返回 `None`。
+    pub async fn resolve(
+        &self,
+        content: &RoomContent,
+        requested: Option<u32>,
+    ) -> Result<Option<(u32, String)>> {
+        let Some(width) = self.select_width(requested) else {
+            return Ok(None);
+        };
+        let Some(target) = self.object_key(content, width) else {
+            return Ok(None);
+        };
+        if self
+            .storage
+            .exists(&target)
+            .await
+            .with_context(|| format!("failed to check {target}"))?
+        {
+            return Ok(Some((width, target)));
+        }
+
+        if content.thumbnail_status == ThumbnailStatus::Ready
+            && let Some(content_id) = content.id
+        {
+            log::info!("Thumbnails of content {content_id} are outdated, queueing regeneration");
+            self.repository
+                .set_thumbnail_status(content_id, ThumbnailStatus::Pending)
+                .await?;
+        }
+        let stored = self.stored_widths(content).await?;
+        Ok(nearest_width(&stored, Some(width))
+            .and_then(|width| Some((width, self.object_key(content, width)?))))
+    }
+
+    /// 当前格式下已生成的缩略图宽度，从小到大排列
+    async fn stored_widths(&self, content: &RoomContent) -> Result<Vec<u32>> {
+        let Some(content_id) = content.id else {
+            return Ok(Vec::new());
+        };
+        let prefix = key::thumbnail_prefix(content.room_id, content_id);
+        let keys = match self.storage.list(&prefix).await {
+            Ok(keys) => keys,
+            Err(StorageError::NotFound(_)) => return Ok(Vec::new()),
+            Err(error) => return Err(error).with_context(|| format!("failed to list {prefix}")),
+        };
+        let suffix = format!(".{}", self.config.format.extension());
+        let mut widths: Vec<u32> = keys
+            .iter()
+            .filter_map(|key| {
+                let name = key.rsplit('/').next()?;
+                name.strip_suffix(suffix.as_str())?.parse().ok()
+            })
+            .collect();
+        widths.sort_unstable();
+        Ok(widths)
+    }
+
+    /// 缩略图在存储层中的对象 key
+    pub fn object_key(&self, content: &RoomContent, width: u32) -> Option<String> {
+        let content_id = content.id?;
+        Some(key::thumbnail_key(
+            content.room_id,
+            content_id,
+            width,
+            self.config.format.extension(),
+        ))
+    }
+
+    /// 删除一条内容的全部缩略图
+    pub async fn remove(&self, room_id: i64, content_id: i64) -> Result<()> {
+        let prefix = key::thumbnail_prefix(room_id, content_id);
+        self.storage
+            .delete_all(&prefix)
+            .await
+            .with_context(|| format!("failed to remove {prefix}"))
+    }
+
+    /// 处理一批等待中的图片
+    pub async fn run_pending(&self) -> Result<ThumbnailRunReport> {
+        let pending = self
+            .repository
+            .list_pending_thumbnails(self.config.batch_limit)
+            .await?;
+        let mut report = ThumbnailRunReport {
+            examined: pending.len() as u64,
+            ..Default::default()
+        };
+
+        for mut content in pending {
+            let Some(content_id) = content.id else {
+                continue;
+            };
+            let status = match self.generate(&content).await {
+                Ok(()) => ThumbnailStatus::Ready,
+                Err(error) => {
+                    log::warn!("Thumbnail generation failed for content {content_id}: {error:#}");
+                    ThumbnailStatus::Failed
+                }
+            };
+
+            let updated = self
+                .repository
+                .set_thumbnail_status(content_id, status)
+                .await?;
+            if !updated {
+                // 生成期间内容已被删除，清理刚写入的缩略图
+                self.remove(content.room_id, content_id).await?;
+                continue;
+            }
+            if status == ThumbnailStatus::Ready {
+                content.thumbnail_status = status;
+                report.ready.push(content);
+            } else {
+                report.failed += 1;
+            }
+        }
+
+        Ok(report)
+    }
+
+    async fn generate(&self, content: &RoomContent) -> Result<()> {
+        let size = content.size.unwrap_or_default();
+        if u64::try_from(size).unwrap_or(u64::MAX) > self.config.max_source_size {
+            anyhow::bail!("source image is {size} bytes, above the thumbnail limit");
+        }
+        let path = content
+            .path
+            .as_deref()
+            .context("content has no stored file")?;
+        let source_key = key::object_key(&self.storage_root, path);
+        let source = match self.storage.get(&source_key).await {
+            Ok(source) => source,
+            Err(StorageError::NotFound(_)) => anyhow::bail!("{source_key} is missing"),
+            Err(error) => {
+                return Err(error).with_context(|| format!("failed to read {source_key}"));
+            }
+        };
+
+        let widths = self.config.widths.clone();
+        let format = self.config.format;
+        let quality = self.config.quality;
+        let rendered = tokio::task::spawn_blocking(move || {
+            render_thumbnails(&source, &widths, format, quality)
+        })
+        .await
+        .context("thumbnail worker panicked")??;
+
+        for (width, data) in rendered {
+            let target = self
+                .object_key(content, width)
+                .context("content has no id")?;
+            self.storage
+                .put(&target, data)
+                .await
+                .with_context(|| format!("failed to store {target}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// 从升序的 `widths` 中选取不小于 `requested` 的最小宽度，没有时取最大宽度；未指定时取最小宽度
+fn nearest_width(widths: &[u32], requested: Option<u32>) -> Option<u32> {
+    match requested {
+        None => widths.first().copied(),
+        Some(requested) => widths
+            .iter()
+            .copied()
+            .find(|width| *width >= requested)
+            .or_else(|| widths.last().copied()),
+    }
+}
+
+/// 解码原图并按各档宽度生成缩略图，不会放大原图
+pub(crate) fn render_thumbnails(
+    source: &[u8],
+    widths: &[u32],
+    format: ThumbnailFormat,
+    quality: u8,
+) -> Result<Vec<(u32, Vec<u8>)>> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_DIMENSION);
+    limits.max_image_height = Some(MAX_SOURCE_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+
+    let mut reader = ImageReader::new(Cursor::new(source)).with_guessed_format()?;
+    reader.limits(limits);
+    let mut decoder = reader.into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+
+    widths
+        .iter()
+        .map(|&width| {
+            let max_height = width.saturating_mul(MAX_ASPECT_RATIO);
+            let resized = if image.width() <= width && image.height() <= max_height {
+                image.clone()
+            } else {
+                image.resize(width, max_height, FilterType::Triangle)
+            };
+            Ok((width, encode(&resized, format, quality)?))
+        })
+        .collect()
+}
+
+fn encode(image: &DynamicImage, format: ThumbnailFormat, quality: u8) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    match format {
+        ThumbnailFormat::Jpeg => {
+            let rgb = image.to_rgb8();
+            JpegEncoder::new_with_quality(&mut buffer, quality).encode(
+                rgb.as_raw(),
+                rgb.width(),
+                rgb.height(),
+                ExtendedColorType::Rgb8,
+            )?;
+        }
+        ThumbnailFormat::Webp => {
+            // image 只提供无损 WebP 编码，`quality` 仅作用于 JPEG
+            let rgba = image.to_rgba8();
+            WebPEncoder::new_lossless(&mut buffer).encode(
+                rgba.as_raw(),
+                rgba.width(),
+                rgba.height(),
+                ExtendedColorType::Rgba8,
+            )?;
+        }
+    }
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = DynamicImage::new_rgba8(width, height);
+        let mut buffer = Cursor::new(Vec::new());
+        image
+            .write_to(&mut buffer, image::ImageFormat::Png)
+            .expect("encode png");
+        buffer.into_inner()
+    }
+
+    #[test]
+    fn render_keeps_aspect_ratio_without_upscaling() {
+        let rendered =
+            render_thumbnails(&png(800, 400), &[320, 960], ThumbnailFormat::Jpeg, 80).unwrap();
+        let sizes: Vec<_> = rendered
+            .iter()
+            .map(|(width, data)| {
+                let image = image::load_from_memory(data).unwrap();
+                (*width, image.width(), image.height())
+            })
+            .collect();
+
+        assert_eq!(sizes, vec![(320, 320, 160), (960, 800, 400)]);
+    }
+
+    #[test]
+    fn render_bounds_tall_images_and_encodes_webp() {
+        let rendered =
+            render_thumbnails(&png(100, 2000), &[50], ThumbnailFormat::Webp, 80).unwrap();
+        let image = image::load_from_memory(&rendered[0].1).unwrap();
+
+        assert_eq!(
+            image::guess_format(&rendered[0].1).unwrap(),
+            image::ImageFormat::WebP
+        );
+        assert_eq!((image.width(), image.height()), (10, 200));
+    }
+
+    #[test]
+    fn render_rejects_non_images() {
+        assert!(render_thumbnails(b"not an image", &[320], ThumbnailFormat::Jpeg, 80).is_err());
+    }
+}
@@ -5,10 +5,17 @@ use std::sync::Arc;
 
 use anyhow::Result;
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, EventBusBackend};
 use crate::db::DbPool;
+use crate::repository::RoomEventRepository;
 use crate::services::Services;
-use crate::websocket::{broadcaster::Broadcaster, connection::ConnectionManager};
+use crate::storage::{OpendalBackend, StorageBackend};
+use crate::websocket::{
+    broadcaster::Broadcaster,
+    connection::{ConnectionManager, ConnectionManagerConfig},
+    event_bus::{EventBus, InProcessEventBus, PgEventBus},
+    event_log::RoomEventLog,
+};
 
 /// 应用程序状态
 ///
@@ -21,6 +28,8 @@ pub struct AppState {
     pub config: AppConfig,
     /// 服务容器
     pub services: Services,
+    /// 文件存储后端
+    pub storage: Arc<dyn StorageBackend>,
     /// WebSocket 连接管理器
     pub connection_manager: Arc<ConnectionManager>,
     /// WebSocket 广播器
@@ -33,19 +42,56 @@ impl AppState {
         // 验证配置
         config.validate()?;
 
+        // 创建存储后端
+        let storage: Arc<dyn StorageBackend> =
+            Arc::new(OpendalBackend::new(config.storage.backend_config())?);
+
+        Self::with_storage(config, db_pool, storage)
+    }
+
+    /// 使用指定的存储后端创建应用程序状态
+    pub fn with_storage(
+        config: AppConfig,
+        db_pool: Arc<DbPool>,
+        storage: Arc<dyn StorageBackend>,
+    ) -> Result<Self> {
+        // 验证配置
+        config.validate()?;
+
         // 创建服务
-        let services = Services::new(&config, db_pool.clone())?;
+        let services = Services::new(&config, db_pool.clone(), storage.clone())?;
 
         // 创建 WebSocket 连接管理器
-        let connection_manager = Arc::new(ConnectionManager::new());
-
-        // 创建 WebSocket 广播器
-        let broadcaster = Arc::new(Broadcaster::new(connection_manager.clone()));
+        let connection_manager =
+            Arc::new(ConnectionManager::with_config(ConnectionManagerConfig {
+                send_queue_depth: config.websocket.send_queue_depth as usize,
+                overflow_policy: config.websocket.overflow_policy,
+                ..Default::default()
+            }));
+
+        // 创建 WebSocket 广播器，多实例部署时通过 PostgreSQL 转发房间事件
+        let event_bus: Arc<dyn EventBus> = match config.websocket.event_bus {
+            EventBusBackend::Memory => Arc::new(InProcessEventBus::new(connection_manager.clone())),
+            EventBusBackend::Postgres => Arc::new(PgEventBus::new(
+                connection_manager.clone(),
+                &config.database.url,
+                &config.websocket.event_channel,
+            )?),
+        };
+        let mut broadcaster = Broadcaster::with_event_bus(event_bus);
+        if config.websocket.event_log_capacity > 0 {
+            broadcaster = broadcaster.with_event_log(Arc::new(RoomEventLog::new(
+                RoomEventRepository::new(db_pool.clone()),
+                config.websocket.event_log_capacity,
+            )));
+        }
+        let broadcaster = Arc::new(broadcaster);
 
         Ok(Self {
             db_pool,
             config,
             services,
+            storage,
             connection_manager,
             broadcaster,
         })
@@ -90,11 +136,42 @@ impl AppState {
         &self.config.storage.root
     }
 
+    /// 便捷方法：获取存储后端
+    pub fn storage(&self) -> &Arc<dyn StorageBackend> {
+        &self.storage
+    }
+
+    /// 便捷方法：获取分块上传暂存区
+    pub fn chunk_staging(&self) -> &crate::chunk_temp_storage::ChunkStaging {
+        &self.services.chunk_staging
+    }
+
+    /// 便捷方法：获取去重存储
+    pub fn blob_store(&self) -> &crate::services::BlobStore {
+        &self.services.blob_store
+    }
+
+    /// 便捷方法：将 `room_contents.path` 转换为存储后端的对象键
+    pub fn storage_key(&self, stored_path: &str) -> String {
+        crate::storage::key::object_key(self.storage_root(), stored_path)
+    }
+
+    /// 便捷方法：将存储后端的对象键转换为持久化的 `room_contents.path`
+    pub fn storage_path(&self, key: &str) -> String {
+        crate::storage::key::stored_path(self.storage_root(), key)
+    }
+
     /// 便捷方法：获取上传预留 TTL
     pub fn upload_reservation_ttl(&self) -> chrono::Duration {
         chrono::Duration::seconds(self.config.storage.upload_reservation_ttl_seconds)
     }
 
+    /// 便捷方法：房间上传图片时是否移除元数据，房间未设置时沿用部署配置
+    pub fn strip_image_metadata(&self, room: &crate::models::Room) -> bool {
+        room.strip_image_metadata
+            .unwrap_or(self.config.storage.strip_image_metadata)
+    }
+
     pub fn room_creation_defaults(&self) -> &crate::config::RoomCreationDefaults {
         &self.config.room.defaults
     }
@@ -117,8 +194,10 @@ mod tests {
         let db_pool = Arc::new(init_db(&db_settings).await?);
 
         // 创建测试配置
+        let storage_root = tempfile::TempDir::new()?;
         let mut config = AppConfig::for_development();
         config.auth = AuthConfig::new("test-secret-key-for-unit-testing-123".to_string())?;
+        config.storage.root = storage_root.path().to_path_buf();
 
         // 创建应用状态
         let app_state = AppState::new(config, db_pool)?;
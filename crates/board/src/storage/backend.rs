@@ -13,13 +13,27 @@
 //! - **Async-first**: All operations are async for better performance
 
 use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::stream::{BoxStream, StreamExt};
 use opendal::{Builder, Operator};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{Range, RangeBounds};
 use std::path::Path;
 
+/// Buffer size used when streaming writes to the backend
+///
+/// S3 multipart uploads require parts of at least 5 MiB, so writers buffer
+/// up to this many bytes before flushing a part.
+pub const WRITE_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
 /// Storage backend type enumeration
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum StorageType {
     /// Local filesystem storage
+    #[default]
     Fs,
     /// S3-compatible object storage (AWS S3, MinIO, R2)
     S3,
@@ -40,8 +54,34 @@ pub struct StorageConfig {
     pub s3_config: Option<S3Config>,
 }
 
+impl std::str::FromStr for StorageType {
+    type Err = StorageError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "fs" | "local" => Ok(Self::Fs),
+            "s3" | "minio" => Ok(Self::S3),
+            other => Err(StorageError::Other(format!(
+                "Unknown storage backend '{other}', expected 'fs' or 's3'"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for StorageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fs => write!(f, "fs"),
+            Self::S3 => write!(f, "s3"),
+        }
+    }
+}
+
 /// S3-compatible storage configuration
-#[derive(Debug, Clone)]
+///
+/// Empty `endpoint` / credentials fall back to opendal's defaults
+/// (AWS endpoint for the region, credentials from the environment).
+#[derive(Clone, Serialize, Deserialize)]
 pub struct S3Config {
     /// S3 endpoint URL (e.g., "https://s3.amazonaws.com")
     pub endpoint: String,
@@ -50,18 +90,74 @@ pub struct S3Config {
     pub bucket: String,
 
     /// AWS access key ID
+    #[serde(default, skip_serializing)]
     pub access_key_id: String,
 
     /// AWS secret access key
+    #[serde(default, skip_serializing)]
     pub secret_access_key: String,
 
     /// AWS region (e.g., "us-east-1")
     pub region: Option<String>,
 }
 
+impl fmt::Debug for S3Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3Config")
+            .field("endpoint", &self.endpoint)
+            .field("bucket", &self.bucket)
+            .field(
+                "access_key_id",
+                &configrs::secret_for_debug(&self.access_key_id),
+            )
+            .field(
+                "secret_access_key",
+                &configrs::secret_for_debug(&self.secret_access_key),
+            )
+            .field("region", &self.region)
+            .finish()
+    }
+}
+
 /// Result type for storage operations
 pub type StorageResult<T> = Result<T, StorageError>;
 
+/// Stream of file contents returned by [`StorageBackend::reader`]
+///
+/// Items are `std::io::Result` so the stream can be handed to HTTP bodies
+/// and async readers without conversion.
+pub type ByteStream = BoxStream<'static, std::io::Result<Bytes>>;
+
+/// Object metadata returned by [`StorageBackend::stat`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectMeta {
+    /// Object size in bytes
+    pub size: u64,
+
+    /// Last modification time, if the backend reports one
+    pub last_modified: Option<DateTime<Utc>>,
+
+    /// Entity tag, if the backend reports one (S3 does, local FS does not)
+    pub etag: Option<String>,
+}
+
+/// Streaming writer returned by [`StorageBackend::writer`]
+///
+/// Data is only guaranteed to be visible once [`close`](StorageWriter::close)
+/// returns successfully; call [`abort`](StorageWriter::abort) to discard a
+/// partially written object.
+#[async_trait]
+pub trait StorageWriter: Send {
+    /// Append a chunk of data
+    async fn write(&mut self, chunk: Bytes) -> StorageResult<()>;
+
+    /// Finish the write and commit the object
+    async fn close(&mut self) -> StorageResult<()>;
+
+    /// Abort the write and discard any uploaded data
+    async fn abort(&mut self) -> StorageResult<()>;
+}
+
 /// Storage error types
 #[derive(Debug, thiserror::Error)]
 pub enum StorageError {
@@ -112,12 +208,49 @@ pub trait StorageBackend: Send + Sync {
     /// * `data` - File contents as bytes
     async fn put(&self, path: &str, data: Vec<u8>) -> StorageResult<()>;
 
+    /// Open a streaming reader for a file
+    ///
+    /// # Arguments
+    /// * `path` - Relative path to the file
+    ///
+    /// # Returns
+    /// A stream yielding the file contents chunk by chunk
+    async fn reader(&self, path: &str) -> StorageResult<ByteStream>;
+
+    /// Open a streaming reader for a byte range of a file
+    ///
+    /// # Arguments
+    /// * `path` - Relative path to the file
+    /// * `range` - Half-open byte range to read, must lie within the file
+    async fn reader_range(&self, path: &str, range: Range<u64>) -> StorageResult<ByteStream>;
+
+    /// Open a streaming writer for a file, replacing any existing file
+    ///
+    /// # Arguments
+    /// * `path` - Relative path where to store the file
+    async fn writer(&self, path: &str) -> StorageResult<Box<dyn StorageWriter>>;
+
+    /// Get file metadata
+    ///
+    /// # Arguments
+    /// * `path` - Relative path to the file
+    ///
+    /// # Returns
+    /// Size, modification time and etag of the file
+    async fn stat(&self, path: &str) -> StorageResult<ObjectMeta>;
+
     /// Delete a file from storage
     ///
     /// # Arguments
     /// * `path` - Relative path to the file to delete
     async fn delete(&self, path: &str) -> StorageResult<()>;
 
+    /// Recursively delete a directory (or key prefix) and everything under it
+    ///
+    /// # Arguments
+    /// * `path` - Relative path to the directory, e.g. `"42/"`
+    async fn delete_all(&self, path: &str) -> StorageResult<()>;
+
     /// List files in a directory
     ///
     /// # Arguments
@@ -127,6 +260,15 @@ pub trait StorageBackend: Send + Sync {
     /// List of file paths in the directory
     async fn list(&self, path: &str) -> StorageResult<Vec<String>>;
 
+    /// List every file below a directory, descending into subdirectories
+    ///
+    /// # Arguments
+    /// * `path` - Relative path to the directory, `""` for the whole root
+    ///
+    /// # Returns
+    /// Keys of all files; directories themselves are not included
+    async fn list_recursive(&self, path: &str) -> StorageResult<Vec<String>>;
+
     /// Check if a file exists
     ///
     /// # Arguments
@@ -135,6 +277,12 @@ pub trait StorageBackend: Send + Sync {
     /// # Returns
     /// `true` if file exists, `false` otherwise
     async fn exists(&self, path: &str) -> StorageResult<bool>;
+
+    /// Verify that the backend is reachable and usable
+    ///
+    /// For S3 this confirms the endpoint answers and the bucket exists with
+    /// the configured credentials; it is called once at startup.
+    async fn check(&self) -> StorageResult<()>;
 }
 
 /// Opendal-based storage backend implementation
@@ -170,11 +318,17 @@ impl OpendalBackend {
 
                 let mut builder = opendal::services::S3::default()
                     .root(&config.root)
-                    .endpoint(&s3_cfg.endpoint)
-                    .bucket(&s3_cfg.bucket)
-                    .access_key_id(&s3_cfg.access_key_id)
-                    .secret_access_key(&s3_cfg.secret_access_key);
+                    .bucket(&s3_cfg.bucket);
 
+                if !s3_cfg.endpoint.is_empty() {
+                    builder = builder.endpoint(&s3_cfg.endpoint);
+                }
+                if !s3_cfg.access_key_id.is_empty() {
+                    builder = builder.access_key_id(&s3_cfg.access_key_id);
+                }
+                if !s3_cfg.secret_access_key.is_empty() {
+                    builder = builder.secret_access_key(&s3_cfg.secret_access_key);
+                }
                 if let Some(region) = s3_cfg.region {
                     builder = builder.region(&region);
                 }
@@ -186,10 +340,46 @@ impl OpendalBackend {
         Ok(Self { operator })
     }
 
+    /// Wrap an already built Opendal operator
+    ///
+    /// Useful for services that are not selectable through [`StorageConfig`],
+    /// e.g. the in-memory service in tests.
+    pub fn from_operator(operator: Operator) -> Self {
+        Self { operator }
+    }
+
     /// Get the underlying Opendal operator
     pub fn operator(&self) -> &Operator {
         &self.operator
     }
+
+    async fn open_stream(
+        &self,
+        path: &str,
+        range: impl RangeBounds<u64>,
+    ) -> StorageResult<ByteStream> {
+        let mut stream = self
+            .operator
+            .reader(path)
+            .await
+            .map_err(|error| map_not_found(path, error))?
+            .into_bytes_stream(range)
+            .await
+            .map_err(|error| map_not_found(path, error))?;
+
+        // Some services only open the file on first poll; pull the first chunk
+        // eagerly so a missing file is reported here rather than mid-response.
+        match stream.next().await {
+            None => Ok(futures::stream::empty().boxed()),
+            Some(Err(error)) if error.kind() == std::io::ErrorKind::NotFound => {
+                Err(StorageError::NotFound(path.to_string()))
+            }
+            Some(Err(error)) => Err(StorageError::Io(error)),
+            Some(Ok(first)) => Ok(futures::stream::once(async move { Ok(first) })
+                .chain(stream)
+                .boxed()),
+        }
+    }
 }
 
 #[async_trait]
@@ -199,7 +389,7 @@ impl StorageBackend for OpendalBackend {
             .operator
             .read(path)
             .await
-            .map_err(StorageError::from)?
+            .map_err(|error| map_not_found(path, error))?
             .to_vec();
         Ok(bytes)
     }
@@ -212,10 +402,51 @@ impl StorageBackend for OpendalBackend {
             .map_err(StorageError::from)
     }
 
+    async fn reader(&self, path: &str) -> StorageResult<ByteStream> {
+        self.open_stream(path, ..).await
+    }
+
+    async fn reader_range(&self, path: &str, range: Range<u64>) -> StorageResult<ByteStream> {
+        self.open_stream(path, range).await
+    }
+
+    async fn writer(&self, path: &str) -> StorageResult<Box<dyn StorageWriter>> {
+        let writer = self
+            .operator
+            .writer_with(path)
+            .chunk(WRITE_CHUNK_SIZE)
+            .await
+            .map_err(StorageError::from)?;
+        Ok(Box::new(OpendalWriter { writer }))
+    }
+
+    async fn stat(&self, path: &str) -> StorageResult<ObjectMeta> {
+        let metadata = self
+            .operator
+            .stat(path)
+            .await
+            .map_err(|error| map_not_found(path, error))?;
+        Ok(ObjectMeta {
+            size: metadata.content_length(),
+            last_modified: metadata
+                .last_modified()
+                .map(|timestamp| DateTime::<Utc>::from(std::time::SystemTime::from(timestamp))),
+            etag: metadata.etag().map(str::to_owned),
+        })
+    }
+
     async fn delete(&self, path: &str) -> StorageResult<()> {
         self.operator.delete(path).await.map_err(StorageError::from)
     }
 
+    async fn delete_all(&self, path: &str) -> StorageResult<()> {
+        self.operator
+            .delete_with(path)
+            .recursive(true)
+            .await
+            .map_err(StorageError::from)
+    }
+
     async fn list(&self, path: &str) -> StorageResult<Vec<String>> {
         let entries = self.operator.list(path).await.map_err(StorageError::from)?;
 
@@ -227,9 +458,62 @@ impl StorageBackend for OpendalBackend {
         Ok(files)
     }
 
+    async fn list_recursive(&self, path: &str) -> StorageResult<Vec<String>> {
+        let entries = self
+            .operator
+            .list_with(path)
+            .recursive(true)
+            .await
+            .map_err(StorageError::from)?;
+
+        let files = entries
+            .into_iter()
+            .filter(|entry| !entry.metadata().is_dir())
+            .map(|entry| entry.path().to_string())
+            .collect();
+
+        Ok(files)
+    }
+
     async fn exists(&self, path: &str) -> StorageResult<bool> {
         self.operator.exists(path).await.map_err(StorageError::from)
     }
+
+    async fn check(&self) -> StorageResult<()> {
+        self.operator.check().await.map_err(StorageError::from)
+    }
+}
+
+/// [`StorageWriter`] backed by an Opendal writer
+struct OpendalWriter {
+    writer: opendal::Writer,
+}
+
+#[async_trait]
+impl StorageWriter for OpendalWriter {
+    async fn write(&mut self, chunk: Bytes) -> StorageResult<()> {
+        self.writer.write(chunk).await.map_err(StorageError::from)
+    }
+
+    async fn close(&mut self) -> StorageResult<()> {
+        self.writer
+            .close()
+            .await
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    async fn abort(&mut self) -> StorageResult<()> {
+        self.writer.abort().await.map_err(StorageError::from)
+    }
+}
+
+fn map_not_found(path: &str, error: opendal::Error) -> StorageError {
+    if error.kind() == opendal::ErrorKind::NotFound {
+        StorageError::NotFound(path.to_string())
+    } else {
+        StorageError::from(error)
+    }
 }
 
 #[cfg(test)]
@@ -267,8 +551,159 @@ mod tests {
         let files = backend.list("").await.unwrap();
         assert!(files.contains(&"test.txt".to_string()));
 
+        backend
+            .put("42/.thumbnails/7/320.jpg", b"thumb".to_vec())
+            .await
+            .unwrap();
+        let mut files = backend.list_recursive("").await.unwrap();
+        files.sort();
+        assert_eq!(files, vec!["42/.thumbnails/7/320.jpg", "test.txt"]);
+
         // Test delete
         backend.delete("test.txt").await.unwrap();
         assert!(!backend.exists("test.txt").await.unwrap());
+
+        // Missing files surface as NotFound
+        assert!(matches!(
+            backend.get("test.txt").await,
+            Err(StorageError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fs_storage_delete_all() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = StorageConfig {
+            storage_type: StorageType::Fs,
+            root: temp_dir.path().to_str().unwrap().to_string(),
+            s3_config: None,
+        };
+        let backend = OpendalBackend::new(config).unwrap();
+
+        backend.put("42/a.txt", b"a".to_vec()).await.unwrap();
+        backend.put("42/b.txt", b"b".to_vec()).await.unwrap();
+        backend.put("43/c.txt", b"c".to_vec()).await.unwrap();
+
+        backend.delete_all("42/").await.unwrap();
+
+        assert!(!temp_dir.path().join("42").exists());
+        assert!(backend.exists("43/c.txt").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_fs_storage_streaming() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = StorageConfig {
+            storage_type: StorageType::Fs,
+            root: temp_dir.path().to_str().unwrap().to_string(),
+            s3_config: None,
+        };
+        let backend = OpendalBackend::new(config).unwrap();
+
+        let mut writer = backend.writer("7/big.bin").await.unwrap();
+        writer.write(Bytes::from_static(b"Hello, ")).await.unwrap();
+        writer.write(Bytes::from_static(b"World!")).await.unwrap();
+        writer.close().await.unwrap();
+
+        let meta = backend.stat("7/big.bin").await.unwrap();
+        assert_eq!(meta.size, 13);
+        assert!(meta.last_modified.is_some());
+
+        let chunks: Vec<Bytes> = backend
+            .reader("7/big.bin")
+            .await
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert_eq!(chunks.concat(), b"Hello, World!");
+
+        let chunks: Vec<Bytes> = backend
+            .reader_range("7/big.bin", 7..12)
+            .await
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert_eq!(chunks.concat(), b"World");
+
+        assert!(matches!(
+            backend.stat("7/missing.bin").await,
+            Err(StorageError::NotFound(_))
+        ));
+        assert!(matches!(
+            backend.reader("7/missing.bin").await,
+            Err(StorageError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_storage_writer_abort_discards_data() {
+        let operator = Operator::new(opendal::services::Memory::default())
+            .unwrap()
+            .finish();
+        let backend = OpendalBackend::from_operator(operator);
+
+        let mut writer = backend.writer("aborted.bin").await.unwrap();
+        writer.write(Bytes::from_static(b"partial")).await.unwrap();
+        writer.abort().await.unwrap();
+
+        assert!(!backend.exists("aborted.bin").await.unwrap());
+    }
+
+    #[test]
+    fn test_storage_type_from_str() {
+        assert_eq!("fs".parse::<StorageType>().unwrap(), StorageType::Fs);
+        assert_eq!(" S3 ".parse::<StorageType>().unwrap(), StorageType::S3);
+        assert_eq!("minio".parse::<StorageType>().unwrap(), StorageType::S3);
+        assert!("gcs".parse::<StorageType>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fs_storage_check() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = StorageConfig {
+            storage_type: StorageType::Fs,
+            root: temp_dir.path().to_str().unwrap().to_string(),
+            s3_config: None,
+        };
+        let backend = OpendalBackend::new(config).unwrap();
+
+        backend.check().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_s3_check_fails_when_endpoint_unreachable() {
+        let config = StorageConfig {
+            storage_type: StorageType::S3,
+            root: "rooms".to_string(),
+            s3_config: Some(S3Config {
+                // Nothing listens on port 1, so the connection is refused immediately.
+                endpoint: "http://127.0.0.1:1".to_string(),
+                bucket: "elizabeth".to_string(),
+                access_key_id: "minio".to_string(),
+                secret_access_key: "minio-secret".to_string(), // pragma: allowlist secret
+                region: Some("us-east-1".to_string()),
+            }),
+        };
+        let backend = OpendalBackend::new(config).unwrap();
+
+        assert!(backend.check().await.is_err());
+    }
+
+    #[test]
+    fn test_s3_config_debug_redacts_credentials() {
+        let config = S3Config {
+            endpoint: "http://127.0.0.1:9000".to_string(),
+            bucket: "elizabeth".to_string(),
+            access_key_id: "debug-access-key".to_string(),
+            secret_access_key: "debug-secret-key".to_string(), // pragma: allowlist secret
+            region: None,
+        };
+
+        let debug = format!("{config:?}");
+        assert!(!debug.contains("debug-access-key"));
+        assert!(!debug.contains("debug-secret-key")); // pragma: allowlist secret
+        assert!(debug.contains("elizabeth"));
     }
 }
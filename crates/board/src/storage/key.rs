@@ -0,0 +1,160 @@
+//! Object key helpers
+//!
+//! `room_contents.path` persists the storage root joined with the object key
+//! (e.g. `storage/rooms/42/report.pdf`), while a [`StorageBackend`](super::StorageBackend)
+//! addresses files by the key relative to its root (`42/report.pdf`).
+//! These helpers convert between the two representations.
+//!
+//! Deduplicated files are shared between rooms and live under [`BLOB_PREFIX`]
+//! instead of a room prefix, addressed by their SHA-256.
+//!
+//! Generated thumbnails stay inside the room prefix (see [`thumbnail_prefix`])
+//! so that purging a room removes them together with the originals.
+//!
+//! Chunked uploads may be staged on the backend under [`CHUNK_PREFIX`] until
+//! they are merged; those keys are owned by the upload reservation, not a room.
+
+use std::path::Path;
+
+/// Key prefix under which all files of a room are stored
+pub fn room_prefix(room_id: i64) -> String {
+    format!("{room_id}/")
+}
+
+/// Object key for a (already sanitized) file name inside a room
+pub fn room_object_key(room_id: i64, file_name: &str) -> String {
+    format!("{}{file_name}", room_prefix(room_id))
+}
+
+/// Key prefix holding every generated thumbnail of a content
+pub fn thumbnail_prefix(room_id: i64, content_id: i64) -> String {
+    format!("{}.thumbnails/{content_id}/", room_prefix(room_id))
+}
+
+/// Object key of a content thumbnail at the given width
+pub fn thumbnail_key(room_id: i64, content_id: i64, width: u32, extension: &str) -> String {
+    format!(
+        "{}{width}.{extension}",
+        thumbnail_prefix(room_id, content_id)
+    )
+}
+
+/// Room and content id owning a thumbnail key, or `None` for other keys
+pub fn thumbnail_owner(key: &str) -> Option<(i64, i64)> {
+    let (room_id, rest) = key.split_once('/')?;
+    let (content_id, _) = rest.strip_prefix(".thumbnails/")?.split_once('/')?;
+    Some((room_id.parse().ok()?, content_id.parse().ok()?))
+}
+
+/// Key prefix shared by all content-addressed blobs
+///
+/// Room prefixes are numeric, so blob keys never collide with room keys.
+pub const BLOB_PREFIX: &str = "blobs/";
+
+/// Object key of a content-addressed blob, fanned out by the first two hex digits
+pub fn blob_key(hash: &str) -> String {
+    let fanout = hash.get(..2).unwrap_or("00");
+    format!("{BLOB_PREFIX}{fanout}/{hash}")
+}
+
+/// Hash addressed by a blob key, or `None` for room-scoped keys
+pub fn blob_hash(key: &str) -> Option<&str> {
+    key.strip_prefix(BLOB_PREFIX)?
+        .rsplit('/')
+        .next()
+        .filter(|hash| !hash.is_empty())
+}
+
+/// Key prefix shared by all staged upload chunks
+pub const CHUNK_PREFIX: &str = "chunks/";
+
+/// Key prefix holding the staged chunks of one upload reservation
+pub fn chunk_staging_prefix(reservation_id: i64) -> String {
+    format!("{CHUNK_PREFIX}{reservation_id}/")
+}
+
+/// Object key of a staged upload chunk
+pub fn chunk_key(reservation_id: i64, chunk_index: i64) -> String {
+    format!(
+        "{}chunk_{chunk_index}",
+        chunk_staging_prefix(reservation_id)
+    )
+}
+
+/// Persisted `room_contents.path` value for an object key
+pub fn stored_path(root: &Path, key: &str) -> String {
+    root.join(key).to_string_lossy().into_owned()
+}
+
+/// Resolve a persisted `room_contents.path` value into an object key
+///
+/// Paths that do not live under `root` are returned unchanged (minus leading
+/// separators) so that the backend reports them as missing instead of
+/// escaping its root.
+pub fn object_key(root: &Path, stored_path: &str) -> String {
+    let path = Path::new(stored_path);
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .to_string_lossy()
+        .replace('\\', "/")
+        .trim_start_matches('/')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_path_round_trips_to_key() {
+        let root = Path::new("storage/rooms");
+        let key = room_object_key(42, "report.pdf");
+        let stored = stored_path(root, &key);
+
+        assert_eq!(key, "42/report.pdf");
+        assert_eq!(object_key(root, &stored), key);
+    }
+
+    #[test]
+    fn thumbnail_keys_live_under_room_prefix() {
+        let key = thumbnail_key(42, 7, 320, "jpg");
+
+        assert_eq!(key, "42/.thumbnails/7/320.jpg");
+        assert!(key.starts_with(&room_prefix(42)));
+        assert!(key.starts_with(&thumbnail_prefix(42, 7)));
+        assert_eq!(thumbnail_owner(&key), Some((42, 7)));
+        assert_eq!(thumbnail_owner("42/report.pdf"), None);
+        assert_eq!(thumbnail_owner("42/.thumbnails/x/320.jpg"), None);
+    }
+
+    #[test]
+    fn blob_keys_round_trip_to_hash() {
+        let hash = "ab".repeat(32);
+        let key = blob_key(&hash);
+
+        assert_eq!(key, format!("blobs/ab/{hash}"));
+        assert_eq!(blob_hash(&key), Some(hash.as_str()));
+        assert_eq!(blob_hash(&room_object_key(1, "blobs.txt")), None);
+        assert_eq!(blob_hash("blobs/"), None);
+    }
+
+    #[test]
+    fn chunk_keys_live_under_chunk_prefix() {
+        let key = chunk_key(5, 3);
+
+        assert_eq!(key, "chunks/5/chunk_3");
+        assert!(key.starts_with(&chunk_staging_prefix(5)));
+        assert_eq!(blob_hash(&key), None);
+        assert_eq!(thumbnail_owner(&key), None);
+    }
+
+    #[test]
+    fn object_key_handles_absolute_roots_and_foreign_paths() {
+        let root = Path::new("/var/lib/elizabeth");
+        assert_eq!(
+            object_key(root, "/var/lib/elizabeth/7/a.txt"),
+            "7/a.txt".to_string()
+        );
+        assert_eq!(object_key(root, "/elsewhere/7/a.txt"), "elsewhere/7/a.txt");
+    }
+}
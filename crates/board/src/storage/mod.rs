@@ -23,8 +23,10 @@
 //! ```
 
 pub mod backend;
+pub mod key;
+pub mod sniff;
 
 pub use backend::{
-    OpendalBackend, S3Config, StorageBackend, StorageConfig, StorageError, StorageResult,
-    StorageType,
+    ByteStream, ObjectMeta, OpendalBackend, S3Config, StorageBackend, StorageConfig, StorageError,
+    StorageResult, StorageType, StorageWriter, WRITE_CHUNK_SIZE,
 };
@@ -0,0 +1,186 @@
+//! Content type detection
+//!
+//! Clients declare a MIME type (multipart header or file extension) that is
+//! kept for display, but serving decisions use the type detected from a file's
+//! leading bytes. Active types that a browser would render or execute on our
+//! origin (HTML, SVG, JavaScript, XML) are never served as themselves, so a
+//! room cannot host phishing pages or scripts.
+
+use std::path::Path;
+
+use tokio::io::AsyncReadExt;
+
+use crate::models::content::ContentType;
+
+/// Number of leading bytes inspected when detecting a type
+pub const SNIFF_LEN: usize = 8 * 1024;
+
+/// Served in place of unknown or active types
+pub const FALLBACK_MIME_TYPE: &str = "application/octet-stream";
+
+const ACTIVE_MIME_TYPES: &[&str] = &[
+    "text/html",
+    "application/xhtml+xml",
+    "image/svg+xml",
+    "text/javascript",
+    "application/javascript",
+    "application/x-javascript",
+    "application/ecmascript",
+    "text/ecmascript",
+    "text/xml",
+    "application/xml",
+    "text/xsl",
+];
+
+/// Tags that mark a document as HTML when they open it (WHATWG MIME sniffing)
+const HTML_PREFIXES: &[&[u8]] = &[
+    b"<!doctype html",
+    b"<html",
+    b"<head",
+    b"<script",
+    b"<iframe",
+    b"<h1",
+    b"<div",
+    b"<font",
+    b"<table",
+    b"<a",
+    b"<style",
+    b"<title",
+    b"<b",
+    b"<body",
+    b"<br",
+    b"<p",
+    b"<!--",
+];
+
+/// Detect a MIME type from the leading bytes of a file
+///
+/// Returns `None` when the bytes match no known signature, e.g. plain text.
+pub fn detect(head: &[u8]) -> Option<&'static str> {
+    let head = &head[..head.len().min(SNIFF_LEN)];
+    detect_markup(head).or_else(|| infer::get(head).map(|kind| kind.mime_type()))
+}
+
+/// Detect the type of a local file from its first [`SNIFF_LEN`] bytes
+pub async fn detect_file(path: &Path) -> std::io::Result<Option<&'static str>> {
+    let file = tokio::fs::File::open(path).await?;
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    file.take(SNIFF_LEN as u64).read_to_end(&mut head).await?;
+    Ok(detect(&head))
+}
+
+/// Whether browsers render or execute this type when served inline
+pub fn is_active(mime: &str) -> bool {
+    let essence = essence(mime);
+    ACTIVE_MIME_TYPES.contains(&essence.as_str()) || essence.ends_with("+xml")
+}
+
+/// Content type recorded for an uploaded file
+///
+/// Only a detected raster image counts as an image; SVG stays a file.
+pub fn content_type_for(detected: Option<&str>) -> ContentType {
+    match detected {
+        Some(mime) if mime.starts_with("image/") && !is_active(mime) => ContentType::Image,
+        _ => ContentType::File,
+    }
+}
+
+/// `Content-Type` used when serving a stored file
+///
+/// The detected type wins over the declared one; if either is active the file
+/// is served as an opaque download instead.
+pub fn served_mime_type(declared: Option<&str>, detected: Option<&str>) -> String {
+    if declared.into_iter().chain(detected).any(is_active) {
+        return FALLBACK_MIME_TYPE.to_string();
+    }
+    detected
+        .or(declared)
+        .unwrap_or(FALLBACK_MIME_TYPE)
+        .to_string()
+}
+
+/// MIME essence: parameters stripped, lowercased
+pub fn essence(mime: &str) -> String {
+    mime.split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+fn detect_markup(head: &[u8]) -> Option<&'static str> {
+    let text = head.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(head);
+    let start = text.iter().position(|byte| !byte.is_ascii_whitespace())?;
+    let text = text[start..].to_ascii_lowercase();
+    if !text.starts_with(b"<") {
+        return None;
+    }
+
+    if text.starts_with(b"<svg") || (text.starts_with(b"<?xml") && contains(&text, b"<svg")) {
+        return Some("image/svg+xml");
+    }
+    if text.starts_with(b"<?xml") {
+        return Some("text/xml");
+    }
+    let is_html = HTML_PREFIXES.iter().any(|prefix| {
+        text.strip_prefix(*prefix)
+            .is_some_and(|rest| matches!(rest.first(), Some(b' ' | b'>' | b'\t' | b'\n' | b'\r')))
+    });
+    is_html.then_some("text/html")
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG_HEADER: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[test]
+    fn detects_binary_and_markup_types() {
+        assert_eq!(detect(PNG_HEADER), Some("image/png"));
+        assert_eq!(detect(b"%PDF-1.7\n"), Some("application/pdf"));
+        assert_eq!(
+            detect(b"\xEF\xBB\xBF  <!DOCTYPE html><html>"),
+            Some("text/html")
+        );
+        assert_eq!(detect(b"<p>hello</p>"), Some("text/html"));
+        assert_eq!(
+            detect(b"<?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\"/>"),
+            Some("image/svg+xml")
+        );
+        assert_eq!(detect(b"<?xml version=\"1.0\"?><feed/>"), Some("text/xml"));
+        assert_eq!(detect(b"<pre is not a tag"), None);
+        assert_eq!(detect(b"just some notes"), None);
+    }
+
+    #[test]
+    fn active_types_are_never_served_as_themselves() {
+        assert_eq!(
+            served_mime_type(Some("image/png"), Some("text/html")),
+            FALLBACK_MIME_TYPE
+        );
+        assert_eq!(
+            served_mime_type(Some("text/javascript; charset=utf-8"), None),
+            FALLBACK_MIME_TYPE
+        );
+        assert_eq!(
+            served_mime_type(Some("image/png"), Some("image/jpeg")),
+            "image/jpeg"
+        );
+        assert_eq!(served_mime_type(Some("text/plain"), None), "text/plain");
+        assert_eq!(served_mime_type(None, None), FALLBACK_MIME_TYPE);
+    }
+
+    #[test]
+    fn only_raster_images_are_images() {
+        assert_eq!(content_type_for(Some("image/png")), ContentType::Image);
+        assert_eq!(content_type_for(Some("image/svg+xml")), ContentType::File);
+        assert_eq!(content_type_for(None), ContentType::File);
+    }
+}
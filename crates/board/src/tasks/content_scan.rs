@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::repository::RoomRepository;
+use crate::scheduler::{ScheduledTask, ScheduledTaskId, TaskRunReport};
+use crate::services::ContentScanService;
+use crate::websocket::broadcaster::Broadcaster;
+
+pub struct ContentScanTask {
+    service: Arc<ContentScanService>,
+    rooms: Arc<RoomRepository>,
+    broadcaster: Arc<Broadcaster>,
+}
+
+impl ContentScanTask {
+    pub fn new(
+        service: Arc<ContentScanService>,
+        rooms: Arc<RoomRepository>,
+        broadcaster: Arc<Broadcaster>,
+    ) -> Self {
+        Self {
+            service,
+            rooms,
+            broadcaster,
+        }
+    }
+}
+
+#[async_trait]
+impl ScheduledTask for ContentScanTask {
+    fn id(&self) -> ScheduledTaskId {
+        ScheduledTaskId::ContentScan
+    }
+
+    async fn run(&self) -> Result<TaskRunReport> {
+        let report = self.service.run_pending().await?;
+
+        // 扫描结束后通知房间内的客户端更新内容状态
+        self.broadcaster
+            .broadcast_contents_updated(self.rooms.as_ref(), &report.finished)
+            .await?;
+
+        Ok(TaskRunReport {
+            examined: report.examined,
+            changed: report.finished.len() as u64,
+        })
+    }
+}
@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::scheduler::{ScheduledTask, ScheduledTaskId, TaskRunReport};
+use crate::services::IntegrityScrubService;
+
+pub struct IntegrityScrubTask {
+    service: Arc<IntegrityScrubService>,
+}
+
+impl IntegrityScrubTask {
+    pub fn new(service: Arc<IntegrityScrubService>) -> Self {
+        Self { service }
+    }
+}
+
+#[async_trait]
+impl ScheduledTask for IntegrityScrubTask {
+    fn id(&self) -> ScheduledTaskId {
+        ScheduledTaskId::IntegrityScrub
+    }
+
+    async fn run(&self) -> Result<TaskRunReport> {
+        let report = self.service.run().await?;
+        if !report.corrupted.is_empty() || !report.missing.is_empty() || report.restored > 0 {
+            log::warn!(
+                "Integrity scrub: {} corrupted contents {:?}, {} missing contents {:?}, {} restored",
+                report.corrupted.len(),
+                report.corrupted,
+                report.missing.len(),
+                report.missing,
+                report.restored
+            );
+        }
+
+        Ok(TaskRunReport {
+            examined: report.examined,
+            changed: report.changed(),
+        })
+    }
+}
@@ -1,7 +1,15 @@
+mod content_scan;
+mod integrity_scrub;
 mod room_lifecycle;
+mod storage_reconcile;
+mod thumbnail;
 mod token_cleanup;
 mod upload_cleanup;
 
+pub use content_scan::ContentScanTask;
+pub use integrity_scrub::IntegrityScrubTask;
 pub use room_lifecycle::RoomLifecycleTask;
+pub use storage_reconcile::StorageReconcileTask;
+pub use thumbnail::ThumbnailTask;
 pub use token_cleanup::TokenCleanupTask;
 pub use upload_cleanup::UploadCleanupTask;
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::scheduler::{ScheduledTask, ScheduledTaskId, TaskRunReport};
+use crate::services::StorageReconcileService;
+
+pub struct StorageReconcileTask {
+    service: Arc<StorageReconcileService>,
+}
+
+impl StorageReconcileTask {
+    pub fn new(service: Arc<StorageReconcileService>) -> Self {
+        Self { service }
+    }
+}
+
+#[async_trait]
+impl ScheduledTask for StorageReconcileTask {
+    fn id(&self) -> ScheduledTaskId {
+        ScheduledTaskId::StorageReconcile
+    }
+
+    async fn run(&self) -> Result<TaskRunReport> {
+        let report = self.service.run().await?;
+        if !report.orphan_files.is_empty()
+            || !report.missing_contents.is_empty()
+            || report.rooms_corrected > 0
+        {
+            log::info!(
+                "Storage reconciliation: {} orphan files ({} deleted), {} missing contents, {} restored, {} rooms corrected",
+                report.orphan_files.len(),
+                report.orphans_deleted,
+                report.missing_contents.len(),
+                report.restored_contents,
+                report.rooms_corrected
+            );
+        }
+
+        Ok(TaskRunReport {
+            examined: report.files_examined + report.contents_examined,
+            changed: report.changed(),
+        })
+    }
+}
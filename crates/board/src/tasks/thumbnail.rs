@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::repository::RoomRepository;
+use crate::scheduler::{ScheduledTask, ScheduledTaskId, TaskRunReport};
+use crate::services::ThumbnailService;
+use crate::websocket::broadcaster::Broadcaster;
+
+pub struct ThumbnailTask {
+    service: Arc<ThumbnailService>,
+    rooms: Arc<RoomRepository>,
+    broadcaster: Arc<Broadcaster>,
+}
+
+impl ThumbnailTask {
+    pub fn new(
+        service: Arc<ThumbnailService>,
+        rooms: Arc<RoomRepository>,
+        broadcaster: Arc<Broadcaster>,
+    ) -> Self {
+        Self {
+            service,
+            rooms,
+            broadcaster,
+        }
+    }
+}
+
+#[async_trait]
+impl ScheduledTask for ThumbnailTask {
+    fn id(&self) -> ScheduledTaskId {
+        ScheduledTaskId::ThumbnailGeneration
+    }
+
+    async fn run(&self) -> Result<TaskRunReport> {
+        let report = self.service.run_pending().await?;
+
+        // 缩略图就绪后通知房间内的客户端刷新内容列表
+        self.broadcaster
+            .broadcast_contents_updated(self.rooms.as_ref(), &report.ready)
+            .await?;
+
+        Ok(TaskRunReport {
+            examined: report.examined,
+            changed: report.ready.len() as u64 + report.failed,
+        })
+    }
+}
@@ -3,16 +3,24 @@ use std::sync::Arc;
 use anyhow::Result;
 use async_trait::async_trait;
 
+use crate::chunk_temp_storage::ChunkStaging;
 use crate::repository::{IRoomUploadReservationRepository, RoomUploadReservationRepository};
 use crate::scheduler::{ScheduledTask, ScheduledTaskId, TaskRunReport};
 
 pub struct UploadCleanupTask {
     repository: Arc<RoomUploadReservationRepository>,
+    chunk_staging: Arc<ChunkStaging>,
 }
 
 impl UploadCleanupTask {
-    pub fn new(repository: Arc<RoomUploadReservationRepository>) -> Self {
-        Self { repository }
+    pub fn new(
+        repository: Arc<RoomUploadReservationRepository>,
+        chunk_staging: Arc<ChunkStaging>,
+    ) -> Self {
+        Self {
+            repository,
+            chunk_staging,
+        }
     }
 }
 
@@ -25,7 +33,9 @@ impl ScheduledTask for UploadCleanupTask {
     async fn run(&self) -> Result<TaskRunReport> {
         let chunked_ids = self.repository.list_expired_chunked_ids().await?;
         for reservation_id in chunked_ids {
-            crate::chunk_temp_storage::remove_reservation_dir(reservation_id).await?;
+            self.chunk_staging
+                .remove_reservation(reservation_id)
+                .await?;
         }
         let changed = self.repository.purge_expired().await?;
         Ok(TaskRunReport {
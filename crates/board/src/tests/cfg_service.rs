@@ -1,6 +1,9 @@
 use crate::{
     cmd,
-    config::{AppConfig as RuntimeAppConfig, RoomConfig as RuntimeRoomConfig},
+    config::{
+        AppConfig as RuntimeAppConfig, RoomConfig as RuntimeRoomConfig,
+        StorageConfig as RuntimeStorageConfig,
+    },
     init::cfg_service,
 };
 use serial_test::serial;
@@ -194,6 +197,92 @@ fn cli_env_file_priority_respected() {
     assert_eq!(cfg.app.logging.level, "debug");
 }
 
+#[test]
+#[serial]
+fn s3_storage_loads_from_yaml() {
+    let temp = tempdir().expect("tempdir");
+    let config_path = temp.path().join("custom.yaml");
+    fs::write(
+        &config_path,
+        "app:\n  storage:\n    backend: s3\n    root: rooms\n    s3:\n      endpoint: http://127.0.0.1:9000\n      bucket: elizabeth\n      region: us-east-1\n",
+    )
+    .expect("write config");
+
+    let cfg = load_custom_config(&config_path).expect("config loaded");
+    let runtime = RuntimeStorageConfig::try_from(&cfg.app).expect("runtime storage config");
+
+    assert_eq!(runtime.backend, crate::storage::StorageType::S3);
+    assert_eq!(runtime.root, PathBuf::from("rooms"));
+    let s3 = runtime.s3.expect("s3 config");
+    assert_eq!(s3.endpoint, "http://127.0.0.1:9000");
+    assert_eq!(s3.bucket, "elizabeth");
+    assert_eq!(s3.region.as_deref(), Some("us-east-1"));
+}
+
+#[test]
+#[serial]
+fn storage_env_overrides_apply_before_cli() {
+    let (_temp_dir, _guard) = with_temp_home();
+    let _env_guard = EnvGuard::set(vec![
+        ("STORAGE_BACKEND", Some("s3".into())),
+        ("STORAGE_ROOT", Some("prefix".into())),
+        ("S3_ENDPOINT", Some("http://minio:9000".into())),
+        ("S3_BUCKET", Some("env-bucket".into())),
+        ("S3_ACCESS_KEY_ID", Some("env-access-key".into())),
+        ("S3_SECRET_ACCESS_KEY", Some("env-secret-key".into())), // pragma: allowlist secret
+        ("S3_REGION", Some("eu-west-1".into())),
+        ("STORAGE_DEDUP", Some("true".into())),
+        ("STORAGE_THUMBNAILS_ENABLED", Some("false".into())),
+        ("UPLOAD_STRIP_IMAGE_METADATA", Some("false".into())),
+        ("STORAGE_SCAN_ENABLED", Some("true".into())),
+        (
+            "STORAGE_SCAN_CLAMD_ADDRESS",
+            Some("unix:/run/clamd.sock".into()),
+        ),
+        ("STORAGE_RECONCILE_DELETE_ORPHANS", Some("true".into())),
+        ("STORAGE_SCRUB_ENABLED", Some("false".into())),
+        ("UPLOAD_CHUNK_STAGING_BACKEND", Some("storage".into())),
+        ("UPLOAD_CHUNK_STAGING_DIR", Some("/data/chunks".into())),
+        ("WEBSOCKET_EVENT_BUS", Some("postgres".into())),
+        ("WEBSOCKET_EVENT_CHANNEL", Some("board_events".into())),
+        ("WEBSOCKET_EVENT_LOG_CAPACITY", Some("0".into())),
+        ("WEBSOCKET_SEND_QUEUE_DEPTH", Some("64".into())),
+        ("WEBSOCKET_OVERFLOW_POLICY", Some("drop_oldest".into())),
+    ]);
+    let args = cmd::CliArgs {
+        s3_bucket: Some("cli-bucket".into()),
+        ..Default::default()
+    };
+
+    let cfg = cfg_service::init(&args).expect("config loaded");
+
+    assert_eq!(cfg.app.storage.backend, "s3");
+    assert_eq!(cfg.app.storage.root, "prefix");
+    assert_eq!(cfg.app.storage.s3.endpoint, "http://minio:9000");
+    assert_eq!(cfg.app.storage.s3.bucket, "cli-bucket");
+    assert_eq!(cfg.app.storage.s3.access_key_id, "env-access-key");
+    assert_eq!(cfg.app.storage.s3.secret_access_key, "env-secret-key"); // pragma: allowlist secret
+    assert_eq!(cfg.app.storage.s3.region.as_deref(), Some("eu-west-1"));
+    assert!(cfg.app.storage.dedup);
+    assert!(!cfg.app.storage.thumbnails.enabled);
+    assert!(!cfg.app.upload.strip_image_metadata);
+    assert!(cfg.app.storage.scan.enabled);
+    assert_eq!(cfg.app.storage.scan.clamd_address, "unix:/run/clamd.sock");
+    assert!(cfg.app.storage.reconcile.delete_orphans);
+    assert!(!cfg.app.storage.scrub.enabled);
+    assert_eq!(cfg.app.upload.chunk_staging_backend, "storage");
+    assert_eq!(cfg.app.upload.chunk_staging_dir, "/data/chunks");
+    assert_eq!(cfg.app.websocket.event_bus, "postgres");
+    assert_eq!(cfg.app.websocket.event_channel, "board_events");
+    assert_eq!(cfg.app.websocket.event_log_capacity, 0);
+    assert_eq!(cfg.app.websocket.send_queue_depth, 64);
+    assert_eq!(cfg.app.websocket.overflow_policy, "drop_oldest");
+
+    let debug = format!("{cfg:?}");
+    assert!(!debug.contains("env-access-key"));
+    assert!(!debug.contains("env-secret-key")); // pragma: allowlist secret
+}
+
 #[test]
 #[serial]
 fn room_expiry_durations_load_from_yaml() {
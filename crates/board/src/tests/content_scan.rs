@@ -0,0 +1,188 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::config::{AppConfig, AuthConfig, ClamdAddress};
+use crate::db::{DbPoolSettings, init_db, run_migrations};
+use crate::models::Room;
+use crate::models::content::{ContentType, RoomContent, ScanStatus, ThumbnailStatus};
+use crate::repository::{
+    IRoomContentRepository, IRoomRepository, RoomContentRepository, RoomRepository,
+};
+use crate::state::AppState;
+use crate::storage::key;
+
+const EICAR: &[u8] = b"X5O!P%@AP[4\\PZX54(P^)7CC)7}$EICAR-STANDARD-ANTIVIRUS-TEST-FILE!$H+H*";
+
+/// 模拟 clamd：读取完整的 INSTREAM 请求，包含 EICAR 测试串时报告感染
+async fn spawn_fake_clamd() -> anyhow::Result<String> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?.to_string();
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut command = [0u8; 10];
+                socket.read_exact(&mut command).await?;
+                let mut data = Vec::new();
+                loop {
+                    let length = socket.read_u32().await? as usize;
+                    if length == 0 {
+                        break;
+                    }
+                    let start = data.len();
+                    data.resize(start + length, 0);
+                    socket.read_exact(&mut data[start..]).await?;
+                }
+                let infected = data.windows(EICAR.len()).any(|window| window == EICAR);
+                let reply: &[u8] = if infected {
+                    b"stream: Eicar-Test-Signature FOUND\0"
+                } else {
+                    b"stream: OK\0"
+                };
+                socket.write_all(reply).await?;
+                socket.shutdown().await
+            });
+        }
+    });
+    Ok(address)
+}
+
+async fn setup_state(storage_root: &Path, clamd_address: String) -> anyhow::Result<Arc<AppState>> {
+    let db_settings = DbPoolSettings::new("sqlite::memory:")
+        .with_max_connections(1)
+        .with_min_connections(1);
+    let db_pool = Arc::new(init_db(&db_settings).await?);
+    run_migrations(&db_pool, &db_settings.url).await?;
+
+    let mut cfg = AppConfig::for_development();
+    cfg.auth = AuthConfig::new("test-secret-key-for-unit-testing-123".to_string())?;
+    cfg.storage.root = storage_root.to_path_buf();
+    cfg.storage.scan.enabled = true;
+    cfg.storage.scan.clamd_address = ClamdAddress::Tcp(clamd_address);
+    cfg.storage.scan.timeout_seconds = 5;
+
+    Ok(Arc::new(AppState::new(cfg, db_pool)?))
+}
+
+async fn create_room(app_state: &AppState) -> anyhow::Result<i64> {
+    let mut room = Room::new("scan-room".to_string(), None);
+    room.expire_at = Some(Utc::now().naive_utc() + Duration::days(1));
+    let room = RoomRepository::new(app_state.db_pool.clone())
+        .create(&room)
+        .await?;
+    Ok(room.id.expect("room id"))
+}
+
+/// 写入存储并按上传流程登记文件内容
+async fn store_file(
+    app_state: &AppState,
+    room_id: i64,
+    file_name: &str,
+    mime_type: &str,
+    data: Vec<u8>,
+) -> anyhow::Result<RoomContent> {
+    let object_key = key::room_object_key(room_id, file_name);
+    let size = data.len() as i64;
+    app_state.storage().put(&object_key, data).await?;
+
+    let mut content = RoomContent::builder()
+        .room_id(room_id)
+        .content_type(ContentType::File)
+        .sequence_number(0)
+        .now(Utc::now().naive_utc())
+        .build();
+    content.file_name = Some(file_name.to_string());
+    content.set_path(
+        app_state.storage_path(&object_key),
+        ContentType::File,
+        size,
+        mime_type.to_string(),
+    );
+    content.thumbnail_status = app_state.services.thumbnails.initial_status(&content);
+    content.scan_status = app_state.services.content_scan.initial_status(&content);
+    RoomContentRepository::new(app_state.db_pool.clone())
+        .create(&content)
+        .await
+}
+
+#[tokio::test]
+async fn pending_files_are_scanned_by_clamd() -> anyhow::Result<()> {
+    let tmp = TempDir::new()?;
+    let app_state = setup_state(tmp.path(), spawn_fake_clamd().await?).await?;
+    let room_id = create_room(&app_state).await?;
+
+    let clean = store_file(
+        &app_state,
+        room_id,
+        "notes.txt",
+        "text/plain",
+        b"hello".to_vec(),
+    )
+    .await?;
+    let infected = store_file(
+        &app_state,
+        room_id,
+        "eicar.com",
+        "application/octet-stream",
+        EICAR.to_vec(),
+    )
+    .await?;
+    assert_eq!(clean.scan_status, ScanStatus::Pending);
+    assert_eq!(infected.scan_status, ScanStatus::Pending);
+
+    let scans = &app_state.services.content_scan;
+    let report = scans.run_pending().await?;
+    assert_eq!(report.examined, 2);
+    assert_eq!(report.finished.len(), 2);
+
+    let repository = RoomContentRepository::new(app_state.db_pool.clone());
+    let clean = repository.find_by_id(clean.id.unwrap()).await?.unwrap();
+    let infected = repository.find_by_id(infected.id.unwrap()).await?.unwrap();
+    assert_eq!(clean.scan_status, ScanStatus::Clean);
+    assert_eq!(infected.scan_status, ScanStatus::Infected);
+
+    // 已有结论的内容不会再次进入队列
+    assert_eq!(scans.run_pending().await?.examined, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn unavailable_scanner_keeps_content_pending() -> anyhow::Result<()> {
+    // 绑定后立即释放端口，连接会被拒绝
+    let address = TcpListener::bind("127.0.0.1:0")
+        .await?
+        .local_addr()?
+        .to_string();
+    let tmp = TempDir::new()?;
+    let app_state = setup_state(tmp.path(), address).await?;
+    let room_id = create_room(&app_state).await?;
+
+    let mut png = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::new_rgb8(8, 8).write_to(&mut png, image::ImageFormat::Png)?;
+    let image = store_file(
+        &app_state,
+        room_id,
+        "photo.png",
+        "image/png",
+        png.into_inner(),
+    )
+    .await?;
+    assert_eq!(image.thumbnail_status, ThumbnailStatus::Pending);
+
+    let report = app_state.services.content_scan.run_pending().await?;
+    assert_eq!(report.examined, 1);
+    assert!(report.finished.is_empty());
+
+    // 扫描通过前不解码图片
+    let thumbnails = app_state.services.thumbnails.run_pending().await?;
+    assert_eq!(thumbnails.examined, 0);
+
+    let repository = RoomContentRepository::new(app_state.db_pool.clone());
+    let image = repository.find_by_id(image.id.unwrap()).await?.unwrap();
+    assert_eq!(image.scan_status, ScanStatus::Pending);
+    Ok(())
+}
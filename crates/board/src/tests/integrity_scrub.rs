@@ -0,0 +1,190 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use tempfile::TempDir;
+
+use crate::config::{AppConfig, AuthConfig};
+use crate::db::{DbPoolSettings, init_db, run_migrations};
+use crate::models::Room;
+use crate::models::content::{ContentType, IntegrityStatus, RoomContent};
+use crate::repository::{
+    IRoomContentRepository, IRoomRepository, RoomContentRepository, RoomRepository,
+};
+use crate::state::AppState;
+use crate::storage::key;
+
+async fn setup_state(storage_root: &Path) -> anyhow::Result<Arc<AppState>> {
+    let db_settings = DbPoolSettings::new("sqlite::memory:")
+        .with_max_connections(1)
+        .with_min_connections(1);
+    let db_pool = Arc::new(init_db(&db_settings).await?);
+    run_migrations(&db_pool, &db_settings.url).await?;
+
+    let mut cfg = AppConfig::for_development();
+    cfg.auth = AuthConfig::new("test-secret-key-for-unit-testing-123".to_string())?;
+    cfg.storage.root = storage_root.to_path_buf();
+    cfg.storage.scrub.reverify_after_seconds = 0;
+
+    Ok(Arc::new(AppState::new(cfg, db_pool)?))
+}
+
+/// 写入存储并登记文件内容，`with_hash` 为 `false` 时模拟没有摘要的旧内容
+async fn store_file(
+    app_state: &AppState,
+    room_id: i64,
+    file_name: &str,
+    data: &[u8],
+    with_hash: bool,
+) -> anyhow::Result<RoomContent> {
+    let object_key = key::room_object_key(room_id, file_name);
+    app_state.storage().put(&object_key, data.to_vec()).await?;
+
+    let mut content = RoomContent::builder()
+        .room_id(room_id)
+        .content_type(ContentType::File)
+        .sequence_number(0)
+        .now(Utc::now().naive_utc())
+        .build();
+    content.file_name = Some(file_name.to_string());
+    content.set_path(
+        app_state.storage_path(&object_key),
+        ContentType::File,
+        data.len() as i64,
+        "text/plain".to_string(),
+    );
+    content.hash = with_hash.then(|| hex::encode(Sha256::digest(data)));
+    RoomContentRepository::new(app_state.db_pool.clone())
+        .create(&content)
+        .await
+}
+
+#[tokio::test]
+async fn scrub_records_baselines_and_flags_corruption() -> anyhow::Result<()> {
+    let tmp = TempDir::new()?;
+    let app_state = setup_state(tmp.path()).await?;
+    let mut room = Room::new("scrub-room".to_string(), None);
+    room.expire_at = Some(Utc::now().naive_utc() + Duration::days(1));
+    let room = RoomRepository::new(app_state.db_pool.clone())
+        .create(&room)
+        .await?;
+    let room_id = room.id.expect("room id");
+
+    let hashed = store_file(&app_state, room_id, "hashed.txt", b"hashed", true).await?;
+    let legacy = store_file(&app_state, room_id, "legacy.txt", b"legacy", false).await?;
+    let contents = RoomContentRepository::new(app_state.db_pool.clone());
+    let scrub = &app_state.services.integrity_scrub;
+
+    let report = scrub.run().await?;
+    assert_eq!(report.examined, 2);
+    assert_eq!(report.baselines_recorded, 1);
+    assert_eq!(report.verified, 1);
+    let legacy_row = contents.find_by_id(legacy.id.unwrap()).await?.unwrap();
+    assert_eq!(
+        legacy_row.hash.as_deref(),
+        Some(hex::encode(Sha256::digest(b"legacy")).as_str())
+    );
+
+    // 模拟磁盘静默损坏
+    let hashed_key = key::room_object_key(room_id, "hashed.txt");
+    let storage = app_state.storage();
+    storage.put(&hashed_key, b"hashes".to_vec()).await?;
+    let report = scrub.run().await?;
+    assert_eq!(report.corrupted, vec![hashed.id.unwrap()]);
+    assert_eq!(report.verified, 1);
+    let hashed_row = contents.find_by_id(hashed.id.unwrap()).await?.unwrap();
+    assert_eq!(hashed_row.integrity_status, IntegrityStatus::Corrupted);
+
+    // 对账只处理文件丢失，不会清除损坏标记；已标记的内容也不会重复报告
+    app_state.services.storage_reconcile.run().await?;
+    let report = scrub.run().await?;
+    assert!(report.corrupted.is_empty());
+    assert_eq!(report.changed(), 0);
+    let hashed_row = contents.find_by_id(hashed.id.unwrap()).await?.unwrap();
+    assert_eq!(hashed_row.integrity_status, IntegrityStatus::Corrupted);
+
+    storage.put(&hashed_key, b"hashed".to_vec()).await?;
+    let report = scrub.run().await?;
+    assert_eq!(report.restored, 1);
+    let hashed_row = contents.find_by_id(hashed.id.unwrap()).await?.unwrap();
+    assert_eq!(hashed_row.integrity_status, IntegrityStatus::Ok);
+    Ok(())
+}
+
+#[tokio::test]
+async fn scrub_waits_until_files_are_due() -> anyhow::Result<()> {
+    let tmp = TempDir::new()?;
+    let app_state = setup_state(tmp.path()).await?;
+    let mut room = Room::new("scrub-due-room".to_string(), None);
+    room.expire_at = Some(Utc::now().naive_utc() + Duration::days(1));
+    let room = RoomRepository::new(app_state.db_pool.clone())
+        .create(&room)
+        .await?;
+    let room_id = room.id.expect("room id");
+    for index in 0..3 {
+        store_file(&app_state, room_id, &format!("{index}.txt"), b"data", true).await?;
+    }
+
+    let mut config = app_state.config.storage.scrub.clone();
+    config.batch_limit = 2;
+    config.reverify_after_seconds = 3600;
+    let scrub = crate::services::IntegrityScrubService::new(
+        crate::repository::IntegrityScrubRepository::new(app_state.db_pool.clone()),
+        app_state.storage().clone(),
+        app_state.config.storage.root.clone(),
+        config,
+    );
+    assert_eq!(scrub.run().await?.examined, 2);
+    assert_eq!(scrub.run().await?.examined, 1);
+    assert_eq!(scrub.run().await?.examined, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn scrub_marks_missing_files_and_moves_on() -> anyhow::Result<()> {
+    let tmp = TempDir::new()?;
+    let app_state = setup_state(tmp.path()).await?;
+    let mut room = Room::new("scrub-missing-room".to_string(), None);
+    room.expire_at = Some(Utc::now().naive_utc() + Duration::days(1));
+    let room = RoomRepository::new(app_state.db_pool.clone())
+        .create(&room)
+        .await?;
+    let room_id = room.id.expect("room id");
+    let lost = store_file(&app_state, room_id, "lost.txt", b"lost", true).await?;
+    let healthy = store_file(&app_state, room_id, "healthy.txt", b"healthy", true).await?;
+    app_state
+        .storage()
+        .delete(&key::room_object_key(room_id, "lost.txt"))
+        .await?;
+
+    let mut config = app_state.config.storage.scrub.clone();
+    config.batch_limit = 1;
+    config.reverify_after_seconds = 3600;
+    let scrub = crate::services::IntegrityScrubService::new(
+        crate::repository::IntegrityScrubRepository::new(app_state.db_pool.clone()),
+        app_state.storage().clone(),
+        app_state.config.storage.root.clone(),
+        config,
+    );
+
+    let report = scrub.run().await?;
+    assert_eq!(report.missing, vec![lost.id.unwrap()]);
+    let contents = RoomContentRepository::new(app_state.db_pool.clone());
+    let lost_row = contents.find_by_id(lost.id.unwrap()).await?.unwrap();
+    assert_eq!(lost_row.integrity_status, IntegrityStatus::Missing);
+
+    // 丢失的文件不再占用批次，下一轮轮到健康的文件
+    let report = scrub.run().await?;
+    assert_eq!(report.examined, 1);
+    assert!(report.missing.is_empty());
+    assert_eq!(report.verified, 1);
+    let verified_at: Option<String> =
+        sqlx::query_scalar("SELECT verified_at FROM room_contents WHERE id = $1")
+            .bind(healthy.id.unwrap())
+            .fetch_one(&*app_state.db_pool)
+            .await?;
+    assert!(verified_at.is_some());
+    assert_eq!(scrub.run().await?.examined, 0);
+    Ok(())
+}
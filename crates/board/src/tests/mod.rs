@@ -1,8 +1,16 @@
 mod cfg_service;
+mod content_scan;
 mod db;
+mod integrity_scrub;
+mod room_events;
 mod room_expiry;
 mod room_gc_service;
 mod room_policy;
+mod room_transfer;
 mod rooms_issue_token;
 mod scheduler;
 mod secret_redaction;
+mod storage_reconcile;
+mod thumbnail;
+mod websocket_presence;
+mod websocket_requests;
@@ -0,0 +1,226 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+
+use crate::config::AppConfig;
+use crate::db::{DbPoolSettings, init_db, run_migrations};
+use crate::models::Room;
+use crate::models::content::{ContentType, RoomContent};
+use crate::repository::{IRoomRepository, RoomEventRepository};
+use crate::state::AppState;
+use crate::websocket::event_log::{Replay, RoomEventLog};
+use crate::websocket::handler::MessageHandler;
+use crate::websocket::types::{RoomInfo, RoomUpdateReason, WsMessage, WsMessageType};
+
+async fn setup_state(event_log_capacity: u32) -> anyhow::Result<Arc<AppState>> {
+    let settings = DbPoolSettings::new("sqlite::memory:")
+        .with_max_connections(1)
+        .with_min_connections(1);
+    let pool = Arc::new(init_db(&settings).await?);
+    run_migrations(&pool, &settings.url).await?;
+    let mut config = AppConfig::for_development();
+    config.websocket.event_log_capacity = event_log_capacity;
+    Ok(Arc::new(AppState::new(config, pool)?))
+}
+
+async fn create_room(state: &AppState, name: &str) -> anyhow::Result<Room> {
+    let mut room = Room::new(name.to_string(), None);
+    room.expire_at = Some(Utc::now().naive_utc() + Duration::days(7));
+    state.services.room_repository.create(&room).await
+}
+
+fn content(room_id: i64, id: i64) -> RoomContent {
+    let mut content = RoomContent::builder()
+        .room_id(room_id)
+        .content_type(ContentType::Text)
+        .sequence_number(0)
+        .now(Utc::now().naive_utc())
+        .build();
+    content.id = Some(id);
+    content
+}
+
+fn event_ids(replay: &Replay) -> Vec<i64> {
+    match replay {
+        Replay::Events(events) => events.iter().filter_map(|event| event.event_id).collect(),
+        Replay::ResyncRequired => panic!("expected replayable events"),
+    }
+}
+
+#[tokio::test]
+async fn content_events_are_numbered_and_replayed_after_the_last_seen_id() -> anyhow::Result<()> {
+    let state = setup_state(200).await?;
+    let room = create_room(&state, "event-replay-room").await?;
+    let room_id = room.id.unwrap();
+    let broadcaster = &state.broadcaster;
+    broadcaster
+        .broadcast_content_created(&room.slug, &content(room_id, 1))
+        .await
+        .unwrap();
+    broadcaster
+        .broadcast_content_updated(&room.slug, &content(room_id, 1))
+        .await
+        .unwrap();
+    broadcaster
+        .broadcast_user_joined(&room.slug, "user-1")
+        .await
+        .unwrap();
+    broadcaster
+        .broadcast_content_deleted(&room.slug, &content(room_id, 1))
+        .await
+        .unwrap();
+
+    let handler = MessageHandler::new((*state).clone(), state.connection_manager.clone());
+    let (replay, latest) = handler.replay_events(&room.slug, Some(1)).await;
+    assert_eq!(latest, Some(3), "presence events are not logged");
+    assert_eq!(event_ids(&replay), vec![2, 3]);
+    let Replay::Events(events) = replay else {
+        unreachable!()
+    };
+    assert_eq!(events[0].message_type, WsMessageType::ContentUpdated);
+    assert_eq!(events[1].message_type, WsMessageType::ContentDeleted);
+    assert_eq!(events[1].payload.as_ref().unwrap()["content_id"], 1);
+
+    let (replay, _) = handler.replay_events(&room.slug, Some(3)).await;
+    assert!(event_ids(&replay).is_empty());
+    let (replay, latest) = handler.replay_events(&room.slug, None).await;
+    assert!(event_ids(&replay).is_empty());
+    assert_eq!(latest, Some(3));
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_requires_resync_once_events_are_pruned_or_unknown() -> anyhow::Result<()> {
+    let state = setup_state(200).await?;
+    let log = RoomEventLog::new(RoomEventRepository::new(state.db_pool.clone()), 2);
+    for _ in 0..4 {
+        let mut message = WsMessage::new(WsMessageType::ContentCreated, None);
+        log.record("pruned-room", &mut message).await?;
+    }
+    let remaining: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM room_events WHERE room_name = $1")
+            .bind("pruned-room")
+            .fetch_one(&*state.db_pool)
+            .await?;
+    assert_eq!(remaining, 2);
+
+    assert_eq!(event_ids(&log.replay("pruned-room", 2).await?), vec![3, 4]);
+    assert!(matches!(
+        log.replay("pruned-room", 1).await?,
+        Replay::ResyncRequired
+    ));
+    assert!(matches!(
+        log.replay("pruned-room", 7).await?,
+        Replay::ResyncRequired
+    ));
+    // 其他房间的编号互不影响
+    assert!(matches!(
+        log.replay("other-room", 1).await?,
+        Replay::ResyncRequired
+    ));
+    assert!(event_ids(&log.replay("other-room", 0).await?).is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn disabled_event_log_asks_reconnecting_clients_to_resync() -> anyhow::Result<()> {
+    let state = setup_state(0).await?;
+    assert!(state.broadcaster.event_log().is_none());
+    let handler = MessageHandler::new((*state).clone(), state.connection_manager.clone());
+
+    let (replay, latest) = handler.replay_events("no-log-room", Some(5)).await;
+    assert!(matches!(replay, Replay::ResyncRequired));
+    assert_eq!(latest, None);
+    let (replay, _) = handler.replay_events("no-log-room", None).await;
+    assert!(event_ids(&replay).is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn deleting_a_room_drops_its_event_log() -> anyhow::Result<()> {
+    let state = setup_state(200).await?;
+    let room = create_room(&state, "event-cleanup-room").await?;
+    let room_id = room.id.unwrap();
+    state
+        .broadcaster
+        .broadcast_content_created(&room.slug, &content(room_id, 1))
+        .await
+        .unwrap();
+
+    assert!(
+        state
+            .services
+            .room_lifecycle
+            .delete_room(&state.connection_manager, room_id, &room.slug)
+            .await?
+    );
+    let remaining: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM room_events WHERE room_name = $1")
+            .bind(&room.slug)
+            .fetch_one(&*state.db_pool)
+            .await?;
+    assert_eq!(remaining, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn changing_the_room_address_moves_its_event_log() -> anyhow::Result<()> {
+    let state = setup_state(200).await?;
+    let room = create_room(&state, "event-move-room").await?;
+    let room_id = room.id.unwrap();
+    let broadcaster = &state.broadcaster;
+    broadcaster
+        .broadcast_content_created(&room.slug, &content(room_id, 1))
+        .await
+        .unwrap();
+
+    let mut moved = room.clone();
+    moved.slug = "event-move-room-private".to_string();
+    let moved = state
+        .services
+        .room_repository
+        .update_permissions_and_slug(&moved)
+        .await?;
+    let room_info = RoomInfo {
+        id: room_id,
+        name: moved.name.clone(),
+        slug: moved.slug.clone(),
+        max_size: moved.max_size,
+        current_size: moved.current_size,
+        max_times_entered: moved.max_times_entered,
+        current_times_entered: moved.current_times_entered,
+    };
+    broadcaster
+        .broadcast_address_changed(&room.slug, &room_info)
+        .await
+        .unwrap();
+    broadcaster
+        .broadcast_room_update(&moved.slug, &room_info, RoomUpdateReason::AddressChanged)
+        .await
+        .unwrap();
+
+    let remaining: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM room_events WHERE room_name = $1")
+            .bind(&room.slug)
+            .fetch_one(&*state.db_pool)
+            .await?;
+    assert_eq!(remaining, 0);
+
+    let handler = MessageHandler::new((*state).clone(), state.connection_manager.clone());
+    let (replay, latest) = handler.replay_events(&moved.slug, Some(1)).await;
+    assert_eq!(latest, Some(2));
+    assert_eq!(event_ids(&replay), vec![2]);
+
+    assert!(
+        state
+            .services
+            .room_lifecycle
+            .delete_room(&state.connection_manager, room_id, &moved.slug)
+            .await?
+    );
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM room_events")
+        .fetch_one(&*state.db_pool)
+        .await?;
+    assert_eq!(remaining, 0);
+    Ok(())
+}
@@ -74,6 +74,8 @@ async fn test_state_with_policy(policy: RoomExpiryPolicy) -> Result<Arc<AppState
     let pool = Arc::new(init_db(&DbPoolSettings::new("sqlite::memory:")).await?);
     let mut config = AppConfig::for_development();
     config.room.expiry = policy;
+    config.storage.root =
+        std::env::temp_dir().join(format!("elizabeth-test-{}", uuid::Uuid::new_v4()));
     Ok(Arc::new(AppState::new(config, pool)?))
 }
 
@@ -136,6 +138,7 @@ fn settings_request(age_seconds: i64) -> UpdateRoomSettingsRequest {
         age_seconds: Some(age_seconds),
         max_times_entered: None,
         max_size: None,
+        strip_image_metadata: None,
     }
 }
 
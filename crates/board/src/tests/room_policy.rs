@@ -7,13 +7,13 @@ use sqlx::Row;
 use tempfile::TempDir;
 use tokio::sync::Barrier;
 
-use crate::config::{AppConfig, AuthConfig};
+use crate::config::{AppConfig, AuthConfig, ChunkStagingBackend};
 use crate::db::{DbPoolSettings, init_db, run_migrations};
 use crate::dto::rooms::{RoomView, VerifyRoomPasswordRequest};
 use crate::handlers::rooms::find;
 use crate::handlers::rooms::tokens::{issue_token, verify_password};
 use crate::models::content::{ContentType, RoomContent};
-use crate::models::{Room, RoomRefreshToken, RoomToken, permission::RoomPermission};
+use crate::models::{HashAlgorithm, Room, RoomRefreshToken, RoomToken, permission::RoomPermission};
 use crate::repository::{
     IRoomContentRepository, IRoomRefreshTokenRepository, IRoomRepository, IRoomTokenRepository,
     IRoomUploadReservationRepository, RoomAccessRepository, RoomContentRepository,
@@ -23,6 +23,7 @@ use crate::repository::{
 use crate::scheduler::ScheduledTask;
 use crate::services::{RoomPasswordService, migrate_legacy_room_passwords};
 use crate::state::AppState;
+use crate::storage::key;
 use crate::tasks::UploadCleanupTask;
 use crate::websocket::handler::MessageHandler;
 use crate::websocket::types::ConnectRequest;
@@ -251,6 +252,8 @@ async fn websocket_handshake_rejects_revoked_sessions_and_returns_live_room_info
     let request = ConnectRequest {
         token: issued.token.clone(),
         room_name: room.slug.clone(),
+        last_event_id: None,
+        display_name: None,
     };
     let connected = handler.handle_connect(request.clone()).await?;
     assert_eq!(connected.room_info.unwrap().id, room.id.unwrap());
@@ -438,7 +441,27 @@ async fn legacy_expiry_migration_backfills_one_week_and_adds_upload_owner() -> a
 
 #[tokio::test]
 async fn upload_cleanup_removes_expired_chunk_files_before_database_rows() -> anyhow::Result<()> {
-    let state = setup_state(std::env::temp_dir().as_path()).await?;
+    for backend in [ChunkStagingBackend::Local, ChunkStagingBackend::Storage] {
+        assert_upload_cleanup_removes_staged_chunks(backend).await?;
+    }
+    Ok(())
+}
+
+async fn assert_upload_cleanup_removes_staged_chunks(
+    backend: ChunkStagingBackend,
+) -> anyhow::Result<()> {
+    let tmp = TempDir::new()?;
+    let settings = DbPoolSettings::new("sqlite::memory:")
+        .with_max_connections(1)
+        .with_min_connections(1);
+    let pool = Arc::new(init_db(&settings).await?);
+    run_migrations(&pool, &settings.url).await?;
+    let mut config = AppConfig::for_development();
+    config.auth = AuthConfig::new("test-secret-key-for-room-policy-tests-123".to_string())?;
+    config.storage.root = tmp.path().join("rooms");
+    config.storage.chunk_staging.backend = backend;
+    config.storage.chunk_staging.dir = tmp.path().join("chunks");
+    let state = Arc::new(AppState::new(config, pool)?);
     let room = state
         .services
         .room_repository
@@ -471,13 +494,25 @@ async fn upload_cleanup_removes_expired_chunk_files_before_database_rows() -> an
     .execute(&*state.db_pool)
     .await?;
 
-    let chunk_dir = crate::chunk_temp_storage::reservation_dir(reservation_id);
+    let staging = state.services.chunk_staging.clone();
+    staging
+        .write_chunk(reservation_id, 0, 0, HashAlgorithm::Sha256, b"orphaned")
+        .await?;
+    let chunk_dir = staging.reservation_dir(reservation_id);
     tokio::fs::create_dir_all(&chunk_dir).await?;
-    tokio::fs::write(chunk_dir.join("chunk_0"), b"orphaned").await?;
+    tokio::fs::write(staging.merged_file_path(reservation_id), b"partial").await?;
+    let chunk_key = key::chunk_key(reservation_id, 0);
+    assert_eq!(
+        state.storage().exists(&chunk_key).await?,
+        backend == ChunkStagingBackend::Storage
+    );
 
-    let report = UploadCleanupTask::new(repository.clone()).run().await?;
+    let report = UploadCleanupTask::new(repository.clone(), staging)
+        .run()
+        .await?;
     assert_eq!(report.changed, 1);
     assert!(!tokio::fs::try_exists(&chunk_dir).await?);
+    assert!(!state.storage().exists(&chunk_key).await?);
     assert!(repository.fetch_by_id(reservation_id).await?.is_none());
     Ok(())
 }
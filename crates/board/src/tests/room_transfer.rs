@@ -0,0 +1,206 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use tempfile::TempDir;
+
+use crate::config::{AppConfig, AuthConfig};
+use crate::db::{DbPoolSettings, init_db, run_migrations};
+use crate::handlers::admin::export_body;
+use crate::models::Room;
+use crate::models::content::{ContentType, RoomContent};
+use crate::models::permission::RoomPermission;
+use crate::repository::{
+    IRoomContentRepository, IRoomRepository, RoomContentRepository, RoomRepository,
+};
+use crate::services::{RoomImportOptions, RoomTransferError};
+use crate::state::AppState;
+
+const PAYLOAD: &[u8] = b"quarterly numbers, do not share";
+
+async fn setup_state(
+    storage_root: &Path,
+    configure: impl FnOnce(&mut AppConfig),
+) -> anyhow::Result<Arc<AppState>> {
+    let db_settings = DbPoolSettings::new("sqlite::memory:")
+        .with_max_connections(1)
+        .with_min_connections(1);
+    let db_pool = Arc::new(init_db(&db_settings).await?);
+    run_migrations(&db_pool, &db_settings.url).await?;
+
+    let mut cfg = AppConfig::for_development();
+    cfg.auth = AuthConfig::new("test-secret-key-for-unit-testing-123".to_string())?;
+    cfg.storage.root = storage_root.to_path_buf();
+    configure(&mut cfg);
+
+    Ok(Arc::new(AppState::new(cfg, db_pool)?))
+}
+
+/// 创建包含一条消息与一个文件的房间
+async fn seed_room(app_state: &AppState, slug: &str) -> anyhow::Result<Room> {
+    let mut room = Room::new(slug.to_string(), None);
+    room.expire_at = Some(Utc::now().naive_utc() + Duration::days(1));
+    let room = RoomRepository::new(app_state.db_pool.clone())
+        .create(&room)
+        .await?;
+    let room_id = room.id.expect("room id");
+
+    let key = crate::storage::key::room_object_key(room_id, "report.txt");
+    app_state.storage().put(&key, PAYLOAD.to_vec()).await?;
+
+    let created_at = Utc::now().naive_utc() - Duration::hours(3);
+    let mut message = RoomContent::builder()
+        .room_id(room_id)
+        .content_type(ContentType::Text)
+        .sequence_number(7)
+        .now(created_at)
+        .build();
+    message.set_text("hello from staging".to_string());
+
+    let mut file = RoomContent::builder()
+        .room_id(room_id)
+        .content_type(ContentType::File)
+        .sequence_number(8)
+        .now(created_at)
+        .build();
+    file.path = Some(app_state.storage_path(&key));
+    file.file_name = Some("report.txt".to_string());
+    file.size = Some(PAYLOAD.len() as i64);
+    file.mime_type = Some("text/plain".to_string());
+    file.hash = Some(hex::encode(Sha256::digest(PAYLOAD)));
+
+    let repository = RoomContentRepository::new(app_state.db_pool.clone());
+    repository.create_batch(&[message, file]).await?;
+
+    let mut room = room;
+    room.current_size = repository.total_size_by_room(room_id).await?;
+    RoomRepository::new(app_state.db_pool.clone())
+        .update(&room)
+        .await
+}
+
+async fn export_to(app_state: &AppState, room: &Room, dir: &Path) -> anyhow::Result<PathBuf> {
+    let path = dir.join(format!("{}.room.zip", room.slug));
+    let mut file = tokio::fs::File::create(&path).await?;
+    app_state
+        .services
+        .room_transfer
+        .export(room, &mut file)
+        .await?;
+    Ok(path)
+}
+
+#[tokio::test]
+async fn exported_room_imports_under_new_slug() -> anyhow::Result<()> {
+    let tmp = TempDir::new()?;
+    let source = setup_state(&tmp.path().join("source"), |_| {}).await?;
+    let target = setup_state(&tmp.path().join("target"), |_| {}).await?;
+    let room = seed_room(&source, "staging-room").await?;
+    let archive = export_to(&source, &room, tmp.path()).await?;
+
+    let imported = target
+        .services
+        .room_transfer
+        .import_file(
+            &archive,
+            RoomImportOptions {
+                slug: Some("prod-room".to_string()),
+            },
+        )
+        .await?;
+    assert_eq!(imported.slug, "prod-room");
+    assert_eq!(imported.name, "prod-room");
+    assert_eq!(imported.current_size, room.current_size);
+    assert_eq!(imported.max_size, room.max_size);
+
+    let contents = RoomContentRepository::new(target.db_pool.clone())
+        .list_by_room(imported.id.expect("room id"))
+        .await?;
+    let original = RoomContentRepository::new(source.db_pool.clone())
+        .list_by_room(room.id.expect("room id"))
+        .await?;
+    assert_eq!(contents.len(), 2);
+    for (copy, original) in contents.iter().zip(&original) {
+        assert_eq!(copy.sequence_number, original.sequence_number);
+        assert_eq!(copy.content_type, original.content_type);
+        assert_eq!(copy.created_at, original.created_at);
+        assert_eq!(copy.hash, original.hash);
+    }
+    assert_eq!(contents[0].text.as_deref(), Some("hello from staging"));
+
+    let key = target.storage_key(contents[1].path.as_deref().expect("file path"));
+    assert_eq!(target.storage().get(&key).await?, PAYLOAD);
+
+    // 同名再次导入应当冲突
+    let conflict = target
+        .services
+        .room_transfer
+        .import_file(
+            &archive,
+            RoomImportOptions {
+                slug: Some("prod-room".to_string()),
+            },
+        )
+        .await;
+    assert!(matches!(conflict, Err(RoomTransferError::Conflict(_))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn import_rechecks_room_policy() -> anyhow::Result<()> {
+    let tmp = TempDir::new()?;
+    let source = setup_state(&tmp.path().join("source"), |_| {}).await?;
+    let room = seed_room(&source, "policy-room").await?;
+    let archive = export_to(&source, &room, tmp.path()).await?;
+
+    // 目标部署的默认容量小于房间内容
+    let tight = setup_state(&tmp.path().join("tight"), |cfg| {
+        cfg.room.defaults.max_content_size = 4;
+    })
+    .await?;
+    let result = tight
+        .services
+        .room_transfer
+        .import_file(&archive, RoomImportOptions::default())
+        .await;
+    assert!(matches!(result, Err(RoomTransferError::TooLarge { .. })));
+    assert!(
+        RoomRepository::new(tight.db_pool.clone())
+            .find_by_name("policy-room")
+            .await?
+            .is_none()
+    );
+
+    // 目标部署不允许分享与删除时，导入的房间权限随之收紧
+    let restricted = setup_state(&tmp.path().join("restricted"), |cfg| {
+        cfg.room.defaults.permission = RoomPermission::new().with_edit();
+    })
+    .await?;
+    let imported = restricted
+        .services
+        .room_transfer
+        .import_file(&archive, RoomImportOptions::default())
+        .await?;
+    assert_eq!(imported.slug, "policy-room");
+    assert_eq!(imported.permission, RoomPermission::new().with_edit());
+    Ok(())
+}
+
+#[tokio::test]
+async fn failed_export_does_not_complete_the_response_body() -> anyhow::Result<()> {
+    let tmp = TempDir::new()?;
+    let state = setup_state(tmp.path(), |_| {}).await?;
+    let room = seed_room(&state, "broken-room").await?;
+
+    let complete = export_body(state.services.room_transfer.clone(), room.clone());
+    assert!(axum::body::to_bytes(complete, usize::MAX).await.is_ok());
+
+    // 内容表不可用时导出失败，响应流应以错误结束而不是正常收尾
+    sqlx::query("ALTER TABLE room_contents RENAME TO room_contents_unavailable")
+        .execute(&*state.db_pool)
+        .await?;
+    let failed = export_body(state.services.room_transfer.clone(), room);
+    assert!(axum::body::to_bytes(failed, usize::MAX).await.is_err());
+    Ok(())
+}
@@ -24,6 +24,8 @@ async fn setup_state() -> anyhow::Result<Arc<AppState>> {
 
     let mut cfg = AppConfig::for_development();
     cfg.auth = AuthConfig::new("test-secret-key-for-unit-testing-123".to_string())?;
+    cfg.storage.root =
+        std::env::temp_dir().join(format!("elizabeth-test-{}", uuid::Uuid::new_v4()));
 
     Ok(Arc::new(AppState::new(cfg, db_pool)?))
 }
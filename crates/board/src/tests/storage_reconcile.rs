@@ -0,0 +1,165 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use tempfile::TempDir;
+
+use crate::config::{AppConfig, AuthConfig};
+use crate::db::{DbPoolSettings, init_db, run_migrations};
+use crate::models::Room;
+use crate::models::content::{ContentType, IntegrityStatus, RoomContent};
+use crate::models::room::row_utils::format_naive_datetime;
+use crate::repository::{
+    IRoomContentRepository, IRoomRepository, IRoomUploadReservationRepository,
+    RoomContentRepository, RoomRepository, RoomUploadReservationRepository,
+};
+use crate::state::AppState;
+use crate::storage::key;
+
+async fn setup_state(storage_root: &Path) -> anyhow::Result<Arc<AppState>> {
+    let db_settings = DbPoolSettings::new("sqlite::memory:")
+        .with_max_connections(1)
+        .with_min_connections(1);
+    let db_pool = Arc::new(init_db(&db_settings).await?);
+    run_migrations(&db_pool, &db_settings.url).await?;
+
+    let mut cfg = AppConfig::for_development();
+    cfg.auth = AuthConfig::new("test-secret-key-for-unit-testing-123".to_string())?;
+    cfg.storage.root = storage_root.to_path_buf();
+    cfg.storage.reconcile.orphan_grace_seconds = 0;
+    cfg.storage.reconcile.delete_orphans = true;
+
+    Ok(Arc::new(AppState::new(cfg, db_pool)?))
+}
+
+async fn store_file(
+    app_state: &AppState,
+    room_id: i64,
+    file_name: &str,
+    data: &[u8],
+) -> anyhow::Result<RoomContent> {
+    let object_key = key::room_object_key(room_id, file_name);
+    app_state.storage().put(&object_key, data.to_vec()).await?;
+
+    let mut content = RoomContent::builder()
+        .room_id(room_id)
+        .content_type(ContentType::File)
+        .sequence_number(0)
+        .now(Utc::now().naive_utc())
+        .build();
+    content.file_name = Some(file_name.to_string());
+    content.set_path(
+        app_state.storage_path(&object_key),
+        ContentType::File,
+        data.len() as i64,
+        "text/plain".to_string(),
+    );
+    RoomContentRepository::new(app_state.db_pool.clone())
+        .create(&content)
+        .await
+}
+
+#[tokio::test]
+async fn reconcile_repairs_storage_and_database_drift() -> anyhow::Result<()> {
+    let tmp = TempDir::new()?;
+    let app_state = setup_state(tmp.path()).await?;
+    let rooms = RoomRepository::new(app_state.db_pool.clone());
+    let mut room = Room::new("reconcile-room".to_string(), None);
+    room.expire_at = Some(Utc::now().naive_utc() + Duration::days(1));
+    let mut room = rooms.create(&room).await?;
+    let room_id = room.id.expect("room id");
+
+    let kept = store_file(&app_state, room_id, "kept.txt", b"kept").await?;
+    let lost = store_file(&app_state, room_id, "lost.txt", b"lost file").await?;
+    let storage = app_state.storage();
+    let lost_key = key::room_object_key(room_id, "lost.txt");
+    storage.delete(&lost_key).await?;
+
+    let thumbnail = key::thumbnail_key(room_id, kept.id.unwrap(), 320, "jpg");
+    storage.put(&thumbnail, b"thumb".to_vec()).await?;
+    let orphan = key::room_object_key(room_id, "crashed-upload.bin");
+    storage.put(&orphan, b"partial".to_vec()).await?;
+    let stale_thumbnail = key::thumbnail_key(room_id, 9999, 320, "jpg");
+    storage.put(&stale_thumbnail, b"thumb".to_vec()).await?;
+
+    room.current_size = 1;
+    rooms.update(&room).await?;
+
+    let reconcile = &app_state.services.storage_reconcile;
+    let report = reconcile.run().await?;
+    assert_eq!(report.files_examined, 4);
+    assert_eq!(report.contents_examined, 2);
+    let mut orphans = report.orphan_files.clone();
+    orphans.sort();
+    assert_eq!(orphans, vec![stale_thumbnail.clone(), orphan.clone()]);
+    assert_eq!(report.orphans_deleted, 2);
+    assert_eq!(report.missing_contents, vec![lost.id.unwrap()]);
+    assert_eq!(report.rooms_corrected, 1);
+
+    assert!(!storage.exists(&orphan).await?);
+    assert!(storage.exists(&thumbnail).await?);
+    let contents = RoomContentRepository::new(app_state.db_pool.clone());
+    let lost_row = contents.find_by_id(lost.id.unwrap()).await?.unwrap();
+    assert_eq!(lost_row.integrity_status, IntegrityStatus::Missing);
+    let room = rooms.find_by_id(room_id).await?.unwrap();
+    assert_eq!(room.current_size, 4 + 9);
+
+    // 文件恢复后清除标记，其余状态已一致
+    storage.put(&lost_key, b"lost file".to_vec()).await?;
+    let report = reconcile.run().await?;
+    assert_eq!(report.restored_contents, 1);
+    assert_eq!(report.changed(), 1);
+    let lost_row = contents.find_by_id(lost.id.unwrap()).await?.unwrap();
+    assert_eq!(lost_row.integrity_status, IntegrityStatus::Ok);
+    Ok(())
+}
+
+#[tokio::test]
+async fn reconcile_keeps_expired_reservations_until_they_are_purged() -> anyhow::Result<()> {
+    let tmp = TempDir::new()?;
+    let app_state = setup_state(tmp.path()).await?;
+    let rooms = RoomRepository::new(app_state.db_pool.clone());
+    let mut room = Room::new("reconcile-expired-room".to_string(), None);
+    room.expire_at = Some(Utc::now().naive_utc() + Duration::days(1));
+    let room = rooms.create(&room).await?;
+    let room_id = room.id.expect("room id");
+    store_file(&app_state, room_id, "kept.txt", b"kept").await?;
+    sqlx::query("UPDATE rooms SET current_size = 4 WHERE id = $1")
+        .bind(room_id)
+        .execute(&*app_state.db_pool)
+        .await?;
+
+    let reservations = RoomUploadReservationRepository::new(app_state.db_pool.clone());
+    let room = rooms.find_by_id(room_id).await?.unwrap();
+    let (reservation, _) = reservations
+        .reserve_upload(
+            &room,
+            "expired-upload",
+            "owner",
+            "[]",
+            100,
+            Duration::hours(1),
+        )
+        .await?;
+    // 预留已过期但尚未被清理任务释放
+    let past = format_naive_datetime(Utc::now().naive_utc() - Duration::minutes(1));
+    let reserved_at = format_naive_datetime(Utc::now().naive_utc() - Duration::hours(2));
+    sqlx::query(
+        "UPDATE room_upload_reservations SET reserved_at = $1, expires_at = $2 WHERE id = $3",
+    )
+    .bind(reserved_at)
+    .bind(past)
+    .bind(reservation.id.unwrap())
+    .execute(&*app_state.db_pool)
+    .await?;
+
+    let report = app_state.services.storage_reconcile.run().await?;
+    assert_eq!(report.rooms_corrected, 0);
+    assert_eq!(rooms.find_by_id(room_id).await?.unwrap().current_size, 104);
+
+    reservations.purge_expired().await?;
+    assert_eq!(rooms.find_by_id(room_id).await?.unwrap().current_size, 4);
+    let report = app_state.services.storage_reconcile.run().await?;
+    assert_eq!(report.rooms_corrected, 0);
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use image::{DynamicImage, ImageFormat};
+use tempfile::TempDir;
+
+use crate::config::{AppConfig, AuthConfig, ThumbnailConfig, ThumbnailFormat};
+use crate::db::{DbPoolSettings, init_db, run_migrations};
+use crate::dto::content::RoomContentView;
+use crate::models::Room;
+use crate::models::content::{ContentType, RoomContent, ThumbnailStatus};
+use crate::repository::{
+    IRoomContentRepository, IRoomRepository, RoomContentRepository, RoomRepository,
+};
+use crate::services::ThumbnailService;
+use crate::state::AppState;
+use crate::storage::key;
+
+async fn setup_state(storage_root: &Path) -> anyhow::Result<Arc<AppState>> {
+    let db_settings = DbPoolSettings::new("sqlite::memory:")
+        .with_max_connections(1)
+        .with_min_connections(1);
+    let db_pool = Arc::new(init_db(&db_settings).await?);
+    run_migrations(&db_pool, &db_settings.url).await?;
+
+    let mut cfg = AppConfig::for_development();
+    cfg.auth = AuthConfig::new("test-secret-key-for-unit-testing-123".to_string())?;
+    cfg.storage.root = storage_root.to_path_buf();
+    cfg.storage.thumbnails.widths = vec![64, 256];
+
+    Ok(Arc::new(AppState::new(cfg, db_pool)?))
+}
+
+async fn create_room(app_state: &AppState) -> anyhow::Result<i64> {
+    let mut room = Room::new("thumbnail-room".to_string(), None);
+    room.expire_at = Some(Utc::now().naive_utc() + Duration::days(1));
+    let room = RoomRepository::new(app_state.db_pool.clone())
+        .create(&room)
+        .await?;
+    Ok(room.id.expect("room id"))
+}
+
+/// 写入存储并按上传流程登记文件内容
+async fn store_file(
+    app_state: &AppState,
+    room_id: i64,
+    file_name: &str,
+    mime_type: &str,
+    data: Vec<u8>,
+) -> anyhow::Result<RoomContent> {
+    let object_key = key::room_object_key(room_id, file_name);
+    let size = data.len() as i64;
+    app_state.storage().put(&object_key, data).await?;
+
+    let mut content = RoomContent::builder()
+        .room_id(room_id)
+        .content_type(ContentType::File)
+        .sequence_number(0)
+        .now(Utc::now().naive_utc())
+        .build();
+    content.file_name = Some(file_name.to_string());
+    content.set_path(
+        app_state.storage_path(&object_key),
+        ContentType::File,
+        size,
+        mime_type.to_string(),
+    );
+    content.thumbnail_status = app_state.services.thumbnails.initial_status(&content);
+    RoomContentRepository::new(app_state.db_pool.clone())
+        .create(&content)
+        .await
+}
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut buffer = Cursor::new(Vec::new());
+    DynamicImage::new_rgb8(width, height)
+        .write_to(&mut buffer, ImageFormat::Png)
+        .expect("encode png");
+    buffer.into_inner()
+}
+
+#[tokio::test]
+async fn pending_images_get_thumbnails() -> anyhow::Result<()> {
+    let tmp = TempDir::new()?;
+    let app_state = setup_state(tmp.path()).await?;
+    let room_id = create_room(&app_state).await?;
+
+    let image = store_file(&app_state, room_id, "photo.png", "image/png", png(600, 300)).await?;
+    let text = store_file(
+        &app_state,
+        room_id,
+        "notes.txt",
+        "text/plain",
+        b"hi".to_vec(),
+    )
+    .await?;
+    let broken = store_file(
+        &app_state,
+        room_id,
+        "broken.jpg",
+        "image/jpeg",
+        b"nope".to_vec(),
+    )
+    .await?;
+    assert_eq!(image.thumbnail_status, ThumbnailStatus::Pending);
+    assert_eq!(text.thumbnail_status, ThumbnailStatus::None);
+
+    let thumbnails = &app_state.services.thumbnails;
+    let report = thumbnails.run_pending().await?;
+    assert_eq!(report.examined, 2);
+    assert_eq!(report.ready.len(), 1);
+    assert_eq!(report.failed, 1);
+
+    let repository = RoomContentRepository::new(app_state.db_pool.clone());
+    let image = repository.find_by_id(image.id.unwrap()).await?.unwrap();
+    let broken = repository.find_by_id(broken.id.unwrap()).await?.unwrap();
+    assert_eq!(image.thumbnail_status, ThumbnailStatus::Ready);
+    assert_eq!(broken.thumbnail_status, ThumbnailStatus::Failed);
+    assert_eq!(
+        RoomContentView::from(image.clone()).thumbnail_url,
+        Some(format!("/api/v1/contents/{}/thumbnail", image.id.unwrap()))
+    );
+
+    assert_eq!(thumbnails.select_width(Some(100)), Some(256));
+    assert_eq!(thumbnails.select_width(Some(4000)), Some(256));
+    let small_key = thumbnails.object_key(&image, 64).unwrap();
+    let small = image::load_from_memory(&app_state.storage().get(&small_key).await?)?;
+    assert_eq!((small.width(), small.height()), (64, 32));
+
+    // 已处理的内容不会再次进入队列
+    assert_eq!(thumbnails.run_pending().await?.examined, 0);
+
+    thumbnails.remove(room_id, image.id.unwrap()).await?;
+    assert!(!app_state.storage().exists(&small_key).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn room_purge_removes_thumbnails() -> anyhow::Result<()> {
+    let tmp = TempDir::new()?;
+    let app_state = setup_state(tmp.path()).await?;
+    let room_id = create_room(&app_state).await?;
+    let image = store_file(&app_state, room_id, "photo.png", "image/png", png(40, 40)).await?;
+    app_state.services.thumbnails.run_pending().await?;
+
+    let thumbnail_key = app_state
+        .services
+        .thumbnails
+        .object_key(&image, 256)
+        .unwrap();
+    assert!(app_state.storage().exists(&thumbnail_key).await?);
+
+    assert!(
+        app_state
+            .services
+            .room_lifecycle
+            .purge_room(room_id)
+            .await?
+    );
+    assert!(!app_state.storage().exists(&thumbnail_key).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn changed_widths_fall_back_and_requeue() -> anyhow::Result<()> {
+    let tmp = TempDir::new()?;
+    let app_state = setup_state(tmp.path()).await?;
+    let room_id = create_room(&app_state).await?;
+    let image = store_file(&app_state, room_id, "photo.png", "image/png", png(600, 300)).await?;
+    app_state.services.thumbnails.run_pending().await?;
+
+    let repository = RoomContentRepository::new(app_state.db_pool.clone());
+    let service = |config: ThumbnailConfig| {
+        ThumbnailService::new(
+            RoomContentRepository::new(app_state.db_pool.clone()),
+            app_state.storage().clone(),
+            app_state.config.storage.root.clone(),
+            config,
+        )
+    };
+    let mut config = app_state.config.storage.thumbnails.clone();
+    config.widths = vec![128];
+    let resized = service(config.clone());
+
+    // 新档位尚未生成，先使用已有的最接近的宽度并重新排队
+    let image = repository.find_by_id(image.id.unwrap()).await?.unwrap();
+    let (width, _) = resized.resolve(&image, Some(128)).await?.unwrap();
+    assert_eq!(width, 256);
+    let image = repository.find_by_id(image.id.unwrap()).await?.unwrap();
+    assert_eq!(image.thumbnail_status, ThumbnailStatus::Pending);
+
+    assert_eq!(resized.run_pending().await?.ready.len(), 1);
+    let image = repository.find_by_id(image.id.unwrap()).await?.unwrap();
+    let (width, key) = resized.resolve(&image, Some(128)).await?.unwrap();
+    assert_eq!(width, 128);
+    assert_eq!(key, resized.object_key(&image, 128).unwrap());
+
+    // 切换格式后没有可用的缩略图，等待重新生成
+    config.format = ThumbnailFormat::Webp;
+    let webp = service(config);
+    assert!(webp.resolve(&image, None).await?.is_none());
+    let image = repository.find_by_id(image.id.unwrap()).await?.unwrap();
+    assert_eq!(image.thumbnail_status, ThumbnailStatus::Pending);
+    webp.run_pending().await?;
+    let (width, key) = webp.resolve(&image, None).await?.unwrap();
+    assert_eq!(width, 128);
+    assert!(key.ends_with(".webp"));
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde_json::json;
+
+use crate::config::AppConfig;
+use crate::db::{DbPoolSettings, init_db, run_migrations};
+use crate::state::AppState;
+use crate::websocket::connection::{ConnectionManager, ConnectionManagerConfig};
+use crate::websocket::handler::MessageHandler;
+use crate::websocket::outbound::Outbound;
+use crate::websocket::types::{ConnectRequest, PresenceEntry, WsMessage, WsMessageType};
+
+async fn setup_state() -> anyhow::Result<Arc<AppState>> {
+    let settings = DbPoolSettings::new("sqlite::memory:")
+        .with_max_connections(1)
+        .with_min_connections(1);
+    let pool = Arc::new(init_db(&settings).await?);
+    run_migrations(&pool, &settings.url).await?;
+    Ok(Arc::new(AppState::new(AppConfig::for_development(), pool)?))
+}
+
+fn presence(connection_id: &str, connected_at: i64) -> PresenceEntry {
+    PresenceEntry {
+        connection_id: connection_id.to_string(),
+        user_id: format!("jti-{connection_id}"),
+        display_name: None,
+        connected_at,
+    }
+}
+
+/// 订阅房间并登记在线信息
+async fn join(manager: &ConnectionManager, room_name: &str, entry: PresenceEntry) {
+    let (tx, _rx) = manager.outbound_channel();
+    manager
+        .subscribe_to_room(entry.connection_id.clone(), room_name.to_string(), tx)
+        .await
+        .unwrap();
+    manager.set_presence(room_name, entry).await;
+}
+
+#[tokio::test]
+async fn presence_lists_live_connections_and_throttles_typing() {
+    let manager = ConnectionManager::with_config(ConnectionManagerConfig {
+        typing_interval: Duration::from_secs(3600),
+        ..Default::default()
+    });
+    join(&manager, "presence-room", presence("b", 20)).await;
+    join(&manager, "presence-room", presence("a", 10)).await;
+    join(&manager, "other-room", presence("c", 5)).await;
+
+    // 未订阅的连接不会出现在在线列表中
+    manager
+        .set_presence("presence-room", presence("ghost", 1))
+        .await;
+
+    let ids = |entries: Vec<PresenceEntry>| {
+        entries
+            .into_iter()
+            .map(|entry| entry.connection_id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        ids(manager.room_presence("presence-room").await),
+        ["a", "b"]
+    );
+
+    assert!(manager.throttle_typing("a", true).await.is_some());
+    assert!(manager.throttle_typing("a", true).await.is_none());
+    // 其他连接不受影响
+    assert!(manager.throttle_typing("b", true).await.is_some());
+    // 停止输入同样受间隔限制，交替发送无法绕过
+    assert!(manager.throttle_typing("a", false).await.is_none());
+    assert!(manager.throttle_typing("a", true).await.is_none());
+    assert!(manager.throttle_typing("ghost", true).await.is_none());
+
+    manager.disconnect("a").await;
+    assert_eq!(ids(manager.room_presence("presence-room").await), ["b"]);
+    assert!(manager.throttle_typing("a", false).await.is_none());
+}
+
+#[tokio::test]
+async fn typing_stop_is_forwarded_only_after_a_forwarded_start() {
+    let manager = ConnectionManager::with_config(ConnectionManagerConfig {
+        typing_interval: Duration::ZERO,
+        ..Default::default()
+    });
+    join(&manager, "typing-room", presence("a", 10)).await;
+
+    assert!(manager.throttle_typing("a", false).await.is_none());
+    assert!(manager.throttle_typing("a", true).await.is_some());
+    assert!(manager.throttle_typing("a", false).await.is_some());
+    assert!(manager.throttle_typing("a", false).await.is_none());
+    assert!(manager.throttle_typing("a", true).await.is_some());
+}
+
+#[tokio::test]
+async fn typing_is_broadcast_without_being_logged_and_presence_can_be_queried() -> anyhow::Result<()>
+{
+    let state = setup_state().await?;
+    let manager = state.connection_manager.clone();
+    let handler = MessageHandler::new((*state).clone(), manager.clone());
+    let (tx, mut rx) = manager.outbound_channel();
+    manager
+        .subscribe_to_room("typist".to_string(), "typing-room".to_string(), tx)
+        .await
+        .unwrap();
+    let mut entry = presence("typist", Utc::now().timestamp());
+    entry.display_name = Some("Alice".to_string());
+    manager.set_presence("typing-room", entry.clone()).await;
+
+    handler
+        .handle_typing(
+            "typing-room",
+            "typist",
+            WsMessage::new(WsMessageType::Typing, Some(json!({ "is_typing": true }))),
+        )
+        .await;
+    // 间隔内重复的开始输入事件被丢弃
+    handler
+        .handle_typing(
+            "typing-room",
+            "typist",
+            WsMessage::new(WsMessageType::Typing, None),
+        )
+        .await;
+
+    let Some(Outbound::Message(typing)) =
+        tokio::time::timeout(Duration::from_secs(1), rx.recv()).await?
+    else {
+        panic!("expected a typing event");
+    };
+    assert_eq!(typing.message_type, WsMessageType::Typing);
+    assert_eq!(typing.event_id, None);
+    let payload = typing.payload.unwrap();
+    assert_eq!(payload["connection_id"], "typist");
+    assert_eq!(payload["display_name"], "Alice");
+    assert_eq!(payload["is_typing"], true);
+    assert!(rx.try_recv().is_none());
+
+    let logged: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM room_events")
+        .fetch_one(&*state.db_pool)
+        .await?;
+    assert_eq!(logged, 0, "typing events are never persisted");
+
+    let reply = handler
+        .handle_presence(
+            "typing-room",
+            WsMessage::new(
+                WsMessageType::Presence,
+                Some(json!({ "request_id": "who" })),
+            ),
+        )
+        .await;
+    assert_eq!(reply.message_type, WsMessageType::Presence);
+    let payload = reply.payload.unwrap();
+    assert_eq!(payload["request_id"], "who");
+    assert_eq!(payload["connections"], json!([entry]));
+    Ok(())
+}
+
+#[test]
+fn display_names_are_trimmed_and_bounded() {
+    let request = |display_name: Option<&str>| ConnectRequest {
+        token: String::new(),
+        room_name: String::new(),
+        last_event_id: None,
+        display_name: display_name.map(str::to_string),
+    };
+    assert_eq!(request(None).display_name(), None);
+    assert_eq!(request(Some("   ")).display_name(), None);
+    assert_eq!(
+        request(Some("  Alice  ")).display_name().as_deref(),
+        Some("Alice")
+    );
+    let long = "名".repeat(100);
+    assert_eq!(
+        request(Some(&long)).display_name().unwrap().chars().count(),
+        64
+    );
+}
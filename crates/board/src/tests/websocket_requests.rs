@@ -0,0 +1,256 @@
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::{Path, State};
+use chrono::{Duration, Utc};
+use serde_json::{Value, json};
+
+use crate::config::{AppConfig, OverflowPolicy};
+use crate::db::{DbPoolSettings, init_db, run_migrations};
+use crate::dto::rooms::IssueTokenRequest;
+use crate::handlers::rooms::tokens::issue_token;
+use crate::models::Room;
+use crate::models::permission::RoomPermission;
+use crate::repository::IRoomRepository;
+use crate::state::AppState;
+use crate::websocket::handler::MessageHandler;
+use crate::websocket::outbound::Outbound;
+use crate::websocket::types::{WsMessage, WsMessageType};
+
+async fn setup_state() -> anyhow::Result<Arc<AppState>> {
+    setup_state_with(AppConfig::for_development()).await
+}
+
+async fn setup_state_with(config: AppConfig) -> anyhow::Result<Arc<AppState>> {
+    let settings = DbPoolSettings::new("sqlite::memory:")
+        .with_max_connections(1)
+        .with_min_connections(1);
+    let pool = Arc::new(init_db(&settings).await?);
+    run_migrations(&pool, &settings.url).await?;
+    Ok(Arc::new(AppState::new(config, pool)?))
+}
+
+/// 创建房间并签发 token，返回房间 slug 与 token
+async fn room_with_token(
+    state: &Arc<AppState>,
+    name: &str,
+    permission: RoomPermission,
+) -> anyhow::Result<(String, String)> {
+    let mut room = Room::new(name.to_string(), None);
+    room.expire_at = Some(Utc::now().naive_utc() + Duration::days(7));
+    room.permission = permission;
+    let room = state.services.room_repository.create(&room).await?;
+    let Json(issued) = issue_token(
+        Path(room.slug.clone()),
+        State(state.clone()),
+        Json(IssueTokenRequest {
+            password: None,
+            token: None,
+            with_refresh_token: false,
+        }),
+    )
+    .await?;
+    Ok((room.slug, issued.token))
+}
+
+fn request(message_type: WsMessageType, payload: Value) -> WsMessage {
+    WsMessage::new(message_type, Some(payload))
+}
+
+fn payload(reply: &WsMessage) -> &Value {
+    reply.payload.as_ref().expect("reply has a payload")
+}
+
+#[tokio::test]
+async fn chat_messages_can_be_sent_edited_and_deleted_over_the_socket() -> anyhow::Result<()> {
+    let state = setup_state().await?;
+    let (room_name, token) =
+        room_with_token(&state, "ws-request-room", RoomPermission::new().with_all()).await?;
+    let handler = MessageHandler::new((*state).clone(), state.connection_manager.clone());
+    let (tx, mut rx) = state.connection_manager.outbound_channel();
+    state
+        .connection_manager
+        .subscribe_to_room("observer".to_string(), room_name.clone(), tx)
+        .await
+        .unwrap();
+
+    let reply = handler
+        .handle_request(
+            &room_name,
+            &token,
+            request(
+                WsMessageType::SendMessage,
+                json!({ "request_id": "send-1", "text": "  hello  " }),
+            ),
+        )
+        .await;
+    assert_eq!(reply.message_type, WsMessageType::Ack);
+    assert_eq!(payload(&reply)["request_id"], "send-1");
+    let message = &payload(&reply)["result"]["message"];
+    assert_eq!(message["text"], "hello");
+    let content_id = message["id"].as_i64().unwrap();
+
+    // 与 REST 接口一样广播给房间内的连接（签发 token 时的 USER_JOINED 可能先到）
+    let broadcast = loop {
+        let received = tokio::time::timeout(std::time::Duration::from_secs(1), rx.recv()).await?;
+        match received {
+            Some(Outbound::Message(message))
+                if message.message_type == WsMessageType::ContentCreated =>
+            {
+                break message;
+            }
+            Some(Outbound::Message(_)) => continue,
+            other => panic!("expected a content event, got {other:?}"),
+        }
+    };
+    assert_eq!(payload(&broadcast)["content_id"], content_id);
+
+    let reply = handler
+        .handle_request(
+            &room_name,
+            &token,
+            request(
+                WsMessageType::UpdateContent,
+                json!({ "request_id": "edit-1", "content_id": content_id, "text": "edited" }),
+            ),
+        )
+        .await;
+    assert_eq!(reply.message_type, WsMessageType::Ack);
+    assert_eq!(payload(&reply)["result"]["updated"]["text"], "edited");
+
+    let reply = handler
+        .handle_request(
+            &room_name,
+            &token,
+            request(
+                WsMessageType::DeleteContent,
+                json!({ "request_id": "delete-1", "ids": [content_id] }),
+            ),
+        )
+        .await;
+    assert_eq!(reply.message_type, WsMessageType::Ack);
+    assert_eq!(payload(&reply)["result"]["deleted"], json!([content_id]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn socket_requests_enforce_room_permissions_and_report_errors() -> anyhow::Result<()> {
+    let state = setup_state().await?;
+    let (room_name, token) =
+        room_with_token(&state, "ws-readonly-room", RoomPermission::new()).await?;
+    let handler = MessageHandler::new((*state).clone(), state.connection_manager.clone());
+
+    let reply = handler
+        .handle_request(
+            &room_name,
+            &token,
+            request(
+                WsMessageType::SendMessage,
+                json!({ "request_id": "send-1", "text": "hello" }),
+            ),
+        )
+        .await;
+    assert_eq!(reply.message_type, WsMessageType::Error);
+    assert_eq!(payload(&reply)["request_id"], "send-1");
+    assert_eq!(payload(&reply)["status"], 403);
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM room_contents")
+        .fetch_one(&*state.db_pool)
+        .await?;
+    assert_eq!(count, 0);
+
+    let reply = handler
+        .handle_request(
+            &room_name,
+            &token,
+            request(
+                WsMessageType::UpdateContent,
+                json!({ "request_id": "edit-1", "text": "missing content id" }),
+            ),
+        )
+        .await;
+    assert_eq!(payload(&reply)["request_id"], "edit-1");
+    assert_eq!(payload(&reply)["status"], 400);
+
+    let reply = handler
+        .handle_request(
+            &room_name,
+            "not-a-token",
+            request(
+                WsMessageType::DeleteContent,
+                json!({ "request_id": "delete-1", "ids": [1] }),
+            ),
+        )
+        .await;
+    assert_eq!(reply.message_type, WsMessageType::Error);
+    assert_eq!(payload(&reply)["request_id"], "delete-1");
+
+    let reply = handler
+        .handle_request(
+            &room_name,
+            &token,
+            request(WsMessageType::SendMessage, json!({ "text": "hello" })),
+        )
+        .await;
+    assert_eq!(reply.message_type, WsMessageType::Error);
+    assert!(payload(&reply).get("request_id").is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn replies_are_delivered_when_the_send_queue_is_full() -> anyhow::Result<()> {
+    let mut config = AppConfig::for_development();
+    config.websocket.send_queue_depth = 2;
+    config.websocket.overflow_policy = OverflowPolicy::DropOldest;
+    let state = setup_state_with(config).await?;
+    let (room_name, token) = room_with_token(
+        &state,
+        "ws-full-queue-room",
+        RoomPermission::new().with_all(),
+    )
+    .await?;
+    let handler = MessageHandler::new((*state).clone(), state.connection_manager.clone());
+    let manager = &state.connection_manager;
+    let (tx, mut rx) = manager.outbound_channel();
+    manager
+        .subscribe_to_room("slow-client".to_string(), room_name.clone(), tx)
+        .await
+        .unwrap();
+
+    let room_update = || WsMessage::new(WsMessageType::RoomUpdate, None);
+    for _ in 0..3 {
+        manager
+            .broadcast_to_room(&room_name, room_update())
+            .await
+            .unwrap();
+    }
+    let reply = handler
+        .handle_request(
+            &room_name,
+            &token,
+            request(
+                WsMessageType::SendMessage,
+                json!({ "request_id": "send-1", "text": "hello" }),
+            ),
+        )
+        .await;
+    assert_eq!(reply.message_type, WsMessageType::Ack);
+    assert!(manager.send_to_connection("slow-client", reply).await);
+    // 回复入队后继续溢出，广播被丢弃但回复保留
+    for _ in 0..3 {
+        manager
+            .broadcast_to_room(&room_name, room_update())
+            .await
+            .unwrap();
+    }
+
+    let mut acks = Vec::new();
+    while let Some(outbound) = rx.try_recv() {
+        if let Outbound::Message(message) = outbound
+            && message.message_type == WsMessageType::Ack
+        {
+            acks.push(payload(&message)["request_id"].clone());
+        }
+    }
+    assert_eq!(acks, vec![json!("send-1")]);
+    Ok(())
+}
@@ -1,22 +1,68 @@
 //! 房间事件广播器
 //!
-//! 向房间内所有订阅者广播事件
+//! 向房间内所有订阅者广播事件，事件经由 [`EventBus`] 送达各实例
 
 use crate::models::content::RoomContent;
+use crate::repository::IRoomRepository;
 use crate::websocket::connection::ConnectionManager;
-use crate::websocket::types::{RoomInfo, RoomUpdateReason, WsMessage, WsMessageType};
+use crate::websocket::event_bus::{EventBus, InProcessEventBus};
+use crate::websocket::event_log::RoomEventLog;
+use crate::websocket::types::{
+    PresenceEntry, RoomInfo, RoomUpdateReason, WsMessage, WsMessageType,
+};
 use serde_json::json;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 use std::sync::Arc;
 
 /// 房间事件广播器
 pub struct Broadcaster {
-    manager: Arc<ConnectionManager>,
+    bus: Arc<dyn EventBus>,
+    event_log: Option<Arc<RoomEventLog>>,
 }
 
 impl Broadcaster {
-    /// 创建新的广播器
+    /// 创建只在本进程内广播的广播器
     pub fn new(manager: Arc<ConnectionManager>) -> Self {
-        Self { manager }
+        Self::with_event_bus(Arc::new(InProcessEventBus::new(manager)))
+    }
+
+    /// 使用指定的事件总线创建广播器
+    pub fn with_event_bus(bus: Arc<dyn EventBus>) -> Self {
+        Self {
+            bus,
+            event_log: None,
+        }
+    }
+
+    /// 记录房间事件，供断线重连的客户端补发
+    pub fn with_event_log(mut self, event_log: Arc<RoomEventLog>) -> Self {
+        self.event_log = Some(event_log);
+        self
+    }
+
+    /// 获取事件总线
+    pub fn event_bus(&self) -> &Arc<dyn EventBus> {
+        &self.bus
+    }
+
+    /// 获取事件日志，未启用时为 `None`
+    pub fn event_log(&self) -> Option<&Arc<RoomEventLog>> {
+        self.event_log.as_ref()
+    }
+
+    /// 记录事件后发布；记录失败时仍然发布，只是该事件没有事件 ID
+    async fn publish(
+        &self,
+        room_name: &str,
+        mut message: WsMessage,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        if let Some(event_log) = &self.event_log
+            && let Err(error) = event_log.record(room_name, &mut message).await
+        {
+            log::warn!("Failed to record room event for {}: {:#}", room_name, error);
+        }
+        self.bus.publish(room_name, message).await
     }
 
     /// 广播内容创建事件
@@ -38,7 +84,7 @@ impl Broadcaster {
 
         let message = WsMessage::new(WsMessageType::ContentCreated, Some(payload));
 
-        self.manager.broadcast_to_room(room_name, message).await
+        self.publish(room_name, message).await
     }
 
     /// 广播内容更新事件
@@ -60,7 +106,7 @@ impl Broadcaster {
 
         let message = WsMessage::new(WsMessageType::ContentUpdated, Some(payload));
 
-        self.manager.broadcast_to_room(room_name, message).await
+        self.publish(room_name, message).await
     }
 
     /// 广播内容删除事件
@@ -82,7 +128,35 @@ impl Broadcaster {
 
         let message = WsMessage::new(WsMessageType::ContentDeleted, Some(payload));
 
-        self.manager.broadcast_to_room(room_name, message).await
+        self.publish(room_name, message).await
+    }
+
+    /// 逐条广播后台任务更新过的内容，同一房间只查询一次地址，房间已被删除的内容跳过
+    ///
+    /// 单条广播失败只记录日志，查询房间失败时返回错误。
+    pub async fn broadcast_contents_updated(
+        &self,
+        rooms: &dyn IRoomRepository,
+        contents: &[RoomContent],
+    ) -> anyhow::Result<()> {
+        let mut slugs = HashMap::new();
+        for content in contents {
+            if let Entry::Vacant(entry) = slugs.entry(content.room_id) {
+                let room = rooms.find_by_id(content.room_id).await?;
+                entry.insert(room.map(|room| room.slug));
+            }
+            let Some(Some(slug)) = slugs.get(&content.room_id) else {
+                continue;
+            };
+            if let Err(e) = self.broadcast_content_updated(slug, content).await {
+                log::warn!(
+                    "Failed to broadcast update of content {:?}: {}",
+                    content.id,
+                    e
+                );
+            }
+        }
+        Ok(())
     }
 
     /// 广播用户加入事件
@@ -98,7 +172,8 @@ impl Broadcaster {
 
         let message = WsMessage::new(WsMessageType::UserJoined, Some(payload));
 
-        self.manager.broadcast_to_room(room_name, message).await
+        // 在线状态不补发
+        self.bus.publish(room_name, message).await
     }
 
     /// 广播用户离开事件
@@ -114,7 +189,54 @@ impl Broadcaster {
 
         let message = WsMessage::new(WsMessageType::UserLeft, Some(payload));
 
-        self.manager.broadcast_to_room(room_name, message).await
+        // 在线状态不补发
+        self.bus.publish(room_name, message).await
+    }
+
+    /// 广播连接上线事件，携带连接 ID 和显示名称，供客户端维护在线列表
+    pub async fn broadcast_connection_joined(
+        &self,
+        room_name: &str,
+        presence: &PresenceEntry,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let message = WsMessage::new(
+            WsMessageType::UserJoined,
+            Some(presence_payload(room_name, presence)),
+        );
+
+        // 在线状态不补发
+        self.bus.publish(room_name, message).await
+    }
+
+    /// 广播连接下线事件
+    pub async fn broadcast_connection_left(
+        &self,
+        room_name: &str,
+        presence: &PresenceEntry,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let message = WsMessage::new(
+            WsMessageType::UserLeft,
+            Some(presence_payload(room_name, presence)),
+        );
+
+        // 在线状态不补发
+        self.bus.publish(room_name, message).await
+    }
+
+    /// 广播“正在输入”状态
+    pub async fn broadcast_typing(
+        &self,
+        room_name: &str,
+        presence: &PresenceEntry,
+        is_typing: bool,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut payload = presence_payload(room_name, presence);
+        payload["is_typing"] = json!(is_typing);
+
+        let message = WsMessage::new(WsMessageType::Typing, Some(payload));
+
+        // 输入状态是瞬时的，不记录也不补发
+        self.bus.publish(room_name, message).await
     }
 
     /// 广播房间更新事件
@@ -132,6 +254,34 @@ impl Broadcaster {
 
         let message = WsMessage::new(WsMessageType::RoomUpdate, Some(payload));
 
-        self.manager.broadcast_to_room(room_name, message).await
+        self.publish(room_name, message).await
+    }
+
+    /// 通知旧地址上的连接房间地址已变更
+    ///
+    /// 事件日志已随房间迁移到新地址，旧地址不再记录事件。
+    pub async fn broadcast_address_changed(
+        &self,
+        old_room_name: &str,
+        room_info: &RoomInfo,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let payload = json!({
+            "room_name": old_room_name,
+            "room_info": room_info,
+            "reason": RoomUpdateReason::AddressChanged,
+        });
+
+        let message = WsMessage::new(WsMessageType::RoomUpdate, Some(payload));
+
+        self.bus.publish(old_room_name, message).await
     }
 }
+
+fn presence_payload(room_name: &str, presence: &PresenceEntry) -> serde_json::Value {
+    json!({
+        "user_id": presence.user_id,
+        "room_name": room_name,
+        "connection_id": presence.connection_id,
+        "display_name": presence.display_name,
+    })
+}
@@ -2,9 +2,13 @@
 //!
 //! 管理 WebSocket 连接和房间订阅关系，支持性能优化和资源限制
 
-use crate::websocket::types::{WsMessage, WsMessageType};
+use crate::config::OverflowPolicy;
+use crate::constants::websocket::{DEFAULT_SEND_QUEUE_DEPTH, TYPING_EVENT_INTERVAL_MS};
+use crate::websocket::outbound::{self, Delivery, OutboundReceiver, OutboundSender};
+use crate::websocket::types::{PresenceEntry, WsMessage, WsMessageType};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
 /// 连接管理器配置
@@ -16,6 +20,12 @@ pub struct ConnectionManagerConfig {
     pub max_global_connections: usize,
     /// 是否启用连接统计
     pub enable_metrics: bool,
+    /// 每个连接的发送队列长度
+    pub send_queue_depth: usize,
+    /// 发送队列已满时的处理方式
+    pub overflow_policy: OverflowPolicy,
+    /// 同一连接转发“正在输入”事件的最小间隔
+    pub typing_interval: Duration,
 }
 
 impl Default for ConnectionManagerConfig {
@@ -24,6 +34,9 @@ impl Default for ConnectionManagerConfig {
             max_connections_per_room: 100,
             max_global_connections: 1000,
             enable_metrics: true,
+            send_queue_depth: DEFAULT_SEND_QUEUE_DEPTH as usize,
+            overflow_policy: OverflowPolicy::Disconnect,
+            typing_interval: Duration::from_millis(TYPING_EVENT_INTERVAL_MS),
         }
     }
 }
@@ -39,6 +52,20 @@ pub struct ConnectionMetrics {
     pub total_connections: usize,
     /// 累计断开连接数
     pub total_disconnections: usize,
+    /// 因发送队列已满被丢弃或合并的消息数
+    pub dropped_messages: usize,
+    /// 因消费过慢被强制断开的连接数
+    pub forced_disconnects: usize,
+}
+
+/// 连接的在线信息
+struct Presence {
+    room_name: String,
+    entry: PresenceEntry,
+    /// 上一次转发的输入状态
+    typing: bool,
+    /// 上一次转发输入状态事件的时间
+    last_typing: Option<Instant>,
 }
 
 /// 连接管理器
@@ -48,7 +75,9 @@ pub struct ConnectionManager {
     /// 房间订阅关系：room_name -> connection_ids
     room_subscribers: RwLock<HashMap<String, Vec<String>>>,
     /// 活跃连接：connection_id -> (room_name, sender)
-    connections: RwLock<HashMap<String, (String, tokio::sync::mpsc::UnboundedSender<WsMessage>)>>,
+    connections: RwLock<HashMap<String, (String, OutboundSender)>>,
+    /// 在线信息：connection_id -> presence，只包含本实例上的连接
+    presence: RwLock<HashMap<String, Presence>>,
     /// 连接统计
     metrics: RwLock<ConnectionMetrics>,
 }
@@ -71,16 +100,22 @@ impl ConnectionManager {
             config,
             room_subscribers: RwLock::new(HashMap::new()),
             connections: RwLock::new(HashMap::new()),
+            presence: RwLock::new(HashMap::new()),
             metrics: RwLock::new(ConnectionMetrics::default()),
         }
     }
 
+    /// 按配置的队列长度和溢出策略创建连接的发送队列
+    pub fn outbound_channel(&self) -> (OutboundSender, OutboundReceiver) {
+        outbound::channel(self.config.send_queue_depth, self.config.overflow_policy)
+    }
+
     /// 订阅房间
     pub async fn subscribe_to_room(
         &self,
         connection_id: String,
         room_name: String,
-        sender: tokio::sync::mpsc::UnboundedSender<WsMessage>,
+        sender: OutboundSender,
     ) -> Result<(), Box<dyn std::error::Error>> {
         // 检查全局连接数限制
         {
@@ -137,26 +172,51 @@ impl ConnectionManager {
         if let Some(connection_ids) = subscribers.get(room_name) {
             let connections = self.connections.read().await;
             let mut count = 0;
+            let mut dropped = 0;
             let mut failed_ids = Vec::new();
+            let mut slow_ids = Vec::new();
 
             for connection_id in connection_ids {
                 if let Some((_, sender)) = connections.get(connection_id) {
-                    if sender.send(message.clone()).is_ok() {
-                        count += 1;
-                    } else {
+                    match sender.send(message.clone()) {
+                        Ok(Delivery::Queued) => count += 1,
+                        Ok(Delivery::Coalesced | Delivery::DroppedOldest) => {
+                            count += 1;
+                            dropped += 1;
+                        }
+                        // 发送任务会以关闭码结束连接
+                        Ok(Delivery::Disconnected) => slow_ids.push(connection_id.clone()),
                         // 记录发送失败的连接 ID
-                        failed_ids.push(connection_id.clone());
+                        Err(_) => failed_ids.push(connection_id.clone()),
                     }
                 }
             }
+            drop(connections);
+            drop(subscribers);
 
-            // 清理失败的连接
-            if !failed_ids.is_empty() {
-                drop(connections);
-                drop(subscribers);
-                for failed_id in failed_ids {
-                    self.disconnect(&failed_id).await;
-                }
+            if dropped > 0 || !slow_ids.is_empty() {
+                let mut metrics = self.metrics.write().await;
+                metrics.dropped_messages += dropped;
+                metrics.forced_disconnects += slow_ids.len();
+            }
+            if dropped > 0 {
+                log::debug!(
+                    "Dropped {} queued messages for slow consumers in room {}",
+                    dropped,
+                    room_name
+                );
+            }
+            for slow_id in &slow_ids {
+                log::warn!(
+                    "Disconnecting slow consumer {} in room {}: send queue is full",
+                    slow_id,
+                    room_name
+                );
+            }
+
+            // 清理失败和被断开的连接
+            for connection_id in failed_ids.iter().chain(&slow_ids) {
+                self.disconnect(connection_id).await;
             }
 
             Ok(count)
@@ -165,11 +225,99 @@ impl ConnectionManager {
         }
     }
 
+    /// 向单个连接发送对其请求的回复（ACK/ERROR），连接不存在或已被关闭时返回 `false`
+    ///
+    /// 回复不受溢出策略影响，客户端一直不读取回复时断开连接。
+    pub async fn send_to_connection(&self, connection_id: &str, message: WsMessage) -> bool {
+        let delivery = {
+            let connections = self.connections.read().await;
+            match connections.get(connection_id) {
+                Some((_, sender)) => sender.reply(message),
+                None => return false,
+            }
+        };
+        match delivery {
+            Ok(Delivery::Disconnected) => {
+                self.metrics.write().await.forced_disconnects += 1;
+                log::warn!(
+                    "Disconnecting slow consumer {}: too many unread replies",
+                    connection_id
+                );
+                self.disconnect(connection_id).await;
+                false
+            }
+            Ok(_) => true,
+            Err(_) => {
+                self.disconnect(connection_id).await;
+                false
+            }
+        }
+    }
+
+    /// 记录已订阅连接的在线信息
+    pub async fn set_presence(&self, room_name: &str, entry: PresenceEntry) {
+        // 持有连接表的读锁，避免与并发的断开交错留下残留
+        let connections = self.connections.read().await;
+        if !connections.contains_key(&entry.connection_id) {
+            return;
+        }
+        self.presence.write().await.insert(
+            entry.connection_id.clone(),
+            Presence {
+                room_name: room_name.to_string(),
+                entry,
+                typing: false,
+                last_typing: None,
+            },
+        );
+    }
+
+    /// 获取房间的在线列表，按连接时间排序
+    pub async fn room_presence(&self, room_name: &str) -> Vec<PresenceEntry> {
+        let presence = self.presence.read().await;
+        let mut entries: Vec<PresenceEntry> = presence
+            .values()
+            .filter(|presence| presence.room_name == room_name)
+            .map(|presence| presence.entry.clone())
+            .collect();
+        entries.sort_by(|a, b| {
+            (a.connected_at, &a.connection_id).cmp(&(b.connected_at, &b.connection_id))
+        });
+        entries
+    }
+
+    /// 判断是否转发连接的“正在输入”事件，返回连接的在线信息
+    ///
+    /// 每个连接在间隔内最多转发一次输入状态，开始与停止共用同一间隔；
+    /// 停止输入仅在上一次转发的是开始输入时转发。被丢弃的停止事件由客户端的过期计时兜底。
+    pub async fn throttle_typing(
+        &self,
+        connection_id: &str,
+        is_typing: bool,
+    ) -> Option<PresenceEntry> {
+        let mut presence = self.presence.write().await;
+        let presence = presence.get_mut(connection_id)?;
+        if !is_typing && !presence.typing {
+            return None;
+        }
+        let now = Instant::now();
+        if presence
+            .last_typing
+            .is_some_and(|last| now.duration_since(last) < self.config.typing_interval)
+        {
+            return None;
+        }
+        presence.typing = is_typing;
+        presence.last_typing = Some(now);
+        Some(presence.entry.clone())
+    }
+
     /// 断开连接
     pub async fn disconnect(&self, connection_id: &str) {
         let mut subscribers = self.room_subscribers.write().await;
         let mut connections = self.connections.write().await;
         let mut metrics = self.metrics.write().await;
+        self.presence.write().await.remove(connection_id);
 
         // 移除连接
         if let Some((room_name, _)) = connections.remove(connection_id) {
@@ -255,6 +403,7 @@ impl ConnectionManager {
         let count = connections.len();
         connections.clear();
         subscribers.clear();
+        self.presence.write().await.clear();
 
         metrics.active_connections = 0;
         metrics.active_rooms = 0;
@@ -0,0 +1,467 @@
+//! 房间事件总线
+//!
+//! [`Broadcaster`](crate::websocket::broadcaster::Broadcaster) 通过事件总线发布房间事件。
+//! 进程内总线只投递给本实例的连接；PostgreSQL 总线在本地投递之外通过 NOTIFY 转发，
+//! 各实例 LISTEN 同一通道后投递给自己的连接，多副本部署时事件可以送达所有客户端。
+//! 在线列表同样经由总线维护：PostgreSQL 总线把各实例的连接登记在 `websocket_presence`
+//! 表中并定期续期，任一实例都能查询房间的完整在线列表。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgListener, PgPool, PgPoolOptions};
+use tokio_util::sync::CancellationToken;
+
+use crate::websocket::connection::ConnectionManager;
+use crate::websocket::types::{PresenceEntry, WsMessage};
+
+/// NOTIFY 载荷的上限（PostgreSQL 要求短于 8000 字节）
+const MAX_NOTIFY_PAYLOAD_BYTES: usize = 8000;
+/// 发布与监听共用的连接数
+const MAX_BUS_CONNECTIONS: u32 = 4;
+/// 监听连接断开后的重试间隔
+const LISTEN_RETRY_MIN: Duration = Duration::from_secs(1);
+const LISTEN_RETRY_MAX: Duration = Duration::from_secs(30);
+/// 续期本实例在线信息的间隔
+const PRESENCE_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+/// 超过该时长未续期的在线信息视为所属实例已退出
+const PRESENCE_EXPIRY: Duration = Duration::from_secs(45);
+
+/// 房间事件总线
+#[async_trait]
+pub trait EventBus: Send + Sync {
+    /// 发布房间事件，返回本实例内送达的连接数
+    async fn publish(
+        &self,
+        room_name: &str,
+        message: WsMessage,
+    ) -> Result<usize, Box<dyn std::error::Error>>;
+
+    /// 持续接收其他实例发布的事件并投递给本地连接，直到 `cancellation` 被取消
+    ///
+    /// 进程内总线没有其他来源，直接返回。
+    async fn run(&self, cancellation: CancellationToken) {
+        let _ = cancellation;
+    }
+
+    /// 登记本实例上连接的在线信息
+    async fn register_presence(&self, room_name: &str, entry: &PresenceEntry) {
+        let _ = (room_name, entry);
+    }
+
+    /// 移除连接的在线信息
+    async fn unregister_presence(&self, connection_id: &str) {
+        let _ = connection_id;
+    }
+
+    /// 房间的在线列表，按连接时间排序；不维护在线信息的总线返回空列表
+    async fn room_presence(&self, room_name: &str) -> Vec<PresenceEntry> {
+        let _ = room_name;
+        Vec::new()
+    }
+}
+
+/// 进程内事件总线，单实例部署时使用
+pub struct InProcessEventBus {
+    manager: Arc<ConnectionManager>,
+}
+
+impl InProcessEventBus {
+    pub fn new(manager: Arc<ConnectionManager>) -> Self {
+        Self { manager }
+    }
+}
+
+#[async_trait]
+impl EventBus for InProcessEventBus {
+    async fn publish(
+        &self,
+        room_name: &str,
+        message: WsMessage,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        self.manager.broadcast_to_room(room_name, message).await
+    }
+
+    async fn room_presence(&self, room_name: &str) -> Vec<PresenceEntry> {
+        self.manager.room_presence(room_name).await
+    }
+}
+
+/// 在实例之间转发的事件
+#[derive(Debug, Serialize, Deserialize)]
+struct RemoteEvent {
+    /// 发布事件的实例，用于跳过自己发出的通知
+    origin: String,
+    room_name: String,
+    message: WsMessage,
+}
+
+/// 基于 PostgreSQL LISTEN/NOTIFY 的事件总线
+///
+/// 事件先投递给本实例的连接，再通过 NOTIFY 转发；监听到的通知来自本实例时跳过，
+/// 因此监听连接中断不影响本实例的客户端。监听断开期间其他实例发布的事件会丢失，
+/// 客户端重连后通过重新拉取内容列表恢复。
+pub struct PgEventBus {
+    manager: Arc<ConnectionManager>,
+    pool: PgPool,
+    channel: String,
+    instance_id: String,
+}
+
+impl PgEventBus {
+    /// 创建事件总线，连接在首次使用时建立
+    pub fn new(
+        manager: Arc<ConnectionManager>,
+        database_url: &str,
+        channel: &str,
+    ) -> Result<Self, sqlx::Error> {
+        let pool = PgPoolOptions::new()
+            .max_connections(MAX_BUS_CONNECTIONS)
+            .connect_lazy(database_url)?;
+        Ok(Self {
+            manager,
+            pool,
+            channel: channel.to_string(),
+            instance_id: uuid::Uuid::new_v4().to_string(),
+        })
+    }
+
+    /// 编码转发给其他实例的通知
+    ///
+    /// 超出 NOTIFY 上限时去掉正文（客户端收到事件后会重新拉取内容），仍然超出则不转发。
+    fn encode(&self, room_name: &str, mut message: WsMessage) -> Option<String> {
+        let mut event = RemoteEvent {
+            origin: self.instance_id.clone(),
+            room_name: room_name.to_string(),
+            message: message.clone(),
+        };
+        let payload = serde_json::to_string(&event).ok()?;
+        if payload.len() < MAX_NOTIFY_PAYLOAD_BYTES {
+            return Some(payload);
+        }
+
+        if let Some(text) = message
+            .payload
+            .as_mut()
+            .and_then(|payload| payload.get_mut("text"))
+        {
+            *text = serde_json::Value::Null;
+        }
+        event.message = message;
+        let payload = serde_json::to_string(&event).ok()?;
+        (payload.len() < MAX_NOTIFY_PAYLOAD_BYTES).then_some(payload)
+    }
+
+    /// 把其他实例发布的事件投递给本地连接
+    async fn deliver(&self, payload: &str) {
+        let event: RemoteEvent = match serde_json::from_str(payload) {
+            Ok(event) => event,
+            Err(error) => {
+                log::warn!("Ignoring malformed room event notification: {error}");
+                return;
+            }
+        };
+        if event.origin == self.instance_id {
+            return;
+        }
+        if let Err(error) = self
+            .manager
+            .broadcast_to_room(&event.room_name, event.message)
+            .await
+        {
+            log::warn!(
+                "Failed to deliver room event to {}: {}",
+                event.room_name,
+                error
+            );
+        }
+    }
+
+    async fn try_register_presence(
+        &self,
+        room_name: &str,
+        entry: &PresenceEntry,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO websocket_presence
+                (connection_id, instance_id, room_name, user_id, display_name, connected_at, heartbeat_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (connection_id) DO UPDATE SET
+                instance_id = excluded.instance_id,
+                room_name = excluded.room_name,
+                user_id = excluded.user_id,
+                display_name = excluded.display_name,
+                heartbeat_at = excluded.heartbeat_at
+            "#,
+        )
+        .bind(&entry.connection_id)
+        .bind(&self.instance_id)
+        .bind(room_name)
+        .bind(&entry.user_id)
+        .bind(&entry.display_name)
+        .bind(entry.connected_at)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn try_room_presence(&self, room_name: &str) -> Result<Vec<PresenceEntry>, sqlx::Error> {
+        let rows: Vec<(String, String, Option<String>, i64)> = sqlx::query_as(
+            r#"
+            SELECT connection_id, user_id, display_name, connected_at
+            FROM websocket_presence
+            WHERE room_name = $1 AND heartbeat_at >= $2
+            ORDER BY connected_at, connection_id
+            "#,
+        )
+        .bind(room_name)
+        .bind(presence_cutoff())
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(
+                |(connection_id, user_id, display_name, connected_at)| PresenceEntry {
+                    connection_id,
+                    user_id,
+                    display_name,
+                    connected_at,
+                },
+            )
+            .collect())
+    }
+
+    /// 续期本实例的在线信息，并清理已退出实例遗留的记录
+    async fn heartbeat(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE websocket_presence SET heartbeat_at = $1 WHERE instance_id = $2")
+            .bind(chrono::Utc::now().timestamp())
+            .bind(&self.instance_id)
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("DELETE FROM websocket_presence WHERE heartbeat_at < $1")
+            .bind(presence_cutoff())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// 定期续期在线信息，直到被取消
+    async fn run_heartbeat(&self, cancellation: &CancellationToken) {
+        let mut interval = tokio::time::interval(PRESENCE_HEARTBEAT_INTERVAL);
+        loop {
+            tokio::select! {
+                () = cancellation.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+            if let Err(error) = self.heartbeat().await {
+                log::warn!("Failed to renew websocket presence: {error}");
+            }
+        }
+        // 退出时移除本实例的在线信息，其他实例无需等待过期
+        if let Err(error) = sqlx::query("DELETE FROM websocket_presence WHERE instance_id = $1")
+            .bind(&self.instance_id)
+            .execute(&self.pool)
+            .await
+        {
+            log::warn!("Failed to clear websocket presence on shutdown: {error}");
+        }
+    }
+
+    async fn listen(&self) -> Result<PgListener, sqlx::Error> {
+        let mut listener = PgListener::connect_with(&self.pool).await?;
+        listener.listen(&self.channel).await?;
+        log::info!("Listening for room events on channel {}", self.channel);
+        Ok(listener)
+    }
+
+    /// 监听直到连接出错或被取消，被取消时返回 `true`
+    async fn receive(&self, listener: &mut PgListener, cancellation: &CancellationToken) -> bool {
+        loop {
+            let notification = tokio::select! {
+                () = cancellation.cancelled() => return true,
+                notification = listener.try_recv() => notification,
+            };
+            match notification {
+                Ok(Some(notification)) => self.deliver(notification.payload()).await,
+                // 下一次接收时自动重连
+                Ok(None) => log::warn!(
+                    "Room event listener lost its connection; events from other instances may be missed"
+                ),
+                Err(error) => {
+                    log::warn!("Room event listener failed: {error}");
+                    return false;
+                }
+            }
+        }
+    }
+
+    /// 监听其他实例的通知，连接断开后按指数退避重连，直到被取消
+    async fn run_listener(&self, cancellation: &CancellationToken) {
+        let mut retry = LISTEN_RETRY_MIN;
+        loop {
+            let listener = tokio::select! {
+                () = cancellation.cancelled() => return,
+                listener = self.listen() => listener,
+            };
+            match listener {
+                Ok(mut listener) => {
+                    retry = LISTEN_RETRY_MIN;
+                    if self.receive(&mut listener, cancellation).await {
+                        return;
+                    }
+                }
+                Err(error) => {
+                    log::warn!(
+                        "Failed to listen on channel {}: {}; retrying in {:?}",
+                        self.channel,
+                        error,
+                        retry
+                    );
+                }
+            }
+            tokio::select! {
+                () = cancellation.cancelled() => return,
+                () = tokio::time::sleep(retry) => {}
+            }
+            retry = (retry * 2).min(LISTEN_RETRY_MAX);
+        }
+    }
+}
+
+#[async_trait]
+impl EventBus for PgEventBus {
+    async fn publish(
+        &self,
+        room_name: &str,
+        message: WsMessage,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let delivered = self
+            .manager
+            .broadcast_to_room(room_name, message.clone())
+            .await?;
+
+        let Some(payload) = self.encode(room_name, message) else {
+            log::warn!(
+                "Room event for {} exceeds the NOTIFY payload limit; other instances will not receive it",
+                room_name
+            );
+            return Ok(delivered);
+        };
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(&self.channel)
+            .bind(payload)
+            .execute(&self.pool)
+            .await?;
+        Ok(delivered)
+    }
+
+    async fn run(&self, cancellation: CancellationToken) {
+        tokio::join!(
+            self.run_listener(&cancellation),
+            self.run_heartbeat(&cancellation)
+        );
+    }
+
+    async fn register_presence(&self, room_name: &str, entry: &PresenceEntry) {
+        if let Err(error) = self.try_register_presence(room_name, entry).await {
+            log::warn!(
+                "Failed to register presence of connection {}: {error}",
+                entry.connection_id
+            );
+        }
+    }
+
+    async fn unregister_presence(&self, connection_id: &str) {
+        if let Err(error) = sqlx::query("DELETE FROM websocket_presence WHERE connection_id = $1")
+            .bind(connection_id)
+            .execute(&self.pool)
+            .await
+        {
+            log::warn!("Failed to remove presence of connection {connection_id}: {error}");
+        }
+    }
+
+    /// 查询失败时退回本实例的在线列表
+    async fn room_presence(&self, room_name: &str) -> Vec<PresenceEntry> {
+        match self.try_room_presence(room_name).await {
+            Ok(entries) => entries,
+            Err(error) => {
+                log::warn!("Failed to load presence of room {room_name}: {error}");
+                self.manager.room_presence(room_name).await
+            }
+        }
+    }
+}
+
+/// 在线信息的有效期起点（Unix 秒）
+fn presence_cutoff() -> i64 {
+    chrono::Utc::now().timestamp() - PRESENCE_EXPIRY.as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket::outbound::{Outbound, OutboundReceiver};
+    use crate::websocket::types::WsMessageType;
+
+    fn bus(manager: Arc<ConnectionManager>) -> PgEventBus {
+        PgEventBus::new(manager, "postgres://localhost/elizabeth", "room_events").unwrap()
+    }
+
+    async fn subscribe(manager: &ConnectionManager, room_name: &str) -> OutboundReceiver {
+        let (tx, rx) = manager.outbound_channel();
+        manager
+            .subscribe_to_room("conn-1".to_string(), room_name.to_string(), tx)
+            .await
+            .unwrap();
+        rx
+    }
+
+    #[tokio::test]
+    async fn delivers_events_from_other_instances_only() {
+        let manager = Arc::new(ConnectionManager::new());
+        let mut rx = subscribe(&manager, "room").await;
+        let local = bus(manager.clone());
+        let remote = bus(Arc::new(ConnectionManager::new()));
+        let message = WsMessage::new(WsMessageType::ContentCreated, None);
+
+        let own = local.encode("room", message.clone()).unwrap();
+        local.deliver(&own).await;
+        assert!(rx.try_recv().is_none());
+
+        let foreign = remote.encode("room", message).unwrap();
+        local.deliver(&foreign).await;
+        let Some(Outbound::Message(received)) = rx.try_recv() else {
+            panic!("expected a room event");
+        };
+        assert_eq!(received.message_type, WsMessageType::ContentCreated);
+
+        local.deliver("not json").await;
+        assert!(rx.try_recv().is_none());
+    }
+
+    #[tokio::test]
+    async fn oversized_events_drop_the_text() {
+        let local = bus(Arc::new(ConnectionManager::new()));
+        let text = "x".repeat(MAX_NOTIFY_PAYLOAD_BYTES);
+        let message = WsMessage::new(
+            WsMessageType::ContentCreated,
+            Some(serde_json::json!({ "content_id": 1, "text": text })),
+        );
+        let payload = local.encode("room", message).unwrap();
+        let event: RemoteEvent = serde_json::from_str(&payload).unwrap();
+        let payload = event.message.payload.unwrap();
+        assert_eq!(payload["content_id"], 1);
+        assert!(payload["text"].is_null());
+
+        let message = WsMessage::new(
+            WsMessageType::RoomUpdate,
+            Some(serde_json::json!({ "room_info": text })),
+        );
+        assert!(local.encode("room", message).is_none());
+    }
+}
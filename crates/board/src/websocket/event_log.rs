@@ -0,0 +1,81 @@
+//! 房间事件日志
+//!
+//! 为房间事件分配递增的事件 ID 并保留每个房间最近的若干条，客户端断线重连时
+//! 携带 `last_event_id`，服务端补发之后的事件；需要的事件已被淘汰时要求客户端重新同步。
+
+use anyhow::{Context, Result};
+
+use crate::repository::RoomEventRepository;
+use crate::websocket::types::WsMessage;
+
+/// 断线期间事件的补发结果
+#[derive(Debug)]
+pub enum Replay {
+    /// 需要补发的事件，按事件 ID 升序
+    Events(Vec<WsMessage>),
+    /// 缺失的事件已不在日志中
+    ResyncRequired,
+}
+
+/// 房间事件日志
+pub struct RoomEventLog {
+    repository: RoomEventRepository,
+    /// 每个房间保留的事件数
+    capacity: u32,
+}
+
+impl RoomEventLog {
+    pub fn new(repository: RoomEventRepository, capacity: u32) -> Self {
+        Self {
+            repository,
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// 记录事件并写入分配的事件 ID，同时淘汰超出容量的旧事件
+    pub async fn record(&self, room_name: &str, message: &mut WsMessage) -> Result<i64> {
+        message.event_id = None;
+        let serialized = serde_json::to_string(message).context("failed to encode room event")?;
+        let event_id = self.repository.append(room_name, &serialized).await?;
+        message.event_id = Some(event_id);
+
+        let expired = event_id - i64::from(self.capacity);
+        if expired > 0 {
+            self.repository.prune(room_name, expired).await?;
+        }
+        Ok(event_id)
+    }
+
+    /// 房间最新的事件 ID
+    pub async fn latest_event_id(&self, room_name: &str) -> Result<Option<i64>> {
+        self.repository.latest_event_id(room_name).await
+    }
+
+    /// 读取 `last_event_id` 之后的事件
+    ///
+    /// 日志中的事件从 `last_event_id + 1` 开始连续时才能补发；客户端的 ID 比日志中
+    /// 最新的还大（如房间被删除后重建）时同样要求重新同步。
+    pub async fn replay(&self, room_name: &str, last_event_id: i64) -> Result<Replay> {
+        let stored = self.repository.list_after(room_name, last_event_id).await?;
+        let Some((first_event_id, _)) = stored.first() else {
+            let latest = self.latest_event_id(room_name).await?.unwrap_or(0);
+            return Ok(if latest == last_event_id {
+                Replay::Events(Vec::new())
+            } else {
+                Replay::ResyncRequired
+            });
+        };
+        if *first_event_id != last_event_id + 1 {
+            return Ok(Replay::ResyncRequired);
+        }
+
+        let mut events = Vec::with_capacity(stored.len());
+        for (event_id, serialized) in stored {
+            let mut message: WsMessage = serde_json::from_str(&serialized)
+                .with_context(|| format!("failed to decode room event {event_id}"))?;
+            message.event_id = Some(event_id);
+            events.push(message);
+        }
+        Ok(Replay::Events(events))
+    }
+}
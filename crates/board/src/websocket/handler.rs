@@ -2,24 +2,41 @@
 //!
 //! 处理 WebSocket 消息和认证
 
-use crate::handlers::verify_room_token;
+use crate::errors::AppError;
+use crate::handlers::{
+    AuthToken, create_message, delete_contents, update_content, verify_room_token,
+};
 use crate::models::room::permission::RoomPermission;
 use crate::state::AppState;
 use crate::websocket::{
     connection::ConnectionManager,
-    types::{ConnectAck, ConnectRequest, RoomInfo, WsError, WsMessage, WsMessageType},
+    event_log::Replay,
+    types::{
+        ConnectAck, ConnectRequest, PresenceEntry, RequestAck, RoomInfo, WsError, WsMessage,
+        WsMessageType,
+    },
 };
+use axum::Json;
+use axum::extract::{Path, State};
+use serde::de::DeserializeOwned;
 use std::sync::Arc;
 
+/// UPDATE_CONTENT 请求中要更新的内容
+#[derive(serde::Deserialize)]
+struct ContentTarget {
+    content_id: i64,
+}
+
 /// WebSocket 消息处理器
 pub struct MessageHandler {
     app_state: AppState,
+    manager: Arc<ConnectionManager>,
 }
 
 impl MessageHandler {
     /// 创建新的消息处理器
-    pub fn new(app_state: AppState, _manager: Arc<ConnectionManager>) -> Self {
-        Self { app_state }
+    pub fn new(app_state: AppState, manager: Arc<ConnectionManager>) -> Self {
+        Self { app_state, manager }
     }
 
     /// 处理连接请求
@@ -48,6 +65,7 @@ impl MessageHandler {
             verified.claims.room_name
         );
 
+        let user_id = verified.claims.jti;
         let room = verified.room;
         let room_info = Some(RoomInfo {
             id: room.id.unwrap_or_default(),
@@ -63,9 +81,222 @@ impl MessageHandler {
             success: true,
             message: "Connected successfully".to_string(),
             room_info,
+            latest_event_id: None,
+            user_id: Some(user_id),
+            connection_id: None,
+            presence: None,
         })
     }
 
+    /// 查询断线重连需要补发的事件，以及房间最新的事件 ID
+    ///
+    /// 未启用事件日志或读取失败时，携带 `last_event_id` 的客户端需要重新同步。
+    pub async fn replay_events(
+        &self,
+        room_name: &str,
+        last_event_id: Option<i64>,
+    ) -> (Replay, Option<i64>) {
+        let Some(event_log) = self.app_state.broadcaster.event_log() else {
+            let replay = match last_event_id {
+                Some(_) => Replay::ResyncRequired,
+                None => Replay::Events(Vec::new()),
+            };
+            return (replay, None);
+        };
+
+        let replay = match last_event_id {
+            Some(last_event_id) => event_log
+                .replay(room_name, last_event_id)
+                .await
+                .unwrap_or_else(|error| {
+                    log::warn!("Failed to replay events of room {}: {:#}", room_name, error);
+                    Replay::ResyncRequired
+                }),
+            None => Replay::Events(Vec::new()),
+        };
+        let latest_event_id = event_log
+            .latest_event_id(room_name)
+            .await
+            .unwrap_or_else(|error| {
+                log::warn!(
+                    "Failed to load latest event id of room {}: {:#}",
+                    room_name,
+                    error
+                );
+                None
+            });
+        (replay, latest_event_id)
+    }
+
+    /// 处理客户端发起的内容操作（SEND_MESSAGE / UPDATE_CONTENT / DELETE_CONTENT）
+    ///
+    /// 直接调用对应的 REST 处理函数，参数校验、token 校验、权限检查和事件广播都与
+    /// HTTP 接口一致。成功时返回 ACK，失败时返回带 `request_id` 的 ERROR。
+    pub async fn handle_request(
+        &self,
+        room_name: &str,
+        token: &str,
+        message: WsMessage,
+    ) -> WsMessage {
+        let payload = message.payload.unwrap_or_default();
+        let Some(request_id) = payload
+            .get("request_id")
+            .and_then(|request_id| request_id.as_str())
+            .map(str::to_string)
+        else {
+            return WsMessage::error("Missing request_id");
+        };
+
+        match self
+            .dispatch_request(room_name, token, message.message_type, payload)
+            .await
+        {
+            Ok(result) => {
+                let ack = RequestAck { request_id, result };
+                WsMessage::new(WsMessageType::Ack, serde_json::to_value(ack).ok())
+            }
+            Err(error) => {
+                log::debug!(
+                    "WebSocket request {} in room {} failed: {}",
+                    request_id,
+                    room_name,
+                    error
+                );
+                WsMessage::new(
+                    WsMessageType::Error,
+                    Some(serde_json::json!({
+                        "request_id": request_id,
+                        "error": error.to_string(),
+                        "code": error.error_code(),
+                        "status": error.status_code().as_u16(),
+                    })),
+                )
+            }
+        }
+    }
+
+    async fn dispatch_request(
+        &self,
+        room_name: &str,
+        token: &str,
+        message_type: WsMessageType,
+        payload: serde_json::Value,
+    ) -> Result<serde_json::Value, AppError> {
+        let app_state = State(Arc::new(self.app_state.clone()));
+        let token = AuthToken(token.to_string());
+        let result = match message_type {
+            WsMessageType::SendMessage => {
+                let Json(response) = create_message(
+                    Path(room_name.to_string()),
+                    token,
+                    app_state,
+                    Json(parse_payload(payload)?),
+                )
+                .await?;
+                serde_json::to_value(response)
+            }
+            WsMessageType::UpdateContent => {
+                let ContentTarget { content_id } = parse_payload(payload.clone())?;
+                let Json(response) = update_content(
+                    Path((room_name.to_string(), content_id)),
+                    token,
+                    app_state,
+                    Json(parse_payload(payload)?),
+                )
+                .await?;
+                serde_json::to_value(response)
+            }
+            WsMessageType::DeleteContent => {
+                let Json(response) = delete_contents(
+                    Path(room_name.to_string()),
+                    token,
+                    app_state,
+                    Json(parse_payload(payload)?),
+                )
+                .await?;
+                serde_json::to_value(response)
+            }
+            other => {
+                return Err(AppError::validation(format!(
+                    "Unsupported request type: {other:?}"
+                )));
+            }
+        };
+        result.map_err(|error| AppError::internal(format!("Failed to encode response: {error}")))
+    }
+
+    /// 登记连接的在线信息
+    ///
+    /// 本实例保留一份用于输入状态限流，事件总线登记的信息用于跨实例的在线列表。
+    pub async fn join_presence(&self, room_name: &str, entry: PresenceEntry) {
+        self.app_state
+            .broadcaster
+            .event_bus()
+            .register_presence(room_name, &entry)
+            .await;
+        self.manager.set_presence(room_name, entry).await;
+    }
+
+    /// 移除连接在事件总线上登记的在线信息，本实例的信息随断开连接一起移除
+    pub async fn leave_presence(&self, connection_id: &str) {
+        self.app_state
+            .broadcaster
+            .event_bus()
+            .unregister_presence(connection_id)
+            .await;
+    }
+
+    /// 房间的在线列表，启用 PostgreSQL 事件总线时包含所有实例上的连接
+    pub async fn room_presence(&self, room_name: &str) -> Vec<PresenceEntry> {
+        self.app_state
+            .broadcaster
+            .event_bus()
+            .room_presence(room_name)
+            .await
+    }
+
+    /// 处理 PRESENCE 查询，返回房间当前的在线列表
+    ///
+    /// 查询中带有 `request_id` 时原样返回，便于客户端对应应答。
+    pub async fn handle_presence(&self, room_name: &str, message: WsMessage) -> WsMessage {
+        let connections = self.room_presence(room_name).await;
+        let mut payload = serde_json::json!({
+            "room_name": room_name,
+            "connections": connections,
+        });
+        if let Some(request_id) = message
+            .payload
+            .as_ref()
+            .and_then(|payload| payload.get("request_id"))
+        {
+            payload["request_id"] = request_id.clone();
+        }
+        WsMessage::new(WsMessageType::Presence, Some(payload))
+    }
+
+    /// 处理 TYPING 消息，限流后转发给房间内的连接
+    ///
+    /// `is_typing` 缺省为 `true`；被限流的事件直接丢弃，不回复错误。
+    pub async fn handle_typing(&self, room_name: &str, connection_id: &str, message: WsMessage) {
+        let is_typing = message
+            .payload
+            .as_ref()
+            .and_then(|payload| payload.get("is_typing"))
+            .and_then(|is_typing| is_typing.as_bool())
+            .unwrap_or(true);
+        let Some(presence) = self.manager.throttle_typing(connection_id, is_typing).await else {
+            return;
+        };
+        if let Err(e) = self
+            .app_state
+            .broadcaster
+            .broadcast_typing(room_name, &presence, is_typing)
+            .await
+        {
+            log::warn!("Failed to broadcast typing event: {}", e);
+        }
+    }
+
     /// 处理 PING 消息
     pub fn handle_ping(&self) -> WsMessage {
         WsMessage::new(WsMessageType::Pong, None)
@@ -106,3 +337,8 @@ impl MessageHandler {
         WsMessage::error(&error.to_string())
     }
 }
+
+fn parse_payload<T: DeserializeOwned>(payload: serde_json::Value) -> Result<T, AppError> {
+    serde_json::from_value(payload)
+        .map_err(|error| AppError::validation(format!("Invalid request payload: {error}")))
+}
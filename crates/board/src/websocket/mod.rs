@@ -4,7 +4,10 @@
 
 pub mod broadcaster;
 pub mod connection;
+pub mod event_bus;
+pub mod event_log;
 pub mod handler;
+pub mod outbound;
 pub mod server;
 pub mod types;
 
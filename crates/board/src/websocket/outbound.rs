@@ -0,0 +1,516 @@
+//! 连接的发送队列
+//!
+//! 每个连接一个有界队列，广播只把消息放入队列，由连接的发送任务写入 socket。
+//! 客户端消费过慢、队列已满时按 [`OverflowPolicy`] 丢弃、合并消息或断开连接，
+//! 避免一个卡住的客户端无限占用内存。丢弃带 `event_id` 的事件后客户端无法再按事件 ID
+//! 补发，发送任务会先通知客户端重新同步。
+//!
+//! 对客户端请求的回复（ACK/ERROR）丢了无法补发，不受溢出策略影响，与广播按顺序排队；
+//! 未发送的回复也达到队列长度时直接断开连接。
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::Notify;
+
+use crate::config::OverflowPolicy;
+use crate::websocket::types::{WsMessage, WsMessageType};
+
+/// 因消费过慢被断开时的关闭码（1013 Try Again Later），客户端应稍后重连
+pub const SLOW_CONSUMER_CLOSE_CODE: u16 = 1013;
+
+/// 发送任务从队列中取出的内容
+#[derive(Debug)]
+pub enum Outbound {
+    Message(WsMessage),
+    /// 队列丢弃过带 `event_id` 的事件，客户端需要重新同步；`latest_event_id` 为已入队的最新事件 ID
+    Resync {
+        latest_event_id: Option<i64>,
+    },
+    /// 以关闭码结束连接，之后不再有消息
+    Close {
+        code: u16,
+        reason: String,
+    },
+}
+
+/// 消息入队的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Queued,
+    /// 替换了队列中同一对象的旧事件
+    Coalesced,
+    /// 丢弃了队列中最早的消息
+    DroppedOldest,
+    /// 队列已满，连接已被关闭，消息未入队
+    Disconnected,
+}
+
+/// 连接已关闭，消息未入队
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Closed;
+
+/// 队列中的一条消息
+struct Entry {
+    message: WsMessage,
+    /// 对客户端请求的回复，溢出时不会被丢弃或合并
+    reply: bool,
+}
+
+struct State {
+    queue: VecDeque<Entry>,
+    /// 队列中的回复数，不计入广播的容量
+    replies: usize,
+    close: Option<(u16, String)>,
+    closed: bool,
+    senders: usize,
+    /// 已入队的最新事件 ID
+    latest_event_id: Option<i64>,
+    /// 丢弃过带 `event_id` 的事件，尚未通知客户端
+    resync: bool,
+}
+
+impl State {
+    /// 队列中的广播数
+    fn broadcasts(&self) -> usize {
+        self.queue.len() - self.replies
+    }
+
+    /// 丢弃队列中最早的广播
+    fn drop_oldest(&mut self) {
+        let Some(position) = self.queue.iter().position(|queued| !queued.reply) else {
+            return;
+        };
+        if self
+            .queue
+            .remove(position)
+            .is_some_and(|dropped| dropped.message.event_id.is_some())
+        {
+            self.resync = true;
+        }
+    }
+
+    /// 丢弃未发送的消息并记录关闭码
+    fn close_with(&mut self, code: u16, reason: String) {
+        self.queue.clear();
+        self.replies = 0;
+        self.close = Some((code, reason));
+        self.closed = true;
+    }
+
+    /// 优先取出关闭与重新同步通知，其次是队列中的消息
+    fn pop(&mut self) -> Option<Outbound> {
+        if let Some((code, reason)) = self.close.take() {
+            return Some(Outbound::Close { code, reason });
+        }
+        if std::mem::take(&mut self.resync) {
+            return Some(Outbound::Resync {
+                latest_event_id: self.latest_event_id,
+            });
+        }
+        let queued = self.queue.pop_front()?;
+        if queued.reply {
+            self.replies -= 1;
+        }
+        Some(Outbound::Message(queued.message))
+    }
+}
+
+struct Shared {
+    capacity: usize,
+    policy: OverflowPolicy,
+    state: Mutex<State>,
+    notify: Notify,
+}
+
+impl Shared {
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("outbound queue lock poisoned")
+    }
+}
+
+/// 创建容量为 `capacity` 的发送队列
+pub fn channel(capacity: usize, policy: OverflowPolicy) -> (OutboundSender, OutboundReceiver) {
+    let shared = Arc::new(Shared {
+        capacity: capacity.max(1),
+        policy,
+        state: Mutex::new(State {
+            queue: VecDeque::new(),
+            replies: 0,
+            close: None,
+            closed: false,
+            senders: 1,
+            latest_event_id: None,
+            resync: false,
+        }),
+        notify: Notify::new(),
+    });
+    (
+        OutboundSender {
+            shared: shared.clone(),
+        },
+        OutboundReceiver { shared },
+    )
+}
+
+/// 发送队列的写入端
+pub struct OutboundSender {
+    shared: Arc<Shared>,
+}
+
+impl OutboundSender {
+    /// 把消息放入队列，队列已满时按溢出策略处理
+    pub fn send(&self, message: WsMessage) -> Result<Delivery, Closed> {
+        let mut state = self.shared.lock();
+        if state.closed {
+            return Err(Closed);
+        }
+
+        let delivery = if state.broadcasts() < self.shared.capacity {
+            Delivery::Queued
+        } else {
+            match self.shared.policy {
+                OverflowPolicy::Disconnect => {
+                    state.close_with(
+                        SLOW_CONSUMER_CLOSE_CODE,
+                        "Client is not keeping up with room events".to_string(),
+                    );
+                    drop(state);
+                    self.shared.notify.notify_one();
+                    return Ok(Delivery::Disconnected);
+                }
+                // 被替换的事件已由同一对象的新事件覆盖，不需要重新同步
+                OverflowPolicy::Coalesce => {
+                    let superseded = state
+                        .queue
+                        .iter()
+                        .position(|queued| !queued.reply && supersedes(&message, &queued.message));
+                    match superseded {
+                        Some(position) => {
+                            state.queue.remove(position);
+                            Delivery::Coalesced
+                        }
+                        None => {
+                            state.drop_oldest();
+                            Delivery::DroppedOldest
+                        }
+                    }
+                }
+                OverflowPolicy::DropOldest => {
+                    state.drop_oldest();
+                    Delivery::DroppedOldest
+                }
+            }
+        };
+        if message.event_id.is_some() {
+            state.latest_event_id = state.latest_event_id.max(message.event_id);
+        }
+        state.queue.push_back(Entry {
+            message,
+            reply: false,
+        });
+        drop(state);
+        self.shared.notify.notify_one();
+        Ok(delivery)
+    }
+
+    /// 把对客户端请求的回复放入队列
+    ///
+    /// 回复不受溢出策略影响，不会被丢弃或合并；未发送的回复已达到队列长度时
+    /// 断开连接并返回 `Disconnected`。
+    pub fn reply(&self, message: WsMessage) -> Result<Delivery, Closed> {
+        let mut state = self.shared.lock();
+        if state.closed {
+            return Err(Closed);
+        }
+
+        if state.replies >= self.shared.capacity {
+            state.close_with(
+                SLOW_CONSUMER_CLOSE_CODE,
+                "Client is not reading replies to its requests".to_string(),
+            );
+            drop(state);
+            self.shared.notify.notify_one();
+            return Ok(Delivery::Disconnected);
+        }
+        state.replies += 1;
+        state.queue.push_back(Entry {
+            message,
+            reply: true,
+        });
+        drop(state);
+        self.shared.notify.notify_one();
+        Ok(Delivery::Queued)
+    }
+
+    /// 丢弃未发送的消息，并让发送任务以关闭码结束连接
+    pub fn close(&self, code: u16, reason: &str) {
+        let mut state = self.shared.lock();
+        if state.closed {
+            return;
+        }
+        state.close_with(code, reason.to_string());
+        drop(state);
+        self.shared.notify.notify_one();
+    }
+}
+
+impl Clone for OutboundSender {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl Drop for OutboundSender {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            drop(state);
+            self.shared.notify.notify_one();
+        }
+    }
+}
+
+/// 发送队列的读取端，由连接的发送任务持有
+pub struct OutboundReceiver {
+    shared: Arc<Shared>,
+}
+
+impl OutboundReceiver {
+    /// 取出下一条消息；队列为空且所有写入端都已释放时返回 `None`
+    pub async fn recv(&mut self) -> Option<Outbound> {
+        loop {
+            {
+                let mut state = self.shared.lock();
+                if let Some(outbound) = state.pop() {
+                    return Some(outbound);
+                }
+                if state.closed || state.senders == 0 {
+                    return None;
+                }
+            }
+            self.shared.notify.notified().await;
+        }
+    }
+
+    /// 不等待，立即取出下一条消息
+    pub fn try_recv(&mut self) -> Option<Outbound> {
+        self.shared.lock().pop()
+    }
+}
+
+impl Drop for OutboundReceiver {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.closed = true;
+        state.queue.clear();
+        state.replies = 0;
+    }
+}
+
+/// 合并时 `message` 能否替代已在队列中的 `queued`
+///
+/// 同一内容的事件、房间信息更新、同一连接的在线状态和输入状态只需保留最新的一条，
+/// 客户端收到后都会重新拉取或直接覆盖对应的数据。
+fn supersedes(message: &WsMessage, queued: &WsMessage) -> bool {
+    use WsMessageType::*;
+
+    let field = |message: &WsMessage, name: &str| {
+        message
+            .payload
+            .as_ref()
+            .and_then(|payload| payload.get(name))
+            .cloned()
+    };
+    match (&message.message_type, &queued.message_type) {
+        (
+            ContentCreated | ContentUpdated | ContentDeleted,
+            ContentCreated | ContentUpdated | ContentDeleted,
+        ) => {
+            let content_id = field(message, "content_id");
+            content_id.as_ref().is_some_and(|id| !id.is_null())
+                && content_id == field(queued, "content_id")
+        }
+        (RoomUpdate, RoomUpdate) => true,
+        (UserJoined | UserLeft, UserJoined | UserLeft) => {
+            let user_id = field(message, "user_id");
+            user_id.is_some()
+                && user_id == field(queued, "user_id")
+                && field(message, "connection_id") == field(queued, "connection_id")
+        }
+        (Typing, Typing) => {
+            let connection_id = field(message, "connection_id");
+            connection_id.is_some() && connection_id == field(queued, "connection_id")
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn content_event(message_type: WsMessageType, content_id: i64) -> WsMessage {
+        WsMessage::new(message_type, Some(json!({ "content_id": content_id })))
+    }
+
+    fn content_id(outbound: Option<Outbound>) -> i64 {
+        match outbound {
+            Some(Outbound::Message(message)) => {
+                message.payload.unwrap()["content_id"].as_i64().unwrap()
+            }
+            other => panic!("expected a message, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_keeps_the_most_recent_messages() {
+        let (tx, mut rx) = channel(2, OverflowPolicy::DropOldest);
+        for id in 1..=3 {
+            tx.send(content_event(WsMessageType::ContentCreated, id))
+                .unwrap();
+        }
+        assert_eq!(
+            tx.send(content_event(WsMessageType::ContentCreated, 4)),
+            Ok(Delivery::DroppedOldest)
+        );
+        assert_eq!(content_id(rx.recv().await), 3);
+        assert_eq!(content_id(rx.recv().await), 4);
+
+        drop(tx);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn coalesce_replaces_older_events_for_the_same_content() {
+        let (tx, mut rx) = channel(2, OverflowPolicy::Coalesce);
+        tx.send(content_event(WsMessageType::ContentCreated, 1))
+            .unwrap();
+        tx.send(content_event(WsMessageType::ContentCreated, 2))
+            .unwrap();
+        assert_eq!(
+            tx.send(content_event(WsMessageType::ContentUpdated, 1)),
+            Ok(Delivery::Coalesced)
+        );
+        assert_eq!(
+            tx.send(WsMessage::new(WsMessageType::RoomUpdate, None)),
+            Ok(Delivery::DroppedOldest)
+        );
+
+        assert_eq!(content_id(rx.recv().await), 1);
+        assert!(matches!(
+            rx.recv().await,
+            Some(Outbound::Message(WsMessage {
+                message_type: WsMessageType::RoomUpdate,
+                ..
+            }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn dropping_a_logged_event_requests_a_resync() {
+        let (tx, mut rx) = channel(2, OverflowPolicy::Coalesce);
+        for id in 1..=3 {
+            let mut message = content_event(WsMessageType::ContentCreated, id);
+            message.event_id = Some(id + 100);
+            tx.send(message).unwrap();
+        }
+
+        assert!(matches!(
+            rx.recv().await,
+            Some(Outbound::Resync {
+                latest_event_id: Some(103)
+            })
+        ));
+        assert_eq!(content_id(rx.recv().await), 2);
+        assert_eq!(content_id(rx.recv().await), 3);
+        assert!(rx.try_recv().is_none());
+    }
+
+    #[tokio::test]
+    async fn replies_survive_a_full_queue() {
+        let (tx, mut rx) = channel(2, OverflowPolicy::DropOldest);
+        tx.send(content_event(WsMessageType::ContentCreated, 1))
+            .unwrap();
+        tx.send(content_event(WsMessageType::ContentCreated, 2))
+            .unwrap();
+        assert_eq!(
+            tx.reply(WsMessage::new(WsMessageType::Ack, None)),
+            Ok(Delivery::Queued)
+        );
+        for id in 3..=5 {
+            assert_eq!(
+                tx.send(content_event(WsMessageType::ContentCreated, id)),
+                Ok(Delivery::DroppedOldest)
+            );
+        }
+
+        assert!(matches!(
+            rx.recv().await,
+            Some(Outbound::Message(WsMessage {
+                message_type: WsMessageType::Ack,
+                ..
+            }))
+        ));
+        assert_eq!(content_id(rx.recv().await), 4);
+        assert_eq!(content_id(rx.recv().await), 5);
+        assert!(rx.try_recv().is_none());
+    }
+
+    #[tokio::test]
+    async fn unread_replies_disconnect_instead_of_dropping() {
+        let (tx, mut rx) = channel(1, OverflowPolicy::Coalesce);
+        tx.reply(WsMessage::new(WsMessageType::Ack, None)).unwrap();
+        assert_eq!(
+            tx.reply(WsMessage::new(WsMessageType::Ack, None)),
+            Ok(Delivery::Disconnected)
+        );
+        assert!(matches!(
+            rx.recv().await,
+            Some(Outbound::Close {
+                code: SLOW_CONSUMER_CLOSE_CODE,
+                ..
+            })
+        ));
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn disconnect_closes_the_queue_with_a_close_code() {
+        let (tx, mut rx) = channel(1, OverflowPolicy::Disconnect);
+        tx.send(content_event(WsMessageType::ContentCreated, 1))
+            .unwrap();
+        assert_eq!(
+            tx.send(content_event(WsMessageType::ContentCreated, 2)),
+            Ok(Delivery::Disconnected)
+        );
+        assert_eq!(
+            tx.send(content_event(WsMessageType::ContentCreated, 3)),
+            Err(Closed)
+        );
+
+        assert!(matches!(
+            rx.recv().await,
+            Some(Outbound::Close {
+                code: SLOW_CONSUMER_CLOSE_CODE,
+                ..
+            })
+        ));
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn sending_after_the_receiver_is_dropped_fails() {
+        let (tx, rx) = channel(4, OverflowPolicy::DropOldest);
+        drop(rx);
+        assert_eq!(
+            tx.send(WsMessage::new(WsMessageType::RoomUpdate, None)),
+            Err(Closed)
+        );
+    }
+}
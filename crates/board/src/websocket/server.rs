@@ -2,21 +2,38 @@
 //!
 //! 提供 WebSocket 服务器功能和路由集成
 
-use axum::extract::{State, ws::WebSocket, ws::WebSocketUpgrade};
+use std::collections::HashSet;
+
+use axum::extract::{
+    State,
+    ws::{CloseFrame, WebSocket, WebSocketUpgrade},
+};
 use futures::{SinkExt, StreamExt};
-use tokio::sync::mpsc;
 use uuid::Uuid;
 
 use crate::state::AppState;
 use crate::websocket::{
     connection::ConnectionManager,
+    event_log::Replay,
     handler::MessageHandler,
-    types::{ConnectRequest, WsMessage, WsMessageType},
+    outbound::{Outbound, OutboundSender},
+    types::{ConnectRequest, PresenceEntry, WsMessage, WsMessageType},
 };
 
 /// WebSocket 服务器
 pub struct WsServer;
 
+/// 握手完成后的连接状态
+struct Handshake {
+    room_name: String,
+    /// 连接使用的房间 token，客户端发起的内容操作以此鉴权
+    token: String,
+    /// 已补发的事件 ID，实时推送中再次出现时跳过
+    replayed: HashSet<i64>,
+    /// 本连接的在线信息
+    presence: PresenceEntry,
+}
+
 impl WsServer {
     /// 处理 WebSocket 连接升级
     pub async fn handle_ws(
@@ -32,59 +49,48 @@ impl WsServer {
         // 分离 socket 为 sink 和 stream
         let (mut sender, mut receiver) = socket.split();
 
-        // 创建消息通道用于接收广播
-        let (tx, mut rx) = mpsc::unbounded_channel::<WsMessage>();
-
         // 生成唯一连接 ID
         let connection_id = Uuid::new_v4().to_string();
 
         // 使用共享的连接管理器
         let manager = app_state.connection_manager.clone();
+
+        // 创建有界发送队列用于接收广播
+        let (tx, mut rx) = manager.outbound_channel();
         let handler = MessageHandler::new(app_state.clone(), manager.clone());
         let room_lifecycle = app_state.services.room_lifecycle.clone();
 
-        // 接收第一条 CONNECT 消息
-        let room_name =
-            match Self::handle_connect_handshake(&mut receiver, &handler, &mut sender).await {
-                Ok(room_name) => room_name,
-                Err(e) => {
-                    log::error!("Connect handshake failed: {}", e);
-                    let error_msg = format!("Connection failed: {}", e);
-                    let _ = sender
-                        .send(axum::extract::ws::Message::Text(
-                            serde_json::to_string(&WsMessage::error(&error_msg))
-                                .unwrap_or_default()
-                                .into(),
-                        ))
-                        .await;
-                    return;
-                }
-            };
-
-        // 订阅房间
-        let room_name_for_log = room_name.clone();
-        let error_to_send: Option<String> = match manager
-            .subscribe_to_room(connection_id.clone(), room_name.clone(), tx)
-            .await
+        // 接收第一条 CONNECT 消息，订阅房间并补发断线期间的事件
+        let handshake = match Self::handle_connect_handshake(
+            &mut receiver,
+            &handler,
+            &mut sender,
+            &manager,
+            &connection_id,
+            tx,
+        )
+        .await
         {
-            Ok(_) => None,
+            Ok(handshake) => handshake,
             Err(e) => {
-                // 立即转换为 String，避免 Box<dyn StdError> 跨越 await
-                let msg = format!("Subscription failed: {}", e);
-                log::error!("Failed to subscribe to room {}: {}", room_name_for_log, msg);
-                Some(msg)
+                log::error!("Connect handshake failed: {}", e);
+                manager.disconnect(&connection_id).await;
+                handler.leave_presence(&connection_id).await;
+                let error_msg = format!("Connection failed: {}", e);
+                let _ = sender
+                    .send(axum::extract::ws::Message::Text(
+                        serde_json::to_string(&WsMessage::error(&error_msg))
+                            .unwrap_or_default()
+                            .into(),
+                    ))
+                    .await;
+                return;
             }
         };
-        // subscribe_result 已被 match 消费，不再存在
-
-        if let Some(error_msg) = error_to_send {
-            let error_response =
-                serde_json::to_string(&WsMessage::error(&error_msg)).unwrap_or_default();
-            let _ = sender
-                .send(axum::extract::ws::Message::Text(error_response.into()))
-                .await;
-            return;
-        }
+        let room_name = handshake.room_name;
+        let token = handshake.token;
+        let mut replayed = handshake.replayed;
+        let presence = handshake.presence;
 
         log::info!(
             "Connection {} established for room {}",
@@ -92,6 +98,14 @@ impl WsServer {
             room_name
         );
 
+        if let Err(e) = app_state
+            .broadcaster
+            .broadcast_connection_joined(&room_name, &presence)
+            .await
+        {
+            log::warn!("Failed to broadcast connection joined event: {}", e);
+        }
+
         if let Err(e) = room_lifecycle.on_room_became_active(&room_name).await {
             log::warn!("Failed to clear room gc markers for {}: {}", room_name, e);
         }
@@ -108,6 +122,7 @@ impl WsServer {
                     &manager_recv,
                     &connection_id_recv,
                     &room_name_recv,
+                    &token,
                 )
                 .await
                 {
@@ -120,8 +135,38 @@ impl WsServer {
 
         // 创建发送广播消息的任务
         let connection_id_send = connection_id.clone();
+        let room_name_send = room_name.clone();
         let mut send_task = tokio::spawn(async move {
-            while let Some(msg) = rx.recv().await {
+            while let Some(outbound) = rx.recv().await {
+                let msg = match outbound {
+                    Outbound::Message(msg) => msg,
+                    Outbound::Resync { latest_event_id } => {
+                        log::info!(
+                            "Connection {} dropped room events, requesting resync",
+                            connection_id_send
+                        );
+                        Self::resync_message(&room_name_send, latest_event_id)
+                    }
+                    Outbound::Close { code, reason } => {
+                        log::info!(
+                            "Closing connection {} with code {}: {}",
+                            connection_id_send,
+                            code,
+                            reason
+                        );
+                        let _ = sender
+                            .send(axum::extract::ws::Message::Close(Some(CloseFrame {
+                                code,
+                                reason: reason.into(),
+                            })))
+                            .await;
+                        break;
+                    }
+                };
+                // 订阅后、补发前发布的事件已经补发过
+                if msg.event_id.is_some_and(|id| replayed.remove(&id)) {
+                    continue;
+                }
                 let json = match serde_json::to_string(&msg) {
                     Ok(s) => s,
                     Err(e) => {
@@ -161,6 +206,18 @@ impl WsServer {
 
         // 清理连接
         manager.disconnect(&connection_id).await;
+        app_state
+            .broadcaster
+            .event_bus()
+            .unregister_presence(&connection_id)
+            .await;
+        if let Err(e) = app_state
+            .broadcaster
+            .broadcast_connection_left(&room_name, &presence)
+            .await
+        {
+            log::warn!("Failed to broadcast connection left event: {}", e);
+        }
         if manager.get_room_connection_count(&room_name).await == 0
             && let Err(e) = room_lifecycle.on_room_became_empty(&room_name).await
         {
@@ -170,11 +227,17 @@ impl WsServer {
     }
 
     /// 处理连接握手
+    ///
+    /// 校验 CONNECT 请求并订阅房间，再发送 CONNECT_ACK 与断线期间的事件。先订阅后
+    /// 查询事件日志，补发与实时推送之间不会遗漏事件；两者重复的事件由发送任务跳过。
     async fn handle_connect_handshake(
         receiver: &mut futures::stream::SplitStream<WebSocket>,
         handler: &MessageHandler,
         sender: &mut futures::stream::SplitSink<WebSocket, axum::extract::ws::Message>,
-    ) -> Result<String, String> {
+        manager: &ConnectionManager,
+        connection_id: &str,
+        tx: OutboundSender,
+    ) -> Result<Handshake, String> {
         // 接收第一条消息
         let first_msg = match receiver.next().await {
             Some(Ok(msg)) => msg,
@@ -215,11 +278,35 @@ impl WsServer {
         .map_err(|e| format!("Failed to parse ConnectRequest: {}", e))?;
 
         // 验证连接请求
-        let ack = handler
+        let mut ack = handler
             .handle_connect(connect_req.clone())
             .await
             .map_err(|e| format!("Connect verification failed: {}", e))?;
 
+        // 订阅房间；立即转换为 String，避免 Box<dyn StdError> 跨越 await
+        let display_name = connect_req.display_name();
+        let room_name = connect_req.room_name;
+        manager
+            .subscribe_to_room(connection_id.to_string(), room_name.clone(), tx)
+            .await
+            .map_err(|e| format!("Subscription failed: {}", e))?;
+
+        // 登记在线信息，确认消息中附带包括本连接在内的在线列表
+        let presence = PresenceEntry {
+            connection_id: connection_id.to_string(),
+            user_id: ack.user_id.clone().unwrap_or_default(),
+            display_name,
+            connected_at: chrono::Utc::now().timestamp(),
+        };
+        handler.join_presence(&room_name, presence.clone()).await;
+        ack.connection_id = Some(connection_id.to_string());
+        ack.presence = Some(handler.room_presence(&room_name).await);
+
+        let (replay, latest_event_id) = handler
+            .replay_events(&room_name, connect_req.last_event_id)
+            .await;
+        ack.latest_event_id = latest_event_id;
+
         // 发送确认消息
         let ack_msg = WsMessage::new(
             WsMessageType::ConnectAck,
@@ -228,24 +315,74 @@ impl WsServer {
                     .map_err(|e| format!("Failed to serialize ConnectAck: {}", e))?,
             ),
         );
-        let ack_json = serde_json::to_string(&ack_msg)
-            .map_err(|e| format!("Failed to serialize WsMessage: {}", e))?;
-
-        sender
-            .send(axum::extract::ws::Message::Text(ack_json.into()))
+        Self::send_direct(sender, &ack_msg)
             .await
             .map_err(|e| format!("Failed to send CONNECT_ACK: {}", e))?;
 
-        Ok(connect_req.room_name)
+        // 补发断线期间的事件
+        let mut replayed = HashSet::new();
+        match replay {
+            Replay::Events(events) => {
+                for event in events {
+                    Self::send_direct(sender, &event)
+                        .await
+                        .map_err(|e| format!("Failed to replay event: {}", e))?;
+                    replayed.extend(event.event_id);
+                }
+            }
+            Replay::ResyncRequired => {
+                log::info!(
+                    "Connection {} to room {} cannot resume from event {:?}",
+                    connection_id,
+                    room_name,
+                    connect_req.last_event_id
+                );
+                let resync = Self::resync_message(&room_name, latest_event_id);
+                Self::send_direct(sender, &resync)
+                    .await
+                    .map_err(|e| format!("Failed to send RESYNC_REQUIRED: {}", e))?;
+            }
+        }
+
+        Ok(Handshake {
+            room_name,
+            token: connect_req.token,
+            replayed,
+            presence,
+        })
+    }
+
+    /// 通知客户端重新拉取数据，并以 `latest_event_id` 作为新的补发起点
+    fn resync_message(room_name: &str, latest_event_id: Option<i64>) -> WsMessage {
+        WsMessage::new(
+            WsMessageType::ResyncRequired,
+            Some(serde_json::json!({
+                "room_name": room_name,
+                "latest_event_id": latest_event_id,
+            })),
+        )
+    }
+
+    /// 在发送任务启动前直接向客户端发送消息
+    async fn send_direct(
+        sender: &mut futures::stream::SplitSink<WebSocket, axum::extract::ws::Message>,
+        message: &WsMessage,
+    ) -> Result<(), String> {
+        let json = serde_json::to_string(message).map_err(|e| e.to_string())?;
+        sender
+            .send(axum::extract::ws::Message::Text(json.into()))
+            .await
+            .map_err(|e| e.to_string())
     }
 
     /// 处理客户端消息
     async fn handle_client_message(
         msg: axum::extract::ws::Message,
-        _handler: &MessageHandler,
-        _manager: &ConnectionManager,
-        _connection_id: &str,
-        _room_name: &str,
+        handler: &MessageHandler,
+        manager: &ConnectionManager,
+        connection_id: &str,
+        room_name: &str,
+        token: &str,
     ) -> Result<(), String> {
         match msg {
             axum::extract::ws::Message::Text(text) => {
@@ -262,6 +399,21 @@ impl WsServer {
                     WsMessageType::Pong => {
                         log::debug!("Received PONG");
                     }
+                    WsMessageType::SendMessage
+                    | WsMessageType::UpdateContent
+                    | WsMessageType::DeleteContent => {
+                        let reply = handler.handle_request(room_name, token, ws_msg).await;
+                        manager.send_to_connection(connection_id, reply).await;
+                    }
+                    WsMessageType::Presence => {
+                        let reply = handler.handle_presence(room_name, ws_msg).await;
+                        manager.send_to_connection(connection_id, reply).await;
+                    }
+                    WsMessageType::Typing => {
+                        handler
+                            .handle_typing(room_name, connection_id, ws_msg)
+                            .await;
+                    }
                     _ => {
                         log::debug!("Received message type: {:?}", ws_msg.message_type);
                     }
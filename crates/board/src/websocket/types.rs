@@ -5,6 +5,8 @@
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+use crate::constants::websocket::MAX_DISPLAY_NAME_CHARS;
+
 /// WebSocket 消息类型
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -31,6 +33,20 @@ pub enum WsMessageType {
     UserLeft,
     /// 房间更新事件
     RoomUpdate,
+    /// 无法补发断线期间的事件，客户端需重新加载房间
+    ResyncRequired,
+    /// 客户端发送聊天消息
+    SendMessage,
+    /// 客户端更新内容
+    UpdateContent,
+    /// 客户端删除内容
+    DeleteContent,
+    /// 客户端请求处理成功
+    Ack,
+    /// 房间在线列表，客户端查询与服务端应答使用同一类型
+    Presence,
+    /// 正在输入状态，只转发给当前在线的连接，不记录也不补发
+    Typing,
 }
 
 /// WebSocket 消息
@@ -40,6 +56,9 @@ pub struct WsMessage {
     pub message_type: WsMessageType,
     pub payload: Option<serde_json::Value>,
     pub timestamp: i64,
+    /// 房间事件 ID，同一房间内递增；心跳、错误等消息没有 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<i64>,
 }
 
 impl WsMessage {
@@ -49,6 +68,7 @@ impl WsMessage {
             message_type,
             payload,
             timestamp: chrono::Utc::now().timestamp(),
+            event_id: None,
         }
     }
 
@@ -58,6 +78,7 @@ impl WsMessage {
             message_type: WsMessageType::Error,
             payload: Some(serde_json::json!({ "error": error })),
             timestamp: chrono::Utc::now().timestamp(),
+            event_id: None,
         }
     }
 }
@@ -68,6 +89,23 @@ impl WsMessage {
 pub struct ConnectRequest {
     pub token: String,
     pub room_name: String,
+    /// 断线前收到的最后一个事件 ID，服务端据此补发之后的事件
+    #[serde(default)]
+    pub last_event_id: Option<i64>,
+    /// 在线列表中显示的名称，可选
+    #[serde(default)]
+    pub display_name: Option<String>,
+}
+
+impl ConnectRequest {
+    /// 去除首尾空白并截断到最大长度后的显示名称，为空时返回 `None`
+    pub fn display_name(&self) -> Option<String> {
+        let name = self.display_name.as_deref()?.trim();
+        if name.is_empty() {
+            return None;
+        }
+        Some(name.chars().take(MAX_DISPLAY_NAME_CHARS).collect())
+    }
 }
 
 /// 连接确认
@@ -78,6 +116,41 @@ pub struct ConnectAck {
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub room_info: Option<RoomInfo>,
+    /// 连接建立时房间的最新事件 ID，客户端重连时作为 `last_event_id` 的起点
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latest_event_id: Option<i64>,
+    /// 连接所用 token 的 ID，与 USER_JOINED / USER_LEFT 中的 `user_id` 相同
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// 本连接的 ID，客户端据此在在线列表和输入状态中识别自己
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_id: Option<String>,
+    /// 连接建立时房间内的在线连接（包括本连接）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presence: Option<Vec<PresenceEntry>>,
+}
+
+/// 在线列表中的一个连接
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct PresenceEntry {
+    pub connection_id: String,
+    /// 连接所用 token 的 ID，同一用户的多个标签页共享
+    pub user_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// 连接建立时间（Unix 秒）
+    pub connected_at: i64,
+}
+
+/// 客户端请求的确认
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RequestAck {
+    /// 客户端请求中的 `request_id`
+    pub request_id: String,
+    /// 与对应 REST 接口相同的响应体
+    pub result: serde_json::Value,
 }
 
 /// 房间信息
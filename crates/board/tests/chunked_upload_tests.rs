@@ -15,12 +15,17 @@ use sha2::{Digest, Sha256};
 use tower::ServiceExt;
 
 use common::{
-    create_test_app,
+    create_test_app, create_test_app_with,
     fixtures::{file_sizes, filenames, passwords, room_names},
     http::{assert_json, assert_status, create_request as create_http_request, send_request},
 };
 
+use board::config::ChunkStagingBackend;
+use board::repository::room_upload_reservation_repository::{
+    IRoomUploadReservationRepository, RoomUploadReservationRepository,
+};
 use board::route::room::api_router;
+use board::storage::{OpendalBackend, key};
 
 fn create_room_request(room_name: &str, password: Option<&str>) -> Request<Body> {
     let payload = match password {
@@ -39,8 +44,39 @@ fn single_chunk_request(
     token: &str,
     upload_token: &str,
     file_data: &str,
+) -> Result<Request<Body>> {
+    chunk_request(room_name, token, upload_token, 0, file_data)
+}
+
+fn chunk_request(
+    room_name: &str,
+    token: &str,
+    upload_token: &str,
+    chunk_index: usize,
+    file_data: &str,
+) -> Result<Request<Body>> {
+    hashed_chunk_request(room_name, token, upload_token, chunk_index, file_data, None)
+}
+
+fn hashed_chunk_request(
+    room_name: &str,
+    token: &str,
+    upload_token: &str,
+    chunk_index: usize,
+    file_data: &str,
+    chunk_hash: Option<&str>,
 ) -> Result<Request<Body>> {
     let boundary = "----chunked-upload-boundary";
+    let hash_field = chunk_hash
+        .map(|hash| {
+            format!(
+                "--{boundary}\r\n\
+                 Content-Disposition: form-data; name=\"chunk_hash\"\r\n\
+                 \r\n\
+                 {hash}\r\n"
+            )
+        })
+        .unwrap_or_default();
     let chunk_body = format!(
         "--{boundary}\r\n\
          Content-Disposition: form-data; name=\"upload_token\"\r\n\
@@ -49,11 +85,12 @@ fn single_chunk_request(
          --{boundary}\r\n\
          Content-Disposition: form-data; name=\"chunk_index\"\r\n\
          \r\n\
-         0\r\n\
+         {chunk_index}\r\n\
          --{boundary}\r\n\
          Content-Disposition: form-data; name=\"chunk_size\"\r\n\
          \r\n\
          {}\r\n\
+         {hash_field}\
          --{boundary}\r\n\
          Content-Disposition: form-data; name=\"chunk_data\"; filename=\"test_file.txt\"\r\n\
          Content-Type: text/plain\r\n\
@@ -190,16 +227,18 @@ async fn test_chunked_upload_complete_workflow() -> Result<()> {
         "test_file.txt"
     );
     assert_eq!(complete_json["merged_files"][0]["file_hash"], final_hash);
+    assert_eq!(complete_json["merged_files"][0]["metadata_retained"], false);
     let content_id = complete_json["merged_files"][0]["content_id"]
         .as_i64()
         .expect("content id");
 
-    let stored_path: String =
-        sqlx::query_scalar("SELECT path FROM room_contents WHERE id = $1 AND room_id = (SELECT id FROM rooms WHERE name = $2)")
+    let (stored_path, stored_hash): (String, String) =
+        sqlx::query_as("SELECT path, hash FROM room_contents WHERE id = $1 AND room_id = (SELECT id FROM rooms WHERE name = $2)")
             .bind(content_id)
             .bind(room_name)
             .fetch_one(_pool.as_ref())
             .await?;
+    assert_eq!(stored_hash, final_hash);
     assert!(
         stored_path.starts_with(std::env::temp_dir().to_string_lossy().as_ref()),
         "stored path should honor configured storage root, got {stored_path}"
@@ -209,10 +248,408 @@ async fn test_chunked_upload_complete_workflow() -> Result<()> {
         "merged file should exist at configured storage root"
     );
 
+    // 7. 下载合并后的文件（流式响应）
+    let download_request = create_http_request(
+        Method::GET,
+        &format!("/api/v1/contents/{content_id}?token={token}"),
+        None,
+    );
+    let download_response = app.clone().oneshot(download_request).await?;
+    assert_eq!(download_response.status(), StatusCode::OK);
+    assert_eq!(
+        download_response.headers()["content-length"],
+        file_data.len().to_string().as_str()
+    );
+    assert!(
+        download_response.headers()["repr-digest"]
+            .to_str()?
+            .starts_with("sha-256=:")
+    );
+    let download_body = axum::body::to_bytes(download_response.into_body(), usize::MAX).await?;
+    assert_eq!(download_body.as_ref(), file_data.as_bytes());
+
+    Ok(())
+}
+
+/// 分块暂存在存储后端时，合并完成后清理暂存分块
+#[tokio::test]
+async fn test_chunked_upload_with_storage_staging() -> Result<()> {
+    let staging_dir = tempfile::tempdir()?;
+    let operator = opendal::Operator::new(opendal::services::Memory::default())?.finish();
+    let storage = std::sync::Arc::new(OpendalBackend::from_operator(operator.clone()));
+    let staging_path = staging_dir.path().to_path_buf();
+    let (app, _pool) = create_test_app_with(Some(storage), move |config| {
+        config.storage.chunk_staging.backend = ChunkStagingBackend::Storage;
+        config.storage.chunk_staging.dir = staging_path;
+    })
+    .await?;
+
+    let room_name = "storage_staging_room";
+    let response = app
+        .clone()
+        .oneshot(create_room_request(room_name, None))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .clone()
+        .oneshot(create_http_request(
+            Method::POST,
+            &format!("/api/v1/rooms/{room_name}/tokens"),
+            Some(Body::from(json!({}).to_string())),
+        ))
+        .await?;
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let token = serde_json::from_slice::<serde_json::Value>(&body)?["token"]
+        .as_str()
+        .expect("token string")
+        .to_string();
+
+    let file_data = "staged on the shared storage backend";
+    let response = app
+        .clone()
+        .oneshot(create_http_request(
+            Method::POST,
+            &format!("/api/v1/rooms/{room_name}/uploads/chunks/prepare?token={token}"),
+            Some(Body::from(
+                json!({
+                    "files": [{
+                        "name": "staged.txt",
+                        "size": file_data.len(),
+                        "mime": "text/plain",
+                        "chunk_size": 1024
+                    }]
+                })
+                .to_string(),
+            )),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let prepare_json: serde_json::Value = serde_json::from_slice(&body)?;
+    let upload_token = prepare_json["upload_token"].as_str().expect("upload token");
+    let reservation_id = prepare_json["reservation_id"]
+        .as_str()
+        .expect("reservation id");
+
+    let response = app
+        .clone()
+        .oneshot(single_chunk_request(
+            room_name,
+            &token,
+            upload_token,
+            file_data,
+        )?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let chunk_key = key::chunk_key(reservation_id.parse()?, 0);
+    assert!(operator.exists(&chunk_key).await?);
+    assert!(!tokio::fs::try_exists(staging_dir.path().join(reservation_id)).await?);
+
+    let final_hash = hex::encode(Sha256::digest(file_data.as_bytes()));
+    let response = app
+        .clone()
+        .oneshot(create_http_request(
+            Method::POST,
+            &format!("/api/v1/rooms/{room_name}/uploads/chunks/complete?token={token}"),
+            Some(Body::from(
+                json!({ "reservation_id": reservation_id, "final_hash": final_hash }).to_string(),
+            )),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let complete_json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(complete_json["merged_files"][0]["file_hash"], final_hash);
+    assert!(!operator.exists(&chunk_key).await?);
+    assert!(!tokio::fs::try_exists(staging_dir.path().join(reservation_id)).await?);
+
+    Ok(())
+}
+
+/// 分块可以乱序、并行上传，状态接口报告缺失的区间
+#[tokio::test]
+async fn test_chunked_upload_out_of_order() -> Result<()> {
+    let (app, _pool) = create_test_app().await?;
+    let room_name = "out_of_order_room";
+    let token = create_room_with_token(&app, room_name).await?;
+    let file_data = "0123456789abcdefXYZ!";
+    let chunks = ["01234567", "89abcdef", "XYZ!"];
+
+    let response = app
+        .clone()
+        .oneshot(create_http_request(
+            Method::POST,
+            &format!("/api/v1/rooms/{room_name}/uploads/chunks/prepare?token={token}"),
+            Some(Body::from(
+                json!({
+                    "files": [{
+                        "name": "parallel.txt",
+                        "size": file_data.len(),
+                        "mime": "text/plain",
+                        "chunk_size": 8
+                    }]
+                })
+                .to_string(),
+            )),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let prepare_json: serde_json::Value = serde_json::from_slice(&body)?;
+    let upload_token = prepare_json["upload_token"].as_str().expect("upload token");
+    let reservation_id = prepare_json["reservation_id"]
+        .as_str()
+        .expect("reservation id");
+
+    // 大小与预留不符、索引越界的分块会被拒绝
+    let response = app
+        .clone()
+        .oneshot(chunk_request(room_name, &token, upload_token, 0, "short")?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app
+        .clone()
+        .oneshot(chunk_request(room_name, &token, upload_token, 3, "XYZ!")?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(chunk_request(
+            room_name,
+            &token,
+            upload_token,
+            2,
+            chunks[2],
+        )?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let status_uri = format!(
+        "/api/v1/rooms/{room_name}/uploads/chunks/status?token={token}&reservation_id={reservation_id}"
+    );
+    let response = app
+        .clone()
+        .oneshot(create_http_request(Method::GET, &status_uri, None))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let status_json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(
+        status_json["missing_ranges"],
+        json!([{ "start_chunk": 0, "end_chunk": 1, "offset": 0, "length": 16 }])
+    );
+
+    let (first, second) = tokio::join!(
+        app.clone().oneshot(chunk_request(
+            room_name,
+            &token,
+            upload_token,
+            1,
+            chunks[1]
+        )?),
+        app.clone().oneshot(chunk_request(
+            room_name,
+            &token,
+            upload_token,
+            0,
+            chunks[0]
+        )?),
+    );
+    assert_eq!(first?.status(), StatusCode::OK);
+    assert_eq!(second?.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(create_http_request(Method::GET, &status_uri, None))
+        .await?;
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let status_json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(status_json["missing_ranges"], json!([]));
+    assert_eq!(status_json["uploaded_size"], file_data.len());
+
+    let final_hash = hex::encode(Sha256::digest(file_data.as_bytes()));
+    let response = app
+        .clone()
+        .oneshot(create_http_request(
+            Method::POST,
+            &format!("/api/v1/rooms/{room_name}/uploads/chunks/complete?token={token}"),
+            Some(Body::from(
+                json!({ "reservation_id": reservation_id, "final_hash": final_hash }).to_string(),
+            )),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let complete_json: serde_json::Value = serde_json::from_slice(&body)?;
+    let content_id = complete_json["merged_files"][0]["content_id"]
+        .as_i64()
+        .expect("content id");
+
+    let response = app
+        .clone()
+        .oneshot(create_http_request(
+            Method::GET,
+            &format!("/api/v1/contents/{content_id}?token={token}"),
+            None,
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    assert_eq!(body.as_ref(), file_data.as_bytes());
+
     Ok(())
 }
 
 /// 测试分块上传错误处理
+/// 预留时选择的校验算法同时用于分块与整体校验
+#[tokio::test]
+async fn test_chunked_upload_hash_algorithms() -> Result<()> {
+    let (app, _pool) = create_test_app().await?;
+    let room_name = "hash_algorithm_room";
+    let token = create_room_with_token(&app, room_name).await?;
+    let file_data = "0123456789abcdefXYZ!";
+    let chunks = ["01234567", "89abcdef", "XYZ!"];
+
+    let prepare = |hash_algorithm: &str| {
+        create_http_request(
+            Method::POST,
+            &format!("/api/v1/rooms/{room_name}/uploads/chunks/prepare?token={token}"),
+            Some(Body::from(
+                json!({
+                    "files": [{
+                        "name": format!("{hash_algorithm}.txt"),
+                        "size": file_data.len(),
+                        "mime": "text/plain",
+                        "chunk_size": 8
+                    }],
+                    "hash_algorithm": hash_algorithm
+                })
+                .to_string(),
+            )),
+        )
+    };
+
+    let response = app.clone().oneshot(prepare("md5")).await?;
+    assert!(response.status().is_client_error());
+
+    let response = app.clone().oneshot(prepare("blake3")).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let prepare_json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(prepare_json["hash_algorithm"], "blake3");
+    let upload_token = prepare_json["upload_token"].as_str().expect("upload token");
+    let reservation_id = prepare_json["reservation_id"]
+        .as_str()
+        .expect("reservation id");
+
+    // SHA-256 摘要不能通过 BLAKE3 校验
+    let sha256 = hex::encode(Sha256::digest(chunks[0].as_bytes()));
+    let response = app
+        .clone()
+        .oneshot(hashed_chunk_request(
+            room_name,
+            &token,
+            upload_token,
+            0,
+            chunks[0],
+            Some(&sha256),
+        )?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        let chunk_hash = blake3::hash(chunk.as_bytes()).to_hex().to_string();
+        let response = app
+            .clone()
+            .oneshot(hashed_chunk_request(
+                room_name,
+                &token,
+                upload_token,
+                index,
+                chunk,
+                Some(&chunk_hash.to_uppercase()),
+            )?)
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        let upload_json: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(upload_json["chunk_hash"], chunk_hash);
+    }
+
+    let response = app
+        .clone()
+        .oneshot(create_http_request(
+            Method::GET,
+            &format!(
+                "/api/v1/rooms/{room_name}/uploads/chunks/status?token={token}&reservation_id={reservation_id}"
+            ),
+            None,
+        ))
+        .await?;
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let status_json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(status_json["hash_algorithm"], "blake3");
+
+    let final_hash = blake3::hash(file_data.as_bytes()).to_hex().to_string();
+    let response = app
+        .clone()
+        .oneshot(create_http_request(
+            Method::POST,
+            &format!("/api/v1/rooms/{room_name}/uploads/chunks/complete?token={token}"),
+            Some(Body::from(
+                json!({ "reservation_id": reservation_id, "final_hash": final_hash }).to_string(),
+            )),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let complete_json: serde_json::Value = serde_json::from_slice(&body)?;
+    // 登记的内容哈希仍为 SHA-256
+    assert_eq!(
+        complete_json["merged_files"][0]["file_hash"],
+        hex::encode(Sha256::digest(file_data.as_bytes()))
+    );
+
+    // CRC32C：整体校验和不符时合并失败
+    let response = app.clone().oneshot(prepare("crc32c")).await?;
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let prepare_json: serde_json::Value = serde_json::from_slice(&body)?;
+    let upload_token = prepare_json["upload_token"].as_str().expect("upload token");
+    let reservation_id = prepare_json["reservation_id"]
+        .as_str()
+        .expect("reservation id");
+    for (index, chunk) in chunks.iter().enumerate() {
+        let chunk_hash = format!("{:08x}", crc32c::crc32c(chunk.as_bytes()));
+        let response = app
+            .clone()
+            .oneshot(hashed_chunk_request(
+                room_name,
+                &token,
+                upload_token,
+                index,
+                chunk,
+                Some(&chunk_hash),
+            )?)
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let final_hash = format!("{:08x}", crc32c::crc32c(b"something else"));
+    let response = app
+        .clone()
+        .oneshot(create_http_request(
+            Method::POST,
+            &format!("/api/v1/rooms/{room_name}/uploads/chunks/complete?token={token}"),
+            Some(Body::from(
+                json!({ "reservation_id": reservation_id, "final_hash": final_hash }).to_string(),
+            )),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_chunked_upload_error_handling() -> Result<()> {
     let (app, _pool) = create_test_app().await?;
@@ -432,3 +869,415 @@ async fn test_chunked_upload_prepare_errors() -> Result<()> {
 
     Ok(())
 }
+
+async fn create_room_with_token(app: &axum::Router, room_name: &str) -> Result<String> {
+    let response = app
+        .clone()
+        .oneshot(create_room_request(room_name, None))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .clone()
+        .oneshot(create_http_request(
+            Method::POST,
+            &format!("/api/v1/rooms/{room_name}/tokens"),
+            Some(Body::from(json!({}).to_string())),
+        ))
+        .await?;
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok(serde_json::from_slice::<serde_json::Value>(&body)?["token"]
+        .as_str()
+        .expect("token string")
+        .to_string())
+}
+
+fn tus_request(
+    method: Method,
+    uri: &str,
+    headers: &[(&str, String)],
+    body: &[u8],
+) -> Result<Request<Body>> {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("tus-resumable", "1.0.0");
+    for (name, value) in headers {
+        builder = builder.header(*name, value);
+    }
+    Ok(builder.body(Body::from(body.to_vec()))?)
+}
+
+fn patch_request(
+    location: &str,
+    token: &str,
+    offset: usize,
+    checksum: Option<String>,
+    data: &[u8],
+) -> Result<Request<Body>> {
+    let mut headers = vec![
+        (
+            "content-type",
+            "application/offset+octet-stream".to_string(),
+        ),
+        ("upload-offset", offset.to_string()),
+        ("authorization", format!("Bearer {token}")),
+    ];
+    if let Some(checksum) = checksum {
+        headers.push(("upload-checksum", checksum));
+    }
+    tus_request(Method::PATCH, location, &headers, data)
+}
+
+/// tus 协议：创建、断点查询、分段追加与校验和
+#[tokio::test]
+async fn test_tus_upload_workflow() -> Result<()> {
+    use base64::{Engine as _, engine::general_purpose::STANDARD};
+    use sha1::{Digest as _, Sha1};
+
+    let (app, _pool) = create_test_app().await?;
+    let room_name = "tus_upload_room";
+    let token = create_room_with_token(&app, room_name).await?;
+    let base = format!("/api/v1/rooms/{room_name}/uploads/tus");
+    let auth = ("authorization", format!("Bearer {token}"));
+    let file_data = b"Resumable upload over the tus protocol, sent in two parts.";
+    let (first, second) = file_data.split_at(20);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::OPTIONS)
+                .uri(&base)
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(response.headers()["tus-version"], "1.0.0");
+    assert_eq!(response.headers()["tus-resumable"], "1.0.0");
+    assert!(
+        response.headers()["tus-extension"]
+            .to_str()?
+            .contains("termination")
+    );
+
+    let metadata = format!(
+        "filename {},filetype {}",
+        STANDARD.encode("notes.txt"),
+        STANDARD.encode("text/plain")
+    );
+    let create_headers = [
+        auth.clone(),
+        ("upload-length", file_data.len().to_string()),
+        ("upload-metadata", metadata),
+    ];
+    let mut unversioned = tus_request(Method::POST, &base, &create_headers, b"")?;
+    unversioned.headers_mut().remove("tus-resumable");
+    let response = app.clone().oneshot(unversioned).await?;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    assert_eq!(response.headers()["tus-version"], "1.0.0");
+
+    let response = app
+        .clone()
+        .oneshot(tus_request(Method::POST, &base, &create_headers, b"")?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert!(response.headers().contains_key("upload-expires"));
+    let location = response.headers()["location"].to_str()?.to_string();
+    assert!(location.starts_with(&format!("{base}/")));
+
+    let response = app
+        .clone()
+        .oneshot(tus_request(
+            Method::HEAD,
+            &location,
+            std::slice::from_ref(&auth),
+            b"",
+        )?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["upload-offset"], "0");
+    assert_eq!(
+        response.headers()["upload-length"],
+        file_data.len().to_string().as_str()
+    );
+    assert_eq!(response.headers()["cache-control"], "no-store");
+
+    let checksum = format!("sha1 {}", STANDARD.encode(Sha1::digest(first)));
+    let response = app
+        .clone()
+        .oneshot(patch_request(&location, &token, 0, Some(checksum), first)?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(
+        response.headers()["upload-offset"],
+        first.len().to_string().as_str()
+    );
+
+    // 偏移量不符
+    let response = app
+        .clone()
+        .oneshot(patch_request(&location, &token, 0, None, second)?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // 校验和不符时丢弃本次数据
+    let checksum = format!("sha256 {}", STANDARD.encode(Sha256::digest(first)));
+    let response = app
+        .clone()
+        .oneshot(patch_request(
+            &location,
+            &token,
+            first.len(),
+            Some(checksum),
+            second,
+        )?)
+        .await?;
+    assert_eq!(response.status().as_u16(), 460);
+    assert_eq!(response.headers()["tus-resumable"], "1.0.0");
+
+    let response = app
+        .clone()
+        .oneshot(tus_request(
+            Method::HEAD,
+            &location,
+            std::slice::from_ref(&auth),
+            b"",
+        )?)
+        .await?;
+    assert_eq!(
+        response.headers()["upload-offset"],
+        first.len().to_string().as_str()
+    );
+
+    let checksum = format!("sha256 {}", STANDARD.encode(Sha256::digest(second)));
+    let response = app
+        .clone()
+        .oneshot(patch_request(
+            &location,
+            &token,
+            first.len(),
+            Some(checksum),
+            second,
+        )?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(
+        response.headers()["upload-offset"],
+        file_data.len().to_string().as_str()
+    );
+    let content_location = response.headers()["content-location"].to_str()?.to_string();
+
+    let (file_name, hash): (String, String) =
+        sqlx::query_as("SELECT file_name, hash FROM room_contents WHERE id = $1")
+            .bind(
+                content_location
+                    .rsplit('/')
+                    .next()
+                    .unwrap()
+                    .parse::<i64>()?,
+            )
+            .fetch_one(_pool.as_ref())
+            .await?;
+    assert_eq!(file_name, "notes.txt");
+    assert_eq!(hash, hex::encode(Sha256::digest(file_data)));
+
+    let response = app
+        .clone()
+        .oneshot(create_http_request(
+            Method::GET,
+            &format!("{content_location}?token={token}"),
+            None,
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    assert_eq!(body.as_ref(), file_data);
+
+    // 已完成的上传不再接受数据
+    let response = app
+        .clone()
+        .oneshot(patch_request(
+            &location,
+            &token,
+            file_data.len(),
+            None,
+            b"x",
+        )?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    Ok(())
+}
+
+/// tus 协议：终止上传后释放预留空间
+#[tokio::test]
+async fn test_tus_upload_termination() -> Result<()> {
+    let (app, pool) = create_test_app().await?;
+    let room_name = "tus_termination_room";
+    let token = create_room_with_token(&app, room_name).await?;
+    let base = format!("/api/v1/rooms/{room_name}/uploads/tus");
+    let auth = ("authorization", format!("Bearer {token}"));
+
+    let response = app
+        .clone()
+        .oneshot(tus_request(
+            Method::POST,
+            &base,
+            &[auth.clone(), ("upload-length", "64".to_string())],
+            b"",
+        )?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let location = response.headers()["location"].to_str()?.to_string();
+
+    let response = app
+        .clone()
+        .oneshot(patch_request(&location, &token, 0, None, &[7u8; 100])?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let response = app
+        .clone()
+        .oneshot(patch_request(&location, &token, 0, None, &[7u8; 16])?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let current_size: i64 = sqlx::query_scalar("SELECT current_size FROM rooms WHERE name = $1")
+        .bind(room_name)
+        .fetch_one(pool.as_ref())
+        .await?;
+    assert_eq!(current_size, 64);
+
+    let response = app
+        .clone()
+        .oneshot(tus_request(
+            Method::DELETE,
+            &location,
+            std::slice::from_ref(&auth),
+            b"",
+        )?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = app
+        .clone()
+        .oneshot(tus_request(
+            Method::HEAD,
+            &location,
+            std::slice::from_ref(&auth),
+            b"",
+        )?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let current_size: i64 = sqlx::query_scalar("SELECT current_size FROM rooms WHERE name = $1")
+        .bind(room_name)
+        .fetch_one(pool.as_ref())
+        .await?;
+    assert_eq!(current_size, 0);
+
+    Ok(())
+}
+
+/// tus 协议：同一上传的并发 PATCH 只有一个能写入
+#[tokio::test]
+async fn test_tus_concurrent_patch_conflicts() -> Result<()> {
+    let (app, _pool) = create_test_app().await?;
+    let room_name = "tus_concurrent_room";
+    let token = create_room_with_token(&app, room_name).await?;
+    let base = format!("/api/v1/rooms/{room_name}/uploads/tus");
+    let auth = ("authorization", format!("Bearer {token}"));
+
+    let response = app
+        .clone()
+        .oneshot(tus_request(
+            Method::POST,
+            &base,
+            &[auth.clone(), ("upload-length", "32".to_string())],
+            b"",
+        )?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let location = response.headers()["location"].to_str()?.to_string();
+
+    // 第一个请求的请求体尚未发送完，写入锁一直被占用
+    let (body_tx, body_rx) = tokio::sync::mpsc::channel::<bytes::Bytes>(1);
+    let stream = futures::stream::unfold(body_rx, |mut rx| async move {
+        rx.recv()
+            .await
+            .map(|chunk| (Ok::<_, std::io::Error>(chunk), rx))
+    });
+    let mut slow = patch_request(&location, &token, 0, None, b"")?;
+    *slow.body_mut() = Body::from_stream(stream);
+    let slow = tokio::spawn(app.clone().oneshot(slow));
+    body_tx.send(bytes::Bytes::from_static(&[1u8; 8])).await?;
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let response = app
+        .clone()
+        .oneshot(patch_request(&location, &token, 0, None, &[2u8; 8])?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    drop(body_tx);
+    let response = slow.await??;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(response.headers()["upload-offset"], "8");
+
+    let response = app
+        .clone()
+        .oneshot(patch_request(&location, &token, 8, None, &[3u8; 8])?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(response.headers()["upload-offset"], "16");
+
+    Ok(())
+}
+
+/// tus 协议：写入租约记录在数据库中，其他实例持有租约时 PATCH 返回 409
+#[tokio::test]
+async fn test_tus_patch_respects_lease_from_another_instance() -> Result<()> {
+    let (app, pool) = create_test_app().await?;
+    let room_name = "tus_lease_room";
+    let token = create_room_with_token(&app, room_name).await?;
+    let base = format!("/api/v1/rooms/{room_name}/uploads/tus");
+    let auth = ("authorization", format!("Bearer {token}"));
+
+    let response = app
+        .clone()
+        .oneshot(tus_request(
+            Method::POST,
+            &base,
+            &[auth.clone(), ("upload-length", "8".to_string())],
+            b"",
+        )?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let location = response.headers()["location"].to_str()?.to_string();
+    let upload_id: i64 = location.rsplit('/').next().unwrap().parse()?;
+
+    // 模拟另一个实例上正在写入的请求
+    let repository = RoomUploadReservationRepository::new(pool.clone());
+    let until = chrono::Utc::now().naive_utc() + chrono::Duration::minutes(5);
+    assert!(
+        repository
+            .acquire_write_lease(upload_id, "other-instance", until)
+            .await?
+    );
+
+    let response = app
+        .clone()
+        .oneshot(patch_request(&location, &token, 0, None, &[1u8; 8])?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    repository
+        .release_write_lease(upload_id, "other-instance")
+        .await?;
+    let response = app
+        .clone()
+        .oneshot(patch_request(&location, &token, 0, None, &[1u8; 8])?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(response.headers()["upload-offset"], "8");
+
+    Ok(())
+}
@@ -16,12 +16,28 @@ use board::constants::{
 };
 use board::db::{DbPoolSettings, run_migrations};
 use board::state::AppState;
+use board::storage::StorageBackend;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 const TEST_DB_URL: &str = "sqlite::memory:";
 
 /// 创建测试应用
 pub async fn create_test_app() -> Result<(axum::Router, Arc<board::db::DbPool>)> {
+    create_test_app_with_storage(None).await
+}
+
+/// 创建使用指定存储后端的测试应用，`None` 时使用临时目录上的本地文件系统
+pub async fn create_test_app_with_storage(
+    storage: Option<Arc<dyn StorageBackend>>,
+) -> Result<(axum::Router, Arc<board::db::DbPool>)> {
+    create_test_app_with(storage, |_| {}).await
+}
+
+/// 创建测试应用，并在构建应用状态前调整配置
+pub async fn create_test_app_with(
+    storage: Option<Arc<dyn StorageBackend>>,
+    configure: impl FnOnce(&mut AppConfig),
+) -> Result<(axum::Router, Arc<board::db::DbPool>)> {
     // 创建测试数据库
     let db_pool = Arc::new(
         DbPoolSettings::new(TEST_DB_URL)
@@ -85,12 +101,13 @@ pub async fn create_test_app() -> Result<(axum::Router, Arc<board::db::DbPool>)>
     .await?;
 
     // 创建测试配置
-    let app_config = AppConfig {
+    let mut app_config = AppConfig {
         server: ServerConfig::default(),
         database: board::config::DatabaseConfig::default(),
         storage: StorageConfig {
             root: std::env::temp_dir().join(format!("elizabeth-test-{}", Uuid::new_v4())),
             upload_reservation_ttl_seconds: DEFAULT_UPLOAD_RESERVATION_TTL_SECONDS,
+            ..Default::default()
         },
         room: RoomConfig {
             defaults: board::config::RoomCreationDefaults {
@@ -103,10 +120,15 @@ pub async fn create_test_app() -> Result<(axum::Router, Arc<board::db::DbPool>)>
             expiry: board::config::RoomExpiryPolicy::default(),
         },
         auth: AuthConfig::new("test-secret-key-for-unit-testing-123456789".to_string())?,
+        websocket: Default::default(),
     };
+    configure(&mut app_config);
 
     // 创建应用状态
-    let app_state = Arc::new(AppState::new(app_config, db_pool.clone())?);
+    let app_state = Arc::new(match storage {
+        Some(storage) => AppState::with_storage(app_config, db_pool.clone(), storage)?,
+        None => AppState::new(app_config, db_pool.clone())?,
+    });
 
     // 创建路由
     let (status_router, mut api) = board::route::status::api_router().split_for_parts();
@@ -42,6 +42,7 @@ fn create_test_room(name: &str) -> Room {
         created_at: now,
         updated_at: now,
         permission: RoomPermission::new().with_all(), // 所有权限都允许
+        strip_image_metadata: None,
     }
 }
 
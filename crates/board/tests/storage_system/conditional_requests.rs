@@ -0,0 +1,150 @@
+use anyhow::Result;
+use axum::{
+    Router,
+    body::Body,
+    http::{Method, Request, StatusCode},
+    response::Response,
+};
+use serde_json::json;
+use tower::ServiceExt;
+
+use crate::common::{create_test_app, http::create_request};
+
+use super::{body_bytes, create_room_and_issue_session, download_content, upload_file};
+
+async fn list_contents(
+    app: &Router,
+    room_name: &str,
+    token: &str,
+    if_none_match: Option<&str>,
+) -> Result<Response> {
+    let mut builder = Request::builder()
+        .method(Method::GET)
+        .uri(format!("/api/v1/rooms/{room_name}/contents?token={token}"));
+    if let Some(etag) = if_none_match {
+        builder = builder.header("if-none-match", etag);
+    }
+    Ok(app.clone().oneshot(builder.body(Body::empty())?).await?)
+}
+
+#[tokio::test]
+async fn test_download_honours_etag_and_last_modified() -> Result<()> {
+    let (app, _pool) = create_test_app().await?;
+    let room_name = "conditional_download_room";
+    let session = create_room_and_issue_session(&app, room_name, None).await?;
+    let uploaded = upload_file(
+        &app,
+        room_name,
+        &session.token,
+        "cached.txt",
+        "text/plain",
+        b"cache me",
+    )
+    .await?;
+    let content_id = uploaded["uploaded"][0]["id"].as_i64().expect("content id");
+
+    let first = download_content(&app, &session.token, content_id, &[]).await?;
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(first.headers()["cache-control"], "private, no-cache");
+    let etag = first.headers()["etag"].to_str()?.to_string();
+    let last_modified = first.headers()["last-modified"].to_str()?.to_string();
+    assert!(etag.starts_with('"'));
+
+    let revalidated = download_content(
+        &app,
+        &session.token,
+        content_id,
+        &[("if-none-match", &etag)],
+    )
+    .await?;
+    assert_eq!(revalidated.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(revalidated.headers()["etag"], etag.as_str());
+    assert!(body_bytes(revalidated).await?.is_empty());
+
+    let since = download_content(
+        &app,
+        &session.token,
+        content_id,
+        &[("if-modified-since", &last_modified)],
+    )
+    .await?;
+    assert_eq!(since.status(), StatusCode::NOT_MODIFIED);
+
+    let stale = download_content(
+        &app,
+        &session.token,
+        content_id,
+        &[
+            ("if-none-match", "\"stale\""),
+            ("if-modified-since", &last_modified),
+        ],
+    )
+    .await?;
+    assert_eq!(stale.status(), StatusCode::OK);
+    assert_eq!(body_bytes(stale).await?, b"cache me");
+
+    let resumed = download_content(
+        &app,
+        &session.token,
+        content_id,
+        &[("range", "bytes=6-"), ("if-range", &etag)],
+    )
+    .await?;
+    assert_eq!(resumed.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(body_bytes(resumed).await?, b"me");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_list_contents_etag_tracks_changes() -> Result<()> {
+    let (app, _pool) = create_test_app().await?;
+    let room_name = "conditional_listing_room";
+    let session = create_room_and_issue_session(&app, room_name, None).await?;
+    let uploaded = upload_file(
+        &app,
+        room_name,
+        &session.token,
+        "first.txt",
+        "text/plain",
+        b"first",
+    )
+    .await?;
+    let first_id = uploaded["uploaded"][0]["id"].as_i64().expect("content id");
+
+    let listing = list_contents(&app, room_name, &session.token, None).await?;
+    assert_eq!(listing.status(), StatusCode::OK);
+    assert_eq!(listing.headers()["cache-control"], "private, no-cache");
+    assert_eq!(listing.headers()["content-type"], "application/json");
+    let etag = listing.headers()["etag"].to_str()?.to_string();
+    assert!(etag.starts_with("W/"));
+
+    let unchanged = list_contents(&app, room_name, &session.token, Some(&etag)).await?;
+    assert_eq!(unchanged.status(), StatusCode::NOT_MODIFIED);
+
+    upload_file(
+        &app,
+        room_name,
+        &session.token,
+        "second.txt",
+        "text/plain",
+        b"second",
+    )
+    .await?;
+    let grown = list_contents(&app, room_name, &session.token, Some(&etag)).await?;
+    assert_eq!(grown.status(), StatusCode::OK);
+    let grown_etag = grown.headers()["etag"].to_str()?.to_string();
+    assert_ne!(grown_etag, etag);
+
+    let delete = app
+        .clone()
+        .oneshot(create_request(
+            Method::DELETE,
+            &format!("/api/v1/rooms/{room_name}/contents?token={}", session.token),
+            Some(Body::from(json!({ "ids": [first_id] }).to_string())),
+        ))
+        .await?;
+    assert_eq!(delete.status(), StatusCode::OK);
+    let shrunk = list_contents(&app, room_name, &session.token, Some(&grown_etag)).await?;
+    assert_eq!(shrunk.status(), StatusCode::OK);
+    Ok(())
+}
@@ -0,0 +1,255 @@
+use anyhow::Result;
+use axum::{
+    Router,
+    body::Body,
+    http::{Method, StatusCode},
+};
+use board::repository::StorageBlobRepository;
+use board::services::BlobStore;
+use board::storage::{OpendalBackend, StorageBackend, key::blob_key};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+
+use crate::common::{create_test_app_with, http::create_request};
+
+use super::{
+    body_bytes, create_room_and_issue_session, download_content, response_json, upload_file,
+};
+
+const PAYLOAD: &[u8] = b"the same installer dropped into two rooms";
+
+async fn create_dedup_app() -> Result<(Router, Arc<board::db::DbPool>, opendal::Operator)> {
+    let operator = opendal::Operator::new(opendal::services::Memory::default())?.finish();
+    let storage = Arc::new(OpendalBackend::from_operator(operator.clone()));
+    let (app, pool) = create_test_app_with(Some(storage), |config| {
+        config.storage.dedup = true;
+    })
+    .await?;
+    Ok((app, pool, operator))
+}
+
+async fn ref_count(pool: &board::db::DbPool, hash: &str) -> Result<Option<i64>> {
+    Ok(
+        sqlx::query_scalar("SELECT ref_count FROM storage_blobs WHERE hash = $1")
+            .bind(hash)
+            .fetch_optional(pool)
+            .await?,
+    )
+}
+
+async fn delete_contents(app: &Router, room_name: &str, token: &str, ids: &[i64]) -> Result<Value> {
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            Method::DELETE,
+            &format!("/api/v1/rooms/{room_name}/contents?token={token}"),
+            Some(Body::from(json!({ "ids": ids }).to_string())),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    response_json(response).await
+}
+
+#[tokio::test]
+async fn test_identical_uploads_share_one_blob() -> Result<()> {
+    let (app, pool, operator) = create_dedup_app().await?;
+    let hash = hex::encode(Sha256::digest(PAYLOAD));
+    let key = blob_key(&hash);
+
+    let first = create_room_and_issue_session(&app, "dedup_room_one", None).await?;
+    let second = create_room_and_issue_session(&app, "dedup_room_two", None).await?;
+    let uploaded_first = upload_file(
+        &app,
+        "dedup_room_one",
+        &first.token,
+        "installer.bin",
+        "application/octet-stream",
+        PAYLOAD,
+    )
+    .await?;
+    let uploaded_second = upload_file(
+        &app,
+        "dedup_room_two",
+        &second.token,
+        "copy.bin",
+        "application/octet-stream",
+        PAYLOAD,
+    )
+    .await?;
+
+    // 每个房间仍按逻辑大小计费
+    assert_eq!(uploaded_first["current_size"], PAYLOAD.len());
+    assert_eq!(uploaded_second["current_size"], PAYLOAD.len());
+    assert!(operator.exists(&key).await?);
+    assert_eq!(ref_count(&pool, &hash).await?, Some(2));
+
+    let first_id = uploaded_first["uploaded"][0]["id"].as_i64().expect("id");
+    let second_id = uploaded_second["uploaded"][0]["id"].as_i64().expect("id");
+    let paths: Vec<String> =
+        sqlx::query_scalar("SELECT path FROM room_contents WHERE id IN ($1, $2)")
+            .bind(first_id)
+            .bind(second_id)
+            .fetch_all(pool.as_ref())
+            .await?;
+    assert_eq!(paths.len(), 2);
+    assert_eq!(paths[0], paths[1]);
+
+    let deleted = delete_contents(&app, "dedup_room_one", &first.token, &[first_id]).await?;
+    assert_eq!(deleted["freed_size"], PAYLOAD.len());
+    assert_eq!(deleted["current_size"], 0);
+    assert!(operator.exists(&key).await?);
+    assert_eq!(ref_count(&pool, &hash).await?, Some(1));
+
+    let download = download_content(&app, &second.token, second_id, &[]).await?;
+    assert_eq!(download.status(), StatusCode::OK);
+    assert_eq!(body_bytes(download).await?, PAYLOAD);
+
+    delete_contents(&app, "dedup_room_two", &second.token, &[second_id]).await?;
+    assert!(!operator.exists(&key).await?);
+    assert_eq!(ref_count(&pool, &hash).await?, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_concurrent_deletes_release_reference_once() -> Result<()> {
+    let (app, pool, operator) = create_dedup_app().await?;
+    let hash = hex::encode(Sha256::digest(PAYLOAD));
+    let key = blob_key(&hash);
+
+    let session = create_room_and_issue_session(&app, "dedup_race_room", None).await?;
+    let mut ids = Vec::new();
+    for name in ["a.bin", "b.bin"] {
+        let uploaded = upload_file(
+            &app,
+            "dedup_race_room",
+            &session.token,
+            name,
+            "application/octet-stream",
+            PAYLOAD,
+        )
+        .await?;
+        ids.push(uploaded["uploaded"][0]["id"].as_i64().expect("id"));
+    }
+    assert_eq!(ref_count(&pool, &hash).await?, Some(2));
+
+    // 两个请求删除同一条内容，只有实际删除记录的一方释放引用
+    let uri = format!(
+        "/api/v1/rooms/dedup_race_room/contents?token={}",
+        session.token
+    );
+    let request = || {
+        app.clone().oneshot(create_request(
+            Method::DELETE,
+            &uri,
+            Some(Body::from(json!({ "ids": [ids[0]] }).to_string())),
+        ))
+    };
+    let (first, second) = tokio::join!(request(), request());
+    let mut deleted = 0;
+    for response in [first?, second?] {
+        if response.status() == StatusCode::OK {
+            let body = response_json(response).await?;
+            deleted += body["deleted"].as_array().map_or(0, Vec::len);
+        } else {
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        }
+    }
+    assert_eq!(deleted, 1);
+    assert_eq!(ref_count(&pool, &hash).await?, Some(1));
+    assert!(operator.exists(&key).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_concurrent_first_uploads_write_blob_once() -> Result<()> {
+    let (_app, pool, operator) = create_dedup_app().await?;
+    let storage: Arc<dyn StorageBackend> = Arc::new(OpendalBackend::from_operator(operator));
+    let blob_store = BlobStore::new(
+        Arc::new(StorageBlobRepository::new(pool.clone())),
+        storage.clone(),
+        true,
+    );
+    let hash = hex::encode(Sha256::digest(PAYLOAD));
+    let size = PAYLOAD.len() as i64;
+
+    let first = blob_store.acquire(&hash, size).await?;
+    assert!(first.needs_upload);
+
+    // 第一个调用方写入期间，第二个调用方等待而不是同时写入同一个对象
+    let waiting = tokio::spawn({
+        let blob_store = blob_store.clone();
+        let hash = hash.clone();
+        async move { blob_store.acquire(&hash, size).await }
+    });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!waiting.is_finished());
+
+    blob_store
+        .upload(&first, storage.put(&first.key, PAYLOAD.to_vec()))
+        .await?;
+    let second = waiting.await??;
+    assert!(!second.needs_upload);
+    assert_eq!(second.key, first.key);
+    assert_eq!(ref_count(&pool, &hash).await?, Some(2));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_room_deletion_releases_blob_references() -> Result<()> {
+    let (app, pool, operator) = create_dedup_app().await?;
+    let hash = hex::encode(Sha256::digest(PAYLOAD));
+    let key = blob_key(&hash);
+
+    let kept = create_room_and_issue_session(&app, "dedup_kept_room", None).await?;
+    let doomed = create_room_and_issue_session(&app, "dedup_doomed_room", None).await?;
+    upload_file(
+        &app,
+        "dedup_kept_room",
+        &kept.token,
+        "kept.bin",
+        "application/octet-stream",
+        PAYLOAD,
+    )
+    .await?;
+    // 同一房间内的重复上传也各持有一次引用
+    for name in ["a.bin", "b.bin"] {
+        upload_file(
+            &app,
+            "dedup_doomed_room",
+            &doomed.token,
+            name,
+            "application/octet-stream",
+            PAYLOAD,
+        )
+        .await?;
+    }
+    assert_eq!(ref_count(&pool, &hash).await?, Some(3));
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            Method::DELETE,
+            &format!("/api/v1/rooms/dedup_doomed_room?token={}", doomed.token),
+            None,
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(ref_count(&pool, &hash).await?, Some(1));
+    assert!(operator.exists(&key).await?);
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            Method::DELETE,
+            &format!("/api/v1/rooms/dedup_kept_room?token={}", kept.token),
+            None,
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(ref_count(&pool, &hash).await?, None);
+    assert!(!operator.exists(&key).await?);
+    Ok(())
+}
@@ -3,13 +3,20 @@ use axum::{
     body::Body,
     http::{Method, StatusCode},
 };
+use base64::{Engine, prelude::BASE64_STANDARD};
+use board::storage::OpendalBackend;
 use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
 use tower::ServiceExt;
 
-use crate::common::{create_test_app, http::create_request};
+use crate::common::{
+    create_test_app, create_test_app_with, create_test_app_with_storage, http::create_request,
+};
 
 use super::{
-    create_room, create_room_and_issue_session, issue_session, response_json, upload_file,
+    body_bytes, create_room, create_room_and_issue_session, download_content, issue_session,
+    response_json, upload_file,
 };
 
 #[tokio::test]
@@ -40,6 +47,10 @@ async fn test_file_metadata() -> Result<()> {
     assert_eq!(item["file_name"], "metadata.txt");
     assert_eq!(item["size"], 16);
     assert_eq!(item["mime_type"], "text/plain");
+    assert_eq!(
+        item["hash"],
+        hex::encode(Sha256::digest(b"metadata payload")).as_str()
+    );
     assert!(
         item.get("path").is_none(),
         "storage paths must not leak through the API"
@@ -47,6 +58,46 @@ async fn test_file_metadata() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_download_sends_content_digest() -> Result<()> {
+    let (app, _pool) = create_test_app().await?;
+    let room_name = "content_digest_test_room";
+    let session = create_room_and_issue_session(&app, room_name, None).await?;
+    let payload = b"digest payload";
+    let uploaded = upload_file(
+        &app,
+        room_name,
+        &session.token,
+        "digest.txt",
+        "text/plain",
+        payload,
+    )
+    .await?;
+    let content_id = uploaded["uploaded"][0]["id"].as_i64().expect("content id");
+    let encoded = BASE64_STANDARD.encode(Sha256::digest(payload));
+
+    let response = download_content(&app, &session.token, content_id, &[]).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["repr-digest"],
+        format!("sha-256=:{encoded}:").as_str()
+    );
+    assert_eq!(
+        response.headers()["digest"],
+        format!("SHA-256={encoded}").as_str()
+    );
+
+    let partial =
+        download_content(&app, &session.token, content_id, &[("range", "bytes=0-5")]).await?;
+    assert_eq!(partial.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        partial.headers()["repr-digest"],
+        format!("sha-256=:{encoded}:").as_str()
+    );
+    assert_eq!(body_bytes(partial).await?, b"digest");
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_storage_cleanup() -> Result<()> {
     let (app, pool) = create_test_app().await?;
@@ -129,3 +180,326 @@ async fn test_storage_permissions() -> Result<()> {
     assert_eq!(authorized.status(), StatusCode::OK);
     Ok(())
 }
+
+#[tokio::test]
+async fn test_download_missing_stored_file() -> Result<()> {
+    let (app, pool) = create_test_app().await?;
+    let room_name = "storage_missing_file_room";
+    let session = create_room_and_issue_session(&app, room_name, None).await?;
+    let uploaded = upload_file(
+        &app,
+        room_name,
+        &session.token,
+        "vanished.txt",
+        "text/plain",
+        b"gone soon",
+    )
+    .await?;
+    let content_id = uploaded["uploaded"][0]["id"].as_i64().expect("content id");
+    let path: String = sqlx::query_scalar("SELECT path FROM room_contents WHERE id = $1")
+        .bind(content_id)
+        .fetch_one(pool.as_ref())
+        .await?;
+    tokio::fs::remove_file(&path).await?;
+
+    let download = app
+        .oneshot(create_request(
+            Method::GET,
+            &format!("/api/v1/contents/{content_id}?token={}", session.token),
+            None,
+        ))
+        .await?;
+    assert_eq!(download.status(), StatusCode::NOT_FOUND);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_object_storage_backend_round_trip() -> Result<()> {
+    let operator = opendal::Operator::new(opendal::services::Memory::default())?.finish();
+    let storage = Arc::new(OpendalBackend::from_operator(operator.clone()));
+    let (app, pool) = create_test_app_with_storage(Some(storage)).await?;
+    let room_name = "object_storage_round_trip_room";
+    let session = create_room_and_issue_session(&app, room_name, None).await?;
+    let uploaded = upload_file(
+        &app,
+        room_name,
+        &session.token,
+        "remote.txt",
+        "text/plain",
+        b"stored remotely",
+    )
+    .await?;
+    let content_id = uploaded["uploaded"][0]["id"].as_i64().expect("content id");
+    let (room_id, path): (i64, String) =
+        sqlx::query_as("SELECT room_id, path FROM room_contents WHERE id = $1")
+            .bind(content_id)
+            .fetch_one(pool.as_ref())
+            .await?;
+    let key = format!("{room_id}/remote.txt");
+    assert!(operator.exists(&key).await?);
+    assert!(!tokio::fs::try_exists(&path).await?);
+
+    let download = app
+        .clone()
+        .oneshot(create_request(
+            Method::GET,
+            &format!("/api/v1/contents/{content_id}?token={}", session.token),
+            None,
+        ))
+        .await?;
+    assert_eq!(download.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(download.into_body(), usize::MAX).await?;
+    assert_eq!(body.as_ref(), b"stored remotely");
+
+    let delete = app
+        .oneshot(create_request(
+            Method::DELETE,
+            &format!("/api/v1/rooms/{room_name}/contents?token={}", session.token),
+            Some(Body::from(json!({ "ids": [content_id] }).to_string())),
+        ))
+        .await?;
+    assert_eq!(delete.status(), StatusCode::OK);
+    assert!(!operator.exists(&key).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_content_type_is_detected_from_file_contents() -> Result<()> {
+    let (app, _pool) = create_test_app().await?;
+    let room_name = "mime_sniffing_test_room";
+    let session = create_room_and_issue_session(&app, room_name, None).await?;
+
+    // 伪装成图片的 HTML 页面不能被当作图片，也不能以 HTML 下发
+    let disguised = upload_file(
+        &app,
+        room_name,
+        &session.token,
+        "cat.png",
+        "image/png",
+        b"<!DOCTYPE html><html><script>alert(1)</script></html>",
+    )
+    .await?;
+    let item = &disguised["uploaded"][0];
+    assert_eq!(item["content_type"], json!({ "type": "file" }));
+    assert_eq!(item["mime_type"], "image/png");
+    assert_eq!(item["detected_mime_type"], "text/html");
+
+    let content_id = item["id"].as_i64().expect("content id");
+    let response = download_content(&app, &session.token, content_id, &[]).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/octet-stream"
+    );
+    assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+
+    // 声明为普通文件的 PNG 按检测结果归为图片
+    let png = upload_file(
+        &app,
+        room_name,
+        &session.token,
+        "scan.bin",
+        "application/octet-stream",
+        b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01\x08\x06\0\0\0",
+    )
+    .await?;
+    let item = &png["uploaded"][0];
+    assert_eq!(item["content_type"], json!({ "type": "image" }));
+    assert_eq!(item["detected_mime_type"], "image/png");
+    let content_id = item["id"].as_i64().expect("content id");
+    let response = download_content(&app, &session.token, content_id, &[]).await?;
+    assert_eq!(response.headers()["content-type"], "image/png");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_inline_disposition_is_limited_to_safe_types() -> Result<()> {
+    let (app, _pool) = create_test_app().await?;
+    let room_name = "inline_preview_test_room";
+    let session = create_room_and_issue_session(&app, room_name, None).await?;
+
+    let text = upload_file(
+        &app,
+        room_name,
+        &session.token,
+        "会议纪要.txt",
+        "text/plain",
+        b"plain notes",
+    )
+    .await?;
+    let text_id = text["uploaded"][0]["id"].as_i64().expect("content id");
+    let html = upload_file(
+        &app,
+        room_name,
+        &session.token,
+        "page.html",
+        "text/html",
+        b"<html><body>hi</body></html>",
+    )
+    .await?;
+    let html_id = html["uploaded"][0]["id"].as_i64().expect("content id");
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            Method::GET,
+            &format!(
+                "/api/v1/contents/{text_id}?token={}&disposition=inline",
+                session.token
+            ),
+            None,
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-disposition"],
+        "inline; filename=\"____.txt\"; filename*=UTF-8''%E4%BC%9A%E8%AE%AE%E7%BA%AA%E8%A6%81.txt"
+    );
+    let csp = response.headers()["content-security-policy"].to_str()?;
+    assert!(csp.contains("sandbox"));
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            Method::GET,
+            &format!(
+                "/api/v1/contents/{html_id}?token={}&disposition=inline",
+                session.token
+            ),
+            None,
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response.headers()["content-disposition"]
+            .to_str()?
+            .starts_with("attachment;")
+    );
+    assert!(response.headers().get("content-security-policy").is_none());
+
+    let response = download_content(&app, &session.token, text_id, &[]).await?;
+    assert!(
+        response.headers()["content-disposition"]
+            .to_str()?
+            .starts_with("attachment;")
+    );
+    Ok(())
+}
+
+/// 在 JPEG 开头插入带 GPS 字样的 EXIF 段
+fn jpeg_with_location() -> Vec<u8> {
+    let mut encoded = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::new_rgb8(8, 8)
+        .write_to(&mut encoded, image::ImageFormat::Jpeg)
+        .expect("encode jpeg");
+    let encoded = encoded.into_inner();
+
+    let mut exif = b"Exif\0\0II*\0\x08\0\0\0\0\0\0\0\0\0".to_vec();
+    exif.extend_from_slice(b"GPSLatitude 31.2304N GPSLongitude 121.4737E");
+    let mut jpeg = encoded[..2].to_vec();
+    jpeg.extend_from_slice(&[0xFF, 0xE1]);
+    jpeg.extend_from_slice(&((exif.len() + 2) as u16).to_be_bytes());
+    jpeg.extend_from_slice(&exif);
+    jpeg.extend_from_slice(&encoded[2..]);
+    jpeg
+}
+
+#[tokio::test]
+async fn test_image_location_metadata_is_stripped() -> Result<()> {
+    let (app, _pool) = create_test_app().await?;
+    let photo = jpeg_with_location();
+
+    let room_name = "strip_metadata_test_room";
+    let session = create_room_and_issue_session(&app, room_name, None).await?;
+    let uploaded = upload_file(
+        &app,
+        room_name,
+        &session.token,
+        "photo.jpg",
+        "image/jpeg",
+        &photo,
+    )
+    .await?;
+    let item = &uploaded["uploaded"][0];
+    let size = item["size"].as_i64().expect("size");
+    assert!(size < photo.len() as i64);
+    assert_eq!(uploaded["current_size"], size);
+
+    let content_id = item["id"].as_i64().expect("content id");
+    let stored = body_bytes(download_content(&app, &session.token, content_id, &[]).await?).await?;
+    assert_eq!(stored.len() as i64, size);
+    assert!(!stored.windows(3).any(|window| window == b"GPS"));
+    assert_eq!(item["hash"], hex::encode(Sha256::digest(&stored)).as_str());
+
+    // 房间关闭该选项后原样保存
+    let keep_room = "keep_metadata_test_room";
+    let session = create_room_and_issue_session(&app, keep_room, None).await?;
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            Method::PUT,
+            &format!("/api/v1/rooms/{keep_room}/settings?token={}", session.token),
+            Some(Body::from(
+                json!({ "strip_image_metadata": false }).to_string(),
+            )),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response_json(response).await?["strip_image_metadata"],
+        false
+    );
+
+    let uploaded = upload_file(
+        &app,
+        keep_room,
+        &session.token,
+        "photo.jpg",
+        "image/jpeg",
+        &photo,
+    )
+    .await?;
+    assert_eq!(uploaded["uploaded"][0]["size"], photo.len() as i64);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_downloads_wait_for_content_scan() -> Result<()> {
+    let (app, pool) = create_test_app_with(None, |config| {
+        config.storage.scan.enabled = true;
+    })
+    .await?;
+    let room_name = "content_scan_room";
+    let session = create_room_and_issue_session(&app, room_name, None).await?;
+    let uploaded = upload_file(
+        &app,
+        room_name,
+        &session.token,
+        "report.pdf",
+        "application/pdf",
+        b"%PDF-1.7\n",
+    )
+    .await?;
+    let item = &uploaded["uploaded"][0];
+    assert_eq!(item["scan_status"], "pending");
+    let content_id = item["id"].as_i64().expect("content id");
+
+    let response = download_content(&app, &session.token, content_id, &[]).await?;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let set_status = |status: i64| {
+        sqlx::query("UPDATE room_contents SET scan_status = $1 WHERE id = $2")
+            .bind(status)
+            .bind(content_id)
+            .execute(pool.as_ref())
+    };
+    set_status(2).await?;
+    let response = download_content(&app, &session.token, content_id, &[]).await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    set_status(0).await?;
+    let response = download_content(&app, &session.token, content_id, &[]).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_bytes(response).await?, b"%PDF-1.7\n");
+    Ok(())
+}
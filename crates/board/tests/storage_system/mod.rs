@@ -1,5 +1,9 @@
+mod conditional_requests;
 mod content_management;
+mod deduplication;
 mod file_metadata;
+mod range_downloads;
+mod room_archive;
 mod upload_and_paths;
 
 use anyhow::Result;
@@ -7,6 +11,7 @@ use axum::{
     Router,
     body::Body,
     http::{Method, Request, StatusCode},
+    response::Response,
 };
 use serde_json::{Value, json};
 use tower::ServiceExt;
@@ -139,3 +144,24 @@ pub(crate) async fn response_json(response: axum::response::Response) -> Result<
     let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
     Ok(serde_json::from_slice(&body)?)
 }
+
+pub(crate) async fn download_content(
+    app: &Router,
+    token: &str,
+    content_id: i64,
+    headers: &[(&str, &str)],
+) -> Result<Response> {
+    let mut builder = Request::builder()
+        .method(Method::GET)
+        .uri(format!("/api/v1/contents/{content_id}?token={token}"));
+    for (name, value) in headers {
+        builder = builder.header(*name, *value);
+    }
+    Ok(app.clone().oneshot(builder.body(Body::empty())?).await?)
+}
+
+pub(crate) async fn body_bytes(response: Response) -> Result<Vec<u8>> {
+    Ok(axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await?
+        .to_vec())
+}
@@ -0,0 +1,105 @@
+use anyhow::Result;
+use axum::{Router, http::StatusCode};
+
+use crate::common::create_test_app;
+
+use super::{body_bytes, create_room_and_issue_session, download_content, upload_file};
+
+const CONTENTS: &[u8] = b"0123456789abcdefghij";
+
+async fn uploaded_content(room_name: &str) -> Result<(Router, String, i64)> {
+    let (app, _pool) = create_test_app().await?;
+    let session = create_room_and_issue_session(&app, room_name, None).await?;
+    let uploaded = upload_file(
+        &app,
+        room_name,
+        &session.token,
+        "range.bin",
+        "application/octet-stream",
+        CONTENTS,
+    )
+    .await?;
+    let content_id = uploaded["uploaded"][0]["id"].as_i64().expect("content id");
+    Ok((app, session.token, content_id))
+}
+
+#[tokio::test]
+async fn test_full_download_advertises_byte_ranges() -> Result<()> {
+    let (app, token, content_id) = uploaded_content("range_full_room").await?;
+
+    let response = download_content(&app, &token, content_id, &[]).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["accept-ranges"], "bytes");
+    assert!(response.headers().contains_key("last-modified"));
+    assert!(!response.headers().contains_key("content-range"));
+    assert_eq!(body_bytes(response).await?, CONTENTS);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_range_download_returns_partial_content() -> Result<()> {
+    let (app, token, content_id) = uploaded_content("range_partial_room").await?;
+
+    let response = download_content(&app, &token, content_id, &[("range", "bytes=2-5")]).await?;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.headers()["content-range"], "bytes 2-5/20");
+    assert_eq!(response.headers()["content-length"], "4");
+    assert_eq!(body_bytes(response).await?, b"2345");
+
+    let response = download_content(&app, &token, content_id, &[("range", "bytes=-3")]).await?;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.headers()["content-range"], "bytes 17-19/20");
+    assert_eq!(body_bytes(response).await?, b"hij");
+
+    let response = download_content(&app, &token, content_id, &[("range", "bytes=15-")]).await?;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(body_bytes(response).await?, b"fghij");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unsatisfiable_and_multi_ranges_are_rejected() -> Result<()> {
+    let (app, token, content_id) = uploaded_content("range_unsatisfiable_room").await?;
+
+    for range in ["bytes=20-", "bytes=0-1,4-5"] {
+        let response = download_content(&app, &token, content_id, &[("range", range)]).await?;
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers()["content-range"], "bytes */20");
+    }
+
+    // 未知单位按 RFC 9110 忽略，返回完整内容
+    let response = download_content(&app, &token, content_id, &[("range", "items=0-1")]).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_if_range_resumes_only_unchanged_content() -> Result<()> {
+    let (app, token, content_id) = uploaded_content("range_if_range_room").await?;
+    let first = download_content(&app, &token, content_id, &[]).await?;
+    let last_modified = first.headers()["last-modified"].to_str()?.to_string();
+
+    let response = download_content(
+        &app,
+        &token,
+        content_id,
+        &[("range", "bytes=10-"), ("if-range", &last_modified)],
+    )
+    .await?;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(body_bytes(response).await?, b"abcdefghij");
+
+    let response = download_content(
+        &app,
+        &token,
+        content_id,
+        &[
+            ("range", "bytes=10-"),
+            ("if-range", "Wed, 21 Oct 2015 07:28:00 GMT"),
+        ],
+    )
+    .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_bytes(response).await?, CONTENTS);
+    Ok(())
+}
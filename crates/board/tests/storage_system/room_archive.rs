@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use async_zip::base::read::mem::ZipFileReader;
+use axum::{
+    Router,
+    body::Body,
+    http::{Method, StatusCode},
+    response::Response,
+};
+use serde_json::json;
+use tower::ServiceExt;
+
+use crate::common::{create_test_app, http::create_request};
+
+use super::{body_bytes, create_room_and_issue_session, upload_file};
+
+async fn download_archive(
+    app: &Router,
+    room_name: &str,
+    token: &str,
+    ids: Option<&str>,
+) -> Result<Response> {
+    let mut uri = format!("/api/v1/rooms/{room_name}/contents/archive?token={token}");
+    if let Some(ids) = ids {
+        uri.push_str(&format!("&ids={ids}"));
+    }
+    Ok(app
+        .clone()
+        .oneshot(create_request(Method::GET, &uri, None))
+        .await?)
+}
+
+/// 解压归档，返回 条目名 -> 内容
+async fn unzip(bytes: Vec<u8>) -> Result<BTreeMap<String, Vec<u8>>> {
+    let reader = ZipFileReader::new(bytes).await?;
+    let mut entries = BTreeMap::new();
+    for index in 0..reader.file().entries().len() {
+        let mut entry = reader.reader_with_entry(index).await?;
+        let name = entry.entry().filename().as_str()?.to_string();
+        let mut data = Vec::new();
+        entry.read_to_end_checked(&mut data).await?;
+        entries.insert(name, data);
+    }
+    Ok(entries)
+}
+
+async fn post_json(app: &Router, uri: &str, body: serde_json::Value) -> Result<()> {
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            Method::POST,
+            uri,
+            Some(Body::from(body.to_string())),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_room_archive_contains_files_messages_and_links() -> Result<()> {
+    let (app, _pool) = create_test_app().await?;
+    let room_name = "archive_everything_room";
+    let session = create_room_and_issue_session(&app, room_name, None).await?;
+    let token = &session.token;
+
+    upload_file(&app, room_name, token, "notes.txt", "text/plain", b"first").await?;
+    upload_file(&app, room_name, token, "notes.txt", "text/plain", b"second").await?;
+    post_json(
+        &app,
+        &format!("/api/v1/rooms/{room_name}/messages?token={token}"),
+        json!({ "text": "hello from the room" }),
+    )
+    .await?;
+    post_json(
+        &app,
+        &format!("/api/v1/rooms/{room_name}/contents/url?token={token}"),
+        json!({ "url": "https://example.com/spec", "name": "Spec" }),
+    )
+    .await?;
+
+    let response = download_archive(&app, room_name, token, None).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/zip");
+    assert_eq!(
+        response.headers()["content-disposition"],
+        format!("attachment; filename=\"{room_name}.zip\"").as_str()
+    );
+    assert!(response.headers().get("content-length").is_none());
+
+    let entries = unzip(body_bytes(response).await?).await?;
+    assert_eq!(
+        entries.keys().map(String::as_str).collect::<Vec<_>>(),
+        ["links.txt", "messages.md", "notes(1).txt", "notes.txt"]
+    );
+    assert_eq!(entries["notes.txt"], b"first");
+    assert_eq!(entries["notes(1).txt"], b"second");
+    let messages = String::from_utf8(entries["messages.md"].clone())?;
+    assert!(messages.starts_with(&format!("# {room_name}\n")));
+    assert!(messages.contains("hello from the room"));
+    assert_eq!(entries["links.txt"], b"Spec\thttps://example.com/spec\n");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_room_archive_honours_selection() -> Result<()> {
+    let (app, _pool) = create_test_app().await?;
+    let room_name = "archive_selection_room";
+    let session = create_room_and_issue_session(&app, room_name, None).await?;
+    let token = &session.token;
+
+    let kept = upload_file(&app, room_name, token, "keep.txt", "text/plain", b"keep").await?;
+    upload_file(&app, room_name, token, "skip.txt", "text/plain", b"skip").await?;
+    let kept_id = kept["uploaded"][0]["id"].as_i64().expect("content id");
+
+    let response = download_archive(&app, room_name, token, Some(&kept_id.to_string())).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let entries = unzip(body_bytes(response).await?).await?;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries["keep.txt"], b"keep");
+
+    let missing = download_archive(&app, room_name, token, Some("999999")).await?;
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    let invalid = download_archive(&app, room_name, token, Some("1,abc")).await?;
+    assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+    let unauthorized = download_archive(&app, room_name, "invalid_token", None).await?;
+    assert_eq!(unauthorized.status(), StatusCode::UNAUTHORIZED);
+    Ok(())
+}
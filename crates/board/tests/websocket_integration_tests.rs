@@ -5,9 +5,17 @@
 
 use board::websocket::broadcaster::Broadcaster;
 use board::websocket::connection::ConnectionManager;
+use board::websocket::outbound::{Outbound, OutboundReceiver};
 use board::websocket::types::{RoomInfo, RoomUpdateReason, WsMessage, WsMessageType};
 use std::sync::Arc;
-use tokio::sync::mpsc;
+
+/// 取出下一条广播消息
+async fn recv_message(rx: &mut OutboundReceiver) -> Option<WsMessage> {
+    match rx.recv().await {
+        Some(Outbound::Message(message)) => Some(message),
+        _ => None,
+    }
+}
 
 // ============================================================================
 // 完整 WebSocket 连接流程测试
@@ -19,7 +27,7 @@ async fn test_complete_websocket_connection_flow() {
     let manager = ConnectionManager::new();
     let room_name = "integration-test-room".to_string();
     let connection_id = "conn-integration-1".to_string();
-    let (tx, mut rx) = mpsc::unbounded_channel::<WsMessage>();
+    let (tx, mut rx) = manager.outbound_channel();
 
     // 步骤 1: 订阅房间
     let subscribe_result = manager
@@ -34,7 +42,7 @@ async fn test_complete_websocket_connection_flow() {
     assert_eq!(broadcast_result.unwrap(), 1, "应该有 1 个接收者");
 
     // 步骤 3: 接收消息验证
-    let received = rx.recv().await;
+    let received = recv_message(&mut rx).await;
     assert!(received.is_some(), "应该收到消息");
 
     // 步骤 4: 断开连接
@@ -57,8 +65,8 @@ async fn test_message_send_and_receive() {
     let room_name = "msg-test-room".to_string();
 
     // 创建多个连接
-    let (tx1, mut rx1) = mpsc::unbounded_channel::<WsMessage>();
-    let (tx2, mut rx2) = mpsc::unbounded_channel::<WsMessage>();
+    let (tx1, mut rx1) = manager.outbound_channel();
+    let (tx2, mut rx2) = manager.outbound_channel();
 
     manager
         .subscribe_to_room("conn-msg-1".to_string(), room_name.clone(), tx1)
@@ -77,8 +85,8 @@ async fn test_message_send_and_receive() {
         .unwrap();
 
     // 验证两个连接都收到消息
-    let msg1 = rx1.recv().await;
-    let msg2 = rx2.recv().await;
+    let msg1 = recv_message(&mut rx1).await;
+    let msg2 = recv_message(&mut rx2).await;
     assert!(msg1.is_some(), "连接 1 应该收到消息");
     assert!(msg2.is_some(), "连接 2 应该收到消息");
     assert_eq!(msg1.unwrap().message_type, WsMessageType::Ping);
@@ -98,8 +106,8 @@ async fn test_room_subscription_and_broadcast() {
     let room2 = "broadcast-room-2".to_string();
 
     // 连接到不同房间
-    let (tx1, mut rx1) = mpsc::unbounded_channel::<WsMessage>();
-    let (tx2, mut rx2) = mpsc::unbounded_channel::<WsMessage>();
+    let (tx1, mut rx1) = manager.outbound_channel();
+    let (tx2, mut rx2) = manager.outbound_channel();
 
     manager
         .subscribe_to_room("conn-broadcast-1".to_string(), room1.clone(), tx1)
@@ -127,10 +135,14 @@ async fn test_room_subscription_and_broadcast() {
         .unwrap();
 
     // 验证只有房间 1 的连接收到消息
-    let msg1 = rx1.recv().await;
+    let msg1 = recv_message(&mut rx1).await;
 
     // 使用 timeout 避免无限等待
-    let msg2 = tokio::time::timeout(std::time::Duration::from_millis(100), rx2.recv()).await;
+    let msg2 = tokio::time::timeout(
+        std::time::Duration::from_millis(100),
+        recv_message(&mut rx2),
+    )
+    .await;
 
     assert!(msg1.is_some(), "房间 1 的连接应该收到消息");
     assert!(
@@ -162,7 +174,7 @@ async fn test_authentication_flow() {
     let mut _receivers = Vec::new(); // 保留接收端
 
     for client_id in clients {
-        let (tx, rx) = mpsc::unbounded_channel::<WsMessage>();
+        let (tx, rx) = manager.outbound_channel();
         _receivers.push(rx); // 保留接收端
         manager
             .subscribe_to_room(client_id.to_string(), room_name.clone(), tx)
@@ -198,8 +210,8 @@ async fn test_error_handling() {
     manager.disconnect("non-existent-conn").await; // 应该不 panic
 
     // 测试重复订阅同一个连接 ID（实际行为取决于实现）
-    let (tx1, _rx) = mpsc::unbounded_channel::<WsMessage>();
-    let (tx2, _rx) = mpsc::unbounded_channel::<WsMessage>();
+    let (tx1, _rx) = manager.outbound_channel();
+    let (tx2, _rx) = manager.outbound_channel();
 
     let result1 = manager
         .subscribe_to_room("conn-dup".to_string(), room_name.clone(), tx1)
@@ -223,7 +235,7 @@ async fn test_concurrent_connections() {
 
     // 顺序创建多个连接（简化版，避免并发问题）
     for i in 0..10 {
-        let (tx, rx) = mpsc::unbounded_channel::<WsMessage>();
+        let (tx, rx) = manager.outbound_channel();
         _receivers.push(rx); // 保留接收端
         let conn_id = format!("conn-concurrent-{}", i);
         manager
@@ -249,7 +261,7 @@ async fn test_connection_lifecycle() {
     let room_name = "lifecycle-room".to_string();
 
     // 创建连接
-    let (tx, mut rx) = mpsc::unbounded_channel::<WsMessage>();
+    let (tx, mut rx) = manager.outbound_channel();
     let conn_id = "conn-lifecycle".to_string();
 
     manager
@@ -265,7 +277,7 @@ async fn test_connection_lifecycle() {
         .unwrap();
 
     // 验证收到消息
-    assert!(rx.recv().await.is_some(), "应该收到消息");
+    assert!(recv_message(&mut rx).await.is_some(), "应该收到消息");
 
     // 断开连接
     manager.disconnect(&conn_id).await;
@@ -324,7 +336,7 @@ async fn test_broadcast_performance() {
 
     // 创建 20 个连接（简化版）
     for i in 0..20 {
-        let (tx, rx) = mpsc::unbounded_channel::<WsMessage>();
+        let (tx, rx) = manager.outbound_channel();
         _receivers.push(rx); // 保留接收端
         let conn_id = format!("conn-perf-{}", i);
         manager
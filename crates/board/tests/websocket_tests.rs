@@ -3,13 +3,25 @@
 //!
 //! 测试 ConnectionManager、Broadcaster 和 MessageHandler
 
-use board::models::room::content::{ContentType, RoomContent};
+use board::config::OverflowPolicy;
+use board::models::room::content::{
+    ContentType, IntegrityStatus, RoomContent, ScanStatus, ThumbnailStatus,
+};
 use board::websocket::broadcaster::Broadcaster;
-use board::websocket::connection::ConnectionManager;
+use board::websocket::connection::{ConnectionManager, ConnectionManagerConfig};
+use board::websocket::event_bus::EventBus;
+use board::websocket::outbound::{Outbound, OutboundReceiver, SLOW_CONSUMER_CLOSE_CODE};
 use board::websocket::types::{RoomInfo, RoomUpdateReason, WsError, WsMessage, WsMessageType};
 use chrono::Utc;
 use std::sync::Arc;
-use tokio::sync::mpsc;
+
+/// 取出下一条广播消息
+async fn recv_message(rx: &mut OutboundReceiver) -> Option<WsMessage> {
+    match rx.recv().await {
+        Some(Outbound::Message(message)) => Some(message),
+        _ => None,
+    }
+}
 
 // ============================================================================
 // ConnectionManager 测试
@@ -26,7 +38,7 @@ async fn test_subscribe_to_room() {
     let manager = ConnectionManager::new();
     let room_name = "test-room".to_string();
     let connection_id = "conn-1".to_string();
-    let (tx, _rx) = mpsc::unbounded_channel::<WsMessage>();
+    let (tx, _rx) = manager.outbound_channel();
 
     let result = manager
         .subscribe_to_room(connection_id, room_name, tx)
@@ -40,7 +52,7 @@ async fn test_broadcast_to_room() {
     let manager = ConnectionManager::new();
     let room_name = "test-room".to_string();
     let connection_id = "conn-1".to_string();
-    let (tx, mut rx) = mpsc::unbounded_channel::<WsMessage>();
+    let (tx, mut rx) = manager.outbound_channel();
 
     // 订阅房间
     manager
@@ -56,7 +68,7 @@ async fn test_broadcast_to_room() {
     assert_eq!(result.unwrap(), 1, "should broadcast to 1 connection");
 
     // 验证消息已发送
-    let received: Option<WsMessage> = rx.recv().await;
+    let received: Option<WsMessage> = recv_message(&mut rx).await;
     assert!(received.is_some(), "should receive message");
 }
 
@@ -65,7 +77,7 @@ async fn test_disconnect() {
     let manager = ConnectionManager::new();
     let room_name = "test-room".to_string();
     let connection_id = "conn-1".to_string();
-    let (tx, _rx) = mpsc::unbounded_channel::<WsMessage>();
+    let (tx, _rx) = manager.outbound_channel();
 
     // 订阅房间
     manager
@@ -94,9 +106,9 @@ async fn test_multiple_subscribers_same_room() {
     let room_name = "test-room".to_string();
 
     // 创建多个连接
-    let (tx1, mut rx1) = mpsc::unbounded_channel::<WsMessage>();
-    let (tx2, mut rx2) = mpsc::unbounded_channel::<WsMessage>();
-    let (tx3, mut rx3) = mpsc::unbounded_channel::<WsMessage>();
+    let (tx1, mut rx1) = manager.outbound_channel();
+    let (tx2, mut rx2) = manager.outbound_channel();
+    let (tx3, mut rx3) = manager.outbound_channel();
 
     manager
         .subscribe_to_room("conn-1".to_string(), room_name.clone(), tx1)
@@ -119,15 +131,84 @@ async fn test_multiple_subscribers_same_room() {
     assert_eq!(result.unwrap(), 3, "should broadcast to 3 connections");
 
     // 验证所有连接都收到消息
-    let msg1: Option<WsMessage> = rx1.recv().await;
-    let msg2: Option<WsMessage> = rx2.recv().await;
-    let msg3: Option<WsMessage> = rx3.recv().await;
+    let msg1: Option<WsMessage> = recv_message(&mut rx1).await;
+    let msg2: Option<WsMessage> = recv_message(&mut rx2).await;
+    let msg3: Option<WsMessage> = recv_message(&mut rx3).await;
 
     assert!(msg1.is_some(), "conn-1 should receive message");
     assert!(msg2.is_some(), "conn-2 should receive message");
     assert!(msg3.is_some(), "conn-3 should receive message");
 }
 
+#[tokio::test]
+async fn test_slow_consumer_is_disconnected_when_queue_is_full() {
+    let manager = ConnectionManager::with_config(ConnectionManagerConfig {
+        send_queue_depth: 2,
+        overflow_policy: OverflowPolicy::Disconnect,
+        ..Default::default()
+    });
+    let room_name = "slow-room".to_string();
+    let (slow_tx, mut slow_rx) = manager.outbound_channel();
+    let (fast_tx, mut fast_rx) = manager.outbound_channel();
+    manager
+        .subscribe_to_room("slow".to_string(), room_name.clone(), slow_tx)
+        .await
+        .unwrap();
+    manager
+        .subscribe_to_room("fast".to_string(), room_name.clone(), fast_tx)
+        .await
+        .unwrap();
+
+    for _ in 0..3 {
+        let message = WsMessage::new(WsMessageType::ContentCreated, None);
+        manager
+            .broadcast_to_room(&room_name, message)
+            .await
+            .unwrap();
+        assert!(recv_message(&mut fast_rx).await.is_some());
+    }
+
+    match slow_rx.recv().await {
+        Some(Outbound::Close { code, .. }) => assert_eq!(code, SLOW_CONSUMER_CLOSE_CODE),
+        other => panic!("slow consumer should be closed, got {other:?}"),
+    }
+    assert_eq!(manager.get_room_connection_count(&room_name).await, 1);
+    let metrics = manager.get_metrics().await;
+    assert_eq!(metrics.forced_disconnects, 1);
+    assert_eq!(metrics.dropped_messages, 0);
+    assert_eq!(metrics.total_disconnections, 1);
+}
+
+#[tokio::test]
+async fn test_drop_oldest_counts_dropped_messages() {
+    let manager = ConnectionManager::with_config(ConnectionManagerConfig {
+        send_queue_depth: 1,
+        overflow_policy: OverflowPolicy::DropOldest,
+        ..Default::default()
+    });
+    let room_name = "lossy-room".to_string();
+    let (tx, mut rx) = manager.outbound_channel();
+    manager
+        .subscribe_to_room("conn-1".to_string(), room_name.clone(), tx)
+        .await
+        .unwrap();
+
+    for message_type in [WsMessageType::ContentCreated, WsMessageType::RoomUpdate] {
+        let delivered = manager
+            .broadcast_to_room(&room_name, WsMessage::new(message_type, None))
+            .await
+            .unwrap();
+        assert_eq!(delivered, 1);
+    }
+
+    let received = recv_message(&mut rx).await.unwrap();
+    assert_eq!(received.message_type, WsMessageType::RoomUpdate);
+    let metrics = manager.get_metrics().await;
+    assert_eq!(metrics.dropped_messages, 1);
+    assert_eq!(metrics.forced_disconnects, 0);
+    assert_eq!(metrics.active_connections, 1);
+}
+
 // ============================================================================
 // Broadcaster 测试
 // ============================================================================
@@ -143,7 +224,12 @@ fn create_test_content() -> RoomContent {
         file_name: None,
         size: None,
         mime_type: None,
+        detected_mime_type: None,
+        hash: None,
         sequence_number: 0,
+        thumbnail_status: ThumbnailStatus::None,
+        scan_status: ScanStatus::Clean,
+        integrity_status: IntegrityStatus::Ok,
         created_at: Utc::now().naive_utc(),
         updated_at: Utc::now().naive_utc(),
     }
@@ -155,7 +241,7 @@ async fn test_broadcaster_content_created() {
     let broadcaster = Broadcaster::new(manager.clone());
     let room_name = "test-room".to_string();
     let connection_id = "conn-1".to_string();
-    let (tx, mut rx) = mpsc::unbounded_channel::<WsMessage>();
+    let (tx, mut rx) = manager.outbound_channel();
 
     // 订阅房间
     manager
@@ -171,7 +257,7 @@ async fn test_broadcaster_content_created() {
         .unwrap();
 
     // 验证收到消息
-    let received: Option<WsMessage> = rx.recv().await;
+    let received: Option<WsMessage> = recv_message(&mut rx).await;
     assert!(received.is_some(), "should receive message");
     let msg = received.unwrap();
     assert_eq!(msg.message_type, WsMessageType::ContentCreated);
@@ -192,7 +278,7 @@ async fn test_broadcaster_content_deleted_includes_content_metadata() {
     let broadcaster = Broadcaster::new(manager.clone());
     let room_name = "test-room".to_string();
     let connection_id = "conn-1".to_string();
-    let (tx, mut rx) = mpsc::unbounded_channel::<WsMessage>();
+    let (tx, mut rx) = manager.outbound_channel();
 
     manager
         .subscribe_to_room(connection_id, room_name.clone(), tx)
@@ -205,7 +291,7 @@ async fn test_broadcaster_content_deleted_includes_content_metadata() {
         .await
         .unwrap();
 
-    let received: Option<WsMessage> = rx.recv().await;
+    let received: Option<WsMessage> = recv_message(&mut rx).await;
     assert!(received.is_some(), "should receive message");
     let msg = received.unwrap();
     assert_eq!(msg.message_type, WsMessageType::ContentDeleted);
@@ -226,7 +312,7 @@ async fn test_broadcaster_user_joined() {
     let broadcaster = Broadcaster::new(manager.clone());
     let room_name = "test-room".to_string();
     let connection_id = "conn-1".to_string();
-    let (tx, mut rx) = mpsc::unbounded_channel::<WsMessage>();
+    let (tx, mut rx) = manager.outbound_channel();
 
     manager
         .subscribe_to_room(connection_id, room_name.clone(), tx)
@@ -238,7 +324,7 @@ async fn test_broadcaster_user_joined() {
         .await
         .unwrap();
 
-    let received: Option<WsMessage> = rx.recv().await;
+    let received: Option<WsMessage> = recv_message(&mut rx).await;
     assert!(received.is_some(), "should receive message");
     let msg = received.unwrap();
     assert_eq!(msg.message_type, WsMessageType::UserJoined);
@@ -250,7 +336,7 @@ async fn test_broadcaster_user_left() {
     let broadcaster = Broadcaster::new(manager.clone());
     let room_name = "test-room".to_string();
     let connection_id = "conn-1".to_string();
-    let (tx, mut rx) = mpsc::unbounded_channel::<WsMessage>();
+    let (tx, mut rx) = manager.outbound_channel();
 
     manager
         .subscribe_to_room(connection_id, room_name.clone(), tx)
@@ -262,7 +348,7 @@ async fn test_broadcaster_user_left() {
         .await
         .unwrap();
 
-    let received: Option<WsMessage> = rx.recv().await;
+    let received: Option<WsMessage> = recv_message(&mut rx).await;
     assert!(received.is_some(), "should receive message");
     let msg = received.unwrap();
     assert_eq!(msg.message_type, WsMessageType::UserLeft);
@@ -274,7 +360,7 @@ async fn test_broadcaster_room_update() {
     let broadcaster = Broadcaster::new(manager.clone());
     let room_name = "test-room".to_string();
     let connection_id = "conn-1".to_string();
-    let (tx, mut rx) = mpsc::unbounded_channel::<WsMessage>();
+    let (tx, mut rx) = manager.outbound_channel();
 
     manager
         .subscribe_to_room(connection_id, room_name.clone(), tx)
@@ -296,7 +382,7 @@ async fn test_broadcaster_room_update() {
         .await
         .unwrap();
 
-    let received: Option<WsMessage> = rx.recv().await;
+    let received: Option<WsMessage> = recv_message(&mut rx).await;
     assert!(received.is_some(), "should receive message");
     let msg = received.unwrap();
     assert_eq!(msg.message_type, WsMessageType::RoomUpdate);
@@ -309,6 +395,65 @@ async fn test_broadcaster_room_update() {
     );
 }
 
+/// 记录发布事件的事件总线，模拟跨实例转发
+#[derive(Default)]
+struct RecordingEventBus {
+    published: tokio::sync::Mutex<Vec<(String, WsMessage)>>,
+}
+
+#[async_trait::async_trait]
+impl EventBus for RecordingEventBus {
+    async fn publish(
+        &self,
+        room_name: &str,
+        message: WsMessage,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        self.published
+            .lock()
+            .await
+            .push((room_name.to_string(), message));
+        Ok(0)
+    }
+}
+
+#[tokio::test]
+async fn test_broadcaster_publishes_through_event_bus() {
+    let bus = Arc::new(RecordingEventBus::default());
+    let broadcaster = Broadcaster::with_event_bus(bus.clone());
+
+    let content = create_test_content();
+    broadcaster
+        .broadcast_content_created("test-room", &content)
+        .await
+        .unwrap();
+    let room_info = RoomInfo {
+        id: 1,
+        name: "test-room".to_string(),
+        slug: "test-room".to_string(),
+        max_size: 1024,
+        current_size: 0,
+        max_times_entered: 100,
+        current_times_entered: 1,
+    };
+    broadcaster
+        .broadcast_room_update("test-room", &room_info, RoomUpdateReason::SettingsChanged)
+        .await
+        .unwrap();
+
+    let published = bus.published.lock().await;
+    let types: Vec<_> = published
+        .iter()
+        .map(|(room, message)| (room.as_str(), message.message_type.clone()))
+        .collect();
+    assert_eq!(
+        types,
+        vec![
+            ("test-room", WsMessageType::ContentCreated),
+            ("test-room", WsMessageType::RoomUpdate),
+        ]
+    );
+}
+
 // ============================================================================
 // MessageHandler 测试
 // ============================================================================
@@ -3,8 +3,9 @@ mod human_duration;
 
 pub use app::{
     AppConfig, CompressionConfig, CorsConfig, DatabaseConfig, DefaultRoomConfig, GcConfig,
-    JwtConfig, LoggingConfig, MiddlewareConfig, RateLimitConfig, RequestIdConfig, RoomConfig,
-    RoomExpiryConfig, RoomPermissionConfig, SecurityConfig, ServerConfig, StorageConfig,
-    TracingConfig, UploadConfig,
+    JwtConfig, LoggingConfig, MiddlewareConfig, RateLimitConfig, ReconcileConfig, RequestIdConfig,
+    RoomConfig, RoomExpiryConfig, RoomPermissionConfig, S3StorageConfig, ScanConfig, ScrubConfig,
+    SecurityConfig, ServerConfig, StorageConfig, ThumbnailConfig, TracingConfig, UploadConfig,
+    WebsocketConfig,
 };
 pub use human_duration::HumanDuration;
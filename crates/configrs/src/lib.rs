@@ -4,9 +4,10 @@ use std::{fs::OpenOptions, path::PathBuf};
 use config::{FileFormat, FileSourceFile, builder::DefaultState};
 pub use configs::{
     AppConfig, CompressionConfig, CorsConfig, DatabaseConfig, DefaultRoomConfig, GcConfig,
-    HumanDuration, JwtConfig, LoggingConfig, MiddlewareConfig, RateLimitConfig, RequestIdConfig,
-    RoomConfig, RoomExpiryConfig, RoomPermissionConfig, SecurityConfig, ServerConfig,
-    StorageConfig, TracingConfig, UploadConfig,
+    HumanDuration, JwtConfig, LoggingConfig, MiddlewareConfig, RateLimitConfig, ReconcileConfig,
+    RequestIdConfig, RoomConfig, RoomExpiryConfig, RoomPermissionConfig, S3StorageConfig,
+    ScanConfig, ScrubConfig, SecurityConfig, ServerConfig, StorageConfig, ThumbnailConfig,
+    TracingConfig, UploadConfig, WebsocketConfig,
 };
 pub use error::{ConfigError, Result};
 use merge::Merge;
@@ -3,6 +3,9 @@
  *
  * This service handles chat message operations using the Content API.
  * Messages are stored as RoomContent with content_type = ContentType.Text (0)
+ *
+ * While the room WebSocket is connected, sending, editing and deleting go over
+ * the socket instead of separate HTTP requests; REST is the fallback.
  */
 
 import { API_ENDPOINTS } from "../lib/config";
@@ -15,12 +18,67 @@ import type {
   MessagePage,
   UpdateContentResponse,
 } from "../lib/types";
+import {
+  WsMessageType,
+  WsNotConnectedError,
+  WsRequestError,
+  type WsRequestType,
+} from "../lib/hooks/use-websocket";
 import {
   backendContentToMessage as convertMessage,
   ContentType as CT,
   parseContentType,
 } from "../lib/types";
 
+// ============================================================================
+// WebSocket Transport
+// ============================================================================
+
+type SocketRequest = <T = unknown>(
+  type: WsRequestType,
+  payload: Record<string, unknown>,
+) => Promise<T>;
+
+let socketTransport: { roomName: string; request: SocketRequest } | null = null;
+
+/**
+ * Register the connected room WebSocket for message operations.
+ * Pass null when the socket disconnects.
+ */
+export function setMessageSocket(
+  transport: { roomName: string; request: SocketRequest } | null,
+): void {
+  socketTransport = transport;
+}
+
+/**
+ * Send a request over the room WebSocket. Returns undefined when the socket
+ * cannot be used, so the caller falls back to REST. The socket authenticates
+ * with the token used to connect, so an explicit token always goes over REST,
+ * and a rejected (expired) token is retried over REST, which refreshes it.
+ */
+async function viaSocket<T>(
+  roomName: string,
+  token: string | undefined,
+  type: WsRequestType,
+  payload: Record<string, unknown>,
+): Promise<T | undefined> {
+  if (token || !socketTransport || socketTransport.roomName !== roomName) {
+    return undefined;
+  }
+  try {
+    return await socketTransport.request<T>(type, payload);
+  } catch (error) {
+    if (
+      error instanceof WsNotConnectedError ||
+      (error instanceof WsRequestError && error.status === 401)
+    ) {
+      return undefined;
+    }
+    throw error;
+  }
+}
+
 // ============================================================================
 // Message Functions
 // ============================================================================
@@ -83,19 +141,30 @@ export async function postMessage(
   sequenceNumber?: number,
   token?: string,
 ): Promise<Message> {
+  const contentString = typeof content === "string"
+    ? content
+    : String(content);
+  const body = { text: contentString, sequence_number: sequenceNumber };
+
+  const viaWs = await viaSocket<CreateMessageResponse>(
+    roomName,
+    token,
+    WsMessageType.SendMessage,
+    body,
+  );
+  if (viaWs) {
+    return { ...convertMessage(viaWs.message), isOwn: true };
+  }
+
   const authToken = token || await getValidToken(roomName);
 
   if (!authToken) {
     throw new Error("Authentication required to send messages");
   }
 
-  const contentString = typeof content === "string"
-    ? content
-    : String(content);
-
   const response = await api.post<CreateMessageResponse>(
     API_ENDPOINTS.content.messages(roomName),
-    { text: contentString, sequence_number: sequenceNumber },
+    body,
     { token: authToken },
   );
 
@@ -116,6 +185,11 @@ export async function deleteMessage(
   messageId: string,
   token?: string,
 ): Promise<void> {
+  const ids = [parseInt(messageId, 10)];
+  if (await viaSocket(roomName, token, WsMessageType.DeleteContent, { ids })) {
+    return;
+  }
+
   const authToken = token || await getValidToken(roomName);
 
   if (!authToken) {
@@ -141,6 +215,11 @@ export async function deleteMessages(
   messageIds: string[],
   token?: string,
 ): Promise<void> {
+  const ids = messageIds.map((id) => parseInt(id, 10));
+  if (await viaSocket(roomName, token, WsMessageType.DeleteContent, { ids })) {
+    return;
+  }
+
   const authToken = token || await getValidToken(roomName);
 
   if (!authToken) {
@@ -172,6 +251,16 @@ export async function updateMessage(
   content: string,
   token?: string,
 ): Promise<Message> {
+  const viaWs = await viaSocket<UpdateContentResponse>(
+    roomName,
+    token,
+    WsMessageType.UpdateContent,
+    { content_id: parseInt(messageId, 10), text: content },
+  );
+  if (viaWs) {
+    return convertMessage(viaWs.updated);
+  }
+
   const authToken = token || await getValidToken(roomName);
 
   if (!authToken) {
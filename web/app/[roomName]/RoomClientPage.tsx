@@ -13,6 +13,7 @@ import { useIsMobile } from "@/hooks/use-mobile";
 import { useAppStore } from "@/lib/store";
 import { RoomPasswordDialog } from "@/components/room/room-password-dialog";
 import { getRoomDetails } from "@/api/roomService";
+import { setMessageSocket } from "@/api/messageService";
 import { getAccessToken, hasValidToken, validateToken } from "@/api/authService";
 import { clearRoomToken, getRoomTokenString } from "@/lib/utils/api";
 import { LoadingSpinner } from "@/components/ui/loading-spinner";
@@ -75,6 +76,9 @@ function RoomRealtimeSync({
   const refreshLatestMessages = useAppStore((state) =>
     state.refreshLatestMessages
   );
+  const displayName = useAppStore((state) => state.displayName);
+  const setRoomPresence = useAppStore((state) => state.setRoomPresence);
+  const setTypingNotifier = useAppStore((state) => state.setTypingNotifier);
 
   const notifyContentChange = (
     action: DesktopNotificationAction,
@@ -125,10 +129,19 @@ function RoomRealtimeSync({
     });
   };
 
-  useRoomEvents({
+  const {
+    connected,
+    request,
+    connectionId,
+    presence,
+    typing,
+    notifyTyping,
+    stopTyping,
+  } = useRoomEvents({
     wsUrl: resolveWebSocketUrl(),
     roomName,
     token,
+    displayName: displayName.trim() || undefined,
     enableCacheInvalidation: true,
     onContentCreated: (payload) => {
       notifyContentChange("created", payload);
@@ -163,6 +176,28 @@ function RoomRealtimeSync({
     },
   });
 
+  // Send, edit and delete messages over the socket while it is connected
+  useEffect(() => {
+    if (!connected) return;
+    setMessageSocket({ roomName, request });
+    return () => setMessageSocket(null);
+  }, [connected, request, roomName]);
+
+  // Share presence and typing state with the room layout
+  useEffect(() => {
+    setRoomPresence({ connectionId, connections: presence, typing });
+  }, [connectionId, presence, typing, setRoomPresence]);
+
+  useEffect(() => {
+    setTypingNotifier({ notify: notifyTyping, stop: stopTyping });
+    return () => setTypingNotifier(null);
+  }, [notifyTyping, stopTyping, setTypingNotifier]);
+
+  useEffect(() => {
+    return () =>
+      setRoomPresence({ connectionId: null, connections: [], typing: [] });
+  }, [setRoomPresence]);
+
   return null;
 }
 
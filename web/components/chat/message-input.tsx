@@ -15,6 +15,7 @@ import {
   type MinimalTiptapEditorMethods,
 } from "./minimal-tiptap-editor";
 import { useTranslations } from "next-intl";
+import { RoomPresence } from "@/components/room/room-presence";
 
 interface MessageInputProps {
   onSend: (content: string) => void;
@@ -55,19 +56,32 @@ export function MessageInput(
   const sendOnEnter = useAppStore((state) => state.sendOnEnter);
   const content = useAppStore((state) => state.composerContent);
   const setContent = useAppStore((state) => state.setComposerContent);
+  const typingNotifier = useAppStore((state) => state.typingNotifier);
   // const diffMarkdown = editingMessage?.originalContent ?? editingMessage?.content;
 
   const handleSend = useCallback(() => {
     const latestContent = editorRef.current?.getMarkdown() ?? content;
     const sendable = getSendableContent(latestContent);
     if (!sendable || isLoading) return;
+    typingNotifier?.stop();
     onSend(sendable);
     setIsExpanded(false);
-  }, [content, isLoading, onSend]);
+  }, [content, isLoading, onSend, typingNotifier]);
+
+  const handleChange = useCallback((value: string) => {
+    setContent(value);
+    if (getSendableContent(value)) {
+      typingNotifier?.notify();
+    } else {
+      typingNotifier?.stop();
+    }
+  }, [setContent, typingNotifier]);
 
   return (
     <>
       <div className="bg-background h-full flex flex-col p-1">
+        <RoomPresence />
+
         {/* Editing Banner */}
         {editingMessage && (
           <div className="shrink-0 mb-2 flex items-center justify-between rounded-lg bg-muted/70 px-3 py-1.5 text-xs text-muted-foreground border border-border/40">
@@ -87,7 +101,7 @@ export function MessageInput(
           <MinimalTiptapEditor
             ref={editorRef}
             value={content}
-            onChange={setContent}
+            onChange={handleChange}
             onRequestSend={handleSend}
             disabled={isLoading}
             placeholder={sendOnEnter
@@ -150,7 +164,7 @@ export function MessageInput(
           <div className="flex-1 min-h-0 bg-transparent p-0">
             <MinimalTiptapEditor
               value={content}
-              onChange={setContent}
+              onChange={handleChange}
               onRequestSend={handleSend}
               disabled={isLoading}
               placeholder={t("messageInput.placeholderDefault")}
@@ -0,0 +1,54 @@
+"use client";
+
+import { Users } from "lucide-react";
+import { useTranslations } from "next-intl";
+import { useAppStore } from "@/lib/store";
+
+/**
+ * Who is connected to the room and who is typing, from the room WebSocket
+ */
+export function RoomPresence() {
+  const t = useTranslations("room");
+  const { connectionId, connections, typing } = useAppStore((state) =>
+    state.roomPresence
+  );
+
+  if (connections.length === 0) {
+    return null;
+  }
+
+  const nameOf = (name?: string | null) => name?.trim() || t("presence.anonymous");
+  const onlineNames = connections
+    .map((entry) =>
+      entry.connection_id === connectionId
+        ? t("presence.you", { name: nameOf(entry.display_name) })
+        : nameOf(entry.display_name)
+    )
+    .join(", ");
+
+  const typists = typing.map((entry) => nameOf(entry.display_name));
+  const typingText = typists.length === 0
+    ? null
+    : typists.length === 1
+    ? t("presence.typingOne", { name: typists[0] })
+    : typists.length === 2
+    ? t("presence.typingTwo", { first: typists[0], second: typists[1] })
+    : t("presence.typingMany", { count: typists.length });
+
+  return (
+    <div
+      className="shrink-0 mb-1 flex items-center justify-between gap-3 px-1 text-xs text-muted-foreground"
+      data-testid="room-presence"
+    >
+      <span className="flex items-center gap-1" title={onlineNames}>
+        <Users className="h-3 w-3" />
+        {t("presence.online", { count: connections.length })}
+      </span>
+      {typingText && (
+        <span className="truncate animate-pulse" aria-live="polite">
+          {typingText}
+        </span>
+      )}
+    </div>
+  );
+}
@@ -130,6 +130,8 @@ export function SettingsDialog({ children }: { children: React.ReactNode }) {
   const {
     sendOnEnter,
     setSendOnEnter,
+    displayName,
+    setDisplayName,
     includeMetadataInCopy,
     setIncludeMetadataInCopy,
     includeMetadataInDownload,
@@ -241,6 +243,21 @@ export function SettingsDialog({ children }: { children: React.ReactNode }) {
                 />
               </SettingRow>
 
+              <SettingRow
+                id="display-name"
+                label={t("displayName.label")}
+                description={t("displayName.description")}
+              >
+                <Input
+                  id="display-name"
+                  value={displayName}
+                  maxLength={64}
+                  placeholder={t("displayName.placeholder")}
+                  onChange={(event) => setDisplayName(event.target.value)}
+                  className="w-40"
+                />
+              </SettingRow>
+
               <SettingRow
                 id="use-heti"
                 label={t("useHeti.label")}
@@ -9,15 +9,21 @@
  * - Handle CONTENT_CREATED, CONTENT_UPDATED, CONTENT_DELETED events
  * - Automatic TanStack Query cache invalidation
  * - User presence tracking (USER_JOINED, USER_LEFT)
+ * - Full refresh when missed events cannot be replayed (RESYNC_REQUIRED)
+ * - Presence list and typing indicators (PRESENCE, TYPING)
  */
 
-import { useCallback, useEffect } from "react";
+import { useCallback, useEffect, useRef, useState } from "react";
 import { useQueryClient } from "@tanstack/react-query";
 
 import { useWebSocket, WsMessageType, type WsMessage } from "./use-websocket";
 import type {
+  ConnectAck,
   ContentEventPayload,
+  PresenceEntry,
+  PresencePayload,
   RoomUpdatePayload,
+  TypingEventPayload,
   UserEventPayload,
 } from "./use-websocket";
 import { ContentType, parseContentType } from "../types";
@@ -51,6 +57,19 @@ export const queryKeys = {
   roomSettings: (roomName: string) => ["room", roomName] as const,
 } as const;
 
+// ============================================================================
+// Configuration
+// ============================================================================
+
+const TYPING_CONFIG = {
+  /** Minimum gap between "typing" notifications we send */
+  SEND_INTERVAL: 2000,
+  /** Send "stopped typing" after this long without input */
+  IDLE_TIMEOUT: 3000,
+  /** Hide someone else's indicator if no update arrives in time */
+  DISPLAY_TIMEOUT: 5000,
+} as const;
+
 // ============================================================================
 // Hook Options
 // ============================================================================
@@ -62,6 +81,8 @@ export interface UseRoomEventsOptions {
   roomName: string;
   /** Room token for authentication */
   token: string;
+  /** Name shown to other connections in the presence list */
+  displayName?: string;
   /** Callback when content is created */
   onContentCreated?: (payload: ContentEventPayload) => void;
   /** Callback when content is updated */
@@ -100,6 +121,7 @@ export function useRoomEvents(options: UseRoomEventsOptions) {
     wsUrl,
     roomName,
     token,
+    displayName,
     onContentCreated,
     onContentUpdated,
     onContentDeleted,
@@ -112,6 +134,36 @@ export function useRoomEvents(options: UseRoomEventsOptions) {
 
   const queryClient = useQueryClient();
 
+  // Presence of this room; connectionId identifies our own entry
+  const [connectionId, setConnectionId] = useState<string | null>(null);
+  const [presence, setPresence] = useState<PresenceEntry[]>([]);
+  const [typing, setTyping] = useState<TypingEventPayload[]>([]);
+  const typingTimersRef = useRef(new Map<string, NodeJS.Timeout>());
+
+  /**
+   * Remove a connection's typing indicator
+   */
+  const clearTyping = useCallback((id: string) => {
+    const timer = typingTimersRef.current.get(id);
+    if (timer) {
+      clearTimeout(timer);
+      typingTimersRef.current.delete(id);
+    }
+    setTyping((prev) => prev.filter((entry) => entry.connection_id !== id));
+  }, []);
+
+  /**
+   * Forget presence and typing state, e.g. after the socket closes
+   */
+  const resetPresence = useCallback(() => {
+    for (const timer of typingTimersRef.current.values()) {
+      clearTimeout(timer);
+    }
+    typingTimersRef.current.clear();
+    setPresence([]);
+    setTyping([]);
+  }, []);
+
   /**
    * Invalidate relevant queries when content changes
    */
@@ -165,18 +217,86 @@ export function useRoomEvents(options: UseRoomEventsOptions) {
         break;
       }
 
+      case WsMessageType.ConnectAck: {
+        const ack = message.payload as ConnectAck;
+        if (ack.success) {
+          setConnectionId(ack.connection_id ?? null);
+          setPresence(ack.presence ?? []);
+        }
+        break;
+      }
+
       case WsMessageType.UserJoined: {
         const payload = message.payload as UserEventPayload;
+        // Token issuance also emits USER_JOINED, only connections are present
+        const joinedId = payload.connection_id;
+        if (joinedId) {
+          setPresence((prev) =>
+            prev.some((entry) => entry.connection_id === joinedId) ? prev : [
+              ...prev,
+              {
+                connection_id: joinedId,
+                user_id: payload.user_id,
+                display_name: payload.display_name,
+                connected_at: message.timestamp,
+              },
+            ]
+          );
+        }
         onUserJoined?.(payload);
         break;
       }
 
       case WsMessageType.UserLeft: {
         const payload = message.payload as UserEventPayload;
+        const leftId = payload.connection_id;
+        if (leftId) {
+          setPresence((prev) => prev.filter((entry) => entry.connection_id !== leftId));
+          clearTyping(leftId);
+        }
         onUserLeft?.(payload);
         break;
       }
 
+      case WsMessageType.Presence: {
+        const payload = message.payload as PresencePayload;
+        setPresence(payload.connections);
+        break;
+      }
+
+      case WsMessageType.Typing: {
+        const payload = message.payload as TypingEventPayload;
+        const typistId = payload.connection_id;
+        if (typistId === connectionId) break;
+        // Connections missing from the presence snapshot show up once they type
+        setPresence((prev) =>
+          prev.some((entry) => entry.connection_id === typistId) ? prev : [
+            ...prev,
+            {
+              connection_id: typistId,
+              user_id: payload.user_id,
+              display_name: payload.display_name,
+              connected_at: message.timestamp,
+            },
+          ]
+        );
+        if (!payload.is_typing) {
+          clearTyping(typistId);
+          break;
+        }
+        const timer = typingTimersRef.current.get(typistId);
+        if (timer) clearTimeout(timer);
+        typingTimersRef.current.set(
+          typistId,
+          setTimeout(() => clearTyping(typistId), TYPING_CONFIG.DISPLAY_TIMEOUT),
+        );
+        setTyping((prev) => [
+          ...prev.filter((entry) => entry.connection_id !== typistId),
+          payload,
+        ]);
+        break;
+      }
+
       case WsMessageType.RoomUpdate: {
         const payload = message.payload as RoomUpdatePayload;
         onRoomUpdate?.(payload);
@@ -184,6 +304,17 @@ export function useRoomEvents(options: UseRoomEventsOptions) {
         break;
       }
 
+      case WsMessageType.ResyncRequired: {
+        if (enableCacheInvalidation) {
+          queryClient.invalidateQueries({
+            queryKey: queryKeys.roomFiles(roomName),
+          });
+        }
+        invalidateRoomQueries();
+        onReconnected?.();
+        break;
+      }
+
       default:
         // Ignore other message types (PING, PONG, CONNECT_ACK, etc.)
         break;
@@ -195,8 +326,14 @@ export function useRoomEvents(options: UseRoomEventsOptions) {
     onUserJoined,
     onUserLeft,
     onRoomUpdate,
+    onReconnected,
+    roomName,
+    queryClient,
+    enableCacheInvalidation,
     invalidateContentQueries,
     invalidateRoomQueries,
+    connectionId,
+    clearTyping,
   ]);
 
   /**
@@ -206,6 +343,7 @@ export function useRoomEvents(options: UseRoomEventsOptions) {
     url: wsUrl,
     token,
     roomName,
+    displayName,
     onMessage: handleMessage,
     onReconnected,
     enableReconnect: true,
@@ -223,8 +361,89 @@ export function useRoomEvents(options: UseRoomEventsOptions) {
     }
   }, [ws.connected, ws.error, roomName]);
 
+  /**
+   * Effect: Presence is rebuilt from the next CONNECT_ACK
+   */
+  const { connected, sendMessage } = ws;
+  useEffect(() => {
+    if (!connected) {
+      resetPresence();
+    }
+  }, [connected, resetPresence]);
+
+  // Our own typing state, throttled before it reaches the server
+  const lastTypingSentRef = useRef(0);
+  const typingIdleTimerRef = useRef<NodeJS.Timeout | null>(null);
+
+  const sendTyping = useCallback((isTyping: boolean) => {
+    if (!connected) return;
+    sendMessage({
+      message_type: WsMessageType.Typing,
+      payload: { is_typing: isTyping },
+      timestamp: Date.now(),
+    });
+  }, [connected, sendMessage]);
+
+  /**
+   * Signal that we stopped typing (message sent, input cleared)
+   */
+  const stopTyping = useCallback(() => {
+    if (typingIdleTimerRef.current) {
+      clearTimeout(typingIdleTimerRef.current);
+      typingIdleTimerRef.current = null;
+    }
+    if (lastTypingSentRef.current !== 0) {
+      lastTypingSentRef.current = 0;
+      sendTyping(false);
+    }
+  }, [sendTyping]);
+
+  /**
+   * Signal that we are typing; call on every input change
+   */
+  const notifyTyping = useCallback(() => {
+    const now = Date.now();
+    if (now - lastTypingSentRef.current >= TYPING_CONFIG.SEND_INTERVAL) {
+      lastTypingSentRef.current = now;
+      sendTyping(true);
+    }
+    if (typingIdleTimerRef.current) {
+      clearTimeout(typingIdleTimerRef.current);
+    }
+    typingIdleTimerRef.current = setTimeout(stopTyping, TYPING_CONFIG.IDLE_TIMEOUT);
+  }, [sendTyping, stopTyping]);
+
+  /**
+   * Ask the server for the current presence list
+   */
+  const refreshPresence = useCallback(() => {
+    if (!connected) return;
+    sendMessage({
+      message_type: WsMessageType.Presence,
+      timestamp: Date.now(),
+    });
+  }, [connected, sendMessage]);
+
+  useEffect(() => {
+    const typingTimers = typingTimersRef.current;
+    return () => {
+      if (typingIdleTimerRef.current) {
+        clearTimeout(typingIdleTimerRef.current);
+      }
+      for (const timer of typingTimers.values()) {
+        clearTimeout(timer);
+      }
+    };
+  }, []);
+
   return {
     ...ws,
+    connectionId,
+    presence,
+    typing,
+    notifyTyping,
+    stopTyping,
+    refreshPresence,
     /**
      * Manually trigger a refresh of all room-related queries
      */
@@ -242,5 +461,6 @@ export function useRoomEvents(options: UseRoomEventsOptions) {
 // ============================================================================
 
 export type { WsMessage, WsMessageType, ContentEventPayload, UserEventPayload };
+export type { PresenceEntry, TypingEventPayload };
 export type { RoomUpdatePayload };
 export { useWebSocket } from "./use-websocket";
@@ -9,10 +9,14 @@
  * - Exponential backoff reconnection
  * - Heartbeat (PING/PONG) handling
  * - Event message handling
+ * - Replay of events missed while disconnected
+ * - Request/acknowledgement for chat messages and content operations
+ * - Presence snapshot and typing indicators
  */
 
 import { useCallback, useEffect, useRef, useState } from "react";
 import type { BackendContentType } from "../types";
+import { generateUUID } from "../utils/uuid";
 
 // ============================================================================
 // Type Definitions (matching backend WebSocket protocol)
@@ -33,6 +37,64 @@ export enum WsMessageType {
   UserJoined = "user_joined",
   UserLeft = "user_left",
   RoomUpdate = "room_update",
+  ResyncRequired = "resync_required",
+  SendMessage = "send_message",
+  UpdateContent = "update_content",
+  DeleteContent = "delete_content",
+  Ack = "ack",
+  Presence = "presence",
+  Typing = "typing",
+}
+
+/**
+ * Client requests answered with an ACK (or ERROR) carrying the same request_id
+ */
+export type WsRequestType =
+  | WsMessageType.SendMessage
+  | WsMessageType.UpdateContent
+  | WsMessageType.DeleteContent;
+
+/**
+ * Acknowledgement payload; `result` is the REST response body of the same operation
+ */
+export interface RequestAck<T = unknown> {
+  request_id: string;
+  result: T;
+}
+
+/**
+ * Error payload for a failed request
+ */
+export interface RequestErrorPayload {
+  request_id?: string;
+  error: string;
+  code?: string;
+  status?: number;
+}
+
+/**
+ * Error thrown when a request is made while the socket is not open; nothing was sent
+ */
+export class WsNotConnectedError extends Error {
+  constructor() {
+    super("WebSocket is not connected");
+    this.name = "WsNotConnectedError";
+  }
+}
+
+/**
+ * Error thrown when the server rejects a WebSocket request
+ */
+export class WsRequestError extends Error {
+  readonly code?: string;
+  readonly status?: number;
+
+  constructor(payload: RequestErrorPayload) {
+    super(payload.error);
+    this.name = "WsRequestError";
+    this.code = payload.code;
+    this.status = payload.status;
+  }
 }
 
 /**
@@ -42,6 +104,8 @@ export interface WsMessage {
   message_type: WsMessageType;
   payload?: unknown;
   timestamp: number;
+  /** Room event id, present on events that can be replayed after reconnecting */
+  event_id?: number;
 }
 
 /**
@@ -50,6 +114,10 @@ export interface WsMessage {
 export interface ConnectRequest {
   token: string;
   room_name: string;
+  /** Last event id seen before the connection dropped */
+  last_event_id?: number;
+  /** Name shown to other connections in the presence list */
+  display_name?: string;
 }
 
 /**
@@ -59,6 +127,30 @@ export interface ConnectAck {
   success: boolean;
   message: string;
   room_info?: RoomInfo;
+  latest_event_id?: number;
+  /** Token id, same as user_id in USER_JOINED / USER_LEFT */
+  user_id?: string;
+  /** Id of this connection */
+  connection_id?: string;
+  /** Connections in the room when this one joined, including itself */
+  presence?: PresenceEntry[];
+}
+
+/**
+ * A live connection in the room presence list
+ */
+export interface PresenceEntry {
+  connection_id: string;
+  user_id: string;
+  display_name?: string | null;
+  /** Unix seconds */
+  connected_at: number;
+}
+
+export interface PresencePayload {
+  room_name: string;
+  connections: PresenceEntry[];
+  request_id?: string;
 }
 
 /**
@@ -102,6 +194,17 @@ export interface ContentEventPayload {
 export interface UserEventPayload {
   user_id: string;
   room_name: string;
+  /** Present when the event is about a WebSocket connection rather than a token */
+  connection_id?: string;
+  display_name?: string | null;
+}
+
+export interface TypingEventPayload {
+  user_id: string;
+  room_name: string;
+  connection_id: string;
+  display_name?: string | null;
+  is_typing: boolean;
 }
 
 // ============================================================================
@@ -113,6 +216,7 @@ const WS_CONFIG = {
   BASE_RECONNECT_DELAY: 1000, // 1 second
   MAX_RECONNECT_DELAY: 30000, // 30 seconds
   HEARTBEAT_INTERVAL: 30000, // 30 seconds
+  REQUEST_TIMEOUT: 10000, // 10 seconds
 } as const;
 
 // ============================================================================
@@ -126,6 +230,8 @@ export interface UseWebSocketOptions {
   token: string;
   /** Room name */
   roomName: string;
+  /** Name shown to other connections in the presence list, sent on the next CONNECT */
+  displayName?: string;
   /** Callback when connection is established */
   onOpen?: (event: Event) => void;
   /** Callback when a message is received */
@@ -161,6 +267,11 @@ export interface UseWebSocketReturn {
   reconnect: () => void;
   /** Send a message through the WebSocket */
   sendMessage: (message: WsMessage) => void;
+  /** Send a request and wait for its ACK; rejects on ERROR, timeout or disconnect */
+  request: <T = unknown>(
+    type: WsRequestType,
+    payload: Record<string, unknown>,
+  ) => Promise<T>;
   /** Close the WebSocket connection */
   disconnect: () => void;
 }
@@ -203,6 +314,7 @@ export function useWebSocket(options: UseWebSocketOptions): UseWebSocketReturn {
     url,
     token,
     roomName,
+    displayName,
     onOpen,
     onMessage,
     onClose,
@@ -220,6 +332,18 @@ export function useWebSocket(options: UseWebSocketOptions): UseWebSocketReturn {
   const isManualCloseRef = useRef(false);
   const connectingRef = useRef(false);
   const connectRef = useRef<() => void>(() => {});
+  // Last room event received, sent on reconnect so the server can replay missed events
+  const lastEventIdRef = useRef<number | null>(null);
+  // Read on each CONNECT so renaming does not force a reconnect
+  const displayNameRef = useRef(displayName);
+  // Requests waiting for their ACK, keyed by request_id
+  const pendingRequestsRef = useRef(
+    new Map<string, {
+      resolve: (result: unknown) => void;
+      reject: (error: Error) => void;
+      timeout: NodeJS.Timeout;
+    }>(),
+  );
 
   type CallbackRefs = Pick<
     UseWebSocketOptions,
@@ -269,6 +393,76 @@ export function useWebSocket(options: UseWebSocketOptions): UseWebSocketReturn {
     }
   }, []);
 
+  /**
+   * Send a request and resolve with the result of its ACK
+   */
+  const request = useCallback(<T = unknown>(
+    type: WsRequestType,
+    payload: Record<string, unknown>,
+  ): Promise<T> => {
+    const ws = wsRef.current;
+    if (ws?.readyState !== WebSocket.OPEN) {
+      return Promise.reject(new WsNotConnectedError());
+    }
+
+    const requestId = generateUUID();
+    return new Promise<T>((resolve, reject) => {
+      const timeout = setTimeout(() => {
+        pendingRequestsRef.current.delete(requestId);
+        reject(new Error(`WebSocket request ${type} timed out`));
+      }, WS_CONFIG.REQUEST_TIMEOUT);
+      pendingRequestsRef.current.set(requestId, {
+        resolve: resolve as (result: unknown) => void,
+        reject,
+        timeout,
+      });
+      const message: WsMessage = {
+        message_type: type,
+        payload: { ...payload, request_id: requestId },
+        timestamp: Date.now(),
+      };
+      ws.send(JSON.stringify(message));
+    });
+  }, []);
+
+  /**
+   * Settle the pending request a reply belongs to; returns false for other messages
+   */
+  const settleRequest = useCallback((message: WsMessage): boolean => {
+    if (
+      message.message_type !== WsMessageType.Ack &&
+      message.message_type !== WsMessageType.Error
+    ) {
+      return false;
+    }
+    const requestId = (message.payload as { request_id?: string } | undefined)
+      ?.request_id;
+    const pending = requestId ? pendingRequestsRef.current.get(requestId) : undefined;
+    if (!requestId || !pending) {
+      return false;
+    }
+
+    pendingRequestsRef.current.delete(requestId);
+    clearTimeout(pending.timeout);
+    if (message.message_type === WsMessageType.Ack) {
+      pending.resolve((message.payload as RequestAck).result);
+    } else {
+      pending.reject(new WsRequestError(message.payload as RequestErrorPayload));
+    }
+    return true;
+  }, []);
+
+  /**
+   * Reject every pending request, the connection they were sent on is gone
+   */
+  const rejectPendingRequests = useCallback((reason: string) => {
+    for (const pending of pendingRequestsRef.current.values()) {
+      clearTimeout(pending.timeout);
+      pending.reject(new Error(reason));
+    }
+    pendingRequestsRef.current.clear();
+  }, []);
+
   /**
    * Send PONG in response to PING
    */
@@ -344,18 +538,23 @@ export function useWebSocket(options: UseWebSocketOptions): UseWebSocketReturn {
         setReconnectAttempt(0);
 
         // Send CONNECT message
+        const lastEventId = lastEventIdRef.current;
         const connectMessage: WsMessage = {
           message_type: WsMessageType.Connect,
           payload: {
             token,
             room_name: roomName,
+            last_event_id: lastEventId ?? undefined,
+            display_name: displayNameRef.current || undefined,
         } as ConnectRequest,
         timestamp: Date.now(),
       };
         ws.send(JSON.stringify(connectMessage));
 
         callbacksRef.current.onOpen?.(event);
-        if (wasReconnecting) {
+        // Missed events are replayed (or RESYNC_REQUIRED is sent) when the
+        // last event id is known; otherwise the caller has to refetch
+        if (wasReconnecting && lastEventId === null) {
           callbacksRef.current.onReconnected?.();
         }
       };
@@ -375,6 +574,9 @@ export function useWebSocket(options: UseWebSocketOptions): UseWebSocketReturn {
           if (message.message_type === WsMessageType.ConnectAck) {
             const ack = message.payload as ConnectAck;
             if (ack.success) {
+              if (lastEventIdRef.current === null && ack.latest_event_id != null) {
+                lastEventIdRef.current = ack.latest_event_id;
+              }
               startHeartbeat();
             } else {
               setError(new Error(ack.message || "Connection failed"));
@@ -382,6 +584,23 @@ export function useWebSocket(options: UseWebSocketOptions): UseWebSocketReturn {
             }
           }
 
+          if (
+            message.event_id != null &&
+            (lastEventIdRef.current === null || message.event_id > lastEventIdRef.current)
+          ) {
+            lastEventIdRef.current = message.event_id;
+          }
+          if (message.message_type === WsMessageType.ResyncRequired) {
+            const latest = (message.payload as { latest_event_id?: number | null } | undefined)
+              ?.latest_event_id;
+            lastEventIdRef.current = latest ?? null;
+          }
+
+          // Replies to our own requests are not room events
+          if (settleRequest(message)) {
+            return;
+          }
+
           callbacksRef.current.onMessage?.(message);
         } catch (err) {
           console.error("Failed to parse WebSocket message:", err);
@@ -393,6 +612,7 @@ export function useWebSocket(options: UseWebSocketOptions): UseWebSocketReturn {
         connectingRef.current = false;
         setConnecting(false);
         stopHeartbeat();
+        rejectPendingRequests("WebSocket closed before the request was acknowledged");
 
         // Don't reconnect if manually closed
         if (isManualCloseRef.current) {
@@ -436,12 +656,23 @@ export function useWebSocket(options: UseWebSocketOptions): UseWebSocketReturn {
     sendPong,
     startHeartbeat,
     stopHeartbeat,
+    settleRequest,
+    rejectPendingRequests,
   ]);
 
   useEffect(() => {
     connectRef.current = connect;
   }, [connect]);
 
+  useEffect(() => {
+    displayNameRef.current = displayName;
+  }, [displayName]);
+
+  // Event ids are per room
+  useEffect(() => {
+    lastEventIdRef.current = null;
+  }, [roomName]);
+
   /**
    * Manually trigger reconnection
    */
@@ -467,6 +698,7 @@ export function useWebSocket(options: UseWebSocketOptions): UseWebSocketReturn {
     isManualCloseRef.current = true;
     clearReconnectTimeout();
     stopHeartbeat();
+    rejectPendingRequests("WebSocket disconnected");
 
     if (wsRef.current) {
       wsRef.current.close();
@@ -477,7 +709,7 @@ export function useWebSocket(options: UseWebSocketOptions): UseWebSocketReturn {
     connectingRef.current = false;
     setConnecting(false);
     setReconnecting(false);
-  }, [clearReconnectTimeout, stopHeartbeat]);
+  }, [clearReconnectTimeout, stopHeartbeat, rejectPendingRequests]);
 
   /**
    * Effect: Manage connection lifecycle
@@ -508,6 +740,7 @@ export function useWebSocket(options: UseWebSocketOptions): UseWebSocketReturn {
     reconnecting,
     reconnect,
     sendMessage,
+    request,
     disconnect,
   };
 }
@@ -27,9 +27,23 @@ import {
   replacePendingMessage,
   replaceSavedMessage,
 } from "./messages/message-cache";
+import type { PresenceEntry, TypingEventPayload } from "./hooks/use-websocket";
 
 export type MessageLoadStatus = "idle" | "loading" | "ready" | "error";
 
+export interface RoomPresence {
+  /** Our own connection, excluded from typing indicators */
+  connectionId: string | null;
+  connections: PresenceEntry[];
+  typing: TypingEventPayload[];
+}
+
+const EMPTY_ROOM_PRESENCE: RoomPresence = {
+  connectionId: null,
+  connections: [],
+  typing: [],
+};
+
 type MessageSaveResult =
   | { kind: "remove"; id: string }
   | { kind: "replace"; pendingId: string; message: Message }
@@ -48,6 +62,8 @@ interface AppState {
   // Settings
   sendOnEnter: boolean;
   setSendOnEnter: (value: boolean) => void;
+  displayName: string;
+  setDisplayName: (value: string) => void;
 
   // Auto-scroll
   autoScroll: boolean;
@@ -165,6 +181,14 @@ interface AppState {
   removeTransfer: (id: string) => void;
   cancelTransfer: (id: string) => void;
 
+  // Room presence and typing indicators from the room WebSocket. Not persisted.
+  roomPresence: RoomPresence;
+  setRoomPresence: (presence: RoomPresence) => void;
+  typingNotifier: { notify: () => void; stop: () => void } | null;
+  setTypingNotifier: (
+    notifier: { notify: () => void; stop: () => void } | null,
+  ) => void;
+
   // Global redirect state (for room renaming)
   roomRedirectTarget: string | null;
   setRoomRedirectTarget: (target: string | null) => void;
@@ -198,6 +222,8 @@ export const useAppStore = create<AppState>()(
       // Settings
       sendOnEnter: true,
       setSendOnEnter: (value) => set({ sendOnEnter: value }),
+      displayName: "",
+      setDisplayName: (value) => set({ displayName: value }),
 
       // Auto-scroll
       autoScroll: true,
@@ -690,6 +716,11 @@ export const useAppStore = create<AppState>()(
             : {}
         ),
 
+      roomPresence: EMPTY_ROOM_PRESENCE,
+      setRoomPresence: (presence) => set({ roomPresence: presence }),
+      typingNotifier: null,
+      setTypingNotifier: (notifier) => set({ typingNotifier: notifier }),
+
       // Transfer state (uploads + downloads)
       transfers: {},
       addTransfer: (item) =>
@@ -736,6 +767,7 @@ export const useAppStore = create<AppState>()(
         // Persist locale and UI preferences
         locale: state.locale,
         sendOnEnter: state.sendOnEnter,
+        displayName: state.displayName,
         autoScroll: state.autoScroll,
         includeMetadataInCopy: state.includeMetadataInCopy,
         includeMetadataInDownload: state.includeMetadataInDownload,
@@ -285,7 +285,7 @@ export function backendContentToFileItem(
   return {
     id: String(content.id),
     name: content.file_name || "Unnamed",
-    thumbnailUrl: null, // Backend doesn't provide thumbnails
+    thumbnailUrl: content.thumbnail_url ?? null,
     size: content.size || undefined,
     type: typeMap[contentType],
     url: fileUrl ?? undefined,
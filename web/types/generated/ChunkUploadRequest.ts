@@ -17,6 +17,6 @@ chunk_index: number,
  */
 chunk_size: number, 
 /**
- * 分块哈希（可选，用于完整性验证）
+ * 分块哈希（可选，用于完整性验证），按预留时选择的算法计算
  */
 chunk_hash?: string, };
@@ -1,7 +1,12 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HashAlgorithm } from "./HashAlgorithm";
 import type { UploadFileDescriptor } from "./UploadFileDescriptor";
 
 /**
  * 分块上传预留请求
  */
-export type ChunkedUploadPreparationRequest = { files: Array<UploadFileDescriptor>, };
+export type ChunkedUploadPreparationRequest = { files: Array<UploadFileDescriptor>, 
+/**
+ * 分块与整体文件的校验算法，默认 SHA-256
+ */
+hash_algorithm?: HashAlgorithm, };
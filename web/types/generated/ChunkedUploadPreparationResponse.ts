@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HashAlgorithm } from "./HashAlgorithm";
 import type { ReservedFileInfo } from "./ReservedFileInfo";
 
 /**
@@ -17,6 +18,10 @@ upload_token: string,
  * 预留过期时间
  */
 expires_at: string, 
+/**
+ * 本次上传使用的校验算法
+ */
+hash_algorithm: HashAlgorithm, 
 /**
  * 文件清单
  */
@@ -3,4 +3,8 @@
 /**
  * 文件合并完成请求
  */
-export type FileMergeRequest = { reservation_id: string, final_hash: string, };
+export type FileMergeRequest = { reservation_id: string, 
+/**
+ * 整个文件的哈希，按预留时选择的算法计算
+ */
+final_hash: string, };
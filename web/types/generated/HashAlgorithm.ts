@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 分块与整体文件的校验算法，由客户端在预留时选择
+ *
+ * 摘要均以小写十六进制表示，CRC32C 为 8 位。
+ */
+export type HashAlgorithm = "sha256" | "blake3" | "crc32c";
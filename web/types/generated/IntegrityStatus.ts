@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 存储文件的一致性状态，由后台对账与完整性巡检任务维护
+ */
+export type IntegrityStatus = "ok" | "missing" | "corrupted";
@@ -3,4 +3,12 @@
 /**
  * 合并后的文件信息
  */
-export type MergedFileInfo = { file_name: string, file_size: number, file_hash: string, content_id?: number, };
+export type MergedFileInfo = { file_name: string, file_size: number, 
+/**
+ * 存储内容的 SHA-256，与上传时选择的校验算法无关
+ */
+file_hash: string, content_id?: number, 
+/**
+ * 房间要求移除图片元数据，但图片超过移除的大小上限，保留了原有元数据
+ */
+metadata_retained: boolean, };
@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 尚未上传的一段连续分块
+ */
+export type MissingChunkRange = { 
+/**
+ * 起始分块索引
+ */
+start_chunk: number, 
+/**
+ * 结束分块索引（含）
+ */
+end_chunk: number, 
+/**
+ * 起始字节偏移
+ */
+offset: number, 
+/**
+ * 字节长度
+ */
+length: number, };
@@ -1,7 +1,10 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { ContentType } from "./ContentType";
+import type { IntegrityStatus } from "./IntegrityStatus";
+import type { ScanStatus } from "./ScanStatus";
+import type { ThumbnailStatus } from "./ThumbnailStatus";
 
 /**
  * 数据库 RoomContent 模型
  */
-export type RoomContent = { id: number | null, room_id: number, content_type: ContentType, text: string | null, url: string | null, path: string | null, file_name: string | null, size: number | null, mime_type: string | null, sequence_number: number, created_at: string, updated_at: string, };
+export type RoomContent = { id: number | null, room_id: number, content_type: ContentType, text: string | null, url: string | null, path: string | null, file_name: string | null, size: number | null, mime_type: string | null, detected_mime_type: string | null, hash: string | null, sequence_number: number, thumbnail_status: ThumbnailStatus, scan_status: ScanStatus, integrity_status: IntegrityStatus, created_at: string, updated_at: string, };
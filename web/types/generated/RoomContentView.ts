@@ -1,4 +1,22 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { ContentType } from "./ContentType";
+import type { IntegrityStatus } from "./IntegrityStatus";
+import type { ScanStatus } from "./ScanStatus";
 
-export type RoomContentView = { id: number, content_type: ContentType, text: string | null, file_name: string | null, url: string | null, size: number | null, mime_type: string | null, sequence_number: number, created_at: string, updated_at: string, };
+export type RoomContentView = { id: number, content_type: ContentType, text: string | null, file_name: string | null, url: string | null, size: number | null, mime_type: string | null, 
+/**
+ * 服务端根据文件内容检测到的类型，无法识别时为空
+ */
+detected_mime_type: string | null, hash: string | null, sequence_number: number, 
+/**
+ * 缩略图就绪后的下载地址，仍需附带 `token` 查询参数
+ */
+thumbnail_url: string | null, 
+/**
+ * 病毒扫描状态，`clean` 以外的文件拒绝下载
+ */
+scan_status: ScanStatus, 
+/**
+ * `missing` 表示存储中的文件已丢失，无法下载
+ */
+integrity_status: IntegrityStatus, created_at: string, updated_at: string, };
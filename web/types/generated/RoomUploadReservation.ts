@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HashAlgorithm } from "./HashAlgorithm";
 import type { UploadStatus } from "./UploadStatus";
 
 /**
@@ -12,4 +13,4 @@ owner_token_jti: string,
 /**
  * Opaque upload lookup token. It is not an access credential by itself.
  */
-token_jti: string, file_manifest: string, reserved_size: number, reserved_at: string, expires_at: string, consumed_at: string | null, created_at: string, updated_at: string, chunked_upload: boolean | null, total_chunks: number | null, uploaded_chunks: number | null, file_hash: string | null, chunk_size: number | null, upload_status: UploadStatus | null, };
+token_jti: string, file_manifest: string, reserved_size: number, reserved_at: string, expires_at: string, consumed_at: string | null, created_at: string, updated_at: string, chunked_upload: boolean | null, total_chunks: number | null, uploaded_chunks: number | null, file_hash: string | null, chunk_size: number | null, upload_status: UploadStatus | null, hash_algorithm: HashAlgorithm, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 病毒扫描状态，扫描通过前文件不可下载
+ */
+export type ScanStatus = "clean" | "pending" | "infected" | "failed";
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 缩略图生成状态
+ */
+export type ThumbnailStatus = "none" | "pending" | "ready" | "failed";
@@ -20,4 +20,8 @@ max_times_entered?: number | null,
 /**
  * 最大容量限制（可选，单位：字节）
  */
-max_size?: number | null, };
+max_size?: number | null, 
+/**
+ * 上传图片时是否移除 EXIF/XMP 元数据（可选）
+ */
+strip_image_metadata?: boolean, };
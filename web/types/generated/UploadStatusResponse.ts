@@ -1,5 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { ChunkStatusInfo } from "./ChunkStatusInfo";
+import type { HashAlgorithm } from "./HashAlgorithm";
+import type { MissingChunkRange } from "./MissingChunkRange";
 import type { UploadStatus } from "./UploadStatus";
 
 /**
@@ -18,6 +20,10 @@ upload_token: string,
  * 上传状态
  */
 upload_status: UploadStatus, 
+/**
+ * 校验算法
+ */
+hash_algorithm: HashAlgorithm, 
 /**
  * 总分块数
  */
@@ -38,6 +44,10 @@ expires_at: string,
  * 已上传分块详细信息
  */
 chunk_details: Array<ChunkStatusInfo>, 
+/**
+ * 尚未上传的分块区间，客户端只需补传这些部分
+ */
+missing_ranges: Array<MissingChunkRange>, 
 /**
  * 预留大小
  */
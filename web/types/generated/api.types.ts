@@ -20,6 +20,7 @@ export * from './TokenBlacklistEntry';
 export * from './RoomUploadReservation';
 export * from './UploadFileDescriptor';
 export * from './UploadStatus';
+export * from './HashAlgorithm';
 export * from './TokenType';
 export * from './RoomTokenClaims';
 export * from './CreateRoomRequest';
@@ -58,6 +59,7 @@ export * from './ChunkUploadRequest';
 export * from './ChunkUploadResponse';
 export * from './UploadStatusQuery';
 export * from './ChunkStatusInfo';
+export * from './MissingChunkRange';
 export * from './UploadStatusResponse';
 export * from './FileMergeRequest';
 export * from './FileMergeResponse';